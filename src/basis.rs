@@ -0,0 +1,550 @@
+use std::thread;
+
+use crate::config;
+use crate::error::Error;
+use crate::linalg::{norm, LinearOperator, Matrix, SparseMatrix};
+use crate::log::{self, Level};
+use crate::operators::{Spin, SpinConvention};
+use crate::sweep::thread_count;
+use crate::{Operator, Slater, SlaterMap, State, TermMask};
+
+/// The number of determinants on which `Basis::estimated_matrix_nnz` counts the matrix elements.
+pub const NNZ_SAMPLES: usize = 256;
+
+/// The approximate memory of one determinant of a basis, in bytes: 8 in the sorted list, and 16 for the key and position in the
+/// hash map with its control byte, at a load factor of 7/8.
+pub const BASIS_BYTES_PER_STATE: usize = 8 + (16 + 1) * 8 / 7;
+
+/// The approximate memory of one element of a sparse matrix during its assembly, in bytes: it is held as a triplet, in its row,
+/// and in the matrix.
+pub const ASSEMBLY_BYTES_PER_ELEMENT: usize = 56;
+
+/// The number of basis states per chunk of `MatrixFree::apply`, so that a chunk of the output stays in cache.
+pub const MATVEC_CHUNK: usize = 4096;
+
+/// Represents a finite set of Slater determinants spanning the space operators are represented in.
+#[derive(Debug, Clone)]
+pub struct Basis {
+    /// The Slater determinants in the basis, sorted by index.
+    states: Vec<Slater>,
+    /// The position of each Slater determinant in `states`.
+    indices: SlaterMap<usize>,
+}
+
+impl Basis {
+    /// Returns a basis consisting of the supplied Slater determinants.
+    /// The determinants are sorted by index and duplicates are removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `states` - The Slater determinants spanning the basis.
+    pub fn new(mut states: Vec<Slater>) -> Basis {
+        states.sort();
+        states.dedup();
+        let indices = states.iter().enumerate().map(|(i, s)| (*s, i)).collect();
+        Basis { states, indices }
+    }
+
+    /// Returns the basis of all Slater determinants that can be formed from `n_orbitals` single particle states.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_orbitals` - The number of single particle states.
+    pub fn fock_space(n_orbitals: u64) -> Basis {
+        Basis::new((0..1u64 << n_orbitals).map(Slater::new).collect())
+    }
+
+    /// Returns the basis of all Slater determinants with exactly `n_particles` particles in `n_orbitals` single particle states.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_orbitals` - The number of single particle states.
+    /// * `n_particles` - The number of occupied single particle states.
+    pub fn fixed_particle_number(n_orbitals: u64, n_particles: u64) -> Basis {
+        let mut states = Vec::new();
+        if n_particles <= n_orbitals {
+            let limit = 1u64 << n_orbitals;
+            let mut index = (1u64 << n_particles) - 1;
+            while index < limit {
+                states.push(Slater::new(index));
+                if index == 0 {
+                    break;
+                }
+                // Gosper's hack: the next larger integer with the same number of set bits.
+                let c = index & index.wrapping_neg();
+                let r = index + c;
+                index = (((r ^ index) >> 2) / c) | r;
+            }
+        }
+        Basis::new(states)
+    }
+
+    /// Returns the basis of all Slater determinants in `n_orbitals` single particle states with an even or odd number of particles,
+    /// the symmetry sectors of Hamiltonians that only conserve fermion parity, e.g. with pairing terms.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_orbitals` - The number of single particle states.
+    /// * `odd` - Whether the number of particles is odd.
+    pub fn fixed_parity(n_orbitals: u64, odd: bool) -> Basis {
+        Basis::new(
+            (0..1u64 << n_orbitals)
+                .filter(|index| (index.count_ones() % 2 == 1) == odd)
+                .map(Slater::new)
+                .collect(),
+        )
+    }
+
+    /// Returns the basis of all Slater determinants with exactly one particle on each of `n_sites` spinful sites,
+    /// which spans the 2^n_sites states of a spin-1/2 model in the fermionic representation.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (site, spin) onto single particle states.
+    /// * `n_sites` - The number of sites.
+    pub fn single_occupancy(convention: SpinConvention, n_sites: u64) -> Basis {
+        Basis::new(
+            (0..1u64 << n_sites)
+                .map(|config| {
+                    let index = (0..n_sites).fold(0, |acc, site| {
+                        let spin = if config & (1 << site) == 0 { Spin::Up } else { Spin::Down };
+                        acc | (1 << convention.orbital(site, spin))
+                    });
+                    Slater::new(index)
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the basis of all Slater determinants of `n_particles` particles on `n_sites` spinful sites without doubly occupied sites,
+    /// the Hilbert space of the t-J model.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (site, spin) onto single particle states.
+    /// * `n_sites` - The number of sites.
+    /// * `n_particles` - The number of particles, at most `n_sites`.
+    pub fn no_double_occupancy(convention: SpinConvention, n_sites: u64, n_particles: u64) -> Basis {
+        let mut states = Vec::new();
+        for sites in Basis::fixed_particle_number(n_sites, n_particles).states() {
+            let occupied: Vec<u64> = (0..n_sites).filter(|i| sites.index & (1 << i) != 0).collect();
+            for config in 0..1u64 << n_particles {
+                let index = occupied.iter().enumerate().fold(0, |acc, (k, site)| {
+                    let spin = if config & (1 << k) == 0 { Spin::Up } else { Spin::Down };
+                    acc | (1 << convention.orbital(*site, spin))
+                });
+                states.push(Slater::new(index));
+            }
+        }
+        Basis::new(states)
+    }
+
+    /// Returns the basis of all Slater determinants with exactly `n_up` spin up and `n_down` spin down particles on `n_sites` spinful sites,
+    /// the (N, S^z) symmetry sectors of spin conserving Hamiltonians.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (site, spin) onto single particle states.
+    /// * `n_sites` - The number of sites.
+    /// * `n_up` - The number of spin up particles.
+    /// * `n_down` - The number of spin down particles.
+    pub fn fixed_spin_numbers(convention: SpinConvention, n_sites: u64, n_up: u64, n_down: u64) -> Basis {
+        let mut span = log::span(Level::Debug, "basis::fixed_spin_numbers", &[("n_sites", n_sites as f64), ("n_up", n_up as f64), ("n_down", n_down as f64)]);
+        let ups = Basis::fixed_particle_number(n_sites, n_up);
+        let downs = Basis::fixed_particle_number(n_sites, n_down);
+        let place = |sites: u64, spin: Spin| {
+            (0..n_sites)
+                .filter(|i| sites & (1 << i) != 0)
+                .fold(0, |acc, i| acc | (1 << convention.orbital(i, spin)))
+        };
+        let mut states = Vec::new();
+        for up in ups.states() {
+            for down in downs.states() {
+                states.push(Slater::new(place(up.index, Spin::Up) | place(down.index, Spin::Down)));
+            }
+        }
+        let basis = Basis::new(states);
+        span.record("dimension", basis.dimension() as f64);
+        basis
+    }
+
+    /// Returns the basis of all Slater determinants at most `max_excitations` particle-hole excitations away from `reference`,
+    /// the space of truncated configuration interaction: 1 for CIS, 2 for CISD and 3 for CISDT.
+    ///
+    /// # Arguments
+    ///
+    /// * `reference` - The reference determinant, e.g. the Hartree-Fock determinant.
+    /// * `n_orbitals` - The number of single particle states.
+    /// * `max_excitations` - The largest number of particles moved from occupied to unoccupied orbitals of the reference.
+    pub fn excitations(reference: Slater, n_orbitals: u64, max_excitations: u64) -> Basis {
+        let occupied: Vec<u64> = (0..n_orbitals).filter(|o| reference.index & (1 << o) != 0).collect();
+        let empty: Vec<u64> = (0..n_orbitals).filter(|o| reference.index & (1 << o) == 0).collect();
+        let pick = |orbitals: &[u64], mask: u64| {
+            orbitals
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| mask & (1 << k) != 0)
+                .fold(0, |acc, (_, o)| acc | (1 << o))
+        };
+        let mut states = Vec::new();
+        for level in 0..=max_excitations.min(occupied.len() as u64).min(empty.len() as u64) {
+            let holes = Basis::fixed_particle_number(occupied.len() as u64, level);
+            let particles = Basis::fixed_particle_number(empty.len() as u64, level);
+            for h in holes.states() {
+                for p in particles.states() {
+                    states.push(Slater::new((reference.index & !pick(&occupied, h.index)) | pick(&empty, p.index)));
+                }
+            }
+        }
+        Basis::new(states)
+    }
+
+    /// Returns the number of Slater determinants in the basis.
+    pub fn dimension(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns the Slater determinants in the basis, sorted by index.
+    pub fn states(&self) -> &[Slater] {
+        &self.states
+    }
+
+    /// Returns the position of `slater` in the basis, or None if it is not part of the basis.
+    pub fn index(&self, slater: &Slater) -> Option<usize> {
+        self.indices.get(slater).copied()
+    }
+
+    /// Returns an estimate of the number of non-zero elements of the matrix of `op` in this basis, counted exactly on up to
+    /// `NNZ_SAMPLES` evenly spread determinants and scaled to the dimension, so that a matrix can be sized before it is built.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    pub fn estimated_matrix_nnz(&self, op: &Operator) -> usize {
+        let n = self.dimension();
+        if n == 0 {
+            return 0;
+        }
+        let masks = op.masks();
+        let stride = n.div_ceil(NNZ_SAMPLES);
+        let mut rows = Vec::new();
+        let (mut sampled, mut total) = (0, 0);
+        for s in self.states.iter().step_by(stride) {
+            rows.clear();
+            rows.extend(masks.iter().filter_map(|(_, mask)| mask.apply(s)).filter_map(|(_, ns)| self.index(&ns)));
+            rows.sort_unstable();
+            rows.dedup();
+            total += rows.len();
+            sampled += 1;
+        }
+        (total as f64 / sampled as f64 * n as f64).round() as usize
+    }
+
+    /// Returns an estimate of the memory held by this basis, in bytes: the determinants and the map from them to their positions.
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.dimension() * BASIS_BYTES_PER_STATE
+    }
+
+    /// Returns the dense matrix representation of `op` in this basis, M_ij = <i|op|j>.
+    /// Contributions leading out of the basis are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    ///
+    /// # Panics
+    ///
+    /// * If the matrix exceeds the memory budget of the configuration, see `try_matrix`.
+    pub fn matrix(&self, op: &Operator) -> Matrix {
+        self.try_matrix(op).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the dense matrix representation of `op` in this basis, M_ij = <i|op|j>, or an error if it does not fit in memory.
+    /// Contributions leading out of the basis are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MemoryBudget` if the matrix exceeds the memory budget of the configuration, see `config::check_memory`.
+    pub fn try_matrix(&self, op: &Operator) -> Result<Matrix, Error> {
+        let _span = log::span(Level::Debug, "basis::matrix", &[("dimension", self.dimension() as f64), ("terms", op.terms.len() as f64)]);
+        let n = self.dimension();
+        config::check_memory("basis::matrix", 8u64.saturating_mul(n as u64).saturating_mul(n as u64))?;
+        let mut m = Matrix::zeros(n, n);
+        let masks = op.masks();
+        for (j, s) in self.states.iter().enumerate() {
+            for (fac, mask) in &masks {
+                if let Some((phase, ns)) = mask.apply(s) {
+                    if let Some(i) = self.index(&ns) {
+                        m[(i, j)] += fac * phase as f64;
+                    }
+                }
+            }
+        }
+        Ok(m)
+    }
+
+    /// Returns the sparse matrix representation of `op` in this basis, M_ij = <i|op|j>.
+    /// Contributions leading out of the basis are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    ///
+    /// # Panics
+    ///
+    /// * If the assembly is projected to exceed the memory budget of the configuration, see `try_sparse_matrix`.
+    pub fn sparse_matrix(&self, op: &Operator) -> SparseMatrix {
+        self.try_sparse_matrix(op).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the sparse matrix representation of `op` in this basis, M_ij = <i|op|j>, or an error if its assembly does not fit in memory.
+    /// Contributions leading out of the basis are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MemoryBudget` if the assembly is projected to exceed the memory budget of the configuration, see `config::check_memory`.
+    pub fn try_sparse_matrix(&self, op: &Operator) -> Result<SparseMatrix, Error> {
+        let mut span = log::span(Level::Debug, "basis::sparse_matrix", &[("dimension", self.dimension() as f64), ("terms", op.terms.len() as f64)]);
+        let bytes = (ASSEMBLY_BYTES_PER_ELEMENT as u64).saturating_mul(self.estimated_matrix_nnz(op) as u64);
+        config::check_memory("basis::sparse_matrix", bytes)?;
+        let mut triplets = Vec::new();
+        let masks = op.masks();
+        for (j, s) in self.states.iter().enumerate() {
+            for (fac, mask) in &masks {
+                if let Some((phase, ns)) = mask.apply(s) {
+                    if let Some(i) = self.index(&ns) {
+                        triplets.push((i, j, fac * phase as f64));
+                    }
+                }
+            }
+        }
+        let m = SparseMatrix::from_triplets(self.dimension(), triplets);
+        span.record("nnz", m.nnz() as f64);
+        Ok(m)
+    }
+
+    /// Returns `op` in this basis as a linear operator that recomputes the matrix elements on every product instead of storing them,
+    /// using `n_threads` threads, where 0 selects the configured number, in chunks of the configured size, see `config::Config`.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    /// * `n_threads` - The number of threads of each product.
+    ///
+    /// # Panics
+    ///
+    /// * If the two vectors of a product exceed the memory budget of the configuration, see `try_matrix_free`.
+    pub fn matrix_free<'a>(&'a self, op: &Operator, n_threads: usize) -> MatrixFree<'a> {
+        self.try_matrix_free(op, n_threads).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns `op` in this basis as a linear operator that recomputes the matrix elements on every product, as `matrix_free`,
+    /// or an error if the vectors of a product do not fit in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to represent.
+    /// * `n_threads` - The number of threads of each product.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MemoryBudget` if the two vectors of a product exceed the memory budget of the configuration, see `config::check_memory`.
+    pub fn try_matrix_free<'a>(&'a self, op: &Operator, n_threads: usize) -> Result<MatrixFree<'a>, Error> {
+        config::check_memory("basis::matrix_free", 16u64.saturating_mul(self.dimension() as u64))?;
+        Ok(MatrixFree {
+            basis: self,
+            adjoint: op.adjoint().masks(),
+            n_threads: thread_count(n_threads),
+            chunk: config::current().chunk_size(),
+        })
+    }
+
+    /// Returns the amplitudes of `state` as a vector in this basis.
+    /// Components outside the basis are discarded.
+    pub fn vector(&self, state: &State) -> Vec<f64> {
+        let mut v = vec![0.0; self.dimension()];
+        for (s, amp) in &state.amplitudes {
+            if let Some(i) = self.index(s) {
+                v[i] = *amp;
+            }
+        }
+        v
+    }
+
+    /// Returns the State with the amplitudes `vector` in this basis, without the amplitudes that are zero to rounding error
+    /// relative to the norm of `vector`.
+    ///
+    /// # Panics
+    ///
+    /// * If the length of `vector` differs from the dimension of the basis.
+    pub fn state(&self, vector: &[f64]) -> State {
+        assert_eq!(vector.len(), self.dimension(), "Vector does not match the basis!");
+        let cutoff = f64::EPSILON * norm(vector);
+        State::new(
+            self.states
+                .iter()
+                .zip(vector)
+                .filter(|(_, amp)| amp.abs() > cutoff)
+                .map(|(s, amp)| (*s, *amp))
+                .collect(),
+        )
+    }
+}
+
+/// Represents an operator in a basis without its matrix, applied in parallel over chunks of the basis.
+#[derive(Debug, Clone)]
+pub struct MatrixFree<'a> {
+    basis: &'a Basis,
+    /// The compiled terms of the adjoint of the operator: applied to |i> they yield the elements <i|op|j> of row i.
+    adjoint: Vec<(f64, TermMask)>,
+    n_threads: usize,
+    /// The number of rows computed together.
+    chunk: usize,
+}
+
+impl MatrixFree<'_> {
+    /// Returns this operator with products computed in chunks of `chunk` rows instead of the configured size.
+    ///
+    /// # Panics
+    ///
+    /// * If `chunk` is zero.
+    pub fn with_chunk_size(mut self, chunk: usize) -> Self {
+        assert!(chunk > 0, "The chunk size must be positive!");
+        self.chunk = chunk;
+        self
+    }
+
+    /// Computes the rows of y = A x starting at `offset`, where `y` holds the rows, using `scratch` for the determinants of a row.
+    fn apply_rows(&self, x: &[f64], offset: usize, y: &mut [f64], scratch: &mut Vec<(f64, Slater)>) {
+        for (k, yi) in y.iter_mut().enumerate() {
+            let s = self.basis.states[offset + k];
+            scratch.clear();
+            scratch.extend(self.adjoint.iter().filter_map(|(fac, mask)| mask.apply(&s).map(|(phase, ns)| (fac * phase as f64, ns))));
+            *yi = scratch.iter().filter_map(|(amp, ns)| self.basis.index(ns).map(|j| amp * x[j])).sum();
+        }
+    }
+}
+
+impl LinearOperator for MatrixFree<'_> {
+    fn dimension(&self) -> usize {
+        self.basis.dimension()
+    }
+
+    /// Computes y = A x. Each chunk of rows is computed by one thread from the adjoint applied to its determinants,
+    /// so the threads write disjoint parts of `y` and need neither locks nor a reduction. A single thread computes all rows
+    /// on the calling thread without spawning, which also serves targets without threads such as wasm32.
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        let n_chunks = y.len().div_ceil(self.chunk);
+        let n_threads = self.n_threads.min(n_chunks);
+        if n_threads <= 1 {
+            self.apply_rows(x, 0, y, &mut Vec::new());
+            return;
+        }
+        // The chunks are dealt out in turn, so that every thread gets a similar share of the basis.
+        let mut shares: Vec<Vec<(usize, &mut [f64])>> = (0..n_threads).map(|_| Vec::new()).collect();
+        for (k, rows) in y.chunks_mut(self.chunk).enumerate() {
+            shares[k % n_threads].push((k * self.chunk, rows));
+        }
+        thread::scope(|scope| {
+            for share in shares {
+                scope.spawn(move || {
+                    let mut scratch = Vec::new();
+                    for (offset, rows) in share {
+                        self.apply_rows(x, offset, rows, &mut scratch);
+                    }
+                });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AC;
+
+    #[test]
+    fn test_fixed_particle_number() {
+        let basis = Basis::fixed_particle_number(4, 2);
+        assert_eq!(basis.dimension(), 6);
+        assert!(basis.states().iter().all(|s| s.index().count_ones() == 2));
+        assert_eq!(Basis::fixed_particle_number(3, 0).dimension(), 1);
+        assert_eq!(Basis::fixed_particle_number(3, 4).dimension(), 0);
+    }
+
+    #[test]
+    fn test_excitations() {
+        // Four particles in eight orbitals: 1 reference, 4 × 4 singles and 6 × 6 doubles.
+        let reference = Slater::new(0b1111);
+        assert_eq!(Basis::excitations(reference, 8, 1).dimension(), 17);
+        assert_eq!(Basis::excitations(reference, 8, 2).dimension(), 53);
+        assert_eq!(Basis::excitations(reference, 8, 4).dimension(), Basis::fixed_particle_number(8, 4).dimension());
+    }
+
+    #[test]
+    fn test_state() {
+        // Amplitudes far below one are kept when the whole vector is that small, and rounding residues are dropped.
+        let basis = Basis::fixed_particle_number(3, 1);
+        assert_eq!(basis.state(&[1e-20, 0.0, 2e-20]).amplitudes.len(), 2);
+        assert_eq!(basis.state(&[1.0, 1e-17, 0.5]).amplitudes.len(), 2);
+    }
+
+    #[test]
+    fn test_matrix() {
+        let hop = Operator::new(vec![
+            (-1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (-1.0, vec![AC::Create(1), AC::Annihilate(0)]),
+        ]);
+        let basis = Basis::fixed_particle_number(2, 1);
+        let m = basis.matrix(&hop);
+        assert_eq!(m, Matrix::from_vec(2, 2, vec![0.0, -1.0, -1.0, 0.0]));
+        assert_eq!(basis.sparse_matrix(&hop).nnz(), 2);
+        assert_eq!(basis.estimated_matrix_nnz(&hop), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "memory budget")]
+    fn test_matrix_over_budget() {
+        let hop = Operator::new(vec![(-1.0, vec![AC::Create(0), AC::Annihilate(1)])]);
+        // The 2 x 2 matrix takes 32 bytes.
+        config::scoped(config::Config::default().with_memory_budget(31), || Basis::fixed_particle_number(2, 1).matrix(&hop));
+    }
+
+    #[test]
+    fn test_try_matrix() {
+        let hop = Operator::new(vec![(-1.0, vec![AC::Create(0), AC::Annihilate(1)])]);
+        let basis = Basis::fixed_particle_number(2, 1);
+        config::scoped(config::Config::default().with_memory_budget(31), || {
+            assert!(matches!(basis.try_matrix(&hop), Err(Error::MemoryBudget { projected: 32, budget: 31 })));
+            assert!(matches!(basis.try_matrix_free(&hop, 1), Err(Error::MemoryBudget { projected: 32, budget: 31 })));
+        });
+        config::scoped(config::Config::default().with_memory_budget(1), || {
+            assert!(matches!(basis.try_sparse_matrix(&hop), Err(Error::MemoryBudget { budget: 1, .. })));
+        });
+        assert_eq!(basis.try_matrix(&hop).unwrap(), basis.matrix(&hop));
+        assert_eq!(basis.try_sparse_matrix(&hop).unwrap().nnz(), 1);
+    }
+
+    #[test]
+    fn test_matrix_free() {
+        // A ring with a non-Hermitian term, to check that the rows rather than the columns are gathered.
+        let mut terms: Vec<(f64, Vec<AC>)> = (0..8).map(|i| (-1.0, vec![AC::Create((i + 1) % 8), AC::Annihilate(i)])).collect();
+        terms.push((0.5, vec![AC::Create(3), AC::Annihilate(0)]));
+        let op = Operator::new(terms);
+        let basis = Basis::fixed_particle_number(8, 3);
+        let x: Vec<f64> = (0..basis.dimension()).map(|i| (i as f64).sin()).collect();
+        let mut expected = vec![0.0; x.len()];
+        basis.sparse_matrix(&op).apply(&x, &mut expected);
+        let mut y = vec![0.0; x.len()];
+        basis.matrix_free(&op, 3).with_chunk_size(5).apply(&x, &mut y);
+        assert!(y.iter().zip(&expected).all(|(a, b)| (a - b).abs() < 1e-12));
+    }
+}
@@ -0,0 +1,258 @@
+use std::fs;
+use std::mem::size_of;
+use std::time::Instant;
+
+use crate::error::Error;
+use crate::input::{Model, Solver, Specification, DEFAULT_TOLERANCE};
+use crate::lanczos::ground_state;
+use crate::lattice::{Boundary, Lattice};
+use crate::linalg::LinearOperator;
+use crate::random::Rng;
+use crate::table::ResultTable;
+use crate::{Operator, Slater};
+
+/// The number of matrix-vector products timed per problem.
+const N_APPLY: usize = 10;
+
+/// Represents a standard benchmark problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// The name, e.g. "hubbard_chain_8".
+    pub name: String,
+    /// The model, sector and solver.
+    pub specification: Specification,
+}
+
+/// Represents the measurements of one benchmark problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timings {
+    /// The name of the problem.
+    pub name: String,
+    /// The number of sites.
+    pub n_sites: u64,
+    /// The dimension of the sector.
+    pub dimension: usize,
+    /// The number of non-zero matrix elements of the Hamiltonian.
+    pub nnz: usize,
+    /// The time to build the basis, in seconds.
+    pub basis_seconds: f64,
+    /// The time to assemble the sparse Hamiltonian, in seconds.
+    pub assembly_seconds: f64,
+    /// The average time of one matrix-vector product, in seconds.
+    pub apply_seconds: f64,
+    /// The time to apply the Hamiltonian to every determinant of the basis through its compiled term masks, in seconds.
+    pub masks_seconds: f64,
+    /// The time to apply the Hamiltonian to every determinant of the basis one creation or annihilation operator at a time, in seconds.
+    pub strings_seconds: f64,
+    /// The time of the Lanczos ground state, in seconds.
+    pub lanczos_seconds: f64,
+    /// The ground state energy, to check the result.
+    pub energy: f64,
+    /// The memory of the basis and the sparse Hamiltonian, in bytes: the determinants and their index map, and the CSR arrays.
+    pub matrix_bytes: usize,
+    /// The peak resident memory of the process, in bytes, where the operating system reports it.
+    pub peak_memory_bytes: Option<u64>,
+}
+
+/// Returns the peak resident memory of this process from /proc/self/status, on Linux.
+fn peak_memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(1024 * kib)
+}
+
+/// Returns the Slater determinants, with amplitudes, obtained by applying `op` to `slater` one creation or annihilation
+/// operator at a time, the rightmost first, as `Operator::apply_slater` did before the terms were compiled to masks.
+fn apply_strings(op: &Operator, slater: &Slater) -> Vec<(f64, Slater)> {
+    op.terms
+        .iter()
+        .filter_map(|(fac, ac)| {
+            ac.iter().rev().try_fold((*fac, *slater), |(amp, s), c| s.apply(c).map(|(phase, ns)| (amp * phase as f64, ns)))
+        })
+        .collect()
+}
+
+/// Returns the binomial coefficient n choose k.
+fn binomial(n: u64, k: u64) -> u64 {
+    (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1))
+}
+
+/// Returns the standard problems whose sectors have at most `max_dimension` states: periodic Hubbard chains at half filling
+/// with U = 4t of 4, 6, 8, ... sites, and Heisenberg L × L squares with periodic boundaries in the lowest S^z sector.
+pub fn standard_problems(max_dimension: u64) -> Vec<Problem> {
+    let solver = Solver::Lanczos {
+        max_steps: 1000,
+        tolerance: DEFAULT_TOLERANCE,
+    };
+    let mut problems = Vec::new();
+    for length in (4..).step_by(2).take_while(|l| binomial(*l, l / 2).pow(2) <= max_dimension) {
+        problems.push(Problem {
+            name: format!("hubbard_chain_{}", length),
+            specification: Specification {
+                lattice: Lattice::chain(length as usize, Boundary::Periodic),
+                model: Model::Hubbard { t: 1.0, u: 4.0, mu: 0.0 },
+                n_up: length / 2,
+                n_down: length / 2,
+                solver,
+                observables: Vec::new(),
+                memory_budget: None,
+            },
+        });
+    }
+    for side in (2..).take_while(|l: &u64| binomial(l * l, l * l / 2) <= max_dimension) {
+        let n = side * side;
+        problems.push(Problem {
+            name: format!("heisenberg_square_{}x{}", side, side),
+            specification: Specification {
+                lattice: Lattice::square([side as usize; 2], [Boundary::Periodic; 2]),
+                model: Model::Heisenberg { jxy: 1.0, jz: 1.0, field: 0.0 },
+                n_up: n / 2,
+                n_down: n - n / 2,
+                solver,
+                observables: Vec::new(),
+                memory_budget: None,
+            },
+        });
+    }
+    problems
+}
+
+/// Returns the timings of building the basis, assembling the Hamiltonian, applying it and finding the ground state of `problem`.
+///
+/// # Errors
+///
+/// * If the sector is invalid or Lanczos does not converge, this function returns an Error.
+pub fn run_problem(problem: &Problem) -> Result<Timings, Error> {
+    let spec = &problem.specification;
+    let hamiltonian = spec.hamiltonian();
+    let clock = Instant::now();
+    let basis = spec.basis()?;
+    let basis_seconds = clock.elapsed().as_secs_f64();
+    let clock = Instant::now();
+    let h = basis.sparse_matrix(&hamiltonian);
+    let assembly_seconds = clock.elapsed().as_secs_f64();
+    let n = h.dimension();
+    let start = Rng::new(0xbe4c).normal_vector(n);
+    let mut w = vec![0.0; n];
+    let clock = Instant::now();
+    for _ in 0..N_APPLY {
+        h.apply(&start, &mut w);
+    }
+    let apply_seconds = clock.elapsed().as_secs_f64() / N_APPLY as f64;
+    let masks = hamiltonian.masks();
+    let clock = Instant::now();
+    let n_masked: usize = basis
+        .states()
+        .iter()
+        .map(|s| {
+            let res: Vec<(f64, Slater)> =
+                masks.iter().filter_map(|(fac, mask)| mask.apply(s).map(|(phase, ns)| (fac * phase as f64, ns))).collect();
+            res.len()
+        })
+        .sum();
+    let masks_seconds = clock.elapsed().as_secs_f64();
+    let clock = Instant::now();
+    let n_strings: usize = basis.states().iter().map(|s| apply_strings(&hamiltonian, s).len()).sum();
+    let strings_seconds = clock.elapsed().as_secs_f64();
+    debug_assert_eq!(n_masked, n_strings);
+    let (max_steps, tolerance) = match spec.solver {
+        Solver::Lanczos { max_steps, tolerance } | Solver::OutOfCore { max_steps, tolerance } => (max_steps, tolerance),
+        Solver::Full => (n, DEFAULT_TOLERANCE),
+    };
+    let clock = Instant::now();
+    let (energy, _) = ground_state(&h, &start, max_steps, tolerance)?;
+    let lanczos_seconds = clock.elapsed().as_secs_f64();
+    let slater = size_of::<u64>();
+    Ok(Timings {
+        name: problem.name.clone(),
+        n_sites: spec.lattice.n_sites(),
+        dimension: n,
+        nnz: h.nnz(),
+        basis_seconds,
+        assembly_seconds,
+        apply_seconds,
+        masks_seconds,
+        strings_seconds,
+        lanczos_seconds,
+        energy,
+        matrix_bytes: n * (3 * slater + size_of::<usize>()) + h.nnz() * (size_of::<usize>() + size_of::<f64>()),
+        peak_memory_bytes: peak_memory(),
+    })
+}
+
+/// Returns the table of the timings of all standard problems with at most `max_dimension` states, one row per problem,
+/// with the problem names in the metadata as "problem_0", "problem_1", ...
+///
+/// # Errors
+///
+/// * If a problem fails, this function returns the Error.
+pub fn run_benchmarks(max_dimension: u64) -> Result<ResultTable, Error> {
+    let mut table = ResultTable::new(&[
+        "problem",
+        "n_sites",
+        "dimension",
+        "nnz",
+        "basis_seconds",
+        "assembly_seconds",
+        "apply_seconds",
+        "masks_seconds",
+        "strings_seconds",
+        "lanczos_seconds",
+        "energy",
+        "matrix_bytes",
+        "peak_memory_bytes",
+    ]);
+    for (k, problem) in standard_problems(max_dimension).iter().enumerate() {
+        let t = run_problem(problem)?;
+        table.set_metadata(&format!("problem_{}", k), &t.name);
+        table.push(vec![
+            k as f64,
+            t.n_sites as f64,
+            t.dimension as f64,
+            t.nnz as f64,
+            t.basis_seconds,
+            t.assembly_seconds,
+            t.apply_seconds,
+            t.masks_seconds,
+            t.strings_seconds,
+            t.lanczos_seconds,
+            t.energy,
+            t.matrix_bytes as f64,
+            t.peak_memory_bytes.map_or(f64::NAN, |b| b as f64),
+        ])?;
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_standard_problems() {
+        let names: Vec<String> = standard_problems(1_000_000).into_iter().map(|p| p.name).collect();
+        assert_eq!(names[4..], ["hubbard_chain_12", "heisenberg_square_2x2", "heisenberg_square_3x3", "heisenberg_square_4x4"]);
+        let table = run_benchmarks(36).unwrap();
+        assert_eq!(table.metadata("problem_1"), Some("heisenberg_square_2x2"));
+        // The half filled Hubbard ring of four sites has 36 states, the four site Heisenberg sector six.
+        assert_eq!(table.column("dimension").unwrap(), vec![36.0, 6.0]);
+        assert!(table.column("apply_seconds").unwrap().iter().all(|t| *t >= 0.0));
+    }
+
+    #[test]
+    fn test_apply_strings() {
+        // The operator strings applied one at a time give the same determinants and amplitudes as the compiled masks.
+        let spec = &standard_problems(400)[1].specification;
+        let hamiltonian = spec.hamiltonian();
+        for s in spec.basis().unwrap().states() {
+            let strings = apply_strings(&hamiltonian, s);
+            let masks = hamiltonian.apply_slater(s);
+            assert_eq!(strings.len(), masks.len());
+            for ((a, sa), (b, sb)) in strings.iter().zip(&masks) {
+                assert_eq!(sa, sb);
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use crate::linalg::SparseMatrix;
+
+/// Represents a finite set of bosonic Fock states |n_0, n_1, ...> on a number of sites, with at most n_max bosons per site.
+#[derive(Debug, Clone)]
+pub struct BosonBasis {
+    /// The largest occupation of a single site.
+    n_max: u32,
+    /// The occupations of each Fock state, in lexicographic order.
+    states: Vec<Vec<u32>>,
+    /// The position of each Fock state in `states`.
+    indices: HashMap<Vec<u32>, usize>,
+}
+
+impl BosonBasis {
+    /// Returns the basis of all Fock states of `n_particles` bosons on `n_sites` sites with at most `n_max` bosons per site.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_sites` - The number of sites.
+    /// * `n_particles` - The total number of bosons.
+    /// * `n_max` - The occupation cutoff of a single site; 1 gives hard-core bosons.
+    pub fn fixed_particle_number(n_sites: usize, n_particles: u32, n_max: u32) -> BosonBasis {
+        fn fill(site: usize, left: u32, n_max: u32, current: &mut Vec<u32>, states: &mut Vec<Vec<u32>>) {
+            if site == current.len() {
+                if left == 0 {
+                    states.push(current.clone());
+                }
+                return;
+            }
+            for n in (0..=left.min(n_max)).rev() {
+                current[site] = n;
+                fill(site + 1, left - n, n_max, current, states);
+            }
+            current[site] = 0;
+        }
+        let mut states = Vec::new();
+        fill(0, n_particles, n_max, &mut vec![0; n_sites], &mut states);
+        states.reverse();
+        let indices = states.iter().enumerate().map(|(i, s)| (s.clone(), i)).collect();
+        BosonBasis { n_max, states, indices }
+    }
+
+    /// Returns the number of Fock states in the basis.
+    pub fn dimension(&self) -> usize {
+        self.states.len()
+    }
+
+    /// Returns the occupation cutoff of a single site.
+    pub fn n_max(&self) -> u32 {
+        self.n_max
+    }
+
+    /// Returns the occupations of all Fock states in the basis.
+    pub fn states(&self) -> &[Vec<u32>] {
+        &self.states
+    }
+
+    /// Returns the position of the Fock state with occupations `occupations` in the basis, if it is part of the basis.
+    pub fn index(&self, occupations: &[u32]) -> Option<usize> {
+        self.indices.get(occupations).copied()
+    }
+
+    /// Returns the sparse matrix of H = Σ a_ij b†_i b_j + D(n) in this basis, with D diagonal in the occupations.
+    /// Contributions leading out of the basis, e.g. above the cutoff, are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `hoppings` - The one body terms (i, j, a_ij), including i = j.
+    /// * `diagonal` - The diagonal part D, a function of the occupations.
+    pub fn sparse_matrix<D: Fn(&[u32]) -> f64>(&self, hoppings: &[(usize, usize, f64)], diagonal: D) -> SparseMatrix {
+        let mut triplets = Vec::new();
+        for (col, state) in self.states.iter().enumerate() {
+            let d = diagonal(state);
+            if d != 0.0 {
+                triplets.push((col, col, d));
+            }
+            for (i, j, a) in hoppings {
+                if state[*j] == 0 {
+                    continue;
+                }
+                let mut next = state.clone();
+                // b_j |n_j> = √n_j |n_j - 1> and b†_i |n_i> = √(n_i + 1) |n_i + 1>
+                let mut amp = a * (next[*j] as f64).sqrt();
+                next[*j] -= 1;
+                amp *= (next[*i] as f64 + 1.0).sqrt();
+                next[*i] += 1;
+                if let Some(row) = self.index(&next) {
+                    triplets.push((row, col, amp));
+                }
+            }
+        }
+        SparseMatrix::from_triplets(self.dimension(), triplets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff() {
+        // Three bosons on three sites: 10 states without cutoff, of which |300>, |030> and |003> exceed n_max = 2.
+        assert_eq!(BosonBasis::fixed_particle_number(3, 3, 3).dimension(), 10);
+        let basis = BosonBasis::fixed_particle_number(3, 3, 2);
+        assert_eq!(basis.dimension(), 7);
+        assert!(basis.index(&[1, 1, 1]).is_some());
+        assert!(basis.index(&[3, 0, 0]).is_none());
+    }
+}
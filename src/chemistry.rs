@@ -0,0 +1,292 @@
+use crate::basis::Basis;
+use crate::error::Error;
+use crate::lanczos::ground_state;
+use crate::linalg::{LinearOperator, Matrix};
+use crate::operators::{Spin, SpinConvention};
+use crate::random::Rng;
+use crate::{Operator, AC};
+
+/// The required residual of the Lanczos ground state of `ActiveSpace::casci`.
+const CASCI_TOLERANCE: f64 = 1e-10;
+
+/// Integrals below this magnitude produce no operator terms.
+const INTEGRAL_CUTOFF: f64 = 1e-14;
+
+/// Represents the integrals of a molecular Hamiltonian in a basis of real spatial orbitals,
+/// H = E_core + Σ_pqσ h_pq c†_pσ c_qσ + ½ Σ_pqrs Σ_στ (pq|rs) c†_pσ c†_rτ c_sτ c_qσ, with the two-electron integrals in chemists' notation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MolecularIntegrals {
+    /// The number of spatial orbitals.
+    n_orbitals: usize,
+    /// The constant energy E_core, e.g. the nuclear repulsion.
+    core_energy: f64,
+    /// The one-electron integrals h_pq.
+    one_body: Matrix,
+    /// The two-electron integrals (pq|rs), stored at ((p n + q) n + r) n + s.
+    two_body: Vec<f64>,
+}
+
+impl MolecularIntegrals {
+    /// Returns the integrals of `n_orbitals` spatial orbitals with all elements set to zero.
+    pub fn zeros(n_orbitals: usize) -> MolecularIntegrals {
+        MolecularIntegrals {
+            n_orbitals,
+            core_energy: 0.0,
+            one_body: Matrix::zeros(n_orbitals, n_orbitals),
+            two_body: vec![0.0; n_orbitals.pow(4)],
+        }
+    }
+
+    /// Returns the number of spatial orbitals.
+    pub fn n_orbitals(&self) -> usize {
+        self.n_orbitals
+    }
+
+    /// Returns the constant energy E_core.
+    pub fn core_energy(&self) -> f64 {
+        self.core_energy
+    }
+
+    /// Returns the one-electron integral h_pq.
+    pub fn one_body(&self, p: usize, q: usize) -> f64 {
+        self.one_body[(p, q)]
+    }
+
+    /// Returns the two-electron integral (pq|rs).
+    pub fn two_body(&self, p: usize, q: usize, r: usize, s: usize) -> f64 {
+        let n = self.n_orbitals;
+        self.two_body[((p * n + q) * n + r) * n + s]
+    }
+
+    /// Sets the constant energy E_core.
+    pub fn set_core_energy(&mut self, value: f64) {
+        self.core_energy = value;
+    }
+
+    /// Sets h_pq and h_qp to `value`.
+    pub fn set_one_body(&mut self, p: usize, q: usize, value: f64) {
+        self.one_body[(p, q)] = value;
+        self.one_body[(q, p)] = value;
+    }
+
+    /// Sets (pq|rs) and the seven integrals equal to it by the permutational symmetry of real orbitals to `value`.
+    pub fn set_two_body(&mut self, p: usize, q: usize, r: usize, s: usize, value: f64) {
+        let n = self.n_orbitals;
+        for (a, b, c, d) in [(p, q, r, s), (q, p, r, s), (p, q, s, r), (q, p, s, r)] {
+            self.two_body[((a * n + b) * n + c) * n + d] = value;
+            self.two_body[((c * n + d) * n + a) * n + b] = value;
+        }
+    }
+
+    /// Returns the second quantized Hamiltonian of these integrals, including E_core as a constant term.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (spatial orbital, spin) onto single particle states.
+    pub fn hamiltonian(&self, convention: SpinConvention) -> Operator {
+        let n = self.n_orbitals;
+        let spins = [Spin::Up, Spin::Down];
+        let o = |p: usize, spin: Spin| convention.orbital(p as u64, spin);
+        let mut terms = Vec::new();
+        if self.core_energy != 0.0 {
+            terms.push((self.core_energy, Vec::new()));
+        }
+        for p in 0..n {
+            for q in 0..n {
+                let h = self.one_body(p, q);
+                if h.abs() > INTEGRAL_CUTOFF {
+                    for spin in spins {
+                        terms.push((h, vec![AC::Create(o(p, spin)), AC::Annihilate(o(q, spin))]));
+                    }
+                }
+            }
+        }
+        for p in 0..n {
+            for q in 0..n {
+                for r in 0..n {
+                    for s in 0..n {
+                        let v = self.two_body(p, q, r, s);
+                        if v.abs() <= INTEGRAL_CUTOFF {
+                            continue;
+                        }
+                        for sigma in spins {
+                            for tau in spins {
+                                terms.push((
+                                    0.5 * v,
+                                    vec![
+                                        AC::Create(o(p, sigma)),
+                                        AC::Create(o(r, tau)),
+                                        AC::Annihilate(o(s, tau)),
+                                        AC::Annihilate(o(q, sigma)),
+                                    ],
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Operator::new(terms)
+    }
+}
+
+/// Represents a complete active space: doubly occupied frozen core orbitals, active orbitals and discarded virtual orbitals.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveSpace {
+    /// The frozen core orbitals, doubly occupied in every determinant.
+    pub frozen: Vec<usize>,
+    /// The active orbitals, numbered 0, 1, ... in this order in the reduced integrals.
+    pub active: Vec<usize>,
+    /// The virtual orbitals, empty in every determinant.
+    pub virtuals: Vec<usize>,
+}
+
+impl ActiveSpace {
+    /// Returns the active space with the given frozen, active and virtual orbitals.
+    pub fn new(frozen: Vec<usize>, active: Vec<usize>, virtuals: Vec<usize>) -> ActiveSpace {
+        ActiveSpace {
+            frozen,
+            active,
+            virtuals,
+        }
+    }
+
+    /// Returns the integrals of the active orbitals, with the mean-field contribution of the frozen core folded into them:
+    /// E_core' = E_core + Σ_c 2 h_cc + Σ_cd [2 (cc|dd) - (cd|dc)] and h'_pq = h_pq + Σ_c [2 (pq|cc) - (pc|cq)].
+    ///
+    /// # Arguments
+    ///
+    /// * `integrals` - The integrals of all orbitals.
+    ///
+    /// # Errors
+    ///
+    /// * If an orbital is out of range or appears more than once in the active space, this function returns an Error.
+    pub fn reduce(&self, integrals: &MolecularIntegrals) -> Result<MolecularIntegrals, Error> {
+        let mut all: Vec<usize> = self.frozen.iter().chain(&self.active).chain(&self.virtuals).copied().collect();
+        all.sort_unstable();
+        let n_all = all.len();
+        all.dedup();
+        if all.len() != n_all || all.last().is_some_and(|o| *o >= integrals.n_orbitals) {
+            return Err(Error::Invalid("The orbitals of the active space must be distinct and exist!"));
+        }
+        let mut reduced = MolecularIntegrals::zeros(self.active.len());
+        let mut core = integrals.core_energy;
+        for c in &self.frozen {
+            core += 2.0 * integrals.one_body(*c, *c);
+            for d in &self.frozen {
+                core += 2.0 * integrals.two_body(*c, *c, *d, *d) - integrals.two_body(*c, *d, *d, *c);
+            }
+        }
+        reduced.core_energy = core;
+        for (i, p) in self.active.iter().enumerate() {
+            for (j, q) in self.active.iter().enumerate() {
+                let folded: f64 = self
+                    .frozen
+                    .iter()
+                    .map(|c| 2.0 * integrals.two_body(*p, *q, *c, *c) - integrals.two_body(*p, *c, *c, *q))
+                    .sum();
+                reduced.one_body[(i, j)] = integrals.one_body(*p, *q) + folded;
+                for (k, r) in self.active.iter().enumerate() {
+                    for (l, s) in self.active.iter().enumerate() {
+                        let n = reduced.n_orbitals;
+                        reduced.two_body[((i * n + j) * n + k) * n + l] = integrals.two_body(*p, *q, *r, *s);
+                    }
+                }
+            }
+        }
+        Ok(reduced)
+    }
+
+    /// Returns the basis of all determinants with `n_up` and `n_down` active electrons in the active orbitals.
+    pub fn basis(&self, convention: SpinConvention, n_up: u64, n_down: u64) -> Basis {
+        Basis::fixed_spin_numbers(convention, self.active.len() as u64, n_up, n_down)
+    }
+
+    /// Returns the CASCI ground state energy, including the frozen core, and the ground state in `ActiveSpace::basis`.
+    ///
+    /// # Arguments
+    ///
+    /// * `integrals` - The integrals of all orbitals.
+    /// * `convention` - The mapping of (active orbital, spin) onto single particle states.
+    /// * `n_up` - The number of active spin up electrons.
+    /// * `n_down` - The number of active spin down electrons.
+    ///
+    /// # Errors
+    ///
+    /// * If the active space does not fit `integrals` or Lanczos does not converge, this function returns an Error.
+    /// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+    pub fn casci(
+        &self,
+        integrals: &MolecularIntegrals,
+        convention: SpinConvention,
+        n_up: u64,
+        n_down: u64,
+    ) -> Result<(f64, Vec<f64>), Error> {
+        let h = self.basis(convention, n_up, n_down).try_sparse_matrix(&self.reduce(integrals)?.hamiltonian(convention))?;
+        let start = Rng::new(0xca5c1).normal_vector(h.dimension());
+        ground_state(&h, &start, h.dimension(), CASCI_TOLERANCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectrum::Spectrum;
+    use crate::Slater;
+
+    /// Returns integrals of `n` orbitals with (pq|rs) = Σ_k A^k_pq A^k_rs for random symmetric A^k, which have the symmetries of real orbitals.
+    fn random_integrals(n: usize, seed: u64) -> MolecularIntegrals {
+        let mut rng = Rng::new(seed);
+        let mut integrals = MolecularIntegrals::zeros(n);
+        integrals.set_core_energy(rng.normal());
+        for p in 0..n {
+            for q in 0..=p {
+                integrals.set_one_body(p, q, rng.normal());
+            }
+        }
+        let factors: Vec<Matrix> = (0..2)
+            .map(|_| {
+                let mut a = Matrix::zeros(n, n);
+                for p in 0..n {
+                    for q in 0..=p {
+                        let x = 0.5 * rng.normal();
+                        a[(p, q)] = x;
+                        a[(q, p)] = x;
+                    }
+                }
+                a
+            })
+            .collect();
+        for p in 0..n {
+            for q in 0..n {
+                for r in 0..n {
+                    for s in 0..n {
+                        let v: f64 = factors.iter().map(|a| a[(p, q)] * a[(r, s)]).sum();
+                        integrals.set_two_body(p, q, r, s, v);
+                    }
+                }
+            }
+        }
+        integrals
+    }
+
+    #[test]
+    fn test_frozen_core() {
+        // Freezing orbital 0 is exact within the determinants where it is doubly occupied.
+        let conv = SpinConvention::Interleaved;
+        let integrals = random_integrals(4, 5);
+        let space = ActiveSpace::new(vec![0], vec![1, 2], vec![3]);
+        let (e_cas, _) = space.casci(&integrals, conv, 1, 1).unwrap();
+        let core = (1 << conv.orbital(0, Spin::Up)) | (1 << conv.orbital(0, Spin::Down));
+        let states = Basis::fixed_spin_numbers(conv, 3, 2, 2)
+            .states()
+            .iter()
+            .filter(|s| s.index & core == core)
+            .copied()
+            .collect::<Vec<Slater>>();
+        assert_eq!(states.len(), 4);
+        let reference = Spectrum::new(&integrals.hamiltonian(conv), Basis::new(states)).ground_state_energy();
+        assert!((e_cas - reference).abs() < 1e-10);
+        assert!(ActiveSpace::new(vec![0], vec![0, 1], Vec::new()).reduce(&integrals).is_err());
+    }
+}
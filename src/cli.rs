@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use crate::bench::run_benchmarks;
+use crate::config::{self, Config};
+use crate::dynamics::loschmidt;
+use crate::error::Error;
+use crate::input::{parse_specification, Solver, Specification, DEFAULT_MAX_STEPS, DEFAULT_TOLERANCE};
+use crate::io::{write_npz_file, NpyArray};
+use crate::log::{self, Format, Level};
+use crate::repl::Repl;
+use crate::table::ResultTable;
+use crate::thermo::observables;
+
+/// The usage message of the command line interface.
+pub const USAGE: &str = "Usage: rust_ed <command> <model.json> [options]
+       rust_ed bench [--max-dimension <n>] [--output <path>]
+       rust_ed repl
+
+Commands:
+    groundstate    Measure the observables of the model file in its ground state
+    spectrum       Write all energies of the sector
+    dynamics       Write the Loschmidt echo of the ground state after a quench
+    thermo         Write the thermodynamics of the sector
+    bench          Time the standard problems with at most --max-dimension states, default 100000
+    repl           Define operators and states and apply them interactively
+
+Options:
+    --output <path>       Write the results to <path>, as .npz if it ends in .npz and as CSV otherwise; default standard output
+    --solver <name>       Override the solver of the model file: full, lanczos or out_of_core
+    --max-steps <n>       Override the maximal number of Lanczos steps
+    --tolerance <x>       Override the Lanczos residual
+    --memory-budget-gb <x> Refuse runs projected to need more memory than this
+    --threads <n>         The number of threads, default all available
+    --scratch <dir>       The directory of the vectors of the out_of_core solver, default the temporary directory
+    --log <level>         Log to standard error at error, warn, info, debug or trace level
+    --log-format <name>   The format of the log: text, default, or json
+    --checkpoint <path>   groundstate, dynamics: record the Lanczos run in <path> every 20 steps and resume from it if it exists
+    --quench <model.json> dynamics: the model evolving the state, in the same sector (required)
+    --t-max <t>           dynamics: the final time, default 10
+    --n-times <n>         dynamics: the number of times, default 101
+    --krylov <n>          dynamics: the number of Lanczos steps, default 100
+    --beta-max <b>        thermo: the largest inverse temperature, default 10
+    --n-beta <n>          thermo: the number of inverse temperatures, default 100";
+
+/// The options accepted by every command, and by the individual commands.
+const COMMON_OPTIONS: [&str; 9] =
+    ["output", "solver", "max-steps", "tolerance", "memory-budget-gb", "threads", "scratch", "log", "log-format"];
+const GROUNDSTATE_OPTIONS: [&str; 1] = ["checkpoint"];
+const DYNAMICS_OPTIONS: [&str; 5] = ["quench", "t-max", "n-times", "krylov", "checkpoint"];
+const THERMO_OPTIONS: [&str; 2] = ["beta-max", "n-beta"];
+const BENCH_OPTIONS: [&str; 4] = ["output", "max-dimension", "log", "log-format"];
+
+/// Represents the parsed command line: a command, a model file and options.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arguments {
+    /// The command, e.g. "groundstate".
+    pub command: String,
+    /// The path of the model specification, which every command but bench needs.
+    pub model: Option<String>,
+    /// The options, without their leading "--".
+    pub options: HashMap<String, String>,
+}
+
+impl Arguments {
+    /// Returns the command line `args`, without the program name, as a command, a model file and "--name value" options.
+    ///
+    /// # Errors
+    ///
+    /// * If the command or the model file is missing, an option has no value or does not belong to the command, this function returns an Error.
+    pub fn parse(args: &[String]) -> Result<Arguments, Error> {
+        let mut args = args.iter();
+        let command = args.next().ok_or(Error::Parse("No command given!"))?.clone();
+        let allowed: Vec<&str> = match command.as_str() {
+            "groundstate" => COMMON_OPTIONS.iter().chain(&GROUNDSTATE_OPTIONS).copied().collect(),
+            "spectrum" => COMMON_OPTIONS.to_vec(),
+            "dynamics" => COMMON_OPTIONS.iter().chain(&DYNAMICS_OPTIONS).copied().collect(),
+            "thermo" => COMMON_OPTIONS.iter().chain(&THERMO_OPTIONS).copied().collect(),
+            "bench" => BENCH_OPTIONS.to_vec(),
+            _ => return Err(Error::Parse("Unknown command!")),
+        };
+        let mut model = None;
+        let mut options = HashMap::new();
+        while let Some(arg) = args.next() {
+            match arg.strip_prefix("--") {
+                Some(name) => {
+                    if !allowed.contains(&name) {
+                        return Err(Error::Parse("Unknown option for this command!"));
+                    }
+                    let value = args.next().ok_or(Error::Parse("Option without a value!"))?;
+                    options.insert(name.to_string(), value.clone());
+                }
+                None if model.is_none() => model = Some(arg.clone()),
+                None => return Err(Error::Parse("More than one model file given!")),
+            }
+        }
+        match (command.as_str(), &model) {
+            ("bench", Some(_)) => return Err(Error::Parse("bench takes no model file!")),
+            ("bench", None) => {}
+            (_, None) => return Err(Error::Parse("No model file given!")),
+            _ => {}
+        }
+        Ok(Arguments { command, model, options })
+    }
+
+    /// Returns the option `name` parsed as a `T`, or `default` if it is not given.
+    fn option<T: std::str::FromStr>(&self, name: &str, default: T) -> Result<T, Error> {
+        match self.options.get(name) {
+            None => Ok(default),
+            Some(value) => value.parse().map_err(|_| Error::Parse("Invalid option value!")),
+        }
+    }
+}
+
+/// Returns the specification in the file `path`, with the solver and the memory budget overridden by the options of `arguments`.
+fn specification(path: &str, arguments: &Arguments) -> Result<Specification, Error> {
+    let text = std::fs::read_to_string(path)?;
+    let mut spec = parse_specification(&text)?;
+    let (max_steps, tolerance) = match spec.solver {
+        Solver::Lanczos { max_steps, tolerance } | Solver::OutOfCore { max_steps, tolerance } => (max_steps, tolerance),
+        Solver::Full => (DEFAULT_MAX_STEPS, DEFAULT_TOLERANCE),
+    };
+    let max_steps = arguments.option("max-steps", max_steps)?;
+    let tolerance = arguments.option("tolerance", tolerance)?;
+    spec.solver = match arguments.options.get("solver").map(|s| s.as_str()) {
+        Some("full") => Solver::Full,
+        Some("lanczos") => Solver::Lanczos { max_steps, tolerance },
+        Some("out_of_core") => Solver::OutOfCore { max_steps, tolerance },
+        Some(_) => return Err(Error::Parse("The solver must be full, lanczos or out_of_core!")),
+        None => match spec.solver {
+            Solver::Full => Solver::Full,
+            Solver::Lanczos { .. } => Solver::Lanczos { max_steps, tolerance },
+            Solver::OutOfCore { .. } => Solver::OutOfCore { max_steps, tolerance },
+        },
+    };
+    if let Some(gb) = arguments.options.get("memory-budget-gb") {
+        spec.memory_budget = Some((gb.parse::<f64>().map_err(|_| Error::Parse("Invalid option value!"))? * 1e9) as u64);
+    }
+    Ok(spec)
+}
+
+/// Returns the results table of the command in `arguments`.
+///
+/// # Errors
+///
+/// * If the model files are invalid or a calculation fails, this function returns an Error.
+pub fn execute(arguments: &Arguments) -> Result<ResultTable, Error> {
+    if arguments.command == "bench" {
+        let mut table = run_benchmarks(arguments.option("max-dimension", 100_000)?)?;
+        table.set_metadata("command", "bench");
+        return Ok(table);
+    }
+    let model = arguments.model.as_deref().ok_or(Error::Parse("No model file given!"))?;
+    let spec = specification(model, arguments)?;
+    let mut table = match arguments.command.as_str() {
+        "groundstate" => {
+            let results = match arguments.options.get("checkpoint") {
+                Some(path) => spec.run_checkpointed(Path::new(path))?,
+                None => spec.run()?,
+            };
+            let names: Vec<&str> = results.iter().map(|(n, _)| n.as_str()).collect();
+            let mut table = ResultTable::new(&names);
+            table.push(results.iter().map(|(_, x)| *x).collect())?;
+            table
+        }
+        "spectrum" => {
+            let spectrum = spec.spectrum()?;
+            let mut table = ResultTable::new(&["index", "energy"]);
+            for (n, e) in spectrum.energies().iter().enumerate() {
+                table.push(vec![n as f64, *e])?;
+            }
+            table
+        }
+        "dynamics" => {
+            let quench = specification(arguments.options.get("quench").ok_or(Error::Parse("dynamics needs a --quench model file!"))?, arguments)?;
+            let (_, state) = match arguments.options.get("checkpoint") {
+                Some(path) => spec.ground_state_checkpointed(Path::new(path))?,
+                None => spec.ground_state()?,
+            };
+            let basis = quench.basis()?;
+            let psi0 = basis.vector(&state);
+            let t_max: f64 = arguments.option("t-max", 10.0)?;
+            let n_times: usize = arguments.option("n-times", 101)?;
+            let times: Vec<f64> = (0..n_times).map(|k| t_max * k as f64 / (n_times.max(2) - 1) as f64).collect();
+            let h = basis.try_sparse_matrix(&quench.hamiltonian())?;
+            let mut table = ResultTable::from(&loschmidt(&psi0, &h, &times, arguments.option("krylov", 100)?));
+            table.set_metadata("quench", &arguments.options["quench"]);
+            table
+        }
+        "thermo" => {
+            let spectrum = spec.spectrum()?;
+            let beta_max: f64 = arguments.option("beta-max", 10.0)?;
+            let n_beta: usize = arguments.option("n-beta", 100)?;
+            let betas: Vec<f64> = (1..=n_beta).map(|k| beta_max * k as f64 / n_beta as f64).collect();
+            ResultTable::from(&observables(spectrum.energies(), &betas))
+        }
+        _ => return Err(Error::Parse("Unknown command!")),
+    };
+    table.set_metadata("command", &arguments.command);
+    table.set_metadata("model", model);
+    Ok(table)
+}
+
+/// Runs the command line `args`, without the program name, and writes the results to the output given or standard output.
+///
+/// # Errors
+///
+/// * If the arguments are invalid, the command fails or the results cannot be written, this function returns an Error.
+pub fn run(args: &[String]) -> Result<(), Error> {
+    if args.first().map(|a| a.as_str()) == Some("repl") {
+        return Ok(Repl::new().run(io::stdin().lock(), io::stdout())?);
+    }
+    let arguments = Arguments::parse(args)?;
+    if let Some(level) = arguments.options.get("log") {
+        let level = match level.as_str() {
+            "error" => Level::Error,
+            "warn" => Level::Warn,
+            "info" => Level::Info,
+            "debug" => Level::Debug,
+            "trace" => Level::Trace,
+            _ => return Err(Error::Parse("The log level must be error, warn, info, debug or trace!")),
+        };
+        let format = match arguments.options.get("log-format").map(|f| f.as_str()) {
+            None | Some("text") => Format::Text,
+            Some("json") => Format::Json,
+            Some(_) => return Err(Error::Parse("The log format must be text or json!")),
+        };
+        log::init_stderr(level, format);
+    }
+    let mut config = Config::default();
+    if let Some(n) = arguments.options.get("threads") {
+        config = config.with_threads(n.parse().map_err(|_| Error::Parse("Invalid option value!"))?);
+    }
+    if let Some(dir) = arguments.options.get("scratch") {
+        config = config.with_scratch_directory(Path::new(dir));
+    }
+    let table = config::scoped(config, || execute(&arguments))?;
+    let written = match arguments.options.get("output") {
+        None => table.write_csv(io::stdout().lock()),
+        Some(path) if path.ends_with(".npz") => write_npz_file(path, &NpyArray::from_table(&table)),
+        Some(path) => File::create(path).and_then(|f| table.write_csv(io::BufWriter::new(f))),
+    };
+    Ok(written?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_groundstate_command() {
+        let dir = std::env::temp_dir();
+        let model = dir.join(format!("rust_ed_cli_{}.json", std::process::id()));
+        let output = dir.join(format!("rust_ed_cli_{}.csv", std::process::id()));
+        fs::write(
+            &model,
+            r#"{"lattice": {"type": "chain", "size": [2]}, "model": {"type": "hubbard", "u": 4.0},
+                "sector": {"n_up": 1, "n_down": 1}, "observables": ["energy", "double_occupancy"]}"#,
+        )
+        .unwrap();
+        let args: Vec<String> = ["groundstate", model.to_str().unwrap(), "--solver", "full", "--output", output.to_str().unwrap()]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        run(&args).unwrap();
+        let table = ResultTable::from_csv(&fs::read_to_string(&output).unwrap()).unwrap();
+        assert_eq!(table.metadata("command"), Some("groundstate"));
+        assert!((table.column("energy").unwrap()[0] - (4.0 - 32f64.sqrt()) / 2.0).abs() < 1e-10);
+        let thermo = Arguments::parse(&["thermo".to_string(), model.to_str().unwrap().to_string(), "--n-beta".to_string(), "3".to_string()]);
+        assert_eq!(execute(&thermo.unwrap()).unwrap().rows().len(), 3);
+        fs::remove_file(&model).unwrap();
+        fs::remove_file(&output).unwrap();
+        assert!(Arguments::parse(&["spectrum".to_string(), "m.json".to_string(), "--t-max".to_string(), "1".to_string()]).is_err());
+        assert!(matches!(Arguments::parse(&["groundstate".to_string()]), Err(Error::Parse("No model file given!"))));
+        let missing = Arguments::parse(&["spectrum".to_string(), "/nonexistent/m.json".to_string()]).unwrap();
+        assert!(matches!(execute(&missing), Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+}
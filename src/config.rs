@@ -0,0 +1,200 @@
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::basis::MATVEC_CHUNK;
+use crate::error::Error;
+use crate::log::{self, Level};
+
+/// Represents the resources the solvers may use: threads, scratch space and the work done at a time.
+/// The installed configuration applies to the whole process, see `install`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The number of threads of requests for 0 threads, where 0 selects the available parallelism.
+    n_threads: usize,
+    /// The number of rows of a matrix-free product computed together.
+    chunk_size: usize,
+    /// The directory of the vectors of the out-of-core solver, the temporary directory if None.
+    scratch_directory: Option<PathBuf>,
+    /// The memory budget of the runs without their own, in bytes.
+    memory_budget: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            n_threads: 0,
+            chunk_size: MATVEC_CHUNK,
+            scratch_directory: None,
+            memory_budget: None,
+        }
+    }
+}
+
+impl Config {
+    /// Returns this configuration using `n_threads` threads wherever the number of threads is left at 0,
+    /// where 0 selects the available parallelism.
+    pub fn with_threads(mut self, n_threads: usize) -> Self {
+        self.n_threads = n_threads;
+        self
+    }
+
+    /// Returns this configuration computing matrix-free products in chunks of `chunk_size` rows.
+    ///
+    /// # Panics
+    ///
+    /// * If `chunk_size` is zero.
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        assert!(chunk_size > 0, "The chunk size must be positive!");
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Returns this configuration keeping the vectors of the out-of-core solver in `directory`.
+    pub fn with_scratch_directory(mut self, directory: &Path) -> Self {
+        self.scratch_directory = Some(directory.to_path_buf());
+        self
+    }
+
+    /// Returns this configuration refusing runs projected to need more than `bytes`, unless the run has a budget of its own.
+    pub fn with_memory_budget(mut self, bytes: u64) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+
+    /// Returns the number of threads to use for a request of `n_threads`, where 0 selects the configured number.
+    pub fn threads(&self, n_threads: usize) -> usize {
+        match (n_threads, self.n_threads) {
+            (0, 0) => std::thread::available_parallelism().map_or(1, |n| n.get()),
+            (0, n) | (n, _) => n,
+        }
+    }
+
+    /// Returns the number of rows of a matrix-free product computed together.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the directory of the vectors of the out-of-core solver.
+    pub fn scratch_directory(&self) -> PathBuf {
+        self.scratch_directory.clone().unwrap_or_else(env::temp_dir)
+    }
+
+    /// Returns the memory budget of the runs without their own, in bytes.
+    pub fn memory_budget(&self) -> Option<u64> {
+        self.memory_budget
+    }
+}
+
+static CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+
+thread_local! {
+    /// The configuration of the innermost `scoped` call on this thread.
+    static SCOPED: RefCell<Option<Config>> = const { RefCell::new(None) };
+}
+
+/// Installs `config` for the whole process, replacing an earlier configuration.
+/// Until a configuration is installed, the default one applies.
+pub fn install(config: Config) {
+    *CONFIG.lock().unwrap() = Some(config);
+}
+
+/// Returns the configuration of the calling thread: that of `scoped` while it runs, otherwise the installed one.
+pub fn current() -> Config {
+    SCOPED
+        .with(|scoped| scoped.borrow().clone())
+        .unwrap_or_else(|| CONFIG.lock().unwrap().clone().unwrap_or_default())
+}
+
+/// Returns f() evaluated with `config` in place of the installed configuration on the calling thread, e.g. for a run with
+/// a memory budget of its own. Threads spawned by `f` see the installed configuration, except the workers of the crate,
+/// which run with the configuration of the thread that spawned them.
+pub fn scoped<R, F: FnOnce() -> R>(config: Config, f: F) -> R {
+    /// Puts the configuration of the enclosing scope back, also when `f` panics.
+    struct Restore(Option<Config>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|scoped| *scoped.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(Some(config))));
+    f()
+}
+
+/// Returns the memory available to new allocations on this host from /proc/meminfo, on Linux.
+pub fn available_memory() -> Option<u64> {
+    let info = fs::read_to_string("/proc/meminfo").ok()?;
+    let line = info.lines().find(|l| l.starts_with("MemAvailable:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(1024 * kib)
+}
+
+/// Returns an error if a calculation projected to need `bytes` exceeds the memory budget of the current configuration,
+/// and warns if it exceeds the memory available on the host.
+///
+/// # Arguments
+///
+/// * `target` - The name of the calculation in the log.
+/// * `bytes` - The projected memory, in bytes.
+///
+/// # Errors
+///
+/// * If `bytes` exceed the memory budget, this function returns `Error::MemoryBudget`.
+pub fn check_memory(target: &str, bytes: u64) -> Result<(), Error> {
+    check_memory_against(target, bytes, current().memory_budget(), available_memory()).map(|_| ())
+}
+
+/// Checks `bytes` against `budget` and `available` like `check_memory`, and returns whether it warned.
+fn check_memory_against(target: &str, bytes: u64, budget: Option<u64>, available: Option<u64>) -> Result<bool, Error> {
+    if let Some(budget) = budget.filter(|budget| bytes > *budget) {
+        log::event(Level::Error, target, "over budget", &[("projected_bytes", bytes as f64)]);
+        return Err(Error::MemoryBudget { projected: bytes, budget });
+    }
+    let warn = available.is_some_and(|available| bytes > available);
+    if warn {
+        log::event(Level::Warn, target, "projected memory exceeds the available memory", &[("projected_bytes", bytes as f64)]);
+    }
+    Ok(warn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = Config::default().with_threads(3).with_chunk_size(17).with_scratch_directory(Path::new("/scratch"));
+        assert_eq!(config.threads(0), 3);
+        assert_eq!(config.threads(2), 2);
+        assert_eq!(config.chunk_size(), 17);
+        assert_eq!(config.scratch_directory(), Path::new("/scratch"));
+        assert_eq!(config.memory_budget(), None);
+        assert!(Config::default().threads(0) >= 1);
+        assert_eq!(Config::default().scratch_directory(), env::temp_dir());
+    }
+
+    #[test]
+    fn test_scoped() {
+        let budget = current().memory_budget();
+        let inner = scoped(current().with_memory_budget(1000), || {
+            assert!(check_memory("test_scoped", 1000).is_ok());
+            assert!(matches!(check_memory("test_scoped", 1001), Err(Error::MemoryBudget { projected: 1001, budget: 1000 })));
+            scoped(current().with_chunk_size(3), || current().chunk_size())
+        });
+        assert_eq!(inner, 3);
+        assert_eq!(current().memory_budget(), budget);
+    }
+
+    #[test]
+    fn test_check_memory() {
+        assert!(matches!(check_memory_against("test_check_memory", 10, Some(20), Some(5)), Ok(true)));
+        assert!(matches!(check_memory_against("test_check_memory", 10, None, Some(50)), Ok(false)));
+        assert!(matches!(check_memory_against("test_check_memory", 10, None, None), Ok(false)));
+        let over = check_memory_against("test_check_memory", 30, Some(20), None);
+        assert!(matches!(over, Err(Error::MemoryBudget { projected: 30, budget: 20 })));
+    }
+}
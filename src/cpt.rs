@@ -0,0 +1,160 @@
+use std::f64::consts::PI;
+
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::lattice::Lattice;
+use crate::linalg::ComplexMatrix;
+use crate::spectral::GreensFunctionMatrix;
+
+/// This represents a hopping T_ij(R) c†_i c_j(R) from orbital j of the cluster displaced by the superlattice vector R to orbital i.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InterClusterHopping {
+    /// The orbital i of the cluster at the origin, a position in the orbitals of the cluster Green's function.
+    pub i: usize,
+    /// The orbital j of the displaced cluster.
+    pub j: usize,
+    /// The superlattice vector R.
+    pub shift: [f64; 2],
+    /// The amplitude T_ij(R).
+    pub amplitude: f64,
+}
+
+/// Represents the tiling of a lattice by copies of a cluster, for cluster perturbation theory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cpt {
+    /// The position r_i of each orbital within the cluster.
+    positions: Vec<[f64; 2]>,
+    /// The hoppings between clusters, each direction listed separately.
+    hoppings: Vec<InterClusterHopping>,
+}
+
+impl Cpt {
+    /// Returns the tiling of a cluster with orbitals at `positions` connected by `hoppings`.
+    /// Every hopping must be accompanied by its Hermitian conjugate T_ji(-R).
+    pub fn new(positions: Vec<[f64; 2]>, hoppings: Vec<InterClusterHopping>) -> Cpt {
+        Cpt { positions, hoppings }
+    }
+
+    /// Returns the tiling of a lattice by a cluster with the sites of `lattice` and open boundaries,
+    /// where the nearest neighbour bonds of `lattice` crossing a periodic boundary connect neighbouring clusters with hopping -t.
+    /// The orbitals are the sites of the cluster, e.g. for spinless fermions or one spin species.
+    pub fn from_lattice(lattice: &Lattice, t: f64) -> Cpt {
+        let periods = lattice.periods();
+        let mut hoppings = Vec::new();
+        for b in lattice.nearest_neighbours().iter().filter(|b| b.winding != [0.0, 0.0]) {
+            let shift = [
+                b.winding[0] * periods[0][0] + b.winding[1] * periods[1][0],
+                b.winding[0] * periods[0][1] + b.winding[1] * periods[1][1],
+            ];
+            let (i, j) = (b.i as usize, b.j as usize);
+            hoppings.push(InterClusterHopping { i, j, shift, amplitude: -t });
+            hoppings.push(InterClusterHopping { i: j, j: i, shift: [-shift[0], -shift[1]], amplitude: -t });
+        }
+        Cpt::new(lattice.positions().to_vec(), hoppings)
+    }
+
+    /// Returns the Fourier transform V_ij(k) = Σ_R T_ij(R) e^{ik·R} of the inter-cluster hopping at the momentum `k`.
+    pub fn hopping_matrix(&self, k: [f64; 2]) -> ComplexMatrix {
+        let mut v = ComplexMatrix::zeros(self.positions.len());
+        for h in &self.hoppings {
+            v[(h.i, h.j)] += Complex::from_polar(h.amplitude, k[0] * h.shift[0] + k[1] * h.shift[1]);
+        }
+        v
+    }
+
+    /// Returns the lattice Green's function G(k, z) = (1/L) Σ_ij e^{-ik·(r_i - r_j)} [G'(z)^{-1} - V(k)]^{-1}_ij,
+    /// if the matrix is invertible.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - The cluster Green's function G'(z) at some frequency z.
+    /// * `k` - The lattice momentum.
+    pub fn lattice_greens_function(&self, cluster: &ComplexMatrix, k: [f64; 2]) -> Option<Complex> {
+        let n = self.positions.len();
+        let mut inverse = cluster.inverse()?;
+        let v = self.hopping_matrix(k);
+        for i in 0..n {
+            for j in 0..n {
+                inverse[(i, j)] -= v[(i, j)];
+            }
+        }
+        let g = inverse.inverse()?;
+        let mut sum = Complex::default();
+        for (i, ri) in self.positions.iter().enumerate() {
+            for (j, rj) in self.positions.iter().enumerate() {
+                let phase = -(k[0] * (ri[0] - rj[0]) + k[1] * (ri[1] - rj[1]));
+                sum += Complex::from_polar(1.0, phase) * g[(i, j)];
+            }
+        }
+        Some(sum / n as f64)
+    }
+
+    /// Returns the lattice spectral function A(k, ω) = -Im G(k, ω + iη) / π, indexed by momentum and then frequency.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - The cluster Green's function, e.g. from `spectral::greens_function_matrix`, with the orbitals of this tiling.
+    /// * `momenta` - The lattice momenta k.
+    /// * `omegas` - The real frequencies ω.
+    /// * `eta` - The broadening η > 0.
+    ///
+    /// # Errors
+    ///
+    /// * If the number of orbitals of `cluster` does not match the tiling, or a matrix is singular, this function returns an Error.
+    pub fn spectral_function(
+        &self,
+        cluster: &GreensFunctionMatrix,
+        momenta: &[[f64; 2]],
+        omegas: &[f64],
+        eta: f64,
+    ) -> Result<Vec<Vec<f64>>, Error> {
+        if cluster.orbitals().len() != self.positions.len() {
+            return Err(Error::DimensionMismatch);
+        }
+        let clusters: Vec<ComplexMatrix> = omegas.iter().map(|w| cluster.evaluate(Complex::new(*w, eta))).collect();
+        momenta
+            .iter()
+            .map(|k| {
+                clusters
+                    .iter()
+                    .map(|g| {
+                        self.lattice_greens_function(g, *k)
+                            .map(|g| -g.im / PI)
+                            .ok_or(Error::Invalid("The CPT Green's function is singular!"))
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::lattice::Boundary;
+    use crate::spectral::greens_function_matrix;
+    use crate::spectrum::Spectrum;
+    use crate::Operator;
+
+    #[test]
+    fn test_noninteracting_chain() {
+        // Without interactions CPT is exact: tiling a two site cluster recovers the band ε_k = -2t cos k of the infinite chain.
+        let lattice = Lattice::chain(2, Boundary::Periodic);
+        let cpt = Cpt::from_lattice(&lattice, 1.0);
+        assert_eq!(cpt.hoppings.len(), 2);
+        let cluster = Lattice::chain(2, Boundary::Open).hopping_hamiltonian(1.0, &Operator::new(Vec::new())).real;
+        let g = greens_function_matrix(&Spectrum::new(&cluster, Basis::fock_space(2)), &[0, 1], f64::INFINITY);
+        let eta = 0.05;
+        let momenta: Vec<[f64; 2]> = (0..5).map(|m| [PI * m as f64 / 4.0, 0.0]).collect();
+        let omegas: Vec<f64> = (0..11).map(|n| -2.5 + 0.5 * n as f64).collect();
+        let a = cpt.spectral_function(&g, &momenta, &omegas, eta).unwrap();
+        for (k, row) in momenta.iter().zip(&a) {
+            let e = -2.0 * k[0].cos();
+            for (w, value) in omegas.iter().zip(row) {
+                let exact = eta / (PI * ((w - e).powi(2) + eta * eta));
+                assert!((value - exact).abs() < 1e-10);
+            }
+        }
+    }
+}
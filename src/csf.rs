@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+
+use crate::basis::Basis;
+use crate::linalg::{dot, LinearOperator, Matrix};
+use crate::operators::{total_spin_squared, Spin, SpinConvention};
+use crate::{Operator, Slater};
+
+/// Eigenvalues of S² within this distance of S(S + 1) belong to total spin S.
+const SPIN_TOLERANCE: f64 = 1e-8;
+
+/// Represents a basis of spin-adapted functions: orthonormal eigenfunctions of S² with a fixed total spin S,
+/// each a combination of the determinants sharing one spatial configuration.
+#[derive(Debug, Clone)]
+pub struct SpinAdaptedBasis {
+    /// The total spin S.
+    spin: f64,
+    /// The determinants the functions are combined from.
+    determinants: Basis,
+    /// The coefficients of each function, as (position in `determinants`, coefficient) pairs.
+    functions: Vec<Vec<(usize, f64)>>,
+}
+
+impl SpinAdaptedBasis {
+    /// Returns the spin-adapted functions with total spin `spin` in the (N↑, N↓) sector of `n_sites` spinful sites.
+    ///
+    /// S² only rearranges the spins of singly occupied sites, so the determinants are grouped by their doubly and singly occupied
+    /// sites and S² is diagonalized within each group; the functions are the eigenvectors with eigenvalue S(S + 1).
+    /// The dimension is reduced by a factor of about 2S + 1 per multiplet compared to the determinant sector, and every
+    /// eigenstate of a spin conserving Hamiltonian in this basis is a pure spin state.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (site, spin) onto single particle states.
+    /// * `n_sites` - The number of sites, or spatial orbitals.
+    /// * `n_up` - The number of spin up particles.
+    /// * `n_down` - The number of spin down particles.
+    /// * `spin` - The total spin S, at least |N↑ - N↓|/2.
+    pub fn new(convention: SpinConvention, n_sites: u64, n_up: u64, n_down: u64, spin: f64) -> SpinAdaptedBasis {
+        let determinants = Basis::fixed_spin_numbers(convention, n_sites, n_up, n_down);
+        let site_mask = |s: &Slater, spin: Spin| {
+            (0..n_sites)
+                .filter(|i| s.index & (1 << convention.orbital(*i, spin)) != 0)
+                .fold(0u64, |acc, i| acc | (1 << i))
+        };
+        let mut configurations: BTreeMap<(u64, u64), Vec<Slater>> = BTreeMap::new();
+        for s in determinants.states() {
+            let (up, down) = (site_mask(s, Spin::Up), site_mask(s, Spin::Down));
+            configurations.entry((up & down, up ^ down)).or_default().push(*s);
+        }
+        let s_squared = total_spin_squared(convention, n_sites);
+        let target = spin * (spin + 1.0);
+        let mut functions = Vec::new();
+        for group in configurations.into_values() {
+            let block = Basis::new(group);
+            let (values, vectors) = block.matrix(&s_squared).eigh();
+            for (k, value) in values.iter().enumerate() {
+                if (value - target).abs() < SPIN_TOLERANCE {
+                    functions.push(
+                        block
+                            .states()
+                            .iter()
+                            .enumerate()
+                            .map(|(i, s)| (determinants.index(s).unwrap(), vectors[(i, k)]))
+                            .filter(|(_, c)| c.abs() > SPIN_TOLERANCE)
+                            .collect(),
+                    );
+                }
+            }
+        }
+        SpinAdaptedBasis {
+            spin,
+            determinants,
+            functions,
+        }
+    }
+
+    /// Returns the total spin S.
+    pub fn spin(&self) -> f64 {
+        self.spin
+    }
+
+    /// Returns the number of spin-adapted functions.
+    pub fn dimension(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Returns the determinant basis the functions are combined from.
+    pub fn determinants(&self) -> &Basis {
+        &self.determinants
+    }
+
+    /// Returns the vector in the determinant basis of the state with coefficients `vector` in this basis.
+    pub fn to_determinants(&self, vector: &[f64]) -> Vec<f64> {
+        let mut res = vec![0.0; self.determinants.dimension()];
+        for (f, c) in self.functions.iter().zip(vector) {
+            for (i, x) in f {
+                res[*i] += c * x;
+            }
+        }
+        res
+    }
+
+    /// Returns the matrix of `op` between the spin-adapted functions. The operator must commute with S².
+    pub fn matrix(&self, op: &Operator) -> Matrix {
+        let h = self.determinants.sparse_matrix(op);
+        let n = self.dimension();
+        let columns: Vec<Vec<f64>> = (0..n)
+            .map(|j| {
+                let mut unit = vec![0.0; n];
+                unit[j] = 1.0;
+                let v = self.to_determinants(&unit);
+                let mut w = vec![0.0; v.len()];
+                h.apply(&v, &mut w);
+                w
+            })
+            .collect();
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            let mut unit = vec![0.0; n];
+            unit[i] = 1.0;
+            let v = self.to_determinants(&unit);
+            for (j, w) in columns.iter().enumerate() {
+                m[(i, j)] = dot(&v, w);
+            }
+        }
+        m
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hubbard, Graph};
+    use crate::spectrum::Spectrum;
+
+    #[test]
+    fn test_hubbard_ring() {
+        let conv = SpinConvention::Blocked { n_sites: 4 };
+        let ring = Graph::new(4, (0..4).map(|i| (i, (i + 1) % 4)).collect());
+        let h = hubbard(&ring, 1.0, 4.0, 0.0, conv);
+        let full = Spectrum::new(&h, Basis::fixed_spin_numbers(conv, 4, 2, 2));
+        // The 36 determinants with S^z = 0 hold 20 singlets, 15 triplets and one quintet.
+        let dimensions: Vec<usize> = (0..3).map(|s| SpinAdaptedBasis::new(conv, 4, 2, 2, s as f64).dimension()).collect();
+        assert_eq!(dimensions, vec![20, 15, 1]);
+        let singlets = SpinAdaptedBasis::new(conv, 4, 2, 2, 0.0);
+        let (energies, vectors) = singlets.matrix(&h).eigh();
+        assert!((energies[0] - full.ground_state_energy()).abs() < 1e-10);
+        // Every eigenstate is a pure singlet.
+        let s2 = singlets.matrix(&total_spin_squared(conv, 4));
+        for k in 0..energies.len() {
+            let v = vectors.column(k);
+            assert!(dot(&v, &s2.matvec(&v)).abs() < 1e-10);
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Vectors kept in files instead of memory, for the out-of-core Lanczos solver.
+//!
+//! The files are read and written with plain reads and writes, `CHUNK` elements at a time: std has no memory mapping, and the
+//! crate has no dependency that would add it. A stored vector can be streamed chunk by chunk with `DiskVectors::stream`, so that
+//! reorthogonalization and the assembly of the eigenvector hold no stored vector in memory. The matrix-vector product itself
+//! is not streamed: it reads and writes vectors held in memory, so a sector needs memory for two vectors of its dimension.
+use std::convert::TryInto;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The number of elements read or written at a time, so that only a bounded buffer of bytes is held besides the vectors.
+const CHUNK: usize = 1 << 16;
+
+/// The number of stores created by this process, to give each its own file.
+static STORES: AtomicUsize = AtomicUsize::new(0);
+
+/// Represents a list of vectors of equal length kept in a file instead of memory, e.g. the Krylov vectors of a
+/// Lanczos run whose sector is too large to hold them all.
+#[derive(Debug)]
+pub struct DiskVectors {
+    file: File,
+    path: PathBuf,
+    /// The length of each vector.
+    len: usize,
+    /// The number of vectors stored.
+    count: usize,
+    /// Whether the file is removed when the store is dropped.
+    temporary: bool,
+}
+
+impl DiskVectors {
+    /// Returns an empty store of vectors of length `len`, in a new file in `directory`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be created, this function returns the error.
+    pub fn create(directory: &Path, len: usize) -> io::Result<DiskVectors> {
+        let n = STORES.fetch_add(1, Ordering::Relaxed);
+        let path = directory.join(format!("rust_ed_{}_{}.vectors", process::id(), n));
+        let file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+        Ok(DiskVectors {
+            file,
+            path,
+            len,
+            count: 0,
+            temporary: true,
+        })
+    }
+
+    /// Returns the store of vectors of length `len` in the file `path`, with the vectors already in it, e.g. those of an
+    /// interrupted run. The file is created if it does not exist and kept when the store is dropped.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be opened, this function returns the error.
+    pub fn open(path: &Path, len: usize) -> io::Result<DiskVectors> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+        let count = (file.metadata()?.len() / (8 * len.max(1)) as u64) as usize;
+        Ok(DiskVectors {
+            file,
+            path: path.to_path_buf(),
+            len,
+            count,
+            temporary: false,
+        })
+    }
+
+    /// Removes the vectors after the first `count`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be shortened, this function returns the error.
+    pub fn truncate(&mut self, count: usize) -> io::Result<()> {
+        if count < self.count {
+            self.file.set_len((8 * self.len * count) as u64)?;
+            self.count = count;
+        }
+        Ok(())
+    }
+
+    /// Waits until the vectors are written to the disk.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be synchronized, this function returns the error.
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_data()
+    }
+
+    /// Returns the length of each vector.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the number of vectors stored.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns whether no vectors are stored.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Appends `vector` to the store.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be written, this function returns the error.
+    ///
+    /// # Panics
+    ///
+    /// * If the length of `vector` differs from the length of the store.
+    pub fn push(&mut self, vector: &[f64]) -> io::Result<()> {
+        assert_eq!(vector.len(), self.len, "Vector does not match the store!");
+        self.file.seek(SeekFrom::Start((8 * self.len * self.count) as u64))?;
+        let mut bytes = Vec::with_capacity(8 * CHUNK.min(self.len));
+        for block in vector.chunks(CHUNK) {
+            bytes.clear();
+            bytes.extend(block.iter().flat_map(|x| x.to_le_bytes()));
+            self.file.write_all(&bytes)?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Reads the vector `k` into `out`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read, this function returns the error.
+    ///
+    /// # Panics
+    ///
+    /// * If there is no vector `k` or the length of `out` differs from the length of the store.
+    pub fn read(&mut self, k: usize, out: &mut [f64]) -> io::Result<()> {
+        assert_eq!(out.len(), self.len, "Vector does not match the store!");
+        self.stream(k, |offset, block| out[offset..offset + block.len()].copy_from_slice(block))
+    }
+
+    /// Calls `f` with the offset and the elements of each chunk of vector `k` in turn, reading at most `CHUNK` elements at a time,
+    /// so that the vector is never held in memory as a whole.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read, this function returns the error.
+    ///
+    /// # Panics
+    ///
+    /// * If there is no vector `k`.
+    pub fn stream<F: FnMut(usize, &[f64])>(&mut self, k: usize, mut f: F) -> io::Result<()> {
+        assert!(k < self.count, "No such vector in the store!");
+        self.file.seek(SeekFrom::Start((8 * self.len * k) as u64))?;
+        let mut bytes = vec![0u8; 8 * CHUNK.min(self.len)];
+        let mut block = Vec::with_capacity(CHUNK.min(self.len));
+        for offset in (0..self.len).step_by(CHUNK) {
+            let bytes = &mut bytes[..8 * CHUNK.min(self.len - offset)];
+            self.file.read_exact(bytes)?;
+            block.clear();
+            block.extend(bytes.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap())));
+            f(offset, &block);
+        }
+        Ok(())
+    }
+}
+
+impl Drop for DiskVectors {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_vectors() {
+        let path;
+        {
+            let mut store = DiskVectors::create(&std::env::temp_dir(), CHUNK + 3).unwrap();
+            path = store.path.clone();
+            let vectors: Vec<Vec<f64>> = (0..3).map(|k| (0..CHUNK + 3).map(|i| (k * i) as f64 - 0.5).collect()).collect();
+            for v in &vectors {
+                store.push(v).unwrap();
+            }
+            assert_eq!(store.count(), 3);
+            let mut out = vec![0.0; CHUNK + 3];
+            store.read(1, &mut out).unwrap();
+            assert_eq!(out, vectors[1]);
+            store.read(2, &mut out).unwrap();
+            assert_eq!(out, vectors[2]);
+            // The vector is longer than one chunk, so it streams as a full chunk and the three elements after it.
+            let mut chunks = Vec::new();
+            store.stream(2, |offset, block| chunks.push((offset, block.to_vec()))).unwrap();
+            assert_eq!(chunks.iter().map(|(offset, block)| (*offset, block.len())).collect::<Vec<_>>(), vec![(0, CHUNK), (CHUNK, 3)]);
+            assert_eq!(chunks.into_iter().flat_map(|(_, block)| block).collect::<Vec<_>>(), vectors[2]);
+            assert!(path.exists());
+        }
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::models::Graph;
+use crate::random::Rng;
+use crate::sweep::parallel_map;
+
+/// The distribution random couplings are drawn from.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Distribution {
+    /// No disorder.
+    None,
+    /// Uniform on [-W, W].
+    Uniform(f64),
+    /// Normal with zero mean and standard deviation σ.
+    Normal(f64),
+}
+
+impl Distribution {
+    /// Returns a number drawn from this distribution.
+    pub fn sample(self, rng: &mut Rng) -> f64 {
+        match self {
+            Distribution::None => 0.0,
+            Distribution::Uniform(w) => w * (2.0 * rng.uniform() - 1.0),
+            Distribution::Normal(sigma) => sigma * rng.normal(),
+        }
+    }
+}
+
+/// Represents a disorder model: random on-site energies and random corrections to the bond strengths.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Disorder {
+    /// The distribution of the on-site energies.
+    pub onsite: Distribution,
+    /// The distribution of the bond corrections.
+    pub bonds: Distribution,
+}
+
+/// Represents a single disorder realization.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Realization {
+    /// The number of the realization within its ensemble.
+    pub index: usize,
+    /// The seed the realization was drawn with, which reproduces it.
+    pub seed: u64,
+    /// The on-site energies ε_i, indexed by site.
+    pub onsite: Vec<f64>,
+    /// The bond corrections δ_b, indexed by bond.
+    pub bonds: Vec<f64>,
+}
+
+impl Realization {
+    /// Returns the realization of `disorder` on `lattice` drawn with `seed`.
+    pub fn new(disorder: &Disorder, lattice: &Graph, index: usize, seed: u64) -> Realization {
+        let mut rng = Rng::new(seed);
+        let onsite = (0..lattice.n_sites).map(|_| disorder.onsite.sample(&mut rng)).collect();
+        let bonds = lattice.bonds.iter().map(|_| disorder.bonds.sample(&mut rng)).collect();
+        Realization {
+            index,
+            seed,
+            onsite,
+            bonds,
+        }
+    }
+
+    /// Returns the edges (i, j, t + δ_b) of `lattice` with the disordered bond strengths, e.g. for `models::tight_binding`.
+    pub fn edges(&self, lattice: &Graph, t: f64) -> Vec<(u64, u64, f64)> {
+        lattice
+            .bonds
+            .iter()
+            .zip(&self.bonds)
+            .map(|((i, j), d)| (*i, *j, t + d))
+            .collect()
+    }
+}
+
+/// Returns the seed of realization `index` of the ensemble with seed `seed`.
+fn realization_seed(seed: u64, index: usize) -> u64 {
+    seed ^ (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Represents the results of a calculation over an ensemble of disorder realizations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisorderAverage {
+    /// The seed of each realization, which reproduces it through `Realization::new`.
+    pub seeds: Vec<u64>,
+    /// The results of each realization.
+    pub samples: Vec<Vec<f64>>,
+    /// The mean of each result over the ensemble.
+    pub mean: Vec<f64>,
+    /// The standard error of each mean, s/√n with the sample standard deviation s.
+    pub error: Vec<f64>,
+}
+
+/// Returns the ensemble average of `calculation` over `n_realizations` realizations of `disorder` on `lattice`.
+///
+/// The realizations are distributed over `n_threads` threads, each taking the next unfinished realization when done,
+/// and every realization is drawn from its own seed, so the results do not depend on the number of threads.
+///
+/// # Arguments
+///
+/// * `disorder` - The disorder model.
+/// * `lattice` - The cluster the disorder lives on.
+/// * `n_realizations` - The size of the ensemble.
+/// * `seed` - The seed of the ensemble.
+/// * `n_threads` - The number of threads, where 0 selects the configured number, see `config::Config`.
+/// * `calculation` - The calculation, returning the same number of results for every realization.
+pub fn disorder_average<F>(
+    disorder: &Disorder,
+    lattice: &Graph,
+    n_realizations: usize,
+    seed: u64,
+    n_threads: usize,
+    calculation: F,
+) -> DisorderAverage
+where
+    F: Fn(&Realization) -> Vec<f64> + Sync,
+{
+    let samples = parallel_map(n_realizations, n_threads, |index| {
+        calculation(&Realization::new(disorder, lattice, index, realization_seed(seed, index)))
+    });
+    let n_values = samples.first().map_or(0, |s| s.len());
+    let n = samples.len() as f64;
+    let mean: Vec<f64> = (0..n_values).map(|k| samples.iter().map(|s| s[k]).sum::<f64>() / n).collect();
+    let error = (0..n_values)
+        .map(|k| {
+            if samples.len() < 2 {
+                return 0.0;
+            }
+            let var = samples.iter().map(|s| (s[k] - mean[k]).powi(2)).sum::<f64>() / (n - 1.0);
+            (var / n).sqrt()
+        })
+        .collect();
+    DisorderAverage {
+        seeds: (0..n_realizations).map(|i| realization_seed(seed, i)).collect(),
+        samples,
+        mean,
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::models::tight_binding;
+    use crate::spectrum::Spectrum;
+
+    #[test]
+    fn test_anderson_ensemble() {
+        let chain = Graph::new(4, vec![(0, 1), (1, 2), (2, 3)]);
+        let disorder = Disorder {
+            onsite: Distribution::Uniform(2.0),
+            bonds: Distribution::None,
+        };
+        let calculation = |r: &Realization| {
+            let h = tight_binding(&r.edges(&chain, -1.0), &r.onsite);
+            let spectrum = Spectrum::new(&h, Basis::fixed_particle_number(4, 1));
+            // The trace of the one particle Hamiltonian is the sum of the on-site energies.
+            vec![spectrum.energies().iter().sum::<f64>(), r.onsite.iter().sum::<f64>()]
+        };
+        let serial = disorder_average(&disorder, &chain, 40, 3, 1, calculation);
+        let parallel = disorder_average(&disorder, &chain, 40, 3, 4, calculation);
+        assert_eq!(serial, parallel);
+        assert!((serial.mean[0] - serial.mean[1]).abs() < 1e-12);
+        // The mean on-site energy vanishes within a few standard errors, and each realization is reproducible from its seed.
+        assert!(serial.mean[1].abs() < 4.0 * serial.error[1]);
+        let r = Realization::new(&disorder, &chain, 7, serial.seeds[7]);
+        assert!((r.onsite.iter().sum::<f64>() - serial.samples[7][1]).abs() < 1e-14);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::basis::Basis;
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::models::siam;
+use crate::operators::{Spin, SpinConvention};
+use crate::spectral::{matsubara_frequencies, matsubara_greens_function, Statistics};
+use crate::spectrum::Spectrum;
+use crate::{Operator, AC};
+
+/// The number of times the simplex of the bath fit is rebuilt around the best point found so far.
+const FIT_RESTARTS: usize = 4;
+
+/// Represents a discrete bath of levels ε_k coupled to the impurity by hybridizations V_k.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bath {
+    /// The bath levels ε_k.
+    pub energies: Vec<f64>,
+    /// The hybridizations V_k, one per bath level.
+    pub hybridizations: Vec<f64>,
+}
+
+impl Bath {
+    /// Returns the bath with levels `energies` and hybridizations `hybridizations`.
+    ///
+    /// # Panics
+    ///
+    /// * If the numbers of bath energies and hybridizations differ.
+    pub fn new(energies: Vec<f64>, hybridizations: Vec<f64>) -> Bath {
+        assert_eq!(energies.len(), hybridizations.len(), "Every bath level needs a hybridization!");
+        Bath {
+            energies,
+            hybridizations,
+        }
+    }
+
+    /// Returns the hybridization function Δ(iω_n) = Σ_k V_k² / (iω_n - ε_k) of the bath on the Matsubara frequencies ω_n.
+    pub fn hybridization(&self, frequencies: &[f64]) -> Vec<Complex> {
+        frequencies
+            .iter()
+            .map(|w| {
+                self.energies
+                    .iter()
+                    .zip(&self.hybridizations)
+                    .map(|(e, v)| (v * v) / (Complex::new(0.0, *w) - *e))
+                    .fold(Complex::default(), |acc, x| acc + x)
+            })
+            .collect()
+    }
+}
+
+/// Returns the point minimizing `f`, searched by the Nelder-Mead simplex method starting from `start` with initial steps `step`.
+fn minimize<F: Fn(&[f64]) -> f64>(f: F, start: &[f64], step: f64, n_iterations: usize) -> Vec<f64> {
+    let n = start.len();
+    let mut best = start.to_vec();
+    for _ in 0..FIT_RESTARTS {
+        let mut simplex: Vec<(f64, Vec<f64>)> = (0..=n)
+            .map(|k| {
+                let mut x = best.clone();
+                if k > 0 {
+                    x[k - 1] += step;
+                }
+                (f(&x), x)
+            })
+            .collect();
+        for _ in 0..n_iterations / FIT_RESTARTS {
+            simplex.sort_by(|a, b| a.0.total_cmp(&b.0));
+            let centroid: Vec<f64> = (0..n).map(|i| simplex[..n].iter().map(|(_, x)| x[i]).sum::<f64>() / n as f64).collect();
+            let towards = |t: f64| -> Vec<f64> {
+                centroid.iter().zip(&simplex[n].1).map(|(c, w)| c + t * (c - w)).collect()
+            };
+            let reflected = towards(1.0);
+            let fr = f(&reflected);
+            if fr < simplex[0].0 {
+                let expanded = towards(2.0);
+                let fe = f(&expanded);
+                simplex[n] = if fe < fr { (fe, expanded) } else { (fr, reflected) };
+            } else if fr < simplex[n - 1].0 {
+                simplex[n] = (fr, reflected);
+            } else {
+                let contracted = towards(-0.5);
+                let fc = f(&contracted);
+                if fc < simplex[n].0 {
+                    simplex[n] = (fc, contracted);
+                } else {
+                    let x0 = simplex[0].1.clone();
+                    for (fv, x) in simplex.iter_mut().skip(1) {
+                        for (xi, bi) in x.iter_mut().zip(&x0) {
+                            *xi = 0.5 * (*xi + bi);
+                        }
+                        *fv = f(x);
+                    }
+                }
+            }
+        }
+        simplex.sort_by(|a, b| a.0.total_cmp(&b.0));
+        best = simplex.swap_remove(0).1;
+    }
+    best
+}
+
+/// Returns the bath whose hybridization function best fits `delta`, and the misfit
+/// χ² = (1/N) Σ_n |Δ(iω_n) - Δ_bath(iω_n)|² / ω_n^p of the N Matsubara frequencies.
+///
+/// The bath levels and hybridizations are optimized by the Nelder-Mead simplex method, starting from `initial`.
+///
+/// # Arguments
+///
+/// * `delta` - The hybridization function Δ(iω_n) to fit.
+/// * `frequencies` - The Matsubara frequencies ω_n > 0 of `delta`.
+/// * `initial` - The starting bath, which also sets the number of bath levels.
+/// * `weight_exponent` - The exponent p of the weights, where p > 0 emphasizes the low frequencies.
+/// * `n_iterations` - The number of simplex steps.
+pub fn fit_bath(delta: &[Complex], frequencies: &[f64], initial: &Bath, weight_exponent: f64, n_iterations: usize) -> (Bath, f64) {
+    let n_bath = initial.energies.len();
+    let unpack = |x: &[f64]| Bath::new(x[..n_bath].to_vec(), x[n_bath..].to_vec());
+    let chi_squared = |bath: &Bath| {
+        bath.hybridization(frequencies)
+            .iter()
+            .zip(delta)
+            .zip(frequencies)
+            .map(|((fit, d), w)| (*fit - *d).norm_sqr() / w.powf(weight_exponent))
+            .sum::<f64>()
+            / frequencies.len() as f64
+    };
+    let start: Vec<f64> = initial.energies.iter().chain(&initial.hybridizations).copied().collect();
+    let bath = unpack(&minimize(|x| chi_squared(&unpack(x)), &start, 0.1, n_iterations));
+    let misfit = chi_squared(&bath);
+    (bath, misfit)
+}
+
+/// The parameters of the exact diagonalization impurity solver.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DmftParameters {
+    /// The impurity interaction U.
+    pub u: f64,
+    /// The chemical potential μ, which places the impurity level at ε_d = -μ.
+    pub mu: f64,
+    /// The inverse temperature β of the Matsubara grid.
+    pub beta: f64,
+    /// The exponent p of the weights 1/ω_n^p of the bath fit.
+    pub weight_exponent: f64,
+    /// The number of simplex steps of the bath fit.
+    pub fit_iterations: usize,
+}
+
+/// Represents the solution of the impurity problem of one DMFT iteration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImpuritySolution {
+    /// The fitted bath.
+    pub bath: Bath,
+    /// The misfit χ² of the bath.
+    pub chi_squared: f64,
+    /// The impurity Green's function G(iω_n).
+    pub greens_function: Vec<Complex>,
+    /// The impurity self-energy Σ(iω_n) = iω_n + μ - Δ_bath(iω_n) - G(iω_n)^{-1}.
+    pub self_energy: Vec<Complex>,
+}
+
+/// Returns the impurity Green's function and self-energy for the hybridization function `delta` of a paramagnetic DMFT iteration.
+///
+/// The hybridization function is fitted by a discrete bath, the single impurity Anderson model of the impurity and bath is fully
+/// diagonalized, and the Green's function of the impurity follows from the Lehmann representation at inverse temperature β.
+/// A bath of n levels needs a basis of 4^(n + 1) states.
+///
+/// # Arguments
+///
+/// * `parameters` - The interaction, chemical potential, temperature and fit settings.
+/// * `delta` - The hybridization function Δ(iω_n) on the first fermionic Matsubara frequencies ω_n = (2n + 1)π/β.
+/// * `initial` - The starting bath of the fit, e.g. the bath of the previous iteration.
+///
+/// # Errors
+///
+/// * If the Green's function vanishes at some frequency, this function returns an Error.
+pub fn solve_impurity(parameters: &DmftParameters, delta: &[Complex], initial: &Bath) -> Result<ImpuritySolution, Error> {
+    let frequencies = matsubara_frequencies(parameters.beta, delta.len(), Statistics::Fermion);
+    let (bath, chi_squared) = fit_bath(delta, &frequencies, initial, parameters.weight_exponent, parameters.fit_iterations);
+    let conv = SpinConvention::Interleaved;
+    let h = siam(-parameters.mu, parameters.u, &bath.energies, &bath.hybridizations, conv);
+    let n_sites = bath.energies.len() as u64 + 1;
+    let spectrum = Spectrum::new(&h, Basis::fock_space(2 * n_sites));
+    let d = conv.orbital(0, Spin::Up);
+    let annihilate = Operator::new(vec![(1.0, vec![AC::Annihilate(d)])]);
+    let create = Operator::new(vec![(1.0, vec![AC::Create(d)])]);
+    let greens_function = matsubara_greens_function(&spectrum, &annihilate, &create, parameters.beta, delta.len());
+    let self_energy = frequencies
+        .iter()
+        .zip(bath.hybridization(&frequencies))
+        .zip(&greens_function)
+        .map(|((w, fit), g)| {
+            if g.norm_sqr() == 0.0 {
+                return Err(Error::Invalid("The impurity Green's function vanishes!"));
+            }
+            Ok(Complex::new(parameters.mu, *w) - fit - g.inv())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(ImpuritySolution {
+        bath,
+        chi_squared,
+        greens_function,
+        self_energy,
+    })
+}
+
+/// Returns the hybridization function Δ(iω_n) = t² G(iω_n) of the next iteration on the Bethe lattice with hopping t,
+/// whose non-interacting density of states is a semicircle of half width 2t.
+pub fn bethe_hybridization(greens_function: &[Complex], t: f64) -> Vec<Complex> {
+    greens_function.iter().map(|g| *g * (t * t)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fit_bath() {
+        let frequencies = matsubara_frequencies(20.0, 64, Statistics::Fermion);
+        let exact = Bath::new(vec![-0.8, 0.5], vec![0.6, 0.4]);
+        let delta = exact.hybridization(&frequencies);
+        let (bath, chi_squared) = fit_bath(&delta, &frequencies, &Bath::new(vec![-1.0, 1.0], vec![0.5, 0.5]), 1.0, 4000);
+        assert!(chi_squared < 1e-10);
+        let mut levels = bath.energies.clone();
+        levels.sort_by(|a, b| a.total_cmp(b));
+        assert!((levels[0] + 0.8).abs() < 1e-3 && (levels[1] - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_bethe_lattice_loop() {
+        let mut parameters = DmftParameters {
+            u: 0.0,
+            mu: 0.0,
+            beta: 10.0,
+            weight_exponent: 1.0,
+            fit_iterations: 2000,
+        };
+        let initial = Bath::new(vec![-1.0, 1.0], vec![0.4, 0.4]);
+        let frequencies = matsubara_frequencies(parameters.beta, 32, Statistics::Fermion);
+        let delta = initial.hybridization(&frequencies);
+        // Without interaction the self-energy vanishes for any bath.
+        let free = solve_impurity(&parameters, &delta, &initial).unwrap();
+        assert!(free.self_energy.iter().all(|s| s.abs() < 1e-8));
+        // At half filling, μ = U/2, the self-consistent Green's function stays particle-hole symmetric with Re G = 0.
+        parameters.u = 2.0;
+        parameters.mu = 1.0;
+        let mut solution = free;
+        for _ in 0..4 {
+            let delta = bethe_hybridization(&solution.greens_function, 0.5);
+            solution = solve_impurity(&parameters, &delta, &solution.bath).unwrap();
+        }
+        assert!(solution.greens_function.iter().all(|g| g.re.abs() < 1e-2 && g.im < 0.0));
+        assert!(solution.self_energy[0].im < 0.0);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::error::Error;
+use crate::spectral::{Kernel, Poles};
+
+/// Represents a histogram of eigenvalues, normalized as a density of states per unit energy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The bin edges, one more than the number of bins.
+    edges: Vec<f64>,
+    /// The number of eigenvalues per unit energy in each bin.
+    density: Vec<f64>,
+}
+
+impl Histogram {
+    /// Returns the bin edges, one more than the number of bins.
+    pub fn edges(&self) -> &[f64] {
+        &self.edges
+    }
+
+    /// Returns the bin centers.
+    pub fn centers(&self) -> Vec<f64> {
+        self.edges.windows(2).map(|w| (w[0] + w[1]) / 2.0).collect()
+    }
+
+    /// Returns the number of eigenvalues per unit energy in each bin.
+    pub fn density(&self) -> &[f64] {
+        &self.density
+    }
+
+    /// Rescales the density to integrate to one, turning the histogram into a probability density.
+    pub fn normalize(&mut self) {
+        let total: f64 = self
+            .edges
+            .windows(2)
+            .zip(&self.density)
+            .map(|(w, d)| (w[1] - w[0]) * d)
+            .sum();
+        if total > 0.0 {
+            for d in self.density.iter_mut() {
+                *d /= total;
+            }
+        }
+    }
+
+}
+
+/// Returns a histogram of `eigenvalues` with `n_bins` equally wide bins.
+///
+/// # Arguments
+///
+/// * `eigenvalues` - The eigenvalues to bin.
+/// * `n_bins` - The number of bins.
+/// * `range` - The energy range to bin, or None to use the smallest and largest eigenvalue.
+///
+/// # Errors
+///
+/// * If there are no bins or the range is empty, this function returns an Error.
+pub fn histogram(
+    eigenvalues: &[f64],
+    n_bins: usize,
+    range: Option<(f64, f64)>,
+) -> Result<Histogram, Error> {
+    let (lo, hi) = range.unwrap_or_else(|| {
+        eigenvalues
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), e| (lo.min(*e), hi.max(*e)))
+    });
+    if n_bins == 0 || hi.partial_cmp(&lo) != Some(std::cmp::Ordering::Greater) {
+        return Err(Error::Invalid("Histogram needs at least one bin and a non-empty range!"));
+    }
+    let width = (hi - lo) / n_bins as f64;
+    let mut counts = vec![0.0; n_bins];
+    for e in eigenvalues {
+        if *e < lo || *e > hi {
+            continue;
+        }
+        let bin = (((e - lo) / width) as usize).min(n_bins - 1);
+        counts[bin] += 1.0;
+    }
+    Ok(Histogram {
+        edges: (0..=n_bins).map(|i| lo + width * i as f64).collect(),
+        density: counts.into_iter().map(|c| c / width).collect(),
+    })
+}
+
+/// Returns the integrated density of states N(E), the number of eigenvalues not larger than E, on `energies`.
+pub fn integrated_dos(eigenvalues: &[f64], energies: &[f64]) -> Vec<f64> {
+    let mut sorted = eigenvalues.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    energies
+        .iter()
+        .map(|e| sorted.partition_point(|x| x <= e) as f64)
+        .collect()
+}
+
+/// Returns the density of states Σ_i δ(E - E_i), broadened by `kernel`, on `energies`.
+pub fn smoothed_dos(eigenvalues: &[f64], energies: &[f64], kernel: &Kernel) -> Vec<f64> {
+    Poles::new(eigenvalues.to_vec(), vec![1.0; eigenvalues.len()]).spectral_function(energies, kernel)
+}
+
+/// Represents the smoothed and integrated density of states on an energy grid.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DosTable {
+    /// The energy grid.
+    pub energies: Vec<f64>,
+    /// The broadened density of states.
+    pub density: Vec<f64>,
+    /// The integrated density of states.
+    pub integrated: Vec<f64>,
+}
+
+impl DosTable {
+    /// Returns the smoothed and integrated density of states of `eigenvalues` on `energies`.
+    ///
+    /// # Arguments
+    ///
+    /// * `eigenvalues` - The eigenvalues, e.g. from a full diagonalization.
+    /// * `energies` - The energy grid.
+    /// * `kernel` - The broadening of the smoothed density of states.
+    pub fn new(eigenvalues: &[f64], energies: &[f64], kernel: &Kernel) -> DosTable {
+        DosTable {
+            energies: energies.to_vec(),
+            density: smoothed_dos(eigenvalues, energies, kernel),
+            integrated: integrated_dos(eigenvalues, energies),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::ResultTable;
+
+    #[test]
+    fn test_histogram() {
+        let h = histogram(&[0.0, 0.1, 0.6, 1.0], 2, None).unwrap();
+        assert_eq!(h.density(), &[4.0, 4.0]);
+        assert_eq!(h.centers(), vec![0.25, 0.75]);
+        assert!(histogram(&[1.0], 3, None).is_err());
+    }
+
+    #[test]
+    fn test_dos_table() {
+        let table = DosTable::new(&[-1.0, 0.0, 0.0, 1.0], &[-2.0, 0.0, 2.0], &Kernel::Gaussian(0.1));
+        assert_eq!(table.integrated, vec![0.0, 3.0, 4.0]);
+        let table = ResultTable::from(&table);
+        assert_eq!(table.columns(), &["energy", "density", "integrated"]);
+        assert_eq!(table.column("integrated"), Some(vec![0.0, 3.0, 4.0]));
+    }
+}
@@ -0,0 +1,243 @@
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+use crate::basis::Basis;
+use crate::linalg::Matrix;
+use crate::Operator;
+
+/// The maximal number of Jacobi sweeps of `eigh`; the off-diagonal elements vanish quadratically once they are small,
+/// so well within this many sweeps.
+const MAX_SWEEPS: usize = 100;
+
+/// The relative precision of a double-double number, 2^-104.
+pub const EPSILON: f64 = 4.930380657631324e-32;
+
+/// This represents a double-double number hi + lo with |lo| ≤ ulp(hi) / 2, carrying about 32 significant digits,
+/// for near-degenerate spectra where the cancellation in f64 destroys small gaps.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Default)]
+pub struct DoubleDouble {
+    /// The leading part, the f64 nearest to the number.
+    pub hi: f64,
+    /// The remainder.
+    pub lo: f64,
+}
+
+/// Returns s = a + b rounded and the error of the rounding, exactly a + b = s + err.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let bb = s - a;
+    (s, (a - (s - bb)) + (b - bb))
+}
+
+/// Returns the sum of a and b like `two_sum`, if |a| ≥ |b|.
+fn quick_two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    (s, b - (s - a))
+}
+
+/// Returns p = a b rounded and the error of the rounding, exactly a b = p + err.
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    let p = a * b;
+    (p, a.mul_add(b, -p))
+}
+
+impl DoubleDouble {
+    /// Returns the double-double number nearest to hi + lo.
+    pub fn new(hi: f64, lo: f64) -> Self {
+        let (hi, lo) = two_sum(hi, lo);
+        Self { hi, lo }
+    }
+
+    /// Returns the f64 nearest to this number.
+    pub fn to_f64(self) -> f64 {
+        self.hi
+    }
+
+    /// Returns the absolute value.
+    pub fn abs(self) -> Self {
+        if self.hi < 0.0 {
+            -self
+        } else {
+            self
+        }
+    }
+
+    /// Returns the square root, NaN for negative numbers.
+    pub fn sqrt(self) -> Self {
+        if self.hi <= 0.0 {
+            return Self::from(self.hi.sqrt());
+        }
+        // One Newton step from the f64 root doubles the digits.
+        let q = self.hi.sqrt();
+        let (p, e) = two_prod(q, q);
+        let r = self - Self { hi: p, lo: e };
+        let (hi, lo) = quick_two_sum(q, r.hi / (2.0 * q));
+        Self { hi, lo }
+    }
+}
+
+impl From<f64> for DoubleDouble {
+    fn from(x: f64) -> Self {
+        Self { hi: x, lo: 0.0 }
+    }
+}
+
+impl Add for DoubleDouble {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        let (s, e) = two_sum(self.hi, other.hi);
+        let (t, f) = two_sum(self.lo, other.lo);
+        let (s, e) = quick_two_sum(s, e + t);
+        let (hi, lo) = quick_two_sum(s, e + f);
+        Self { hi, lo }
+    }
+}
+
+impl Sub for DoubleDouble {
+    type Output = Self;
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl Mul for DoubleDouble {
+    type Output = Self;
+    fn mul(self, other: Self) -> Self {
+        let (p, e) = two_prod(self.hi, other.hi);
+        let (hi, lo) = quick_two_sum(p, e + (self.hi * other.lo + self.lo * other.hi));
+        Self { hi, lo }
+    }
+}
+
+impl Div for DoubleDouble {
+    type Output = Self;
+    fn div(self, other: Self) -> Self {
+        // Long division: each quotient digit is an f64, the remainders are exact to double-double precision.
+        let q1 = self.hi / other.hi;
+        let r = self - other * Self::from(q1);
+        let q2 = r.hi / other.hi;
+        let r = r - other * Self::from(q2);
+        let q3 = r.hi / other.hi;
+        let (hi, lo) = quick_two_sum(q1, q2);
+        Self { hi, lo } + Self::from(q3)
+    }
+}
+
+impl Neg for DoubleDouble {
+    type Output = Self;
+    fn neg(self) -> Self {
+        Self {
+            hi: -self.hi,
+            lo: -self.lo,
+        }
+    }
+}
+
+impl AddAssign for DoubleDouble {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for DoubleDouble {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+/// Returns the eigenvalues, in ascending order, and the eigenvectors, one per eigenvalue, of the symmetric matrix `m`
+/// in double-double precision, by cyclic Jacobi rotations. The cost grows as the cube of the dimension, with a large
+/// prefactor, so this is meant for the small systems where f64 does not resolve the gaps.
+///
+/// # Panics
+///
+/// * If the matrix is not square.
+pub fn eigh(m: &Matrix) -> (Vec<DoubleDouble>, Vec<Vec<DoubleDouble>>) {
+    assert_eq!(m.rows(), m.cols(), "Only square matrices can be diagonalized!");
+    let n = m.rows();
+    let mut a: Vec<Vec<DoubleDouble>> = (0..n).map(|i| (0..n).map(|j| DoubleDouble::from(m[(i, j)])).collect()).collect();
+    let mut v: Vec<Vec<DoubleDouble>> = (0..n)
+        .map(|i| (0..n).map(|j| DoubleDouble::from(if i == j { 1.0 } else { 0.0 })).collect())
+        .collect();
+    let scale: f64 = m.data().iter().map(|x| x * x).sum::<f64>().sqrt();
+    for _ in 0..MAX_SWEEPS {
+        let off: f64 = (0..n).flat_map(|i| (0..i).map(move |j| (i, j))).map(|(i, j)| a[i][j].hi * a[i][j].hi).sum();
+        if off.sqrt() <= EPSILON * scale {
+            break;
+        }
+        for p in 0..n {
+            for q in p + 1..n {
+                if a[p][q].hi == 0.0 {
+                    continue;
+                }
+                // The rotation by the angle with cot 2θ = (a_qq - a_pp) / (2 a_pq), taking the smaller root of t = tan θ.
+                let one = DoubleDouble::from(1.0);
+                let theta = (a[q][q] - a[p][p]) / (DoubleDouble::from(2.0) * a[p][q]);
+                let t = one / (theta.abs() + (theta * theta + one).sqrt());
+                let t = if theta.hi < 0.0 { -t } else { t };
+                let c = one / (t * t + one).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (top, bottom) = a.split_at_mut(q);
+                for (apk, aqk) in top[p].iter_mut().zip(bottom[0].iter_mut()) {
+                    let (x, y) = (*apk, *aqk);
+                    *apk = c * x - s * y;
+                    *aqk = s * x + c * y;
+                }
+                for row in v.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|i, j| a[*i][*i].partial_cmp(&a[*j][*j]).unwrap());
+    let values = order.iter().map(|i| a[*i][*i]).collect();
+    let vectors = order.iter().map(|j| v.iter().map(|row| row[*j]).collect()).collect();
+    (values, vectors)
+}
+
+/// Returns the energies of `hamiltonian` in `basis`, in ascending order and in double-double precision, see `eigh`.
+pub fn energies(hamiltonian: &Operator, basis: &Basis) -> Vec<DoubleDouble> {
+    eigh(&basis.matrix(hamiltonian)).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let third = DoubleDouble::from(1.0) / DoubleDouble::from(3.0);
+        let error = (third * DoubleDouble::from(3.0) - DoubleDouble::from(1.0)).abs();
+        assert!(error.to_f64() < 4.0 * EPSILON);
+        let root = DoubleDouble::from(2.0).sqrt();
+        assert!((root * root - DoubleDouble::from(2.0)).abs().to_f64() < 8.0 * EPSILON);
+        // 1 + 1e-20 is 1 in f64 but not in double-double.
+        assert_eq!((DoubleDouble::from(1.0) + DoubleDouble::from(1e-20) - DoubleDouble::from(1.0)).to_f64(), 1e-20);
+    }
+
+    #[test]
+    fn test_eigh() {
+        // The eigenvalues 1 ± 1e-20 coincide in f64.
+        let m = Matrix::from_vec(2, 2, vec![1.0, 1e-20, 1e-20, 1.0]);
+        let (values, _) = eigh(&m);
+        assert!(((values[1] - values[0]).to_f64() - 2e-20).abs() < 1e-30);
+        let m = Matrix::from_vec(3, 3, vec![2.0, -1.0, 0.0, -1.0, 2.0, -1.0, 0.0, -1.0, 2.0]);
+        let (values, vectors) = eigh(&m);
+        let (expected, _) = m.eigh();
+        for (v, e) in values.iter().zip(&expected) {
+            assert!((v.to_f64() - e).abs() < 1e-12);
+        }
+        // The eigenvector of 2 is (1, 0, -1) / sqrt(2).
+        assert!((vectors[1][0] + vectors[1][2]).abs().to_f64() < 1e-28);
+        let hop = Operator::new(vec![(-1.0, vec![crate::AC::Create(0), crate::AC::Annihilate(1)]), (-1.0, vec![crate::AC::Create(1), crate::AC::Annihilate(0)])]);
+        let e = energies(&hop, &Basis::fixed_particle_number(2, 1));
+        assert_eq!(e.iter().map(|x| x.to_f64()).collect::<Vec<_>>(), vec![-1.0, 1.0]);
+    }
+}
@@ -0,0 +1,371 @@
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::lanczos::{krylov, tridiagonalize};
+use crate::linalg::{norm, LinearOperator};
+use crate::log;
+use crate::table::ResultTable;
+
+/// Represents the Loschmidt echo L(t) = |<ψ_0|e^{-iHt}|ψ_0>|² of a quench on a grid of times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoschmidtEcho {
+    /// The times t.
+    pub times: Vec<f64>,
+    /// The return amplitudes G(t) = <ψ_0|e^{-iHt}|ψ_0>.
+    pub amplitudes: Vec<Complex>,
+    /// The Loschmidt echo L(t) = |G(t)|².
+    pub echo: Vec<f64>,
+}
+
+impl LoschmidtEcho {
+    /// Returns the return rate λ(t) = -ln L(t) / N, the dynamical free energy density whose kinks mark dynamical quantum phase transitions.
+    ///
+    /// # Arguments
+    ///
+    /// * `n_sites` - The system size N.
+    pub fn return_rate(&self, n_sites: u64) -> Vec<f64> {
+        self.echo.iter().map(|l| -l.ln() / n_sites as f64).collect()
+    }
+}
+
+/// Returns the Loschmidt echo of the initial state `psi0` evolving under `h`.
+///
+/// The return amplitude is evaluated as Σ_j w_j e^{-iε_j t} from the poles of a Lanczos run started from |ψ_0>,
+/// which is exact once `n_lanczos` reaches the dimension of the Krylov space and accurate up to times of order n_lanczos / ||H|| otherwise.
+///
+/// # Arguments
+///
+/// * `psi0` - The initial state |ψ_0>, which need not be normalized.
+/// * `h` - The Hamiltonian after the quench.
+/// * `times` - The times t.
+/// * `n_lanczos` - The number of Lanczos steps.
+pub fn loschmidt<H: LinearOperator + ?Sized>(psi0: &[f64], h: &H, times: &[f64], n_lanczos: usize) -> LoschmidtEcho {
+    let poles = tridiagonalize(h, psi0, n_lanczos).poles();
+    let total = poles.total_weight();
+    let amplitudes: Vec<Complex> = times
+        .iter()
+        .map(|t| {
+            let mut g = Complex::default();
+            for (e, w) in poles.positions().iter().zip(poles.weights()) {
+                g += Complex::from_polar(w / total, -e * t);
+            }
+            g
+        })
+        .collect();
+    LoschmidtEcho {
+        times: times.to_vec(),
+        echo: amplitudes.iter().map(|g| g.norm_sqr()).collect(),
+        amplitudes,
+    }
+}
+
+/// Returns e^{-iHt}|ψ> using a single Krylov space of `n_krylov` vectors for each of the real and imaginary parts of |ψ>.
+/// The Krylov approximation is accurate for times up to order n_krylov / ||H||; longer times should be split into steps.
+///
+/// # Arguments
+///
+/// * `h` - The real symmetric Hamiltonian.
+/// * `psi` - The state |ψ>.
+/// * `t` - The time step.
+/// * `n_krylov` - The dimension of the Krylov spaces.
+pub fn evolve<H: LinearOperator + ?Sized>(h: &H, psi: &[Complex], t: f64, n_krylov: usize) -> Vec<Complex> {
+    let mut res = vec![Complex::default(); psi.len()];
+    let parts = [
+        (psi.iter().map(|z| z.re).collect::<Vec<f64>>(), Complex::new(1.0, 0.0)),
+        (psi.iter().map(|z| z.im).collect::<Vec<f64>>(), Complex::i()),
+    ];
+    for (part, unit) in parts {
+        if norm(&part) == 0.0 {
+            continue;
+        }
+        // e^{-iHt} = cos(Ht) - i sin(Ht) maps real vectors to real and imaginary parts.
+        let k = krylov(h, &part, n_krylov);
+        let re = k.apply_function(|e| (e * t).cos());
+        let im = k.apply_function(|e| -(e * t).sin());
+        for ((r, x), y) in res.iter_mut().zip(re).zip(im) {
+            *r += unit * Complex::new(x, y);
+        }
+    }
+    res
+}
+
+/// Returns e^{-iHt}|ψ> in Krylov steps no longer than `max_step`.
+fn propagate<H: LinearOperator + ?Sized>(h: &H, psi: &[Complex], t: f64, n_krylov: usize, max_step: f64) -> Vec<Complex> {
+    let n_steps = (t.abs() / max_step).ceil().max(1.0) as usize;
+    let dt = t / n_steps as f64;
+    let mut x = psi.to_vec();
+    for _ in 0..n_steps {
+        x = evolve(h, &x, dt, n_krylov);
+    }
+    x
+}
+
+/// Returns O|ψ> for a real operator O.
+fn apply_complex<O: LinearOperator + ?Sized>(op: &O, psi: &[Complex]) -> Vec<Complex> {
+    let re: Vec<f64> = psi.iter().map(|z| z.re).collect();
+    let im: Vec<f64> = psi.iter().map(|z| z.im).collect();
+    let (mut ore, mut oim) = (vec![0.0; psi.len()], vec![0.0; psi.len()]);
+    op.apply(&re, &mut ore);
+    op.apply(&im, &mut oim);
+    ore.into_iter().zip(oim).map(|(x, y)| Complex::new(x, y)).collect()
+}
+
+/// Returns <a|b>.
+fn inner(a: &[Complex], b: &[Complex]) -> Complex {
+    let mut res = Complex::default();
+    for (x, y) in a.iter().zip(b) {
+        res += x.conj() * *y;
+    }
+    res
+}
+
+/// Returns the out-of-time-order correlator F(t) = <ψ|W(t) V W(t) V|ψ>/<ψ|ψ>, with W(t) = e^{iHt} W e^{-iHt}, for Hermitian W and V.
+///
+/// It is evaluated as <u|V|v> with |u> = W(t)|ψ> and |v> = W(t)V|ψ>, which takes four forward or backward propagations per time.
+/// For operators squaring to one, e.g. Pauli matrices, the squared commutator is <|[W(t), V]|²> = 2 (1 - Re F(t)).
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `w` - The operator W.
+/// * `v` - The operator V.
+/// * `psi` - The state |ψ>, e.g. a ground state or a random vector for infinite temperature.
+/// * `times` - The times t.
+/// * `n_krylov` - The dimension of the Krylov space of each propagation step.
+/// * `max_step` - The longest time step of a single Krylov propagation.
+pub fn otoc<H, W, V>(
+    h: &H,
+    w: &W,
+    v: &V,
+    psi: &[f64],
+    times: &[f64],
+    n_krylov: usize,
+    max_step: f64,
+) -> Vec<Complex>
+where
+    H: LinearOperator + ?Sized,
+    W: LinearOperator + ?Sized,
+    V: LinearOperator + ?Sized,
+{
+    let psi: Vec<Complex> = psi.iter().map(|x| Complex::from(*x)).collect();
+    let norm_sqr = inner(&psi, &psi).re;
+    let heisenberg = |x: &[Complex], t: f64| {
+        let forward = propagate(h, x, t, n_krylov, max_step);
+        propagate(h, &apply_complex(w, &forward), -t, n_krylov, max_step)
+    };
+    let v_psi = apply_complex(v, &psi);
+    times
+        .iter()
+        .map(|t| {
+            let u = heisenberg(&psi, *t);
+            let wv = heisenberg(&v_psi, *t);
+            inner(&u, &apply_complex(v, &wv)) / norm_sqr
+        })
+        .collect()
+}
+
+/// The first line of an evolution snapshot, identifying the file.
+const SNAPSHOT_MAGIC: &str = "rust_ed evolution snapshot";
+
+/// Represents a time evolution in progress: the state reached after some time steps and the observables measured so far.
+/// Long evolutions can be written to a snapshot and continued from it later, e.g. in another job allocation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Evolution {
+    /// The number of time steps taken.
+    pub step: usize,
+    /// The length of a time step.
+    pub dt: f64,
+    /// The state e^{-iHt}|ψ_0> at t = step · dt.
+    pub state: Vec<Complex>,
+    /// The time and the expectation values of the observables after every step, starting at t = 0.
+    pub table: ResultTable,
+}
+
+impl Evolution {
+    /// Returns the evolution of `psi0` before the first step, recording the observables named `names`.
+    ///
+    /// # Arguments
+    ///
+    /// * `psi0` - The initial state, which need not be normalized.
+    /// * `dt` - The length of a time step.
+    /// * `names` - The names of the observables, the columns of the table after "time".
+    pub fn new(psi0: &[f64], dt: f64, names: &[&str]) -> Evolution {
+        let columns: Vec<&str> = ["time"].iter().chain(names).copied().collect();
+        Evolution {
+            step: 0,
+            dt,
+            state: psi0.iter().map(|x| Complex::from(*x)).collect(),
+            table: ResultTable::new(&columns),
+        }
+    }
+
+    /// Returns the time reached.
+    pub fn time(&self) -> f64 {
+        self.step as f64 * self.dt
+    }
+
+    /// Returns the row of the time and the expectation values of `observables` in the current state.
+    fn measure(&self, observables: &[&dyn LinearOperator]) -> Vec<f64> {
+        let norm_sqr = inner(&self.state, &self.state).re;
+        let mut row = vec![self.time()];
+        row.extend(observables.iter().map(|o| inner(&self.state, &apply_complex(*o, &self.state)).re / norm_sqr));
+        row
+    }
+
+    /// Evolves the state under `h` until `n_steps` steps are taken in total, measuring `observables` after every step,
+    /// and at t = 0 if nothing was measured yet. With `snapshot`, the evolution is written to the path every so many steps.
+    ///
+    /// # Arguments
+    ///
+    /// * `h` - The real symmetric Hamiltonian.
+    /// * `observables` - The real symmetric observables, in the order of the columns of the table.
+    /// * `n_steps` - The total number of time steps, including those taken before.
+    /// * `n_krylov` - The dimension of the Krylov spaces of each step.
+    /// * `snapshot` - The path of the snapshot and the number of steps between snapshots.
+    ///
+    /// # Errors
+    ///
+    /// * If the number of observables does not match the table or a snapshot cannot be written, this function returns an Error.
+    pub fn advance<H: LinearOperator + ?Sized>(
+        &mut self,
+        h: &H,
+        observables: &[&dyn LinearOperator],
+        n_steps: usize,
+        n_krylov: usize,
+        snapshot: Option<(&Path, usize)>,
+    ) -> Result<(), Error> {
+        if self.table.rows().is_empty() {
+            self.table.push(self.measure(observables))?;
+        }
+        while self.step < n_steps {
+            self.state = evolve(h, &self.state, self.dt, n_krylov);
+            self.step += 1;
+            self.table.push(self.measure(observables))?;
+            log::progress("dynamics::evolution", self.step, n_steps);
+            if let Some((path, interval)) = snapshot {
+                if self.step.is_multiple_of(interval.max(1)) || self.step == n_steps {
+                    self.write_snapshot(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the evolution to the snapshot file `path`, replacing it in one step, so that an interrupted write leaves the
+    /// previous snapshot intact. The file has a JSON header line with the step, the time step and the table as CSV,
+    /// followed by the real and imaginary parts of the state as little-endian f64.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be written, this function returns `Error::IoError`.
+    pub fn write_snapshot(&self, path: &Path) -> Result<(), Error> {
+        let mut csv = Vec::new();
+        self.table.write_csv(&mut csv)?;
+        let header = Json::Object(vec![
+            ("format".to_string(), Json::String(SNAPSHOT_MAGIC.to_string())),
+            ("step".to_string(), Json::Integer(self.step as u64)),
+            ("dt".to_string(), Json::Number(self.dt)),
+            ("dimension".to_string(), Json::Integer(self.state.len() as u64)),
+            ("table".to_string(), Json::String(String::from_utf8_lossy(&csv).into_owned())),
+        ]);
+        let mut bytes = format!("{}\n", header).into_bytes();
+        bytes.extend(self.state.iter().flat_map(|z| z.re.to_le_bytes()));
+        bytes.extend(self.state.iter().flat_map(|z| z.im.to_le_bytes()));
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        fs::write(&partial, bytes)?;
+        Ok(fs::rename(&partial, path)?)
+    }
+
+    /// Returns the evolution saved in the snapshot file `path` by `write_snapshot` or `advance`, to continue it with `advance`.
+    ///
+    /// # Errors
+    ///
+    /// * If the file cannot be read, this function returns `Error::IoError`.
+    /// * If the file is not a valid snapshot, this function returns an Error.
+    pub fn resume(path: &Path) -> Result<Evolution, Error> {
+        let bytes = fs::read(path)?;
+        let end = bytes.iter().position(|b| *b == b'\n').ok_or(Error::Parse("Invalid snapshot!"))?;
+        let header = json::parse(std::str::from_utf8(&bytes[..end]).map_err(|_| Error::Parse("Invalid snapshot!"))?)?;
+        if header.get("format").and_then(|f| f.as_str()) != Some(SNAPSHOT_MAGIC) {
+            return Err(Error::Parse("Invalid snapshot!"));
+        }
+        let integer = |key| header.get(key).and_then(|n| n.as_u64()).ok_or(Error::Parse("Invalid snapshot!"));
+        let (step, n) = (integer("step")? as usize, integer("dimension")? as usize);
+        let dt = header.get("dt").and_then(|x| x.as_f64()).ok_or(Error::Parse("Invalid snapshot!"))?;
+        let table = ResultTable::from_csv(header.get("table").and_then(|t| t.as_str()).ok_or(Error::Parse("Invalid snapshot!"))?)?;
+        let data = &bytes[end + 1..];
+        if data.len() != 16 * n {
+            return Err(Error::Parse("Invalid snapshot!"));
+        }
+        let value = |k: usize| f64::from_le_bytes(data[8 * k..8 * k + 8].try_into().unwrap());
+        let state = (0..n).map(|k| Complex::new(value(k), value(n + k))).collect();
+        Ok(Evolution { step, dt, state, table })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::Matrix;
+
+    #[test]
+    fn test_two_level_quench() {
+        // |0> under σx: G(t) = cos t.
+        let h = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let times = [0.0, 0.4, 1.0, 2.5];
+        let res = loschmidt(&[3.0, 0.0], &h, &times, 4);
+        for (t, l) in times.iter().zip(&res.echo) {
+            assert!((l - t.cos() * t.cos()).abs() < 1e-12);
+        }
+        assert!(res.return_rate(1)[0].abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_evolve() {
+        let h = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let t = 0.7;
+        let x = evolve(&h, &[Complex::new(0.0, 1.0), Complex::default()], t, 4);
+        // i e^{-iσx t}|0> = i (cos t, -i sin t)
+        assert!((x[0].im - t.cos()).abs() < 1e-12 && x[0].re.abs() < 1e-12);
+        assert!((x[1].re - t.sin()).abs() < 1e-12 && x[1].im.abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_resumed_evolution() {
+        // Under σx, <σz>(t) = cos 2t in |0>.
+        let h = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let sz = Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, -1.0]);
+        let path = std::env::temp_dir().join(format!("rust_ed_evolution_{}.snapshot", std::process::id()));
+        let mut first = Evolution::new(&[1.0, 0.0], 0.1, &["sz"]);
+        first.advance(&h, &[&sz], 4, 4, Some((&path, 2))).unwrap();
+        let mut resumed = Evolution::resume(&path).unwrap();
+        assert_eq!(resumed, first);
+        let missing = Path::new("/nonexistent/evolution.snapshot");
+        assert!(matches!(Evolution::resume(missing), Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound));
+        assert!(matches!(first.write_snapshot(missing), Err(Error::IoError(_))));
+        resumed.advance(&h, &[&sz], 10, 4, None).unwrap();
+        let sz_t = resumed.table.column("sz").unwrap();
+        assert_eq!(sz_t.len(), 11);
+        for (t, x) in resumed.table.column("time").unwrap().iter().zip(sz_t) {
+            assert!((x - (2.0 * t).cos()).abs() < 1e-10);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_otoc_of_single_spin() {
+        // H = σx and W = V = σz: W(t) = cos 2t σz ± sin 2t σy, so W(t) σz W(t) σz = cos 4t + i sin 4t σx and F(t) = cos 4t in |0>.
+        let h = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let sz = Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, -1.0]);
+        let times = [0.0, 0.3, 1.1];
+        let f = otoc(&h, &sz, &sz, &[1.0, 0.0], &times, 4, 0.5);
+        for (t, x) in times.iter().zip(f) {
+            assert!((x.re - (4.0 * t).cos()).abs() < 1e-10 && x.im.abs() < 1e-10);
+        }
+    }
+}
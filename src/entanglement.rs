@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::linalg::Matrix;
+use crate::table::ResultTable;
+use crate::State;
+
+/// Schmidt values below this magnitude are treated as zero.
+const SCHMIDT_CUTOFF: f64 = 1e-14;
+
+/// Returns the Schmidt coefficient matrix ψ(a, b) of `state` for the bipartition into the orbitals `subsystem` and their complement.
+/// Rows and columns only cover the subsystem configurations present in the state.
+///
+/// The fermionic sign of reordering each determinant with the subsystem orbitals first is included.
+fn schmidt_matrix(state: &State, subsystem: &[u64]) -> (Vec<u64>, Matrix) {
+    let mask_a = subsystem.iter().fold(0u64, |m, o| m | (1 << o));
+    let mut rows: HashMap<u64, usize> = HashMap::new();
+    let mut cols: HashMap<u64, usize> = HashMap::new();
+    let mut entries = Vec::with_capacity(state.amplitudes.len());
+    for (slater, amp) in &state.amplitudes {
+        let index = slater.index;
+        let a = subsystem
+            .iter()
+            .enumerate()
+            .fold(0u64, |c, (k, o)| c | (((index >> o) & 1) << k));
+        let b = index & !mask_a;
+        let swaps: u32 = subsystem
+            .iter()
+            .filter(|o| (index >> *o) & 1 == 1)
+            .map(|o| (b & ((1 << o) - 1)).count_ones())
+            .sum();
+        let sign = if swaps.is_multiple_of(2) { 1.0 } else { -1.0 };
+        let n_rows = rows.len();
+        let r = *rows.entry(a).or_insert(n_rows);
+        let n_cols = cols.len();
+        let c = *cols.entry(b).or_insert(n_cols);
+        entries.push((r, c, sign * amp));
+    }
+    let mut m = Matrix::zeros(rows.len(), cols.len());
+    for (r, c, v) in entries {
+        m[(r, c)] += v;
+    }
+    let mut configurations = vec![0; rows.len()];
+    for (a, r) in rows {
+        configurations[r] = a;
+    }
+    (configurations, m)
+}
+
+/// Returns the Schmidt values λ_i, the nonzero eigenvalues of the reduced density matrix, in descending order and normalized to sum to one.
+fn schmidt_values(state: &State, subsystem: &[u64]) -> Vec<f64> {
+    let (_, m) = schmidt_matrix(state, subsystem);
+    // The nonzero eigenvalues of ψψᵀ and ψᵀψ agree, diagonalize the smaller one.
+    let gram = if m.rows() <= m.cols() {
+        m.matmul(&m.transpose())
+    } else {
+        m.transpose().matmul(&m)
+    };
+    let (values, _) = gram.eigh();
+    let total: f64 = values.iter().sum();
+    let mut lambdas: Vec<f64> = values
+        .into_iter()
+        .rev()
+        .map(|v| v / total)
+        .filter(|v| *v > SCHMIDT_CUTOFF)
+        .collect();
+    let sum: f64 = lambdas.iter().sum();
+    for l in lambdas.iter_mut() {
+        *l /= sum;
+    }
+    lambdas
+}
+
+/// Returns the reduced density matrix ρ_A = Tr_B |ψ><ψ| / <ψ|ψ> of the orbitals `subsystem`, as a dense matrix over the subsystem Fock space.
+///
+/// Bit k of a row or column index is the occupation of `subsystem[k]`, and the subsystem orbitals are ordered before
+/// the rest when fixing fermionic signs. The matrix has dimension 2^|A|, so the subsystem should be kept small.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `subsystem` - The single particle states of subsystem A.
+pub fn reduced_density_matrix(state: &State, subsystem: &[u64]) -> Matrix {
+    let (configurations, m) = schmidt_matrix(state, subsystem);
+    let gram = m.matmul(&m.transpose());
+    let total: f64 = (0..gram.rows()).map(|r| gram[(r, r)]).sum();
+    let dim = 1usize << subsystem.len();
+    let mut rho = Matrix::zeros(dim, dim);
+    for (r, a) in configurations.iter().enumerate() {
+        for (c, a2) in configurations.iter().enumerate() {
+            rho[(*a as usize, *a2 as usize)] = gram[(r, c)] / total;
+        }
+    }
+    rho
+}
+
+/// Returns the von Neumann entanglement entropy S = -Σ_i λ_i ln λ_i between the orbitals `subsystem` and the rest.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `subsystem` - The single particle states of subsystem A.
+pub fn entropy(state: &State, subsystem: &[u64]) -> f64 {
+    renyi(&schmidt_values(state, subsystem), 1.0)
+}
+
+/// Returns the mutual information I(A:B) = S_A + S_B - S_AB between two disjoint sets of orbitals.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `region_a` - The single particle states of region A.
+/// * `region_b` - The single particle states of region B.
+///
+/// # Errors
+///
+/// * If the regions share an orbital, this function returns an Error.
+pub fn mutual_information(state: &State, region_a: &[u64], region_b: &[u64]) -> Result<f64, Error> {
+    if region_a.iter().any(|o| region_b.contains(o)) {
+        return Err(Error::Invalid("The regions of the mutual information must be disjoint!"));
+    }
+    let union: Vec<u64> = region_a.iter().chain(region_b).copied().collect();
+    Ok(entropy(state, region_a) + entropy(state, region_b) - entropy(state, &union))
+}
+
+/// Returns the Rényi entropy S_n = ln(Σ_i λ_i^n) / (1 - n) between the orbitals `subsystem` and the rest.
+///
+/// The limits are taken explicitly: n = 1 gives the von Neumann entropy and n = ∞ the min-entropy -ln λ_max.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `subsystem` - The single particle states of subsystem A.
+/// * `order` - The non-negative order n, which may be `f64::INFINITY`.
+pub fn renyi_entropy(state: &State, subsystem: &[u64], order: f64) -> f64 {
+    renyi(&schmidt_values(state, subsystem), order)
+}
+
+/// Represents the entanglement spectrum ξ_i = -ln λ_i of a bipartition.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntanglementSpectrum {
+    /// The entanglement energies ξ_i, in ascending order.
+    pub levels: Vec<f64>,
+}
+
+impl EntanglementSpectrum {
+    /// Returns the distinct entanglement energies and their degeneracies.
+    /// Consecutive levels closer than `tolerance` are counted as one multiplet, represented by its lowest level.
+    ///
+    /// # Arguments
+    ///
+    /// * `tolerance` - The largest spacing between levels of the same multiplet.
+    pub fn multiplets(&self, tolerance: f64) -> Vec<(f64, usize)> {
+        let mut res: Vec<(f64, usize)> = Vec::new();
+        let mut previous = f64::NEG_INFINITY;
+        for xi in &self.levels {
+            match res.last_mut() {
+                Some((_, count)) if xi - previous < tolerance => *count += 1,
+                _ => res.push((*xi, 1)),
+            }
+            previous = *xi;
+        }
+        res
+    }
+
+    /// Returns the table of the multiplets, with columns level and degeneracy, see `multiplets`.
+    ///
+    /// # Arguments
+    ///
+    /// * `tolerance` - The largest spacing between levels of the same multiplet.
+    pub fn multiplet_table(&self, tolerance: f64) -> ResultTable {
+        let mut table = ResultTable::new(&["level", "degeneracy"]);
+        for (xi, d) in self.multiplets(tolerance) {
+            table.push(vec![xi, d as f64]).unwrap();
+        }
+        table
+    }
+}
+
+/// Returns the entanglement spectrum between the orbitals `subsystem` and the rest.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `subsystem` - The single particle states of subsystem A.
+pub fn entanglement_spectrum(state: &State, subsystem: &[u64]) -> EntanglementSpectrum {
+    EntanglementSpectrum {
+        levels: schmidt_values(state, subsystem).iter().map(|l| -l.ln()).collect(),
+    }
+}
+
+/// Returns the Rényi entropy of order `order` of the normalized spectrum `lambdas`, sorted in descending order.
+fn renyi(lambdas: &[f64], order: f64) -> f64 {
+    if order.is_infinite() {
+        -lambdas.first().copied().unwrap_or(1.0).ln()
+    } else if (order - 1.0).abs() < 1e-12 {
+        lambdas.iter().map(|l| -l * l.ln()).sum()
+    } else {
+        lambdas.iter().map(|l| l.powf(order)).sum::<f64>().ln() / (1.0 - order)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Slater;
+
+    #[test]
+    fn test_product_state() {
+        let state = State::new(vec![(Slater::new(0b0101), 1.0)]);
+        assert!(entropy(&state, &[0, 1]).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_singlet() {
+        let state = State::new(vec![(Slater::new(0b0110), 1.0), (Slater::new(0b1001), -1.0)]);
+        assert!((entropy(&state, &[0, 1]) - 2.0_f64.ln()).abs() < 1e-12);
+        // The entropy of a pure state is symmetric in the bipartition.
+        assert!((entropy(&state, &[2, 3]) - 2.0_f64.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_renyi_entropies() {
+        // Schmidt values 0.8 and 0.2.
+        let state = State::new(vec![(Slater::new(0b0110), 2.0), (Slater::new(0b1001), 1.0)]);
+        let a = [0, 1];
+        assert!((renyi_entropy(&state, &a, 1.0) - entropy(&state, &a)).abs() < 1e-14);
+        assert!((renyi_entropy(&state, &a, 2.0) + (0.68_f64).ln()).abs() < 1e-12);
+        assert!((renyi_entropy(&state, &a, f64::INFINITY) + 0.8_f64.ln()).abs() < 1e-12);
+        assert!((renyi_entropy(&state, &a, 0.0) - 2.0_f64.ln()).abs() < 1e-12);
+        // S_n decreases with n.
+        assert!(renyi_entropy(&state, &a, 1.0) > renyi_entropy(&state, &a, 2.0));
+    }
+
+    #[test]
+    fn test_entanglement_spectrum() {
+        // Two singlets across the cut give four degenerate levels at ln 4.
+        let singlet = [(0b0110u64, 1.0), (0b1001u64, -1.0)];
+        let mut terms = Vec::new();
+        for (a, x) in singlet {
+            for (b, y) in singlet {
+                // Orbitals 0-3 show the first singlet, 4-7 the second; A = {0, 1, 4, 5}.
+                terms.push((Slater::new(a | (b << 4)), x * y));
+            }
+        }
+        let spectrum = entanglement_spectrum(&State::new(terms), &[0, 1, 4, 5]);
+        let multiplets = spectrum.multiplets(1e-8);
+        assert_eq!(multiplets.len(), 1);
+        assert_eq!(multiplets[0].1, 4);
+        assert!((multiplets[0].0 - 4.0_f64.ln()).abs() < 1e-12);
+        assert_eq!(spectrum.multiplet_table(1e-8).column("degeneracy"), Some(vec![4.0]));
+    }
+
+    #[test]
+    fn test_mutual_information() {
+        let state = State::new(vec![(Slater::new(0b0110), 1.0), (Slater::new(0b1001), -1.0)]);
+        // The two sites of a singlet share 2 ln 2 of mutual information.
+        let i = mutual_information(&state, &[0, 1], &[2, 3]).unwrap();
+        assert!((i - 2.0 * 2.0_f64.ln()).abs() < 1e-12);
+        assert!(mutual_information(&state, &[0, 1], &[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_reduced_density_matrix() {
+        // One particle in the bonding orbital: ρ of site 0 is diagonal with occupations 1/2.
+        let state = State::new(vec![(Slater::new(0b01), 1.0), (Slater::new(0b10), 1.0)]);
+        let rho = reduced_density_matrix(&state, &[0]);
+        assert_eq!(rho.rows(), 2);
+        assert!((rho[(0, 0)] - 0.5).abs() < 1e-14 && (rho[(1, 1)] - 0.5).abs() < 1e-14);
+        assert!(rho[(0, 1)].abs() < 1e-14);
+        // Both orbitals: the pure state projector.
+        let rho = reduced_density_matrix(&state, &[1, 0]);
+        assert!((rho[(1, 2)] - 0.5).abs() < 1e-14 && rho[(3, 3)].abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_fermionic_signs() {
+        // (c†_0 c†_1 + c†_2 c†_3 + c†_0 c†_3 - c†_1 c†_2)|0> splits into A = {0, 2} and B = {1, 3} as a product state
+        // (c†_0 + c†_2)(c†_1 + c†_3)|0> only once the reordering signs are accounted for.
+        let state = State::new(vec![
+            (Slater::new(0b0011), 1.0),
+            (Slater::new(0b1100), 1.0),
+            (Slater::new(0b1001), 1.0),
+            (Slater::new(0b0110), -1.0),
+        ]);
+        assert!(entropy(&state, &[0, 2]).abs() < 1e-12);
+    }
+}
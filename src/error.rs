@@ -0,0 +1,74 @@
+use core::error;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+
+/// Represents the errors of the crate.
+#[derive(Debug)]
+pub enum Error {
+    /// The orbital does not fit in the determinant representation.
+    InvalidOrbital(u64),
+    /// The orbital is occupied more than once.
+    DuplicateOrbital(u64),
+    /// A vector or checkpoint does not match the dimension of the operator.
+    DimensionMismatch,
+    /// The iterative solver did not reach the required residual in the allowed number of steps.
+    SolverNotConverged,
+    /// The projected memory of a calculation exceeds the memory budget, both in bytes.
+    MemoryBudget { projected: u64, budget: u64 },
+    /// A text or file could not be parsed, described by the message.
+    Parse(&'static str),
+    /// A file could not be read or written.
+    #[cfg(feature = "std")]
+    IoError(io::Error),
+    /// Any other error, described by the message.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidOrbital(j) => write!(f, "Orbital {} does not fit in a determinant!", j),
+            Error::DuplicateOrbital(j) => write!(f, "Orbital {} is occupied more than once!", j),
+            Error::DimensionMismatch => write!(f, "The dimensions do not match!"),
+            Error::SolverNotConverged => write!(f, "The solver did not converge!"),
+            Error::MemoryBudget { projected, budget } => {
+                write!(f, "The projected memory of {} bytes exceeds the memory budget of {} bytes!", projected, budget)
+            }
+            Error::Parse(message) => write!(f, "{}", message),
+            #[cfg(feature = "std")]
+            Error::IoError(e) => write!(f, "{}", e),
+            Error::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::IoError(e)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error() {
+        assert_eq!(Error::Parse("Invalid JSON!").to_string(), "Invalid JSON!");
+        assert_eq!(Error::DuplicateOrbital(3).to_string(), "Orbital 3 is occupied more than once!");
+        let e = Error::from(io::Error::new(io::ErrorKind::NotFound, "missing"));
+        assert!(error::Error::source(&e).is_some());
+    }
+}
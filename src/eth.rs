@@ -0,0 +1,140 @@
+use std::io::{self, Write};
+
+use crate::error::Error;
+use crate::spectrum::Spectrum;
+use crate::Operator;
+
+/// Represents eigenstate thermalization diagnostics of an observable O in the eigenstates |n> of a Hamiltonian.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EigenstateThermalization {
+    /// The eigenvalues E_n, in ascending order.
+    pub energies: Vec<f64>,
+    /// The eigenstate expectation values O_nn = <n|O|n>.
+    pub diagonal: Vec<f64>,
+    /// The differences |O_{n+1,n+1} - O_nn| between neighbouring eigenstates, one fewer than the eigenvalues.
+    pub diagonal_fluctuations: Vec<f64>,
+    /// The centers of the frequency bins ω = E_m - E_n of the off-diagonal statistics.
+    pub frequencies: Vec<f64>,
+    /// The mean |O_mn|² in each frequency bin, over pairs whose mean energy lies in the energy window.
+    pub off_diagonal_variance: Vec<f64>,
+    /// The ratio <|O_mn|²> / <|O_mn|>² over all binned off-diagonal elements, π/2 for Gaussian distributed elements.
+    pub gaussianity: f64,
+}
+
+impl EigenstateThermalization {
+    /// Writes the eigenstate expectation values as whitespace separated columns with a commented header line.
+    pub fn write_diagonal<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "# energy expectation_value")?;
+        for (e, o) in self.energies.iter().zip(&self.diagonal) {
+            writeln!(w, "{} {}", e, o)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the off-diagonal statistics as whitespace separated columns with a commented header line.
+    pub fn write_off_diagonal<W: Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "# frequency variance")?;
+        for (omega, v) in self.frequencies.iter().zip(&self.off_diagonal_variance) {
+            writeln!(w, "{} {}", omega, v)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the eigenstate thermalization diagnostics of the observable `op` from the full spectrum of a Hamiltonian.
+///
+/// The off-diagonal elements O_mn, m ≠ n, are restricted to pairs with mean energy (E_m + E_n)/2 inside `energy_window`,
+/// typically a narrow window in the middle of the spectrum, and binned in ω = |E_m - E_n| up to `max_frequency`.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, restricted to a single symmetry sector.
+/// * `op` - The observable O.
+/// * `energy_window` - The lower and upper bound of the mean energy of the off-diagonal pairs.
+/// * `n_bins` - The number of frequency bins.
+/// * `max_frequency` - The largest frequency binned.
+///
+/// # Errors
+///
+/// * If there are no bins or `max_frequency` is not positive, this function returns an Error.
+pub fn diagnostics(
+    spectrum: &Spectrum,
+    op: &Operator,
+    energy_window: (f64, f64),
+    n_bins: usize,
+    max_frequency: f64,
+) -> Result<EigenstateThermalization, Error> {
+    if n_bins == 0 || max_frequency.partial_cmp(&0.0) != Some(std::cmp::Ordering::Greater) {
+        return Err(Error::Invalid("ETH diagnostics need at least one bin and a positive maximal frequency!"));
+    }
+    let elements = spectrum.matrix_elements(op);
+    let energies = spectrum.energies();
+    let n = energies.len();
+    let diagonal: Vec<f64> = (0..n).map(|i| elements[(i, i)]).collect();
+    let width = max_frequency / n_bins as f64;
+    let mut sums = vec![0.0; n_bins];
+    let mut counts = vec![0usize; n_bins];
+    let (mut abs_sum, mut sqr_sum, mut total) = (0.0, 0.0, 0usize);
+    for m in 0..n {
+        for k in m + 1..n {
+            let mean = (energies[m] + energies[k]) / 2.0;
+            let omega = energies[k] - energies[m];
+            if mean < energy_window.0 || mean > energy_window.1 || omega > max_frequency {
+                continue;
+            }
+            let bin = ((omega / width) as usize).min(n_bins - 1);
+            let o = elements[(m, k)];
+            sums[bin] += o * o;
+            counts[bin] += 1;
+            abs_sum += o.abs();
+            sqr_sum += o * o;
+            total += 1;
+        }
+    }
+    let gaussianity = if abs_sum > 0.0 {
+        sqr_sum * total as f64 / (abs_sum * abs_sum)
+    } else {
+        f64::NAN
+    };
+    Ok(EigenstateThermalization {
+        energies: energies.to_vec(),
+        diagonal_fluctuations: diagonal.windows(2).map(|w| (w[1] - w[0]).abs()).collect(),
+        diagonal,
+        frequencies: (0..n_bins).map(|b| (b as f64 + 0.5) * width).collect(),
+        off_diagonal_variance: sums
+            .iter()
+            .zip(&counts)
+            .map(|(s, c)| if *c > 0 { s / *c as f64 } else { 0.0 })
+            .collect(),
+        gaussianity,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::AC;
+
+    #[test]
+    fn test_diagnostics() {
+        let mut terms = Vec::new();
+        for i in 0..5 {
+            terms.push((-1.0, vec![AC::Create(i), AC::Annihilate(i + 1)]));
+            terms.push((-1.0, vec![AC::Create(i + 1), AC::Annihilate(i)]));
+            terms.push((1.3, vec![AC::Create(i), AC::Annihilate(i), AC::Create(i + 1), AC::Annihilate(i + 1)]));
+        }
+        let h = Operator::new(terms);
+        let spectrum = Spectrum::new(&h, Basis::fixed_particle_number(6, 3));
+        let n0 = Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let eth = diagnostics(&spectrum, &n0, (-1.0, 1.0), 10, 5.0).unwrap();
+        // Σ_n <n|n_0|n> = Tr n_0 is the number of determinants with orbital 0 occupied, C(5, 2).
+        assert!((eth.diagonal.iter().sum::<f64>() - 10.0).abs() < 1e-10);
+        assert_eq!(eth.diagonal_fluctuations.len(), 19);
+        assert!(eth.gaussianity >= 1.0);
+        // The Hamiltonian itself has no off-diagonal elements.
+        let itself = diagnostics(&spectrum, &h, (-10.0, 10.0), 4, 10.0).unwrap();
+        assert!(itself.off_diagonal_variance.iter().all(|v| *v < 1e-20));
+        assert!(diagnostics(&spectrum, &n0, (0.0, 1.0), 0, 1.0).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+use crate::lanczos::{krylov, Krylov};
+use crate::linalg::{dot, LinearOperator, Matrix};
+use crate::random::Rng;
+use crate::spectral::Poles;
+use crate::thermo::Thermodynamics;
+
+/// Dynamical FTLM weights below this magnitude are dropped.
+const WEIGHT_CUTOFF: f64 = 1e-14;
+
+/// The seed of the probe vectors of `trace_estimate`.
+const TRACE_SEED: u64 = 0x5eed;
+
+/// Controls the stochastic sampling of the finite-temperature Lanczos method.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Sampling {
+    /// The number of random starting vectors.
+    pub n_random: usize,
+    /// The number of Lanczos steps per random vector.
+    pub n_lanczos: usize,
+    /// The seed of the random vectors.
+    pub seed: u64,
+}
+
+impl Sampling {
+    /// Returns sampling with `n_random` random vectors of `n_lanczos` Lanczos steps each, seeded by `seed`.
+    pub fn new(n_random: usize, n_lanczos: usize, seed: u64) -> Sampling {
+        Sampling {
+            n_random,
+            n_lanczos,
+            seed,
+        }
+    }
+}
+
+/// The Lanczos data of a single random vector |r>.
+struct Sample {
+    krylov: Krylov,
+    /// The Ritz values ε_j.
+    ritz_values: Vec<f64>,
+    /// The Ritz vectors expressed in the Lanczos vectors, stored as columns.
+    ritz_vectors: Matrix,
+}
+
+impl Sample {
+    fn new<H: LinearOperator + ?Sized>(h: &H, r: &[f64], n_lanczos: usize) -> Sample {
+        let krylov = krylov(h, r, n_lanczos);
+        let (ritz_values, ritz_vectors) = krylov.ritz();
+        Sample {
+            krylov,
+            ritz_values,
+            ritz_vectors,
+        }
+    }
+
+    /// Returns <r|ψ_j> for all Ritz vectors.
+    fn start_overlaps(&self) -> Vec<f64> {
+        let b0 = self.krylov.coefficients().b()[0];
+        (0..self.ritz_values.len())
+            .map(|j| b0 * self.ritz_vectors[(0, j)])
+            .collect()
+    }
+
+    /// Returns <ψ_j|x> for all Ritz vectors.
+    fn overlaps(&self, x: &[f64]) -> Vec<f64> {
+        let projections: Vec<f64> = self.krylov.vectors().iter().map(|v| dot(v, x)).collect();
+        self.ritz_vectors.transpose().matvec(&projections)
+    }
+}
+
+/// Returns FTLM estimates of the thermodynamics of `h`, including thermal expectation values of `operators`.
+///
+/// The thermal trace Tr[e^{-βH} A] is estimated as (1/R) Σ_r Σ_j e^{-βε_j} <r|ψ_j><ψ_j|A|r>,
+/// with ε_j and |ψ_j> the Ritz pairs of a Lanczos run started from the random vector |r>.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `operators` - The operators, represented in the same space as `h`, whose thermal expectation values are computed.
+/// * `betas` - The finite inverse temperatures.
+/// * `sampling` - The number of random vectors and Lanczos steps.
+pub fn thermodynamics<H: LinearOperator + ?Sized>(
+    h: &H,
+    operators: &[&dyn LinearOperator],
+    betas: &[f64],
+    sampling: Sampling,
+) -> Thermodynamics {
+    let mut rng = Rng::new(sampling.seed);
+    let d = h.dimension();
+    // Per sample: Ritz values, |<r|ψ_j>|², and <r|ψ_j><ψ_j|A|r> for each operator.
+    let mut samples = Vec::with_capacity(sampling.n_random);
+    let mut ar = vec![0.0; d];
+    for _ in 0..sampling.n_random {
+        let r = rng.sign_vector(d);
+        let sample = Sample::new(h, &r, sampling.n_lanczos);
+        let start = sample.start_overlaps();
+        let weights: Vec<f64> = start.iter().map(|x| x * x).collect();
+        let elements: Vec<Vec<f64>> = operators
+            .iter()
+            .map(|op| {
+                op.apply(&r, &mut ar);
+                sample
+                    .overlaps(&ar)
+                    .iter()
+                    .zip(&start)
+                    .map(|(a, s)| a * s)
+                    .collect()
+            })
+            .collect();
+        samples.push((sample.ritz_values, weights, elements));
+    }
+    let e_min = samples
+        .iter()
+        .flat_map(|(e, _, _)| e.iter())
+        .cloned()
+        .fold(f64::INFINITY, f64::min);
+
+    let mut res = Thermodynamics {
+        betas: betas.to_vec(),
+        log_partition_function: Vec::with_capacity(betas.len()),
+        free_energy: Vec::with_capacity(betas.len()),
+        energy: Vec::with_capacity(betas.len()),
+        entropy: Vec::with_capacity(betas.len()),
+        specific_heat: Vec::with_capacity(betas.len()),
+        expectation_values: vec![Vec::with_capacity(betas.len()); operators.len()],
+    };
+    for beta in betas {
+        let (mut z, mut e, mut e2) = (0.0, 0.0, 0.0);
+        let mut ops = vec![0.0; operators.len()];
+        for (ritz, weights, elements) in &samples {
+            for (j, eps) in ritz.iter().enumerate() {
+                let boltzmann = (-beta * (eps - e_min)).exp();
+                z += boltzmann * weights[j];
+                e += boltzmann * weights[j] * eps;
+                e2 += boltzmann * weights[j] * eps * eps;
+                for (o, el) in ops.iter_mut().zip(elements) {
+                    *o += boltzmann * el[j];
+                }
+            }
+        }
+        let energy = e / z;
+        let log_z = (z / sampling.n_random as f64).ln() - beta * e_min;
+        res.log_partition_function.push(log_z);
+        res.free_energy.push(-log_z / beta);
+        res.energy.push(energy);
+        res.entropy.push(log_z + beta * energy);
+        res.specific_heat.push(beta * beta * (e2 / z - energy * energy));
+        for (ev, o) in res.expectation_values.iter_mut().zip(ops) {
+            ev.push(o / z);
+        }
+    }
+    res
+}
+
+/// Returns a stochastic Lanczos quadrature estimate of Tr f(H).
+///
+/// Each of the `n_samples` random sign vectors |r> gives the Gauss quadrature <r|f(H)|r> ≈ Σ_j |<r|ψ_j>|² f(ε_j),
+/// with ε_j and |ψ_j> the Ritz pairs of `n_lanczos` Lanczos steps, and the Hutchinson average of these is the trace estimate.
+/// The probe vectors are seeded deterministically, so repeated calls give identical estimates.
+///
+/// # Arguments
+///
+/// * `f` - The function, e.g. |e| (-beta * e).exp() for the partition function.
+/// * `h` - The Hermitian operator.
+/// * `n_samples` - The number of random probe vectors.
+/// * `n_lanczos` - The number of Lanczos steps per probe vector.
+pub fn trace_estimate<F, H>(f: F, h: &H, n_samples: usize, n_lanczos: usize) -> f64
+where
+    F: Fn(f64) -> f64,
+    H: LinearOperator + ?Sized,
+{
+    let mut rng = Rng::new(TRACE_SEED);
+    let d = h.dimension();
+    let mut total = 0.0;
+    for _ in 0..n_samples {
+        let r = rng.sign_vector(d);
+        let sample = Sample::new(h, &r, n_lanczos);
+        total += sample
+            .start_overlaps()
+            .iter()
+            .zip(&sample.ritz_values)
+            .map(|(o, e)| o * o * f(*e))
+            .sum::<f64>();
+    }
+    total / n_samples as f64
+}
+
+/// Returns the FTLM estimate of the dynamical correlation function
+/// S(ω) = (1/Z) Σ_mn e^{-βE_m} |<n|b|m>|² δ(ω - (E_n - E_m)) as a sum of poles.
+///
+/// Each random vector |r> requires a second Lanczos run started from b|r>, following Jaklič and Prelovšek.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `b` - The operator, represented in the same space as `h`.
+/// * `beta` - The finite inverse temperature.
+/// * `sampling` - The number of random vectors and Lanczos steps.
+pub fn correlation_function<H: LinearOperator + ?Sized, B: LinearOperator + ?Sized>(
+    h: &H,
+    b: &B,
+    beta: f64,
+    sampling: Sampling,
+) -> Poles {
+    let mut rng = Rng::new(sampling.seed);
+    let d = h.dimension();
+    let mut contributions = Vec::new();
+    let mut partition = Vec::new();
+    let mut bx = vec![0.0; d];
+    for _ in 0..sampling.n_random {
+        let r = rng.sign_vector(d);
+        let left = Sample::new(h, &r, sampling.n_lanczos);
+        b.apply(&r, &mut bx);
+        let right = Sample::new(h, &bx, sampling.n_lanczos);
+        let start = left.start_overlaps();
+        let excited = right.start_overlaps();
+        // <ψ_i|b†|ψ'_j> = <b ψ_i|ψ'_j>
+        let mut elements = Matrix::zeros(left.ritz_values.len(), right.ritz_values.len());
+        for i in 0..left.ritz_values.len() {
+            let psi = left.krylov.combine(&left.ritz_vectors.column(i));
+            b.apply(&psi, &mut bx);
+            for (j, o) in right.overlaps(&bx).into_iter().enumerate() {
+                elements[(i, j)] = o;
+            }
+        }
+        for (i, ei) in left.ritz_values.iter().enumerate() {
+            partition.push((*ei, start[i] * start[i]));
+            for (j, ej) in right.ritz_values.iter().enumerate() {
+                let w = start[i] * elements[(i, j)] * excited[j];
+                contributions.push((*ei, ej - ei, w));
+            }
+        }
+    }
+    let e_min = partition
+        .iter()
+        .map(|(e, _)| *e)
+        .fold(f64::INFINITY, f64::min);
+    let z: f64 = partition
+        .iter()
+        .map(|(e, w)| w * (-beta * (e - e_min)).exp())
+        .sum();
+    let mut positions = Vec::new();
+    let mut weights = Vec::new();
+    for (e, omega, w) in contributions {
+        let weight = w * (-beta * (e - e_min)).exp() / z;
+        if weight.abs() > WEIGHT_CUTOFF {
+            positions.push(omega);
+            weights.push(weight);
+        }
+    }
+    Poles::new(positions, weights)
+}
+
+/// Returns the FTLM estimate of the single particle Green's function <<c; c†>> as a sum of poles,
+/// combining the particle part S_{c†}(ω) and the mirrored hole part S_c(-ω).
+/// Both operators must act within the space of `h`, e.g. a grand canonical Fock space basis.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian, including the chemical potential.
+/// * `annihilate` - The annihilation operator c.
+/// * `create` - The creation operator c†.
+/// * `beta` - The finite inverse temperature.
+/// * `sampling` - The number of random vectors and Lanczos steps.
+pub fn greens_function<H, C, D>(h: &H, annihilate: &C, create: &D, beta: f64, sampling: Sampling) -> Poles
+where
+    H: LinearOperator + ?Sized,
+    C: LinearOperator + ?Sized,
+    D: LinearOperator + ?Sized,
+{
+    let particle = correlation_function(h, create, beta, sampling);
+    let hole = correlation_function(h, annihilate, beta, sampling);
+    let positions = particle
+        .positions()
+        .iter()
+        .copied()
+        .chain(hole.positions().iter().map(|p| -p))
+        .collect();
+    let weights = particle
+        .weights()
+        .iter()
+        .chain(hole.weights())
+        .copied()
+        .collect();
+    Poles::new(positions, weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::spectrum::Spectrum;
+    use crate::thermo;
+    use crate::{Operator, AC};
+
+    fn chain(l: u64, mu: f64) -> Operator {
+        let mut terms = Vec::new();
+        for i in 0..l - 1 {
+            terms.push((-1.0, vec![AC::Create(i), AC::Annihilate(i + 1)]));
+            terms.push((-1.0, vec![AC::Create(i + 1), AC::Annihilate(i)]));
+        }
+        for i in 0..l {
+            terms.push((-mu, vec![AC::Create(i), AC::Annihilate(i)]));
+        }
+        Operator::new(terms)
+    }
+
+    #[test]
+    fn test_exact_in_full_krylov_space() {
+        // With as many Lanczos steps as the dimension FTLM is only limited by the random trace.
+        let h = chain(4, 0.3);
+        let basis = Basis::fock_space(4);
+        let m = basis.sparse_matrix(&h);
+        let n0 = basis.sparse_matrix(&Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]));
+        let betas = [0.5, 2.0];
+        let ftlm = thermodynamics(&m, &[&n0], &betas, Sampling::new(200, 16, 11));
+        let exact = thermo::observables(Spectrum::new(&h, basis).energies(), &betas);
+        for i in 0..betas.len() {
+            assert!((ftlm.energy[i] - exact.energy[i]).abs() < 0.1);
+            assert!((ftlm.log_partition_function[i] - exact.log_partition_function[i]).abs() < 0.1);
+            assert!(ftlm.expectation_values[0][i] > 0.0 && ftlm.expectation_values[0][i] < 1.0);
+        }
+    }
+
+    #[test]
+    fn test_trace_estimate() {
+        let h = chain(5, 0.2);
+        let basis = Basis::fock_space(5);
+        let m = basis.sparse_matrix(&h);
+        // The quadrature integrates the identity exactly for sign vectors.
+        assert!((trace_estimate(|_| 1.0, &m, 3, 10) - 32.0).abs() < 1e-10);
+        let exact = thermo::observables(Spectrum::new(&h, basis).energies(), &[1.0]);
+        let z = trace_estimate(|e| (-e).exp(), &m, 100, 20);
+        assert!((z / exact.partition_function(0) - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_greens_function_sum_rule() {
+        let h = chain(3, 0.0);
+        let basis = Basis::fock_space(3);
+        let c = basis.sparse_matrix(&Operator::new(vec![(1.0, vec![AC::Annihilate(1)])]));
+        let cd = basis.sparse_matrix(&Operator::new(vec![(1.0, vec![AC::Create(1)])]));
+        let g = greens_function(&basis.sparse_matrix(&h), &c, &cd, 1.0, Sampling::new(100, 8, 5));
+        assert!((g.total_weight() - 1.0).abs() < 0.05);
+    }
+}
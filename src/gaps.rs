@@ -0,0 +1,135 @@
+use crate::basis::Basis;
+use crate::error::Error;
+use crate::lanczos::ground_state;
+use crate::linalg::{LinearOperator, SparseMatrix};
+use crate::operators::SpinConvention;
+use crate::random::Rng;
+use crate::Operator;
+
+/// The required residual of the Lanczos ground states of each sector.
+const GAP_TOLERANCE: f64 = 1e-10;
+
+/// Represents the lowest energies of the sectors around (N↑, N↓) and the charge and spin gaps derived from them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gaps {
+    /// The ground state energy E(N) of the (N↑, N↓) sector.
+    pub ground_state_energy: f64,
+    /// The lowest energy E(N + 1) of the (N↑ + 1, N↓) and (N↑, N↓ + 1) sectors.
+    pub particle_energy: f64,
+    /// The lowest energy E(N - 1) of the (N↑ - 1, N↓) and (N↑, N↓ - 1) sectors.
+    pub hole_energy: f64,
+    /// The lowest energy at N particles with S^z ± 1, i.e. in the (N↑ + 1, N↓ - 1) and (N↑ - 1, N↓ + 1) sectors.
+    pub spin_flip_energy: f64,
+}
+
+impl Gaps {
+    /// Returns the chemical potential μ+ = E(N + 1) - E(N) of adding a particle.
+    pub fn mu_plus(&self) -> f64 {
+        self.particle_energy - self.ground_state_energy
+    }
+
+    /// Returns the chemical potential μ- = E(N) - E(N - 1) of removing a particle.
+    pub fn mu_minus(&self) -> f64 {
+        self.ground_state_energy - self.hole_energy
+    }
+
+    /// Returns the window (μ-, μ+) of chemical potentials for which N particles minimize E - μN,
+    /// given a Hamiltonian without chemical potential term.
+    pub fn chemical_potential_window(&self) -> (f64, f64) {
+        (self.mu_minus(), self.mu_plus())
+    }
+
+    /// Returns the charge gap Δc = E(N + 1) + E(N - 1) - 2 E(N) = μ+ - μ-.
+    pub fn charge_gap(&self) -> f64 {
+        self.mu_plus() - self.mu_minus()
+    }
+
+    /// Returns the spin gap Δs = E(N, S^z ± 1) - E(N, S^z).
+    pub fn spin_gap(&self) -> f64 {
+        self.spin_flip_energy - self.ground_state_energy
+    }
+}
+
+/// Returns the Lanczos ground state energy and vector of the Hamiltonian matrix `h` of a symmetry sector.
+pub(crate) fn sector_ground_state(h: &SparseMatrix) -> Result<(f64, Vec<f64>), Error> {
+    let start = Rng::new(0x6a95).normal_vector(h.dimension());
+    ground_state(h, &start, h.dimension(), GAP_TOLERANCE)
+}
+
+/// Returns the lowest energy of `hamiltonian` among the sectors (N↑, N↓) that exist on `n_sites` sites.
+fn lowest_energy(
+    hamiltonian: &Operator,
+    convention: SpinConvention,
+    n_sites: u64,
+    sectors: &[(i64, i64)],
+) -> Result<f64, Error> {
+    let mut lowest: Option<f64> = None;
+    for (n_up, n_down) in sectors {
+        if *n_up < 0 || *n_down < 0 || *n_up as u64 > n_sites || *n_down as u64 > n_sites {
+            continue;
+        }
+        let basis = Basis::fixed_spin_numbers(convention, n_sites, *n_up as u64, *n_down as u64);
+        let (e, _) = sector_ground_state(&basis.try_sparse_matrix(hamiltonian)?)?;
+        lowest = Some(lowest.map_or(e, |l| l.min(e)));
+    }
+    lowest.ok_or(Error::Invalid("The neighbouring sectors are empty!"))
+}
+
+/// Returns the ground state energies of the (N↑, N↓) sector and of its neighbouring sectors with one particle more or less
+/// and with one spin flipped, from Lanczos runs in each sector.
+///
+/// The Hamiltonian must conserve N↑ and N↓. With an SU(2) symmetric Hamiltonian and N↑ = N↓ the spin gap is the singlet-triplet gap.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian, without a chemical potential for `Gaps::chemical_potential_window` to be meaningful.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `n_sites` - The number of sites.
+/// * `n_up` - The number of spin up particles N↑.
+/// * `n_down` - The number of spin down particles N↓.
+///
+/// # Errors
+///
+/// * If N↑ or N↓ exceeds the number of sites, or the system is empty, full or cannot flip a spin, this function returns an Error.
+/// * If Lanczos does not converge in a sector, this function returns the Error.
+/// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+pub fn gaps(
+    hamiltonian: &Operator,
+    convention: SpinConvention,
+    n_sites: u64,
+    n_up: u64,
+    n_down: u64,
+) -> Result<Gaps, Error> {
+    if n_up > n_sites || n_down > n_sites {
+        return Err(Error::Invalid("There are more particles of one spin than sites!"));
+    }
+    let (u, d) = (n_up as i64, n_down as i64);
+    Ok(Gaps {
+        ground_state_energy: lowest_energy(hamiltonian, convention, n_sites, &[(u, d)])?,
+        particle_energy: lowest_energy(hamiltonian, convention, n_sites, &[(u + 1, d), (u, d + 1)])?,
+        hole_energy: lowest_energy(hamiltonian, convention, n_sites, &[(u - 1, d), (u, d - 1)])?,
+        spin_flip_energy: lowest_energy(hamiltonian, convention, n_sites, &[(u + 1, d - 1), (u - 1, d + 1)])?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hubbard, Graph};
+
+    #[test]
+    fn test_hubbard_dimer() {
+        let (t, u) = (1.0, 4.0);
+        let conv = SpinConvention::Interleaved;
+        let h = hubbard(&Graph::new(2, vec![(0, 1)]), t, u, 0.0, conv);
+        let g = gaps(&h, conv, 2, 1, 1).unwrap();
+        // Singlet ground state E = (U - √(U² + 16t²))/2, degenerate triplet at 0, one particle levels ±t and three particle levels U ± t.
+        let singlet = (u - (u * u + 16.0 * t * t).sqrt()) / 2.0;
+        assert!((g.ground_state_energy - singlet).abs() < 1e-10);
+        assert!((g.spin_gap() + singlet).abs() < 1e-10);
+        assert!((g.mu_minus() - (singlet + t)).abs() < 1e-10);
+        assert!((g.mu_plus() - (u - t - singlet)).abs() < 1e-10);
+        assert!((g.charge_gap() - (u - 2.0 * t - 2.0 * singlet)).abs() < 1e-10);
+        assert!(gaps(&h, conv, 2, 2, 2).is_err());
+    }
+}
@@ -0,0 +1,122 @@
+use crate::basis::Basis;
+use crate::complex::Complex;
+use crate::linalg::{LinearOperator, SparseMatrix};
+use crate::Operator;
+
+/// Represents a Hermitian operator H = R + iI with complex amplitudes, split into two operators with real amplitudes.
+/// The real part R must be Hermitian and the imaginary part I anti-Hermitian, e.g. I = c†_i c_j - c†_j c_i.
+#[derive(Debug, Clone)]
+pub struct HermitianOperator {
+    /// The real part R.
+    pub real: Operator,
+    /// The imaginary part I.
+    pub imaginary: Operator,
+}
+
+impl HermitianOperator {
+    /// Returns the Hermitian operator R + iI.
+    pub fn new(real: Operator, imaginary: Operator) -> HermitianOperator {
+        HermitianOperator { real, imaginary }
+    }
+
+    /// Returns the Hermitian operator with real amplitudes `op`.
+    pub fn from_real(op: Operator) -> HermitianOperator {
+        HermitianOperator {
+            real: op,
+            imaginary: Operator::new(Vec::new()),
+        }
+    }
+
+    /// Returns the real representation of this operator in `basis`, see `RealifiedMatrix`.
+    pub fn realified(&self, basis: &Basis) -> RealifiedMatrix {
+        RealifiedMatrix {
+            real: basis.sparse_matrix(&self.real),
+            imaginary: basis.sparse_matrix(&self.imaginary),
+        }
+    }
+}
+
+/// Represents a complex Hermitian matrix H = R + iI of dimension n as the real symmetric matrix [[R, -I], [I, R]] of dimension 2n,
+/// acting on vectors (Re ψ, Im ψ).
+///
+/// Every eigenvalue of H appears twice, with eigenvectors (Re ψ, Im ψ) and (-Im ψ, Re ψ), so all real Krylov and
+/// thermal machinery can be used unchanged; traces over the real representation count each state twice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealifiedMatrix {
+    real: SparseMatrix,
+    imaginary: SparseMatrix,
+}
+
+impl RealifiedMatrix {
+    /// Returns the dimension n of the complex space.
+    pub fn complex_dimension(&self) -> usize {
+        self.real.dimension()
+    }
+
+    /// Returns the complex vector ψ represented by (Re ψ, Im ψ).
+    pub fn to_complex(x: &[f64]) -> Vec<Complex> {
+        let n = x.len() / 2;
+        (0..n).map(|i| Complex::new(x[i], x[n + i])).collect()
+    }
+
+    /// Returns the real representation (Re ψ, Im ψ) of the complex vector ψ.
+    pub fn from_complex(psi: &[Complex]) -> Vec<f64> {
+        psi.iter().map(|z| z.re).chain(psi.iter().map(|z| z.im)).collect()
+    }
+}
+
+impl LinearOperator for RealifiedMatrix {
+    fn dimension(&self) -> usize {
+        2 * self.real.dimension()
+    }
+
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        let n = self.real.dimension();
+        let (x_re, x_im) = x.split_at(n);
+        let (y_re, y_im) = y.split_at_mut(n);
+        let mut tmp = vec![0.0; n];
+        self.real.apply(x_re, y_re);
+        self.imaginary.apply(x_im, &mut tmp);
+        for (y, t) in y_re.iter_mut().zip(&tmp) {
+            *y -= t;
+        }
+        self.real.apply(x_im, y_im);
+        self.imaginary.apply(x_re, &mut tmp);
+        for (y, t) in y_im.iter_mut().zip(&tmp) {
+            *y += t;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AC;
+
+    #[test]
+    fn test_complex_hopping() {
+        // H = e^{iφ} c†_0 c_1 + e^{-iφ} c†_1 c_0 has eigenvalues ±1 for any φ, each appearing twice.
+        let phi: f64 = 0.3;
+        let h = HermitianOperator::new(
+            Operator::new(vec![
+                (phi.cos(), vec![AC::Create(0), AC::Annihilate(1)]),
+                (phi.cos(), vec![AC::Create(1), AC::Annihilate(0)]),
+            ]),
+            Operator::new(vec![
+                (phi.sin(), vec![AC::Create(0), AC::Annihilate(1)]),
+                (-phi.sin(), vec![AC::Create(1), AC::Annihilate(0)]),
+            ]),
+        );
+        let basis = Basis::fixed_particle_number(2, 1);
+        let m = h.realified(&basis);
+        assert_eq!(m.dimension(), 4);
+        // ψ = (e^{iφ}, 1)/√2 in the basis (|0>, |1>) has eigenvalue 1.
+        let psi = [Complex::from_polar(1.0, phi), Complex::new(1.0, 0.0)];
+        let x = RealifiedMatrix::from_complex(&psi);
+        let mut y = vec![0.0; 4];
+        m.apply(&x, &mut y);
+        for (a, b) in RealifiedMatrix::to_complex(&y).iter().zip(&psi) {
+            assert!((*a - *b).abs() < 1e-14);
+        }
+    }
+}
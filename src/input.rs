@@ -0,0 +1,525 @@
+use std::fs;
+use std::path::Path;
+
+use crate::basis::{Basis, ASSEMBLY_BYTES_PER_ELEMENT, BASIS_BYTES_PER_STATE};
+use crate::config::{self, Config};
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::lanczos::{ground_state, ground_state_checkpointed, ground_state_out_of_core};
+use crate::lattice::{Boundary, Lattice};
+use crate::models::{heisenberg, hubbard, t_j};
+use crate::observables::expectation_value;
+use crate::operators::{number, spin_product, total_spin_squared, Spin, SpinConvention};
+use crate::random::Rng;
+use crate::spectrum::Spectrum;
+use crate::{Operator, State};
+
+/// The default maximal number of Lanczos steps of a specification.
+pub(crate) const DEFAULT_MAX_STEPS: usize = 500;
+
+/// The default required residual of the Lanczos ground state of a specification.
+pub(crate) const DEFAULT_TOLERANCE: f64 = 1e-10;
+
+/// The number of Lanczos steps between the checkpoints of `Specification::ground_state_checkpointed`.
+pub const CHECKPOINT_INTERVAL: usize = 20;
+
+/// The model of a specification, on the bonds of its lattice.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Model {
+    /// The Hubbard model, see `models::hubbard`.
+    Hubbard { t: f64, u: f64, mu: f64 },
+    /// The XXZ model, see `models::heisenberg`.
+    Heisenberg { jxy: f64, jz: f64, field: f64 },
+    /// The t-J model, see `models::t_j`.
+    TJ { t: f64, j: f64 },
+}
+
+/// The method used to find the ground state of a specification.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Solver {
+    /// Full diagonalization of the dense Hamiltonian matrix.
+    Full,
+    /// The Lanczos ground state, with at most `max_steps` steps and the residual `tolerance`.
+    Lanczos { max_steps: usize, tolerance: f64 },
+    /// The Lanczos ground state for sectors too large for memory: the Hamiltonian is applied without storing its matrix and the
+    /// Lanczos vectors are kept in a scratch file in the scratch directory of the configuration, see `Config::with_scratch_directory`.
+    OutOfCore { max_steps: usize, tolerance: f64 },
+}
+
+/// An observable measured in the ground state of a specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Observable {
+    /// The ground state energy.
+    Energy,
+    /// The average double occupancy Σ_i <n_i↑ n_i↓> / N.
+    DoubleOccupancy,
+    /// The total spin <S²>.
+    SpinSquared,
+    /// The spin correlations <S_0 · S_j> for every site j.
+    SpinCorrelations,
+    /// The densities <n_j> for every site j.
+    Densities,
+}
+
+/// Represents a complete exact diagonalization run: a lattice, a model on it, a symmetry sector, a solver and the observables to measure.
+///
+/// A specification is written as a JSON object, e.g.
+///
+/// ```text
+/// {
+///     "lattice": {"type": "chain", "size": [6], "boundary": "periodic"},
+///     "model": {"type": "hubbard", "t": 1.0, "u": 4.0},
+///     "sector": {"n_up": 3, "n_down": 3},
+///     "solver": {"type": "lanczos", "max_steps": 200, "tolerance": 1e-10},
+///     "observables": ["energy", "double_occupancy", "spin_correlations"]
+/// }
+/// ```
+///
+/// The lattice types are those of `Lattice`: chain and ladder with one size, square, triangular, honeycomb and kagome with two.
+/// The boundary is "open" or "periodic", or a list with one of them per direction. The model types are "hubbard" (t, u, mu),
+/// "heisenberg" (jxy, jz, field) and "t_j" (t, j); missing parameters are zero, except the hopping and exchange amplitudes which
+/// default to one. The solver "full" diagonalizes the dense matrix and "lanczos", the default, finds the ground state only;
+/// "out_of_core" finds it without storing the matrix and with the Lanczos vectors on disk, see `Solver::OutOfCore`.
+/// An optional "memory_budget_gb" limits the projected memory of the run, see `Specification::estimated_memory_bytes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Specification {
+    /// The cluster.
+    pub lattice: Lattice,
+    /// The model on the nearest neighbour bonds of the cluster.
+    pub model: Model,
+    /// The number of spin up particles.
+    pub n_up: u64,
+    /// The number of spin down particles.
+    pub n_down: u64,
+    /// The ground state solver.
+    pub solver: Solver,
+    /// The observables to measure, in order.
+    pub observables: Vec<Observable>,
+    /// The memory the solver may use, in bytes; runs projected to need more are refused before they start.
+    /// If None, the budget of the installed `config::Config` applies.
+    pub memory_budget: Option<u64>,
+}
+
+/// Returns the number `key` of the object `json`, or `default` if it is missing.
+fn number_or(json: &Json, key: &str, default: f64) -> Result<f64, Error> {
+    match json.get(key) {
+        None => Ok(default),
+        Some(x) => x.as_f64().ok_or(Error::Parse("Specification parameters must be numbers!")),
+    }
+}
+
+/// Returns the boundary named `name`.
+fn boundary(name: &Json) -> Result<Boundary, Error> {
+    match name.as_str() {
+        Some("open") => Ok(Boundary::Open),
+        Some("periodic") => Ok(Boundary::Periodic),
+        _ => Err(Error::Parse("The boundary must be \"open\" or \"periodic\"!")),
+    }
+}
+
+/// Returns the lattice of the specification `json`.
+fn lattice(json: &Json) -> Result<Lattice, Error> {
+    let size: Vec<usize> = json
+        .get("size")
+        .and_then(|s| s.as_array())
+        .ok_or(Error::Parse("The lattice needs a size!"))?
+        .iter()
+        .map(|n| n.as_u64().map(|n| n as usize).ok_or(Error::Parse("The lattice size must be a list of integers!")))
+        .collect::<Result<_, _>>()?;
+    let boundaries = match json.get("boundary") {
+        None => vec![Boundary::Open; 2],
+        Some(Json::Array(b)) => b.iter().map(boundary).collect::<Result<_, _>>()?,
+        Some(b) => vec![boundary(b)?],
+    };
+    if boundaries.is_empty() {
+        return Err(Error::Parse("The lattice needs a boundary!"));
+    }
+    let two = |values: &[usize]| match values {
+        [a, b] => Ok([*a, *b]),
+        _ => Err(Error::Parse("This lattice needs two sizes!")),
+    };
+    let one = |values: &[usize]| match values {
+        [a] => Ok(*a),
+        _ => Err(Error::Parse("This lattice needs one size!")),
+    };
+    let both = [boundaries[0], *boundaries.get(1).unwrap_or(&boundaries[0])];
+    match json.get("type").and_then(|t| t.as_str()) {
+        Some("chain") => Ok(Lattice::chain(one(&size)?, boundaries[0])),
+        Some("ladder") => Ok(Lattice::ladder(one(&size)?, boundaries[0])),
+        Some("square") => Ok(Lattice::square(two(&size)?, both)),
+        Some("triangular") => Ok(Lattice::triangular(two(&size)?, both)),
+        Some("honeycomb") => Ok(Lattice::honeycomb(two(&size)?, both)),
+        Some("kagome") => Ok(Lattice::kagome(two(&size)?, both)),
+        _ => Err(Error::Parse("Unknown lattice type!")),
+    }
+}
+
+impl Specification {
+    /// Returns the specification of the JSON object `json`, see `Specification`.
+    ///
+    /// # Errors
+    ///
+    /// * If a section is missing or invalid, this function returns an Error.
+    pub fn from_json(json: &Json) -> Result<Specification, Error> {
+        let lattice = lattice(json.get("lattice").ok_or(Error::Parse("The specification needs a lattice!"))?)?;
+        let model = json.get("model").ok_or(Error::Parse("The specification needs a model!"))?;
+        let model = match model.get("type").and_then(|t| t.as_str()) {
+            Some("hubbard") => Model::Hubbard {
+                t: number_or(model, "t", 1.0)?,
+                u: number_or(model, "u", 0.0)?,
+                mu: number_or(model, "mu", 0.0)?,
+            },
+            Some("heisenberg") => {
+                let jz = number_or(model, "jz", 1.0)?;
+                Model::Heisenberg {
+                    jxy: number_or(model, "jxy", jz)?,
+                    jz,
+                    field: number_or(model, "field", 0.0)?,
+                }
+            }
+            Some("t_j") => Model::TJ {
+                t: number_or(model, "t", 1.0)?,
+                j: number_or(model, "j", 1.0)?,
+            },
+            _ => return Err(Error::Parse("Unknown model type!")),
+        };
+        let sector = json.get("sector").ok_or(Error::Parse("The specification needs a sector!"))?;
+        let count = |key| sector.get(key).and_then(|n| n.as_u64()).ok_or(Error::Parse("The sector needs the integers n_up and n_down!"));
+        let (n_up, n_down) = (count("n_up")?, count("n_down")?);
+        let solver = match json.get("solver") {
+            None => Solver::Lanczos {
+                max_steps: DEFAULT_MAX_STEPS,
+                tolerance: DEFAULT_TOLERANCE,
+            },
+            Some(s) => match s.get("type").and_then(|t| t.as_str()) {
+                Some("full") => Solver::Full,
+                Some("lanczos") | None => Solver::Lanczos {
+                    max_steps: number_or(s, "max_steps", DEFAULT_MAX_STEPS as f64)? as usize,
+                    tolerance: number_or(s, "tolerance", DEFAULT_TOLERANCE)?,
+                },
+                Some("out_of_core") => Solver::OutOfCore {
+                    max_steps: number_or(s, "max_steps", DEFAULT_MAX_STEPS as f64)? as usize,
+                    tolerance: number_or(s, "tolerance", DEFAULT_TOLERANCE)?,
+                },
+                _ => return Err(Error::Parse("Unknown solver type!")),
+            },
+        };
+        let observables = match json.get("observables") {
+            None => vec![Observable::Energy],
+            Some(list) => list
+                .as_array()
+                .ok_or(Error::Parse("The observables must be a list of names!"))?
+                .iter()
+                .map(|name| match name.as_str() {
+                    Some("energy") => Ok(Observable::Energy),
+                    Some("double_occupancy") => Ok(Observable::DoubleOccupancy),
+                    Some("spin_squared") => Ok(Observable::SpinSquared),
+                    Some("spin_correlations") => Ok(Observable::SpinCorrelations),
+                    Some("densities") => Ok(Observable::Densities),
+                    _ => Err(Error::Parse("Unknown observable!")),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+        let memory_budget = match json.get("memory_budget_gb") {
+            None => None,
+            Some(gb) => Some((gb.as_f64().ok_or(Error::Parse("The memory budget must be a number!"))? * 1e9) as u64),
+        };
+        Ok(Specification {
+            lattice,
+            model,
+            n_up,
+            n_down,
+            solver,
+            observables,
+            memory_budget,
+        })
+    }
+
+    /// Returns the spin convention of the specification, with all spin up orbitals first.
+    pub fn convention(&self) -> SpinConvention {
+        SpinConvention::Blocked {
+            n_sites: self.lattice.n_sites(),
+        }
+    }
+
+    /// Returns the Hamiltonian of the model on the lattice.
+    pub fn hamiltonian(&self) -> Operator {
+        let (graph, convention) = (self.lattice.graph(), self.convention());
+        match self.model {
+            Model::Hubbard { t, u, mu } => hubbard(&graph, t, u, mu, convention),
+            Model::Heisenberg { jxy, jz, field } => heisenberg(&graph, jxy, jz, field, convention),
+            Model::TJ { t, j } => t_j(&graph, t, j, convention),
+        }
+    }
+
+    /// Returns the basis of the sector; for the Heisenberg and t-J models without doubly occupied sites.
+    ///
+    /// # Errors
+    ///
+    /// * If the sector does not fit on the lattice, this function returns an Error.
+    pub fn basis(&self) -> Result<Basis, Error> {
+        let (n_sites, convention) = (self.lattice.n_sites(), self.convention());
+        if self.n_up > n_sites || self.n_down > n_sites {
+            return Err(Error::Invalid("The sector has more particles of one spin than sites!"));
+        }
+        let n_particles = self.n_up + self.n_down;
+        match self.model {
+            Model::Hubbard { .. } => return Ok(Basis::fixed_spin_numbers(convention, n_sites, self.n_up, self.n_down)),
+            Model::Heisenberg { .. } if n_particles != n_sites => return Err(Error::Invalid("The Heisenberg model needs one particle per site!")),
+            Model::TJ { .. } if n_particles > n_sites => return Err(Error::Invalid("The t-J model has at most one particle per site!")),
+            _ => {}
+        }
+        let up_mask = (0..n_sites).fold(0u64, |acc, i| acc | (1 << convention.orbital(i, Spin::Up)));
+        let states = Basis::no_double_occupancy(convention, n_sites, n_particles)
+            .states()
+            .iter()
+            .filter(|s| (s.index & up_mask).count_ones() as u64 == self.n_up)
+            .copied()
+            .collect();
+        Ok(Basis::new(states))
+    }
+
+    /// Returns the number of states of the sector, computed without building its basis.
+    pub fn sector_dimension(&self) -> u64 {
+        let n_sites = self.lattice.n_sites();
+        let binomial = |n: u64, k: u64| if k > n { 0 } else { (0..k).fold(1u128, |acc, i| acc * (n - i) as u128 / (i + 1) as u128) };
+        let dimension = match self.model {
+            Model::Hubbard { .. } => binomial(n_sites, self.n_up) * binomial(n_sites, self.n_down),
+            // Singly occupied sites: choose the occupied sites, then the up spins among them.
+            Model::Heisenberg { .. } | Model::TJ { .. } => binomial(n_sites, self.n_up + self.n_down) * binomial(self.n_up + self.n_down, self.n_up),
+        };
+        dimension.min(u64::MAX as u128) as u64
+    }
+
+    /// Returns the projected peak memory of finding the ground state with the solver of the specification, in bytes:
+    /// the basis, and the dense matrix and its eigenvectors for full diagonalization, the sparse matrix during assembly and
+    /// the Lanczos vectors of `max_steps` steps for Lanczos, or three vectors for the out-of-core solver.
+    ///
+    /// # Arguments
+    ///
+    /// * `basis` - The basis of the sector.
+    /// * `hamiltonian` - The Hamiltonian.
+    pub fn estimated_memory_bytes(&self, basis: &Basis, hamiltonian: &Operator) -> u64 {
+        let n = basis.dimension() as u64;
+        let basis_bytes = basis.estimated_memory_bytes() as u64;
+        let solver_bytes = match self.solver {
+            Solver::Full => (2 * 8u64).saturating_mul(n).saturating_mul(n),
+            Solver::Lanczos { max_steps, .. } => (ASSEMBLY_BYTES_PER_ELEMENT as u64)
+                .saturating_mul(basis.estimated_matrix_nnz(hamiltonian) as u64)
+                .saturating_add(8u64.saturating_mul(n).saturating_mul((max_steps as u64).min(n))),
+            Solver::OutOfCore { .. } => (3 * 8u64).saturating_mul(n),
+        };
+        basis_bytes.saturating_add(solver_bytes)
+    }
+
+    /// Returns the installed configuration with the memory budget of the specification, if it has one.
+    fn config(&self) -> Config {
+        match self.memory_budget {
+            Some(bytes) => config::current().with_memory_budget(bytes),
+            None => config::current(),
+        }
+    }
+
+    /// Returns the basis of the sector after checking that the basis alone fits in the memory budget.
+    fn checked_basis(&self) -> Result<Basis, Error> {
+        config::check_memory("input::memory", self.sector_dimension().saturating_mul(BASIS_BYTES_PER_STATE as u64))?;
+        self.basis()
+    }
+
+    /// Returns the spectrum of the sector by full diagonalization, after checking its projected memory against the budget.
+    ///
+    /// # Errors
+    ///
+    /// * If the sector is invalid or the projected memory exceeds the budget, this function returns an Error.
+    pub fn spectrum(&self) -> Result<Spectrum, Error> {
+        config::scoped(self.config(), || {
+            let basis = self.checked_basis()?;
+            let full = Specification {
+                solver: Solver::Full,
+                ..self.clone()
+            };
+            let h = self.hamiltonian();
+            config::check_memory("input::memory", full.estimated_memory_bytes(&basis, &h))?;
+            Spectrum::try_new(&h, basis)
+        })
+    }
+
+    /// Returns the ground state energy and the ground state in the sector, found with the solver of the specification.
+    ///
+    /// # Errors
+    ///
+    /// * If the sector is invalid or Lanczos does not converge, this function returns an Error.
+    pub fn ground_state(&self) -> Result<(f64, State), Error> {
+        self.solve(None)
+    }
+
+    /// Returns the ground state energy and the ground state like `ground_state`, with the Lanczos run recorded in the checkpoint
+    /// file `checkpoint` every `CHECKPOINT_INTERVAL` steps and resumed from it if it exists, see `lanczos::ground_state_checkpointed`.
+    ///
+    /// # Errors
+    ///
+    /// * If the solver is full diagonalization, the sector is invalid, the checkpoint cannot be used or Lanczos does not converge,
+    ///   this function returns an Error.
+    pub fn ground_state_checkpointed(&self, checkpoint: &Path) -> Result<(f64, State), Error> {
+        self.solve(Some(checkpoint))
+    }
+
+    /// Returns the ground state energy and the ground state, checkpointed to `checkpoint` if one is given.
+    fn solve(&self, checkpoint: Option<&Path>) -> Result<(f64, State), Error> {
+        config::scoped(self.config(), || self.solve_in_budget(checkpoint))
+    }
+
+    /// Returns the ground state energy and the ground state like `solve`, with the budget of the specification in place.
+    fn solve_in_budget(&self, checkpoint: Option<&Path>) -> Result<(f64, State), Error> {
+        let basis = self.checked_basis()?;
+        let h = self.hamiltonian();
+        config::check_memory("input::memory", self.estimated_memory_bytes(&basis, &h))?;
+        let start = Rng::new(0x1a7e).normal_vector(basis.dimension());
+        let (e, x) = match (self.solver, checkpoint) {
+            (Solver::Full, None) => {
+                let spectrum = Spectrum::try_new(&h, basis)?;
+                return Ok((spectrum.ground_state_energy(), spectrum.eigenstate(0)));
+            }
+            (Solver::Full, Some(_)) => return Err(Error::Invalid("Only the Lanczos solvers can be checkpointed!")),
+            (Solver::Lanczos { max_steps, tolerance }, None) => ground_state(&basis.try_sparse_matrix(&h)?, &start, max_steps, tolerance)?,
+            (Solver::OutOfCore { max_steps, tolerance }, None) => {
+                ground_state_out_of_core(&basis.try_matrix_free(&h, 0)?, &start, max_steps, tolerance, &config::current().scratch_directory())?
+            }
+            (Solver::Lanczos { max_steps, tolerance }, Some(path)) => {
+                ground_state_checkpointed(&basis.try_sparse_matrix(&h)?, &start, max_steps, tolerance, path, CHECKPOINT_INTERVAL)?
+            }
+            (Solver::OutOfCore { max_steps, tolerance }, Some(path)) => {
+                ground_state_checkpointed(&basis.try_matrix_free(&h, 0)?, &start, max_steps, tolerance, path, CHECKPOINT_INTERVAL)?
+            }
+        };
+        Ok((e, basis.state(&x)))
+    }
+
+    /// Returns the named observables measured in the ground state, in the order requested.
+    /// The spin correlations and densities give one value per site j, named e.g. "spin_correlation_3" and "density_3".
+    ///
+    /// # Errors
+    ///
+    /// * If the sector is invalid, this function returns `Error::Invalid`.
+    /// * If the run is projected to exceed the memory budget, this function returns `Error::MemoryBudget`.
+    /// * If Lanczos does not converge, this function returns `Error::SolverNotConverged`.
+    pub fn run(&self) -> Result<Vec<(String, f64)>, Error> {
+        let (energy, state) = self.ground_state()?;
+        Ok(self.measure(energy, &state))
+    }
+
+    /// Returns the named observables like `run`, with the ground state found by `ground_state_checkpointed`.
+    ///
+    /// # Errors
+    ///
+    /// * If the solver is full diagonalization, the sector is invalid, the checkpoint cannot be used or Lanczos does not converge,
+    ///   this function returns an Error.
+    pub fn run_checkpointed(&self, checkpoint: &Path) -> Result<Vec<(String, f64)>, Error> {
+        let (energy, state) = self.ground_state_checkpointed(checkpoint)?;
+        Ok(self.measure(energy, &state))
+    }
+
+    /// Returns the observables of the specification in the ground state `state` with the energy `energy`.
+    fn measure(&self, energy: f64, state: &State) -> Vec<(String, f64)> {
+        let (n_sites, convention) = (self.lattice.n_sites(), self.convention());
+        let mut res = Vec::new();
+        for observable in &self.observables {
+            match observable {
+                Observable::Energy => res.push(("energy".to_string(), energy)),
+                Observable::DoubleOccupancy => {
+                    let d: f64 = (0..n_sites)
+                        .map(|i| {
+                            let up = number(convention.orbital(i, Spin::Up));
+                            expectation_value(state, &up.product(&number(convention.orbital(i, Spin::Down))))
+                        })
+                        .sum();
+                    res.push(("double_occupancy".to_string(), d / n_sites as f64));
+                }
+                Observable::SpinSquared => {
+                    res.push(("spin_squared".to_string(), expectation_value(state, &total_spin_squared(convention, n_sites))))
+                }
+                Observable::SpinCorrelations => {
+                    for j in 0..n_sites {
+                        let c = expectation_value(state, &spin_product(convention, 0, j));
+                        res.push((format!("spin_correlation_{}", j), c));
+                    }
+                }
+                Observable::Densities => {
+                    for j in 0..n_sites {
+                        let n = expectation_value(state, &number(convention.orbital(j, Spin::Up)))
+                            + expectation_value(state, &number(convention.orbital(j, Spin::Down)));
+                        res.push((format!("density_{}", j), n));
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Returns the specification in the JSON text `text`, see `Specification`.
+///
+/// # Errors
+///
+/// * If `text` is not valid JSON or not a valid specification, this function returns an Error.
+pub fn parse_specification(text: &str) -> Result<Specification, Error> {
+    Specification::from_json(&json::parse(text)?)
+}
+
+/// Returns the results of the run described by the JSON specification file at `path`, see `Specification::run`.
+///
+/// # Errors
+///
+/// * If the file cannot be read, this function returns `Error::IoError`; if it is not a valid specification or the run fails,
+///   the Error of the run.
+pub fn run_from_file<P: AsRef<Path>>(path: P) -> Result<Vec<(String, f64)>, Error> {
+    parse_specification(&fs::read_to_string(path)?)?.run()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_from_file() {
+        let path = std::env::temp_dir().join(format!("rust_ed_specification_{}.json", std::process::id()));
+        fs::write(
+            &path,
+            r#"{
+                "lattice": {"type": "chain", "size": [2]},
+                "model": {"type": "hubbard", "t": 1.0, "u": 4.0},
+                "sector": {"n_up": 1, "n_down": 1},
+                "solver": {"type": "full"},
+                "observables": ["energy", "double_occupancy", "spin_squared", "densities"]
+            }"#,
+        )
+        .unwrap();
+        let results = run_from_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        // The Hubbard dimer ground state energy is (U - sqrt(U² + 16 t²)) / 2.
+        let exact = (4.0 - 32f64.sqrt()) / 2.0;
+        assert_eq!(results[0].0, "energy");
+        assert!((results[0].1 - exact).abs() < 1e-10);
+        assert!(results[2].1.abs() < 1e-10);
+        assert_eq!(results.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>()[3..], ["density_0", "density_1"]);
+        let lanczos = r#"{"lattice": {"type": "chain", "size": [4], "boundary": "periodic"},
+            "model": {"type": "heisenberg"}, "sector": {"n_up": 2, "n_down": 2}}"#;
+        // The four site Heisenberg ring has E = -2 J.
+        assert!((parse_specification(lanczos).unwrap().run().unwrap()[0].1 + 2.0).abs() < 1e-8);
+        assert!(parse_specification(&lanczos.replace("\"n_up\": 2", "\"n_up\": 3")).unwrap().run().is_err());
+        let short = lanczos.replace("}}", "}, \"solver\": {\"type\": \"lanczos\", \"max_steps\": 1}}");
+        assert!(matches!(parse_specification(&short).unwrap().run(), Err(Error::SolverNotConverged)));
+        // The four site ring has six states: the two Néel states flip into four others and those into two, and each has a diagonal
+        // element. 1 kB is not enough for Lanczos.
+        let spec = parse_specification(&lanczos.replace("}}", "}, \"memory_budget_gb\": 1e-6}")).unwrap();
+        assert_eq!(spec.sector_dimension(), 6);
+        assert_eq!(spec.basis().unwrap().estimated_matrix_nnz(&spec.hamiltonian()), 6 + 2 * 4 + 4 * 2);
+        assert!(matches!(spec.run(), Err(Error::MemoryBudget { budget: 1000, .. })));
+        // Without a budget of its own the run has that of the configuration, which a budget of its own replaces.
+        let tiny = Config::default().with_memory_budget(1000);
+        let over = config::scoped(tiny.clone(), || parse_specification(lanczos).unwrap().run());
+        assert!(matches!(over, Err(Error::MemoryBudget { budget: 1000, .. })));
+        let own = parse_specification(&lanczos.replace("}}", "}, \"memory_budget_gb\": 1}")).unwrap();
+        assert!(config::scoped(tiny, || own.run()).is_ok());
+    }
+}
@@ -0,0 +1,719 @@
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::basis::Basis;
+use crate::chemistry::MolecularIntegrals;
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::linalg::Matrix;
+use crate::operators::SpinConvention;
+use crate::table::ResultTable;
+use crate::{Operator, Slater, State, AC, MAX_ORBITALS};
+
+/// Represents the contents of an FCIDUMP file: the molecular integrals and the header describing the electronic state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fcidump {
+    /// The one- and two-electron integrals and the core energy.
+    pub integrals: MolecularIntegrals,
+    /// The number of electrons NELEC.
+    pub n_electrons: u64,
+    /// Twice the spin projection, MS2 = N↑ - N↓.
+    pub ms2: i64,
+    /// The irreducible representation of each orbital, ORBSYM.
+    pub orbital_symmetries: Vec<u32>,
+    /// The irreducible representation of the state, ISYM.
+    pub state_symmetry: u32,
+}
+
+impl Fcidump {
+    /// Returns the numbers of spin up and spin down electrons (N↑, N↓) given by NELEC and MS2, if they are consistent.
+    pub fn spin_numbers(&self) -> Option<(u64, u64)> {
+        let n = self.n_electrons as i64;
+        if (n + self.ms2) % 2 != 0 || self.ms2.abs() > n {
+            return None;
+        }
+        Some((((n + self.ms2) / 2) as u64, ((n - self.ms2) / 2) as u64))
+    }
+
+    /// Returns the molecular Hamiltonian of the integrals.
+    ///
+    /// # Arguments
+    ///
+    /// * `convention` - The mapping of (spatial orbital, spin) onto single particle states.
+    pub fn hamiltonian(&self, convention: SpinConvention) -> Operator {
+        self.integrals.hamiltonian(convention)
+    }
+}
+
+/// Returns the number in `token`, accepting Fortran exponents such as 1.0D-02.
+fn parse_number(token: &str) -> Result<f64, Error> {
+    token.replace(['D', 'd'], "E").parse().map_err(|_| Error::Parse("Invalid number in FCIDUMP!"))
+}
+
+/// Returns the FCIDUMP contents of `text`, see `read_fcidump`.
+///
+/// # Errors
+///
+/// * If the header lacks NORB or NELEC, or a line or orbital index is invalid, this function returns an Error.
+pub fn parse_fcidump(text: &str) -> Result<Fcidump, Error> {
+    let mut lines = text.lines();
+    let mut header = String::new();
+    for line in lines.by_ref() {
+        let trimmed = line.trim();
+        let upper = trimmed.to_ascii_uppercase();
+        if upper == "/" || upper.starts_with("&END") {
+            break;
+        }
+        if let Some(end) = upper.find("&END").or_else(|| upper.rfind('/')) {
+            header.push_str(&trimmed[..end]);
+            break;
+        }
+        header.push_str(trimmed);
+        header.push(' ');
+    }
+    let upper = header.to_ascii_uppercase().replace("&FCI", " ").replace('=', " = ").replace(',', " ");
+    let tokens: Vec<&str> = upper.split_whitespace().collect();
+    let mut values: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut k = 0;
+    while k < tokens.len() {
+        if tokens.get(k + 1) != Some(&"=") {
+            return Err(Error::Parse("Invalid FCIDUMP header!"));
+        }
+        let key = tokens[k];
+        k += 2;
+        let mut list = Vec::new();
+        while k < tokens.len() && tokens.get(k + 1) != Some(&"=") {
+            list.push(tokens[k]);
+            k += 1;
+        }
+        values.insert(key, list);
+    }
+    let integer = |key: &str| -> Result<Option<i64>, Error> {
+        match values.get(key).and_then(|v| v.first()) {
+            Some(v) => v.parse().map(Some).map_err(|_| Error::Parse("Invalid integer in FCIDUMP header!")),
+            None => Ok(None),
+        }
+    };
+    let n_orbitals = integer("NORB")?.ok_or(Error::Parse("FCIDUMP header lacks NORB!"))?;
+    let n_electrons = integer("NELEC")?.ok_or(Error::Parse("FCIDUMP header lacks NELEC!"))?;
+    if n_orbitals < 0 || n_electrons < 0 {
+        return Err(Error::Parse("Invalid integer in FCIDUMP header!"));
+    }
+    let n_orbitals = n_orbitals as usize;
+    let orbital_symmetries = match values.get("ORBSYM") {
+        Some(list) => list
+            .iter()
+            .map(|v| v.parse().map_err(|_| Error::Parse("Invalid ORBSYM in FCIDUMP header!")))
+            .collect::<Result<Vec<u32>, _>>()?,
+        None => vec![1; n_orbitals],
+    };
+    let mut integrals = MolecularIntegrals::zeros(n_orbitals);
+    for line in lines {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.is_empty() {
+            continue;
+        }
+        if fields.len() != 5 {
+            return Err(Error::Parse("An FCIDUMP integral line needs a value and four indices!"));
+        }
+        let value = parse_number(fields[0])?;
+        let mut indices = [0usize; 4];
+        for (index, field) in indices.iter_mut().zip(&fields[1..]) {
+            *index = field.parse().map_err(|_| Error::Parse("Invalid orbital index in FCIDUMP!"))?;
+            if *index > n_orbitals {
+                return Err(Error::Parse("FCIDUMP orbital index exceeds NORB!"));
+            }
+        }
+        match indices {
+            [0, 0, 0, 0] => integrals.set_core_energy(value),
+            [i, j, 0, 0] if i > 0 && j > 0 => integrals.set_one_body(i - 1, j - 1, value),
+            [i, j, k, l] if i > 0 && j > 0 && k > 0 && l > 0 => integrals.set_two_body(i - 1, j - 1, k - 1, l - 1, value),
+            // Orbital energies (i, 0, 0, 0) carry no information for the Hamiltonian.
+            [_, 0, 0, 0] => {}
+            _ => return Err(Error::Parse("Invalid orbital indices in FCIDUMP!")),
+        }
+    }
+    Ok(Fcidump {
+        integrals,
+        n_electrons: n_electrons as u64,
+        ms2: integer("MS2")?.unwrap_or(0),
+        orbital_symmetries,
+        state_symmetry: integer("ISYM")?.unwrap_or(1) as u32,
+    })
+}
+
+/// Returns the contents of the FCIDUMP file at `path`, the integral format written by e.g. PySCF and Molpro.
+///
+/// The namelist header gives NORB, NELEC, MS2, ORBSYM and ISYM, and each following line a value and four 1-based orbital indices:
+/// (ij|kl) for four nonzero indices, h_ij for i j 0 0 and the core energy for 0 0 0 0. Integrals are stored once per
+/// permutational symmetry class, for real orbitals.
+///
+/// # Errors
+///
+/// * If the file cannot be read, this function returns `Error::IoError`.
+/// * If the file is not a valid FCIDUMP, this function returns an Error.
+pub fn read_fcidump<P: AsRef<Path>>(path: P) -> Result<Fcidump, Error> {
+    parse_fcidump(&fs::read_to_string(path)?)
+}
+
+/// Returns the OpenFermion notation "0^ 1" of a product of creation and annihilation operators.
+fn openfermion_term(ac: &[AC]) -> String {
+    ac.iter()
+        .map(|c| match c {
+            AC::Create(i) => format!("{}^", i),
+            AC::Annihilate(i) => format!("{}", i),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Returns the product of creation and annihilation operators in the OpenFermion notation `term`, e.g. "0^ 1".
+fn parse_openfermion_term(term: &str) -> Result<Vec<AC>, Error> {
+    let ac: Vec<AC> = term
+        .split_whitespace()
+        .map(|factor| match factor.strip_suffix('^') {
+            Some(i) => i.parse().map(AC::Create),
+            None => factor.parse().map(AC::Annihilate),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(|_| Error::Parse("Invalid OpenFermion operator!"))?;
+    if ac.iter().any(|c| c.orbital() >= MAX_ORBITALS) {
+        return Err(Error::Parse("OpenFermion orbitals must be below 64!"));
+    }
+    Ok(ac)
+}
+
+/// Returns the real coefficient `token`, which may be written as a Python complex number such as (0.5+0j).
+fn parse_coefficient(token: &str) -> Result<f64, Error> {
+    let token = token.trim().trim_start_matches('(').trim_end_matches(')');
+    let Some(complex) = token.strip_suffix('j') else {
+        return token.parse().map_err(|_| Error::Parse("Invalid OpenFermion coefficient!"));
+    };
+    // The imaginary part starts at the last sign that does not belong to an exponent.
+    let split = complex
+        .char_indices()
+        .skip(1)
+        .filter(|(k, c)| (*c == '+' || *c == '-') && !complex[..*k].ends_with(['e', 'E']))
+        .map(|(k, _)| k)
+        .last();
+    let (re, im) = match split {
+        Some(k) => (&complex[..k], &complex[k..]),
+        None => ("0", complex),
+    };
+    let im: f64 = im.parse().map_err(|_| Error::Parse("Invalid OpenFermion coefficient!"))?;
+    if im != 0.0 {
+        return Err(Error::Parse("Only real OpenFermion coefficients can be imported!"));
+    }
+    re.parse().map_err(|_| Error::Parse("Invalid OpenFermion coefficient!"))
+}
+
+/// Returns `op` in the text format of OpenFermion's FermionOperator, e.g. "-1.0 [0^ 1] +\n-1.0 [1^ 0]",
+/// which `openfermion.FermionOperator` parses term by term.
+pub fn to_openfermion(op: &Operator) -> String {
+    op.terms
+        .iter()
+        .map(|(a, ac)| format!("{:?} [{}]", a, openfermion_term(ac)))
+        .collect::<Vec<_>>()
+        .join(" +\n")
+}
+
+/// Returns the operator printed by `str()` of an OpenFermion FermionOperator, terms "coefficient [i^ j ...]" joined by "+".
+///
+/// # Errors
+///
+/// * If a term is malformed or has a complex coefficient, this function returns an Error.
+pub fn from_openfermion(text: &str) -> Result<Operator, Error> {
+    let mut terms = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let open = rest.find('[').ok_or(Error::Parse("Invalid OpenFermion operator!"))?;
+        let close = rest.find(']').filter(|c| *c > open).ok_or(Error::Parse("Invalid OpenFermion operator!"))?;
+        let coefficient = rest[..open].trim().trim_start_matches('+');
+        let a = if coefficient.trim().is_empty() { 1.0 } else { parse_coefficient(coefficient)? };
+        terms.push((a, parse_openfermion_term(&rest[open + 1..close])?));
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(Operator::new(terms))
+}
+
+/// Returns `op` as a JSON object mapping OpenFermion term strings such as "0^ 1" to [real, imaginary] coefficients,
+/// i.e. the `terms` dictionary of a FermionOperator with its keys written in the notation of the text format.
+/// Repeated terms are summed.
+pub fn to_openfermion_json(op: &Operator) -> String {
+    let mut coefficients: Vec<(String, f64)> = Vec::new();
+    for (a, ac) in &op.terms {
+        let key = openfermion_term(ac);
+        match coefficients.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, c)) => *c += a,
+            None => coefficients.push((key, *a)),
+        }
+    }
+    let entries: Vec<String> = coefficients.iter().map(|(k, c)| format!("\"{}\": [{:?}, 0.0]", k, c)).collect();
+    format!("{{{}}}", entries.join(", "))
+}
+
+/// Returns the operator of a JSON object mapping OpenFermion term strings to coefficients, see `to_openfermion_json`.
+/// A coefficient is either a number or a [real, imaginary] pair.
+///
+/// # Errors
+///
+/// * If the text is not such an object or a coefficient is complex, this function returns an Error.
+pub fn from_openfermion_json(text: &str) -> Result<Operator, Error> {
+    let Json::Object(entries) = json::parse(text)? else {
+        return Err(Error::Parse("Invalid OpenFermion JSON!"));
+    };
+    let mut terms = Vec::new();
+    for (key, value) in &entries {
+        let (re, im) = match value.as_array() {
+            Some([re, im]) => (re.as_f64(), im.as_f64()),
+            _ => (value.as_f64(), Some(0.0)),
+        };
+        let (Some(coefficient), Some(im)) = (re, im) else {
+            return Err(Error::Parse("Invalid OpenFermion JSON!"));
+        };
+        if im != 0.0 {
+            return Err(Error::Parse("Only real OpenFermion coefficients can be imported!"));
+        }
+        terms.push((coefficient, parse_openfermion_term(key)?));
+    }
+    Ok(Operator::new(terms))
+}
+
+/// Represents a named array of 64 bit floats in row-major order, as stored in a NumPy .npy file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NpyArray {
+    /// The name, which becomes the key of the array in a .npz archive.
+    pub name: String,
+    /// The length of each axis.
+    pub shape: Vec<usize>,
+    /// The elements in row-major order.
+    pub data: Vec<f64>,
+}
+
+impl NpyArray {
+    /// Returns the array of the elements of `data` with the given shape.
+    ///
+    /// # Panics
+    ///
+    /// * If the number of elements does not match the shape, this function panics.
+    pub fn new(name: &str, shape: Vec<usize>, data: Vec<f64>) -> NpyArray {
+        assert_eq!(shape.iter().product::<usize>(), data.len(), "The data must fill the shape of the array!");
+        NpyArray {
+            name: name.to_string(),
+            shape,
+            data,
+        }
+    }
+
+    /// Returns the one-dimensional array of `vector`.
+    pub fn from_vector(name: &str, vector: &[f64]) -> NpyArray {
+        NpyArray::new(name, vec![vector.len()], vector.to_vec())
+    }
+
+    /// Returns the two-dimensional array of `matrix`, e.g. a dense Hamiltonian or the eigenvectors returned by `Matrix::eigh` as columns.
+    pub fn from_matrix(name: &str, matrix: &Matrix) -> NpyArray {
+        NpyArray::new(name, vec![matrix.rows(), matrix.cols()], matrix.data().to_vec())
+    }
+
+    /// Returns one array per column of `table`, named by the column, for `write_npz`.
+    pub fn from_table(table: &ResultTable) -> Vec<NpyArray> {
+        table
+            .columns()
+            .iter()
+            .map(|name| NpyArray::from_vector(name, &table.column(name).unwrap()))
+            .collect()
+    }
+
+    /// Returns the contents of the .npy file of this array, format version 1.0 with little endian doubles.
+    pub fn to_npy(&self) -> Vec<u8> {
+        let shape = match self.shape.as_slice() {
+            [n] => format!("({},)", n),
+            shape => format!("({})", shape.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(", ")),
+        };
+        let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': {}, }}", shape);
+        // The magic string, version and header length take 10 bytes, and the data must start 64 byte aligned.
+        let padding = 63 - (10 + header.len()) % 64;
+        header.push_str(&" ".repeat(padding));
+        header.push('\n');
+        let mut res = Vec::with_capacity(10 + header.len() + 8 * self.data.len());
+        res.extend_from_slice(b"\x93NUMPY\x01\x00");
+        res.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        res.extend_from_slice(header.as_bytes());
+        for x in &self.data {
+            res.extend_from_slice(&x.to_le_bytes());
+        }
+        res
+    }
+}
+
+/// Returns the CRC-32 checksum of `bytes`, as used by the zip format.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in bytes {
+        crc ^= *b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Writes `array` as a .npy file to `path`, which `numpy.load` reads back as a float64 array.
+pub fn write_npy<P: AsRef<Path>>(path: P, array: &NpyArray) -> io::Result<()> {
+    fs::write(path, array.to_npy())
+}
+
+/// Writes `arrays` to `w` as an uncompressed .npz archive, which `numpy.load` reads as a mapping from the array names to arrays.
+///
+/// # Errors
+///
+/// * If an array or the archive exceeds the 4 GiB limit of the zip format, or writing fails, this function returns an Error.
+pub fn write_npz<W: Write>(w: W, arrays: &[NpyArray]) -> io::Result<()> {
+    let too_large = || io::Error::new(io::ErrorKind::InvalidInput, "The .npz archive exceeds the size limit of the zip format!");
+    let mut w = BufWriter::new(w);
+    let mut directory = Vec::new();
+    let mut offset = 0u32;
+    for array in arrays {
+        let data = array.to_npy();
+        let name = format!("{}.npy", array.name);
+        let size = u32::try_from(data.len()).map_err(|_| too_large())?;
+        // Version 2.0, no flags, stored without compression, dated 1980-01-01.
+        let mut fields = Vec::new();
+        for (value, width) in [(20u32, 2), (0, 2), (0, 2), (0, 2), (0x21, 2), (crc32(&data), 4), (size, 4), (size, 4)] {
+            fields.extend_from_slice(&value.to_le_bytes()[..width]);
+        }
+        fields.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        fields.extend_from_slice(&0u16.to_le_bytes());
+        w.write_all(&0x0403_4b50u32.to_le_bytes())?;
+        w.write_all(&fields)?;
+        w.write_all(name.as_bytes())?;
+        w.write_all(&data)?;
+        directory.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes());
+        directory.extend_from_slice(&fields);
+        // No comment, disk 0, no attributes and the offset of the local header.
+        directory.extend_from_slice(&[0; 10]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+        offset = (30 + name.len() + data.len()).try_into().ok().and_then(|n: u32| offset.checked_add(n)).ok_or_else(too_large)?;
+    }
+    let entries = u16::try_from(arrays.len()).map_err(|_| too_large())?;
+    w.write_all(&directory)?;
+    w.write_all(&0x0605_4b50u32.to_le_bytes())?;
+    w.write_all(&[0; 4])?;
+    w.write_all(&entries.to_le_bytes())?;
+    w.write_all(&entries.to_le_bytes())?;
+    w.write_all(&(directory.len() as u32).to_le_bytes())?;
+    w.write_all(&offset.to_le_bytes())?;
+    w.write_all(&[0; 2])?;
+    w.flush()
+}
+
+/// Writes `arrays` as an uncompressed .npz archive to `path`, see `write_npz`.
+pub fn write_npz_file<P: AsRef<Path>>(path: P, arrays: &[NpyArray]) -> io::Result<()> {
+    write_npz(File::create(path)?, arrays)
+}
+
+/// The magic bytes opening every binary state and basis file.
+const BINARY_MAGIC: &[u8; 4] = b"RSED";
+
+/// The length of the header of a binary file: the magic bytes, the version, the kind, the determinant width, the amplitude type,
+/// three reserved bytes and the number of records.
+const BINARY_HEADER: usize = 20;
+
+/// The version of the binary state and basis format written by this crate. Files of every earlier version stay readable:
+/// later versions may only add kinds and amplitude types or use the reserved header bytes, and never change existing layouts.
+pub const BINARY_VERSION: u16 = 1;
+
+/// The kinds of binary files.
+const KIND_STATE: u8 = 1;
+const KIND_BASIS: u8 = 2;
+
+/// The precision of the amplitudes in a binary state file.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Amplitudes {
+    /// Little-endian f64, which round-trips exactly.
+    F64,
+    /// Little-endian f32, half the size.
+    F32,
+}
+
+impl Amplitudes {
+    fn code(self) -> u8 {
+        match self {
+            Amplitudes::F64 => 1,
+            Amplitudes::F32 => 2,
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Amplitudes::F64 => 8,
+            Amplitudes::F32 => 4,
+        }
+    }
+}
+
+/// Returns the binary file of `count` records written by `record`, each a determinant of `width` bytes and an amplitude of
+/// `amplitudes`, with the header in front and the CRC-32 checksum of all preceding bytes at the end.
+fn binary_file<F: FnMut(&mut Vec<u8>)>(kind: u8, width: u8, amplitudes: Option<Amplitudes>, count: usize, mut record: F) -> Vec<u8> {
+    let size = width as usize + amplitudes.map_or(0, |a| a.size());
+    let mut bytes = Vec::with_capacity(BINARY_HEADER + size * count + 4);
+    bytes.extend(BINARY_MAGIC);
+    bytes.extend(BINARY_VERSION.to_le_bytes());
+    bytes.extend([kind, width, amplitudes.map_or(0, |a| a.code()), 0, 0, 0]);
+    bytes.extend((count as u64).to_le_bytes());
+    record(&mut bytes);
+    let crc = crc32(&bytes);
+    bytes.extend(crc.to_le_bytes());
+    bytes
+}
+
+/// Returns the fewest bytes holding the largest of `indices`, at least one.
+fn determinant_width<I: Iterator<Item = u64>>(indices: I) -> u8 {
+    let max = indices.max().unwrap_or(0);
+    (((64 - max.leading_zeros()) as u8).div_ceil(8)).max(1)
+}
+
+/// Returns the determinant width, the amplitude type and the records of the binary file `bytes` of kind `kind`,
+/// after checking the magic bytes, the checksum, the version and the length.
+fn parse_binary(bytes: &[u8], kind: u8) -> Result<(usize, Option<Amplitudes>, &[u8]), Error> {
+    if bytes.len() < BINARY_HEADER + 4 || &bytes[..4] != BINARY_MAGIC {
+        return Err(Error::Parse("Not a rust_ed binary file!"));
+    }
+    let (body, crc) = bytes.split_at(bytes.len() - 4);
+    if crc32(body) != u32::from_le_bytes(crc.try_into().unwrap()) {
+        return Err(Error::Parse("The checksum of the binary file does not match, it is damaged!"));
+    }
+    let version = u16::from_le_bytes([body[4], body[5]]);
+    if version == 0 || version > BINARY_VERSION {
+        return Err(Error::Parse("The binary file was written by a newer version of the format!"));
+    }
+    if body[6] != kind {
+        return Err(Error::Parse("The binary file holds another kind of object!"));
+    }
+    let width = body[7] as usize;
+    let amplitudes = match body[8] {
+        0 => None,
+        1 => Some(Amplitudes::F64),
+        2 => Some(Amplitudes::F32),
+        _ => return Err(Error::Parse("Unknown amplitude type in the binary file!")),
+    };
+    let count = u64::from_le_bytes(body[12..20].try_into().unwrap());
+    let records = &body[BINARY_HEADER..];
+    let size = width + amplitudes.map_or(0, |a| a.size());
+    if !(1..=8).contains(&width) || (kind == KIND_STATE) != amplitudes.is_some() || Some(records.len() as u64) != count.checked_mul(size as u64) {
+        return Err(Error::Parse("Invalid binary file!"));
+    }
+    Ok((width, amplitudes, records))
+}
+
+/// Returns the determinant stored in the first `width` bytes of `record`.
+fn read_determinant(record: &[u8], width: usize) -> Slater {
+    let mut index = [0u8; 8];
+    index[..width].copy_from_slice(&record[..width]);
+    Slater::new(u64::from_le_bytes(index))
+}
+
+/// Returns `state` in the versioned binary format: a header, one record per determinant sorted by index, each the
+/// determinant in as few bytes as the largest one needs followed by its amplitude, and a CRC-32 checksum.
+///
+/// # Arguments
+///
+/// * `state` - The state to save.
+/// * `amplitudes` - The precision of the saved amplitudes.
+pub fn state_to_bytes(state: &State, amplitudes: Amplitudes) -> Vec<u8> {
+    let mut records: Vec<(u64, f64)> = state.amplitudes.iter().map(|(s, a)| (s.index, *a)).collect();
+    records.sort_by_key(|(index, _)| *index);
+    let width = determinant_width(records.iter().map(|(index, _)| *index));
+    binary_file(KIND_STATE, width, Some(amplitudes), records.len(), |bytes| {
+        for (index, a) in &records {
+            bytes.extend(&index.to_le_bytes()[..width as usize]);
+            match amplitudes {
+                Amplitudes::F64 => bytes.extend(a.to_le_bytes()),
+                Amplitudes::F32 => bytes.extend((*a as f32).to_le_bytes()),
+            }
+        }
+    })
+}
+
+/// Returns the state saved by `state_to_bytes`.
+///
+/// # Errors
+///
+/// * If `bytes` is not a binary state file, is damaged or was written by a newer version of the format, this function returns an Error.
+pub fn state_from_bytes(bytes: &[u8]) -> Result<State, Error> {
+    let (width, amplitudes, records) = parse_binary(bytes, KIND_STATE)?;
+    let amplitudes = amplitudes.ok_or(Error::Parse("Invalid binary file!"))?;
+    let states = records
+        .chunks_exact(width + amplitudes.size())
+        .map(|record| {
+            let a = &record[width..];
+            let a = match amplitudes {
+                Amplitudes::F64 => f64::from_le_bytes(a.try_into().unwrap()),
+                Amplitudes::F32 => f32::from_le_bytes(a.try_into().unwrap()) as f64,
+            };
+            (read_determinant(record, width), a)
+        })
+        .collect();
+    Ok(State::new(states))
+}
+
+/// Returns `basis` in the versioned binary format of `state_to_bytes`, with records of determinants only.
+pub fn basis_to_bytes(basis: &Basis) -> Vec<u8> {
+    let width = determinant_width(basis.states().iter().map(|s| s.index));
+    binary_file(KIND_BASIS, width, None, basis.dimension(), |bytes| {
+        for s in basis.states() {
+            bytes.extend(&s.index.to_le_bytes()[..width as usize]);
+        }
+    })
+}
+
+/// Returns the basis saved by `basis_to_bytes`.
+///
+/// # Errors
+///
+/// * If `bytes` is not a binary basis file, is damaged or was written by a newer version of the format, this function returns an Error.
+pub fn basis_from_bytes(bytes: &[u8]) -> Result<Basis, Error> {
+    let (width, _, records) = parse_binary(bytes, KIND_BASIS)?;
+    Ok(Basis::new(records.chunks_exact(width).map(|record| read_determinant(record, width)).collect()))
+}
+
+/// Writes `state` to the file `path` in the binary format of `state_to_bytes`, with f64 amplitudes.
+pub fn write_state<P: AsRef<Path>>(path: P, state: &State) -> io::Result<()> {
+    fs::write(path, state_to_bytes(state, Amplitudes::F64))
+}
+
+/// Returns the state in the binary file `path`, see `state_from_bytes`.
+///
+/// # Errors
+///
+/// * If the file cannot be read, this function returns `Error::IoError`.
+/// * If the file is not a valid binary state file, this function returns an Error.
+pub fn read_state<P: AsRef<Path>>(path: P) -> Result<State, Error> {
+    state_from_bytes(&fs::read(path)?)
+}
+
+/// Writes `basis` to the file `path` in the binary format of `basis_to_bytes`.
+pub fn write_basis<P: AsRef<Path>>(path: P, basis: &Basis) -> io::Result<()> {
+    fs::write(path, basis_to_bytes(basis))
+}
+
+/// Returns the basis in the binary file `path`, see `basis_from_bytes`.
+///
+/// # Errors
+///
+/// * If the file cannot be read, this function returns `Error::IoError`.
+/// * If the file is not a valid binary basis file, this function returns an Error.
+pub fn read_basis<P: AsRef<Path>>(path: P) -> Result<Basis, Error> {
+    basis_from_bytes(&fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::spectrum::Spectrum;
+    use crate::sweep::SweepTable;
+
+    #[test]
+    fn test_hydrogen_molecule() {
+        // H2 in a minimal basis at 0.74 Å.
+        let text = " &FCI NORB=  2,NELEC=2,MS2=0,
+  ORBSYM=1,5,
+  ISYM=1,
+ &END
+  0.6757101548D+00   1   1   1   1
+  0.6645817400E+00   2   2   1   1
+  0.1809270275E+00   2   1   2   1
+  0.6985609212E+00   2   2   2   2
+ -0.1252477303E+01   1   1   0   0
+ -0.4759344611E+00   2   2   0   0
+  0.7137758743E+00   0   0   0   0
+";
+        let dump = parse_fcidump(text).unwrap();
+        assert_eq!(dump.orbital_symmetries, vec![1, 5]);
+        assert_eq!(dump.spin_numbers(), Some((1, 1)));
+        assert_eq!(dump.integrals.two_body(0, 1, 0, 1), dump.integrals.two_body(1, 0, 1, 0));
+        let conv = SpinConvention::Interleaved;
+        let spectrum = Spectrum::new(&dump.hamiltonian(conv), Basis::fixed_spin_numbers(conv, 2, 1, 1));
+        // The singlet ground state mixes the closed shells |1↑1↓> and |2↑2↓>, coupled by the exchange integral (12|12).
+        let (a, b, k): (f64, f64, f64) = (2.0 * -1.252477303 + 0.6757101548, 2.0 * -0.4759344611 + 0.6985609212, 0.1809270275);
+        let exact = (a + b) / 2.0 - ((a - b).powi(2) / 4.0 + k * k).sqrt() + 0.7137758743;
+        assert!((spectrum.ground_state_energy() - exact).abs() < 1e-10);
+        assert!(matches!(parse_fcidump(" &FCI NELEC=2 &END"), Err(Error::Parse("FCIDUMP header lacks NORB!"))));
+        assert!(matches!(read_fcidump("/nonexistent/FCIDUMP"), Err(Error::IoError(e)) if e.kind() == io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_openfermion() {
+        let op = Operator::new(vec![
+            (-1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (0.25, vec![AC::Create(1), AC::Create(0), AC::Annihilate(0), AC::Annihilate(1)]),
+            (0.5, Vec::new()),
+        ]);
+        let text = to_openfermion(&op);
+        assert_eq!(text, "-1.0 [0^ 1] +\n0.25 [1^ 0^ 0 1] +\n0.5 []");
+        assert_eq!(from_openfermion(&text).unwrap().terms, op.terms);
+        assert_eq!(
+            from_openfermion("(2+0j) [3^ 2] +\n(-1e-05-0j) [2^ 3]").unwrap().terms,
+            vec![(2.0, vec![AC::Create(3), AC::Annihilate(2)]), (-1e-05, vec![AC::Create(2), AC::Annihilate(3)])]
+        );
+        assert!(from_openfermion("(1+1j) [0^ 0]").is_err());
+        let json = to_openfermion_json(&op);
+        assert_eq!(from_openfermion_json(&json).unwrap().terms, op.terms);
+        assert_eq!(from_openfermion_json("{\"0^ 0\": 1.5}").unwrap().terms, vec![(1.5, vec![AC::Create(0), AC::Annihilate(0)])]);
+    }
+
+    #[test]
+    fn test_npz() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        let m = Matrix::from_vec(2, 3, vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let npy = NpyArray::from_matrix("h", &m).to_npy();
+        assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+        assert_eq!(npy.len() % 64, 48);
+        assert!(String::from_utf8_lossy(&npy[10..128]).contains("'shape': (2, 3), }"));
+        assert_eq!(&npy[npy.len() - 8..], &6.0f64.to_le_bytes());
+        let table = SweepTable {
+            parameter_names: vec!["U".to_string()],
+            result_names: vec!["E".to_string()],
+            parameters: vec![vec![1.0], vec![2.0]],
+            results: vec![vec![-1.5], vec![-1.0]],
+        };
+        let arrays = NpyArray::from_table(&ResultTable::from(&table));
+        assert_eq!(arrays[1], NpyArray::new("E", vec![2], vec![-1.5, -1.0]));
+        let mut npz = Vec::new();
+        write_npz(&mut npz, &arrays).unwrap();
+        // Two local headers with "U.npy" and "E.npy", 144 byte arrays, two directory entries and the end record.
+        assert_eq!(npz.len(), 2 * (30 + 5 + 144) + 2 * (46 + 5) + 22);
+        assert_eq!(&npz[npz.len() - 22..npz.len() - 18], &0x0605_4b50u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_binary_state() {
+        let state = State::new(vec![(Slater::new(0b1010), 0.6), (Slater::new(0x1_0003), -0.8)]);
+        let bytes = state_to_bytes(&state, Amplitudes::F64);
+        // A 20 byte header, two records of a three byte determinant and an f64, and the checksum.
+        assert_eq!(bytes.len(), 20 + 2 * (3 + 8) + 4);
+        assert_eq!(state_from_bytes(&bytes).unwrap().amplitudes, state.amplitudes);
+        let single = state_from_bytes(&state_to_bytes(&state, Amplitudes::F32)).unwrap();
+        assert!((single.amplitudes[&Slater::new(0b1010)] - 0.6).abs() < 1e-7);
+        let mut damaged = bytes.clone();
+        damaged[25] ^= 1;
+        assert!(state_from_bytes(&damaged).is_err());
+        let basis = Basis::fixed_particle_number(6, 3);
+        let restored = basis_from_bytes(&basis_to_bytes(&basis)).unwrap();
+        assert_eq!(restored.states(), basis.states());
+        assert!(basis_from_bytes(&bytes).is_err());
+        let path = std::env::temp_dir().join(format!("rust_ed_state_{}.bin", std::process::id()));
+        write_state(&path, &state).unwrap();
+        assert_eq!(read_state(&path).unwrap().amplitudes, state.amplitudes);
+        assert!(read_basis(&path).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(read_state(&path), Err(Error::IoError(e)) if e.kind() == io::ErrorKind::NotFound));
+        assert!(matches!(read_basis(&path), Err(Error::IoError(e)) if e.kind() == io::ErrorKind::NotFound));
+    }
+}
@@ -0,0 +1,413 @@
+use std::fmt;
+use std::str::CharIndices;
+
+use crate::error::Error;
+use crate::{Operator, Slater, State, AC};
+
+/// This represents a JSON value. Objects keep their keys in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    /// A non-negative integer, kept exact beyond the 53 bits of an f64.
+    Integer(u64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Returns the value of `key`, if this is an object containing it.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns the number, if this is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(x) => Some(*x),
+            Json::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Returns the number as an unsigned integer, if this is a non-negative integral number.
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Json::Integer(i) => Some(*i),
+            Json::Number(x) if *x >= 0.0 && x.fract() == 0.0 && *x < u64::MAX as f64 => Some(*x as u64),
+            _ => None,
+        }
+    }
+
+    /// Returns the string, if this is a string.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements, if this is an array.
+    pub fn as_array(&self) -> Option<&[Json]> {
+        match self {
+            Json::Array(a) => Some(a),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            // Debug formatting of f64 round-trips exactly.
+            Json::Number(x) if x.is_finite() => write!(f, "{:?}", x),
+            Json::Number(_) => write!(f, "null"),
+            Json::Integer(i) => write!(f, "{}", i),
+            Json::String(s) => write_string(f, s),
+            Json::Array(a) => {
+                write!(f, "[")?;
+                for (k, v) in a.iter().enumerate() {
+                    if k > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(entries) => {
+                write!(f, "{{")?;
+                for (k, (key, v)) in entries.iter().enumerate() {
+                    if k > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", v)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Writes `s` as a quoted JSON string.
+fn write_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '\r' => write!(f, "\\r")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+/// A recursive descent parser over the bytes of a JSON text.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    /// Skips whitespace and returns the next byte, if any.
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b) = self.text.as_bytes().get(self.position) {
+            if b.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                return Some(*b);
+            }
+        }
+        None
+    }
+
+    /// Consumes the byte `b` after whitespace.
+    fn expect(&mut self, b: u8) -> Result<(), Error> {
+        if self.peek() == Some(b) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(Error::Parse("Invalid JSON!"))
+        }
+    }
+
+    /// Consumes the literal `word`.
+    fn literal(&mut self, word: &str, value: Json) -> Result<Json, Error> {
+        if self.text[self.position..].starts_with(word) {
+            self.position += word.len();
+            Ok(value)
+        } else {
+            Err(Error::Parse("Invalid JSON!"))
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, Error> {
+        match self.peek().ok_or(Error::Parse("Unexpected end of JSON!"))? {
+            b'n' => self.literal("null", Json::Null),
+            b't' => self.literal("true", Json::Bool(true)),
+            b'f' => self.literal("false", Json::Bool(false)),
+            b'"' => self.string().map(Json::String),
+            b'[' => {
+                self.position += 1;
+                let mut elements = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.position += 1;
+                    return Ok(Json::Array(elements));
+                }
+                loop {
+                    elements.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b']') => {
+                            self.position += 1;
+                            return Ok(Json::Array(elements));
+                        }
+                        _ => return Err(Error::Parse("Invalid JSON array!")),
+                    }
+                }
+            }
+            b'{' => {
+                self.position += 1;
+                let mut entries = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.position += 1;
+                    return Ok(Json::Object(entries));
+                }
+                loop {
+                    if self.peek() != Some(b'"') {
+                        return Err(Error::Parse("Invalid JSON object!"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    entries.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.position += 1,
+                        Some(b'}') => {
+                            self.position += 1;
+                            return Ok(Json::Object(entries));
+                        }
+                        _ => return Err(Error::Parse("Invalid JSON object!")),
+                    }
+                }
+            }
+            _ => {
+                let start = self.position;
+                let bytes = self.text.as_bytes();
+                while self.position < bytes.len() && matches!(bytes[self.position], b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                    self.position += 1;
+                }
+                let token = &self.text[start..self.position];
+                match token.parse() {
+                    Ok(i) => Ok(Json::Integer(i)),
+                    Err(_) => token.parse().map(Json::Number).map_err(|_| Error::Parse("Invalid JSON number!")),
+                }
+            }
+        }
+    }
+
+    /// Parses a quoted string starting at the current position.
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect(b'"')?;
+        let mut res = String::new();
+        let mut chars = self.text[self.position..].char_indices();
+        while let Some((k, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.position += k + 1;
+                    return Ok(res);
+                }
+                '\\' => {
+                    let (_, e) = chars.next().ok_or(Error::Parse("Invalid JSON string!"))?;
+                    res.push(match e {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'u' => {
+                            let mut code = hex_escape(&mut chars)?;
+                            // Characters above U+FFFF are escaped as a high surrogate followed by a low one.
+                            if (0xd800..0xdc00).contains(&code) {
+                                if !matches!((chars.next(), chars.next()), (Some((_, '\\')), Some((_, 'u')))) {
+                                    return Err(Error::Parse("Invalid JSON string!"));
+                                }
+                                let low = hex_escape(&mut chars)?;
+                                if !(0xdc00..0xe000).contains(&low) {
+                                    return Err(Error::Parse("Invalid JSON string!"));
+                                }
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or(Error::Parse("Invalid JSON string!"))?
+                        }
+                        _ => return Err(Error::Parse("Invalid JSON string!")),
+                    });
+                }
+                c => res.push(c),
+            }
+        }
+        Err(Error::Parse("Unterminated JSON string!"))
+    }
+}
+
+/// Returns the code unit of the four hex digits of a \u escape, taken from `chars`.
+fn hex_escape(chars: &mut CharIndices) -> Result<u32, Error> {
+    (0..4).try_fold(0, |code, _| {
+        let digit = chars.next().and_then(|(_, h)| h.to_digit(16)).ok_or(Error::Parse("Invalid JSON string!"))?;
+        Ok(16 * code + digit)
+    })
+}
+
+/// Returns the JSON value of `text`.
+///
+/// # Errors
+///
+/// * If `text` is not a single valid JSON value, this function returns an Error.
+pub fn parse(text: &str) -> Result<Json, Error> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.value()?;
+    if parser.peek().is_some() {
+        return Err(Error::Parse("Trailing characters after JSON value!"));
+    }
+    Ok(value)
+}
+
+/// Conversion of a type to and from a stable JSON representation, for persistence and communication between processes.
+///
+/// The representations are those a derived serde implementation would give: enums as {"Variant": value},
+/// structs as objects of their fields and tuples as arrays. A `State` lists its amplitudes as [slater, amplitude] pairs sorted by index.
+pub trait ToJson: Sized {
+    /// Returns the JSON representation.
+    fn to_json(&self) -> Json;
+
+    /// Returns the value of the JSON representation `json`.
+    ///
+    /// # Errors
+    ///
+    /// * If `json` does not represent a value of this type, this function returns an Error.
+    fn from_json(json: &Json) -> Result<Self, Error>;
+}
+
+impl ToJson for AC {
+    fn to_json(&self) -> Json {
+        match self {
+            AC::Create(i) => Json::Object(vec![("Create".to_string(), Json::Integer(*i))]),
+            AC::Annihilate(i) => Json::Object(vec![("Annihilate".to_string(), Json::Integer(*i))]),
+        }
+    }
+
+    fn from_json(json: &Json) -> Result<AC, Error> {
+        if let Some(i) = json.get("Create").and_then(|i| i.as_u64()) {
+            Ok(AC::Create(i))
+        } else if let Some(i) = json.get("Annihilate").and_then(|i| i.as_u64()) {
+            Ok(AC::Annihilate(i))
+        } else {
+            Err(Error::Parse("Invalid JSON for AC!"))
+        }
+    }
+}
+
+impl ToJson for Slater {
+    fn to_json(&self) -> Json {
+        Json::Object(vec![("index".to_string(), Json::Integer(self.index))])
+    }
+
+    fn from_json(json: &Json) -> Result<Slater, Error> {
+        json.get("index").and_then(|i| i.as_u64()).map(Slater::new).ok_or(Error::Parse("Invalid JSON for Slater!"))
+    }
+}
+
+impl ToJson for Operator {
+    fn to_json(&self) -> Json {
+        let terms = self
+            .terms
+            .iter()
+            .map(|(a, ac)| Json::Array(vec![Json::Number(*a), Json::Array(ac.iter().map(|c| c.to_json()).collect())]))
+            .collect();
+        Json::Object(vec![("terms".to_string(), Json::Array(terms))])
+    }
+
+    fn from_json(json: &Json) -> Result<Operator, Error> {
+        let terms = json.get("terms").and_then(|t| t.as_array()).ok_or(Error::Parse("Invalid JSON for Operator!"))?;
+        terms
+            .iter()
+            .map(|term| match term.as_array() {
+                Some([a, ac]) => Ok((
+                    a.as_f64().ok_or(Error::Parse("Invalid JSON for Operator!"))?,
+                    ac.as_array().ok_or(Error::Parse("Invalid JSON for Operator!"))?.iter().map(AC::from_json).collect::<Result<_, _>>()?,
+                )),
+                _ => Err(Error::Parse("Invalid JSON for Operator!")),
+            })
+            .collect::<Result<_, _>>()
+            .map(Operator::new)
+    }
+}
+
+impl ToJson for State {
+    fn to_json(&self) -> Json {
+        let mut amplitudes: Vec<(&Slater, &f64)> = self.amplitudes.iter().collect();
+        amplitudes.sort_by_key(|(s, _)| **s);
+        let pairs = amplitudes
+            .into_iter()
+            .map(|(s, a)| Json::Array(vec![s.to_json(), Json::Number(*a)]))
+            .collect();
+        Json::Object(vec![("amplitudes".to_string(), Json::Array(pairs))])
+    }
+
+    fn from_json(json: &Json) -> Result<State, Error> {
+        let pairs = json.get("amplitudes").and_then(|t| t.as_array()).ok_or(Error::Parse("Invalid JSON for State!"))?;
+        pairs
+            .iter()
+            .map(|pair| match pair.as_array() {
+                Some([s, a]) => Ok((Slater::from_json(s)?, a.as_f64().ok_or(Error::Parse("Invalid JSON for State!"))?)),
+                _ => Err(Error::Parse("Invalid JSON for State!")),
+            })
+            .collect::<Result<_, _>>()
+            .map(State::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let json = parse(r#" {"a": [1, -2.5e-3, true, null], "b\n": "x\"yé", "c": {}} "#).unwrap();
+        assert_eq!(json.get("a").unwrap().as_array().unwrap()[1], Json::Number(-2.5e-3));
+        assert_eq!(json.get("b\n").unwrap().as_str(), Some("x\"yé"));
+        assert_eq!(parse(&json.to_string()).unwrap(), json);
+        assert!(matches!(parse("[1, 2"), Err(Error::Parse(_))));
+        assert!(parse("{} x").is_err());
+        assert_eq!(parse(r#""\u00e9\uD83D\uDE00""#).unwrap().as_str(), Some("é😀"));
+        assert!(parse(r#""\uD83D""#).is_err());
+        assert!(parse(r#""\uDE00""#).is_err());
+        assert!(parse(r#""\u+041""#).is_err());
+        assert!(parse(r#""\u04""#).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let op = Operator::new(vec![(-0.1, vec![AC::Create(0), AC::Annihilate(7)]), (2.0, Vec::new())]);
+        assert_eq!(op.to_json().to_string(), r#"{"terms":[[-0.1,[{"Create":0},{"Annihilate":7}]],[2.0,[]]]}"#);
+        assert_eq!(Operator::from_json(&parse(&op.to_json().to_string()).unwrap()).unwrap().terms, op.terms);
+        let state = State::new(vec![(Slater::new(6), 0.6), (Slater::new(3), -0.8), (Slater::new(u64::MAX), 0.0)]);
+        let text = state.to_json().to_string();
+        assert_eq!(text, r#"{"amplitudes":[[{"index":3},-0.8],[{"index":6},0.6],[{"index":18446744073709551615},0.0]]}"#);
+        assert_eq!(State::from_json(&parse(&text).unwrap()).unwrap().amplitudes, state.amplitudes);
+        assert!(AC::from_json(&parse(r#"{"Create": -1}"#).unwrap()).is_err());
+    }
+}
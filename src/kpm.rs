@@ -0,0 +1,232 @@
+use std::f64::consts::PI;
+
+use crate::lanczos::tridiagonalize;
+use crate::linalg::{dot, LinearOperator};
+use crate::random::Rng;
+
+/// Relative padding applied to spectral bounds so the rescaled spectrum stays strictly inside (-1, 1).
+const BOUND_PADDING: f64 = 0.01;
+
+/// Represents the Chebyshev moments μ_n of a spectral density rescaled to [-1, 1] by E = center + half_width x.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChebyshevMoments {
+    /// The moments μ_n.
+    moments: Vec<f64>,
+    /// The center of the spectral interval.
+    center: f64,
+    /// Half the width of the spectral interval.
+    half_width: f64,
+}
+
+impl ChebyshevMoments {
+    /// Returns the moments μ_n.
+    pub fn moments(&self) -> &[f64] {
+        &self.moments
+    }
+
+    /// Returns the energy interval (E_min, E_max) the moments were computed on.
+    pub fn bounds(&self) -> (f64, f64) {
+        (self.center - self.half_width, self.center + self.half_width)
+    }
+
+    /// Returns the density reconstructed from the moments, damped by the Jackson kernel, at `energies`.
+    /// Energies outside the spectral interval get zero density.
+    pub fn density(&self, energies: &[f64]) -> Vec<f64> {
+        let g = jackson_kernel(self.moments.len());
+        energies
+            .iter()
+            .map(|e| {
+                let x = (e - self.center) / self.half_width;
+                if x.abs() >= 1.0 {
+                    return 0.0;
+                }
+                let (mut t_prev, mut t) = (1.0, x);
+                let mut sum = g[0] * self.moments[0];
+                for (gn, mu) in g.iter().zip(&self.moments).skip(1) {
+                    sum += 2.0 * gn * mu * t;
+                    let t_next = 2.0 * x * t - t_prev;
+                    t_prev = t;
+                    t = t_next;
+                }
+                sum / (PI * (1.0 - x * x).sqrt() * self.half_width)
+            })
+            .collect()
+    }
+}
+
+/// Returns the Jackson kernel coefficients g_n for `n_moments` moments.
+pub fn jackson_kernel(n_moments: usize) -> Vec<f64> {
+    let np1 = n_moments as f64 + 1.0;
+    let q = PI / np1;
+    (0..n_moments)
+        .map(|n| {
+            let n = n as f64;
+            ((np1 - n) * (q * n).cos() + (q * n).sin() / q.tan()) / np1
+        })
+        .collect()
+}
+
+/// Returns padded lower and upper bounds of the spectrum of `h`, estimated from the extremal Ritz values of a short Lanczos run.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `n_steps` - The number of Lanczos steps.
+/// * `seed` - The seed of the random starting vector.
+pub fn spectral_bounds<H: LinearOperator + ?Sized>(h: &H, n_steps: usize, seed: u64) -> (f64, f64) {
+    let start = Rng::new(seed).normal_vector(h.dimension());
+    let ritz = tridiagonalize(h, &start, n_steps).poles();
+    let (lo, hi) = ritz
+        .positions()
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), e| (lo.min(*e), hi.max(*e)));
+    let pad = BOUND_PADDING * (hi - lo).max(1.0);
+    (lo - pad, hi + pad)
+}
+
+/// Returns the moments μ_n = <s|T_n(H̃)|s> of the local density of states of |s>, where H̃ is `h` rescaled to `bounds`.
+/// The reconstructed density integrates to <s|s>.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `start` - The state |s>, e.g. c†|0> for the particle part of a spectral function.
+/// * `n_moments` - The number of Chebyshev moments.
+/// * `bounds` - Bounds (E_min, E_max) enclosing the whole spectrum of `h`.
+pub fn spectral_moments<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    n_moments: usize,
+    bounds: (f64, f64),
+) -> ChebyshevMoments {
+    let center = (bounds.0 + bounds.1) / 2.0;
+    let half_width = (bounds.1 - bounds.0) / 2.0;
+    ChebyshevMoments {
+        moments: chebyshev_moments(h, start, n_moments, center, half_width),
+        center,
+        half_width,
+    }
+}
+
+/// Returns the moments μ_n = Tr T_n(H̃) / D of the density of states of `h`, normalized to one,
+/// with the trace estimated stochastically from random ±1 vectors.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `n_moments` - The number of Chebyshev moments.
+/// * `n_random` - The number of random vectors in the trace estimate.
+/// * `bounds` - Bounds (E_min, E_max) enclosing the whole spectrum of `h`, e.g. from `spectral_bounds`.
+/// * `seed` - The seed of the random vectors.
+pub fn dos_moments<H: LinearOperator + ?Sized>(
+    h: &H,
+    n_moments: usize,
+    n_random: usize,
+    bounds: (f64, f64),
+    seed: u64,
+) -> ChebyshevMoments {
+    let center = (bounds.0 + bounds.1) / 2.0;
+    let half_width = (bounds.1 - bounds.0) / 2.0;
+    let mut rng = Rng::new(seed);
+    let d = h.dimension() as f64;
+    let mut moments = vec![0.0; n_moments];
+    for _ in 0..n_random {
+        let r = rng.sign_vector(h.dimension());
+        for (m, mr) in moments
+            .iter_mut()
+            .zip(chebyshev_moments(h, &r, n_moments, center, half_width))
+        {
+            *m += mr / (d * n_random as f64);
+        }
+    }
+    ChebyshevMoments {
+        moments,
+        center,
+        half_width,
+    }
+}
+
+/// Returns <s|T_n(H̃)|s> for n < n_moments using the doubling relations
+/// μ_2n = 2<α_n|α_n> - μ_0 and μ_(2n+1) = 2<α_(n+1)|α_n> - μ_1, where α_n = T_n(H̃)|s>.
+fn chebyshev_moments<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    n_moments: usize,
+    center: f64,
+    half_width: f64,
+) -> Vec<f64> {
+    let mut moments = vec![0.0; n_moments];
+    if n_moments == 0 {
+        return moments;
+    }
+    let rescaled = |x: &[f64], y: &mut [f64]| {
+        h.apply(x, y);
+        for (yi, xi) in y.iter_mut().zip(x) {
+            *yi = (*yi - center * xi) / half_width;
+        }
+    };
+    let mut previous = start.to_vec();
+    let mut current = vec![0.0; start.len()];
+    rescaled(&previous, &mut current);
+    let mu0 = dot(&previous, &previous);
+    let mu1 = dot(&current, &previous);
+    moments[0] = mu0;
+    if n_moments > 1 {
+        moments[1] = mu1;
+    }
+    let mut next = vec![0.0; start.len()];
+    let mut n = 1;
+    while 2 * n < n_moments {
+        moments[2 * n] = 2.0 * dot(&current, &current) - mu0;
+        if 2 * n + 1 < n_moments {
+            rescaled(&current, &mut next);
+            for (x, p) in next.iter_mut().zip(&previous) {
+                *x = 2.0 * *x - p;
+            }
+            moments[2 * n + 1] = 2.0 * dot(&next, &current) - mu1;
+            std::mem::swap(&mut previous, &mut current);
+            std::mem::swap(&mut current, &mut next);
+        }
+        n += 1;
+    }
+    moments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::Matrix;
+
+    #[test]
+    fn test_moments_diagonal() {
+        let levels = [-0.7, -0.1, 0.2, 0.5];
+        let mut h = Matrix::zeros(4, 4);
+        for (i, e) in levels.iter().enumerate() {
+            h[(i, i)] = *e;
+        }
+        let kpm = dos_moments(&h, 9, 1, (-1.0, 1.0), 3);
+        for (n, mu) in kpm.moments().iter().enumerate() {
+            let exact: f64 = levels.iter().map(|e| (n as f64 * e.acos()).cos()).sum::<f64>() / 4.0;
+            assert!((mu - exact).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_local_density_normalization() {
+        let n = 40;
+        let mut h = Matrix::zeros(n, n);
+        for i in 0..n - 1 {
+            h[(i, i + 1)] = -1.0;
+            h[(i + 1, i)] = -1.0;
+        }
+        let bounds = spectral_bounds(&h, 30, 1);
+        assert!(bounds.0 < -1.99 && bounds.1 > 1.99);
+        let mut s = vec![0.0; n];
+        s[0] = 1.0;
+        let kpm = spectral_moments(&h, &s, 128, bounds);
+        let de = 0.001;
+        let energies: Vec<f64> = (0..6000).map(|i| -3.0 + de * i as f64).collect();
+        let integral: f64 = kpm.density(&energies).iter().sum::<f64>() * de;
+        assert!((integral - 1.0).abs() < 1e-2);
+    }
+}
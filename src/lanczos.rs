@@ -0,0 +1,695 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::complex::Complex;
+use crate::config;
+use crate::disk::DiskVectors;
+use crate::error::Error;
+use crate::json::{self, Json};
+use crate::linalg::{dot, norm, tridiagonal_eigh, LinearOperator, Matrix};
+use crate::log::{self, Level};
+use crate::orthogonalize::{orthogonalize, Reorthogonalization};
+use crate::spectral::Poles;
+
+/// Lanczos recursions stop once the next off-diagonal coefficient drops below this value.
+const BREAKDOWN_TOLERANCE: f64 = 1e-12;
+
+/// Represents the continued fraction <s|(z - H)^{-1}|s> = b_0² / (z - a_0 - b_1² / (z - a_1 - ...))
+/// generated by a Lanczos run started from |s>.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuedFraction {
+    /// The diagonal Lanczos coefficients a_n = <v_n|H|v_n>.
+    a: Vec<f64>,
+    /// The off-diagonal Lanczos coefficients, b_0 = √<s|s> and b_n = ||H v_(n-1) - a_(n-1) v_(n-1) - b_(n-1) v_(n-2)||.
+    b: Vec<f64>,
+}
+
+impl ContinuedFraction {
+    /// Returns the continued fraction with the supplied coefficients.
+    ///
+    /// # Panics
+    ///
+    /// * If there are not as many b_n as a_n.
+    pub fn new(a: Vec<f64>, b: Vec<f64>) -> ContinuedFraction {
+        assert_eq!(a.len(), b.len(), "Every level of the continued fraction needs an a_n and a b_n!");
+        ContinuedFraction { a, b }
+    }
+
+    /// Returns the diagonal coefficients a_n.
+    pub fn a(&self) -> &[f64] {
+        &self.a
+    }
+
+    /// Returns the off-diagonal coefficients b_n, where b_0² is the norm of the starting vector.
+    pub fn b(&self) -> &[f64] {
+        &self.b
+    }
+
+    /// Returns the number of levels in the continued fraction.
+    pub fn depth(&self) -> usize {
+        self.a.len()
+    }
+
+    /// Returns the value of the continued fraction at the complex frequency `z`.
+    pub fn evaluate(&self, z: Complex) -> Complex {
+        let mut tail = Complex::default();
+        for (a, b) in self.a.iter().zip(&self.b).rev() {
+            tail = (b * b) / (z - *a - tail);
+        }
+        tail
+    }
+
+    /// Returns the poles and weights of the continued fraction, obtained by diagonalizing the tridiagonal Lanczos matrix.
+    pub fn poles(&self) -> Poles {
+        if self.a.is_empty() {
+            return Poles::new(Vec::new(), Vec::new());
+        }
+        let (positions, vectors) = tridiagonal_eigh(&self.a, &self.b[1..]);
+        let norm = self.b[0] * self.b[0];
+        let weights = (0..positions.len())
+            .map(|k| norm * vectors[(0, k)] * vectors[(0, k)])
+            .collect();
+        Poles::new(positions, weights)
+    }
+}
+
+/// Returns the continued fraction of <s|(z - H)^{-1}|s> after at most `n_steps` Lanczos steps started from `start`.
+/// The recursion terminates early if the Krylov space becomes invariant.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `start` - The starting vector |s>, which does not need to be normalized.
+/// * `n_steps` - The maximal number of Lanczos steps.
+pub fn tridiagonalize<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    n_steps: usize,
+) -> ContinuedFraction {
+    let mut a = Vec::with_capacity(n_steps);
+    let mut b = Vec::with_capacity(n_steps);
+    let b0 = norm(start);
+    if b0 < BREAKDOWN_TOLERANCE {
+        return ContinuedFraction::new(a, b);
+    }
+    b.push(b0);
+    let mut previous = vec![0.0; start.len()];
+    let mut current: Vec<f64> = start.iter().map(|x| x / b0).collect();
+    let mut w = vec![0.0; start.len()];
+    for step in 0..n_steps {
+        h.apply(&current, &mut w);
+        let an = dot(&current, &w);
+        a.push(an);
+        let bn = if step == 0 { 0.0 } else { b[step] };
+        for ((wi, ci), pi) in w.iter_mut().zip(&current).zip(&previous) {
+            *wi -= an * ci + bn * pi;
+        }
+        let next = norm(&w);
+        if next < BREAKDOWN_TOLERANCE || step + 1 == n_steps {
+            break;
+        }
+        b.push(next);
+        for x in w.iter_mut() {
+            *x /= next;
+        }
+        std::mem::swap(&mut previous, &mut current);
+        std::mem::swap(&mut current, &mut w);
+    }
+    ContinuedFraction::new(a, b)
+}
+
+/// Represents an orthonormal Krylov basis generated by a Lanczos run, together with its tridiagonal representation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Krylov {
+    /// The orthonormal Lanczos vectors v_n.
+    vectors: Vec<Vec<f64>>,
+    /// The Lanczos coefficients; b_0 is the norm of the starting vector.
+    coefficients: ContinuedFraction,
+}
+
+impl Krylov {
+    /// Returns the orthonormal Lanczos vectors.
+    pub fn vectors(&self) -> &[Vec<f64>] {
+        &self.vectors
+    }
+
+    /// Returns the Lanczos coefficients.
+    pub fn coefficients(&self) -> &ContinuedFraction {
+        &self.coefficients
+    }
+
+    /// Returns the Ritz values, in ascending order, and the Ritz vectors expressed in the Lanczos vectors, stored as columns.
+    pub fn ritz(&self) -> (Vec<f64>, Matrix) {
+        tridiagonal_eigh(&self.coefficients.a, &self.coefficients.b[1.min(self.coefficients.b.len())..])
+    }
+
+    /// Returns the Krylov approximation of f(H)|s> for the starting vector |s>.
+    pub fn apply_function<F: Fn(f64) -> f64>(&self, f: F) -> Vec<f64> {
+        if self.vectors.is_empty() {
+            return Vec::new();
+        }
+        let (values, vectors) = self.ritz();
+        let b0 = self.coefficients.b[0];
+        let mut c = vec![0.0; values.len()];
+        for (j, e) in values.iter().enumerate() {
+            let fj = b0 * f(*e) * vectors[(0, j)];
+            for (k, ck) in c.iter_mut().enumerate() {
+                *ck += vectors[(k, j)] * fj;
+            }
+        }
+        self.combine(&c)
+    }
+
+    /// Returns the vector Σ_n c_n v_n in the original space.
+    pub fn combine(&self, coefficients: &[f64]) -> Vec<f64> {
+        let mut x = vec![0.0; self.vectors.first().map_or(0, |v| v.len())];
+        for (c, v) in coefficients.iter().zip(&self.vectors) {
+            for (xi, vi) in x.iter_mut().zip(v) {
+                *xi += c * vi;
+            }
+        }
+        x
+    }
+}
+
+/// Returns the Krylov basis spanned by at most `n_steps` Lanczos vectors started from `start`,
+/// with full reorthogonalization. The recursion terminates early if the Krylov space becomes invariant.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `start` - The starting vector, which does not need to be normalized.
+/// * `n_steps` - The maximal number of Lanczos steps.
+pub fn krylov<H: LinearOperator + ?Sized>(h: &H, start: &[f64], n_steps: usize) -> Krylov {
+    let mut a = Vec::new();
+    let mut b = Vec::new();
+    let mut vectors = Vec::new();
+    let b0 = norm(start);
+    if b0 < BREAKDOWN_TOLERANCE || n_steps == 0 {
+        return Krylov {
+            vectors,
+            coefficients: ContinuedFraction::new(a, b),
+        };
+    }
+    b.push(b0);
+    vectors.push(start.iter().map(|x| x / b0).collect::<Vec<f64>>());
+    let mut w = vec![0.0; start.len()];
+    loop {
+        let step = vectors.len() - 1;
+        h.apply(&vectors[step], &mut w);
+        a.push(dot(&vectors[step], &w));
+        let next = orthogonalize(&mut w, &vectors, Reorthogonalization::Twice);
+        if next < BREAKDOWN_TOLERANCE || vectors.len() == n_steps {
+            break;
+        }
+        b.push(next);
+        vectors.push(w.iter().map(|x| x / next).collect());
+    }
+    Krylov {
+        vectors,
+        coefficients: ContinuedFraction::new(a, b),
+    }
+}
+
+/// Returns the lowest eigenvalue and the corresponding normalized eigenvector of `h`,
+/// computed by Lanczos iteration with full reorthogonalization.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `start` - The starting vector, which must have an overlap with the ground state.
+/// * `max_steps` - The maximal number of Lanczos steps.
+/// * `tolerance` - The required residual norm ||H x - E x|| of the returned eigenpair.
+///
+/// # Errors
+///
+/// * If the Lanczos vectors exceed the memory budget of the configuration, see `config::check_memory`, the starting vector vanishes
+///   or the residual is not below `tolerance` after `max_steps` steps, this function returns an Error.
+pub fn ground_state<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    max_steps: usize,
+    tolerance: f64,
+) -> Result<(f64, Vec<f64>), Error> {
+    let mut span = log::span(Level::Debug, "lanczos::ground_state", &[("dimension", h.dimension() as f64)]);
+    let n = h.dimension() as u64;
+    config::check_memory("lanczos::ground_state", 8u64.saturating_mul(n).saturating_mul((max_steps as u64).min(n)))?;
+    let b0 = norm(start);
+    if b0 < BREAKDOWN_TOLERANCE {
+        return Err(Error::Invalid("Lanczos starting vector vanishes!"));
+    }
+    let mut vectors: Vec<Vec<f64>> = vec![start.iter().map(|x| x / b0).collect()];
+    let mut a = Vec::new();
+    let mut b: Vec<f64> = Vec::new();
+    let mut w = vec![0.0; start.len()];
+    for step in 0..max_steps.min(h.dimension()) {
+        h.apply(&vectors[step], &mut w);
+        a.push(dot(&vectors[step], &w));
+        let next = orthogonalize(&mut w, &vectors, Reorthogonalization::Once);
+        let (values, ritz) = tridiagonal_eigh(&a, &b);
+        let residual = next * ritz[(step, 0)].abs();
+        log::event(Level::Trace, "lanczos::ground_state", "step", &[("step", step as f64), ("energy", values[0]), ("residual", residual)]);
+        if residual < tolerance || next < BREAKDOWN_TOLERANCE || step + 1 == h.dimension() {
+            span.record("steps", (step + 1) as f64);
+            span.record("energy", values[0]);
+            let mut x = vec![0.0; start.len()];
+            for (k, v) in vectors.iter().enumerate() {
+                for (xi, vi) in x.iter_mut().zip(v) {
+                    *xi += ritz[(k, 0)] * vi;
+                }
+            }
+            let nx = norm(&x);
+            for xi in x.iter_mut() {
+                *xi /= nx;
+            }
+            return Ok((values[0], x));
+        }
+        b.push(next);
+        vectors.push(w.iter().map(|x| x / next).collect());
+    }
+    Err(Error::SolverNotConverged)
+}
+
+/// Continues the Lanczos run whose vectors v_0, ..., v_k are in `vectors` and whose coefficients a_0, ..., a_{k-1} and
+/// b_0, ..., b_{k-1} are `a` and `b`, reading the vectors back from the store to reorthogonalize. After every step `save`
+/// receives the store, with the next vector appended, and the coefficients.
+fn lanczos_on_disk<H, S>(
+    h: &H,
+    vectors: &mut DiskVectors,
+    mut a: Vec<f64>,
+    mut b: Vec<f64>,
+    max_steps: usize,
+    tolerance: f64,
+    mut save: S,
+) -> Result<(f64, Vec<f64>), Error>
+where
+    H: LinearOperator + ?Sized,
+    S: FnMut(&DiskVectors, &[f64], &[f64]) -> Result<(), Error>,
+{
+    let mut span = log::span(Level::Debug, "lanczos::ground_state_out_of_core", &[("dimension", h.dimension() as f64)]);
+    let n = vectors.len();
+    config::check_memory("lanczos::ground_state_out_of_core", 16u64.saturating_mul(n as u64))?;
+    let mut v = vec![0.0; n];
+    vectors.read(vectors.count() - 1, &mut v)?;
+    let mut w = vec![0.0; n];
+    for step in a.len()..max_steps.min(h.dimension()) {
+        h.apply(&v, &mut w);
+        a.push(dot(&v, &w));
+        // Modified Gram-Schmidt with two passes over each stored vector, for the overlap and the projection, which are streamed.
+        for k in 0..vectors.count() {
+            let mut overlap = 0.0;
+            vectors.stream(k, |offset, block| overlap += dot(&w[offset..offset + block.len()], block))?;
+            vectors.stream(k, |offset, block| {
+                for (wi, vi) in w[offset..].iter_mut().zip(block) {
+                    *wi -= overlap * vi;
+                }
+            })?;
+        }
+        let next = norm(&w);
+        let (values, ritz) = tridiagonal_eigh(&a, &b);
+        let residual = next * ritz[(step, 0)].abs();
+        log::event(Level::Trace, "lanczos::ground_state_out_of_core", "step", &[("step", step as f64), ("energy", values[0]), ("residual", residual)]);
+        if residual < tolerance || next < BREAKDOWN_TOLERANCE || step + 1 == h.dimension() {
+            span.record("steps", (step + 1) as f64);
+            span.record("energy", values[0]);
+            // The vector v is no longer needed, and holds the eigenvector instead.
+            let x = &mut v;
+            x.iter_mut().for_each(|xi| *xi = 0.0);
+            for k in 0..vectors.count() {
+                vectors.stream(k, |offset, block| {
+                    for (xi, vi) in x[offset..].iter_mut().zip(block) {
+                        *xi += ritz[(k, 0)] * vi;
+                    }
+                })?;
+            }
+            let nx = norm(x);
+            for xi in x.iter_mut() {
+                *xi /= nx;
+            }
+            return Ok((values[0], v));
+        }
+        b.push(next);
+        for (vi, wi) in v.iter_mut().zip(&w) {
+            *vi = wi / next;
+        }
+        vectors.push(&v)?;
+        save(vectors, &a, &b)?;
+    }
+    Err(Error::SolverNotConverged)
+}
+
+/// Returns the lowest eigenvalue and eigenvector of `h` as `ground_state` does, but with the Krylov vectors kept in a scratch file
+/// in `directory`, so that only the two vectors of the product with `h` are held in memory however many steps are taken.
+/// Every step streams the stored vectors back twice to reorthogonalize, and the eigenvector is assembled in one more pass.
+///
+/// # Arguments
+///
+/// * `h` - The operator, e.g. a `basis::MatrixFree` when neither the matrix nor the vectors fit in memory.
+/// * `start` - The starting vector, which must not vanish.
+/// * `max_steps` - The maximal number of Lanczos steps.
+/// * `tolerance` - The required residual ‖H x - E x‖ of the eigenpair.
+/// * `directory` - The directory of the scratch file, which is removed before returning.
+///
+/// # Errors
+///
+/// * If the two vectors in memory exceed the memory budget of the configuration, see `config::check_memory`, the starting vector
+///   vanishes, the scratch file cannot be used or Lanczos does not converge, this function returns an Error.
+pub fn ground_state_out_of_core<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    max_steps: usize,
+    tolerance: f64,
+    directory: &Path,
+) -> Result<(f64, Vec<f64>), Error> {
+    let b0 = norm(start);
+    if b0 < BREAKDOWN_TOLERANCE {
+        return Err(Error::Invalid("Lanczos starting vector vanishes!"));
+    }
+    let mut vectors = DiskVectors::create(directory, start.len())?;
+    vectors.push(&start.iter().map(|x| x / b0).collect::<Vec<_>>())?;
+    lanczos_on_disk(h, &mut vectors, Vec::new(), Vec::new(), max_steps, tolerance, |_, _, _| Ok(()))
+}
+
+/// Returns the path of the file holding the Lanczos vectors of the checkpoint `checkpoint`.
+fn vectors_path(checkpoint: &Path) -> PathBuf {
+    let mut path = checkpoint.as_os_str().to_owned();
+    path.push(".vectors");
+    PathBuf::from(path)
+}
+
+/// Returns the lowest eigenvalue and eigenvector of `h` as `ground_state_out_of_core` does, with the run recorded every `interval`
+/// steps in the checkpoint file `checkpoint` and the Lanczos vectors in the file next to it with ".vectors" appended, so that a run
+/// that is interrupted, e.g. by preemption on a cluster, can be continued with `resume`. If the checkpoint exists, the run is
+/// resumed from it instead of started from `start`, so rerunning the same calculation picks up where it stopped.
+/// Both files are removed once the run converges.
+///
+/// The checkpoint is a JSON object with the dimension, the interval and the tridiagonal coefficients a and b. The starting vector
+/// is the first Lanczos vector, so no random state is needed to continue.
+///
+/// # Arguments
+///
+/// * `h` - The operator.
+/// * `start` - The starting vector, which must not vanish.
+/// * `max_steps` - The maximal number of Lanczos steps, including those of earlier runs.
+/// * `tolerance` - The required residual ‖H x - E x‖ of the eigenpair.
+/// * `checkpoint` - The path of the checkpoint file.
+/// * `interval` - The number of steps between checkpoints.
+///
+/// # Errors
+///
+/// * If the starting vector vanishes, the files cannot be used or belong to an operator of another dimension, or Lanczos does not
+///   converge, this function returns an Error.
+pub fn ground_state_checkpointed<H: LinearOperator + ?Sized>(
+    h: &H,
+    start: &[f64],
+    max_steps: usize,
+    tolerance: f64,
+    checkpoint: &Path,
+    interval: usize,
+) -> Result<(f64, Vec<f64>), Error> {
+    if checkpoint.exists() {
+        return resume(h, checkpoint, max_steps, tolerance);
+    }
+    let b0 = norm(start);
+    if b0 < BREAKDOWN_TOLERANCE {
+        return Err(Error::Invalid("Lanczos starting vector vanishes!"));
+    }
+    let mut vectors = DiskVectors::open(&vectors_path(checkpoint), start.len())?;
+    vectors.truncate(0)?;
+    vectors.push(&start.iter().map(|x| x / b0).collect::<Vec<_>>())?;
+    run_checkpointed(h, vectors, Vec::new(), Vec::new(), max_steps, tolerance, checkpoint, interval.max(1))
+}
+
+/// Returns the lowest eigenvalue and eigenvector of `h` found by continuing the Lanczos run recorded in `checkpoint` by
+/// `ground_state_checkpointed`, checkpointing at the same interval.
+///
+/// # Arguments
+///
+/// * `h` - The operator of the interrupted run.
+/// * `checkpoint` - The path of the checkpoint file.
+/// * `max_steps` - The maximal number of Lanczos steps, including those of earlier runs.
+/// * `tolerance` - The required residual ‖H x - E x‖ of the eigenpair.
+///
+/// # Errors
+///
+/// * If the files cannot be read or belong to an operator of another dimension, or Lanczos does not converge, this function returns an Error.
+pub fn resume<H: LinearOperator + ?Sized>(h: &H, checkpoint: &Path, max_steps: usize, tolerance: f64) -> Result<(f64, Vec<f64>), Error> {
+    let text = fs::read_to_string(checkpoint)?;
+    let json = json::parse(&text)?;
+    let integer = |key| json.get(key).and_then(|n| n.as_u64()).ok_or(Error::Parse("Invalid Lanczos checkpoint!"));
+    let numbers = |key| {
+        json.get(key)
+            .and_then(|l| l.as_array())
+            .ok_or(Error::Parse("Invalid Lanczos checkpoint!"))?
+            .iter()
+            .map(|x| x.as_f64().ok_or(Error::Parse("Invalid Lanczos checkpoint!")))
+            .collect::<Result<Vec<f64>, _>>()
+    };
+    let (dimension, interval) = (integer("dimension")? as usize, integer("interval")? as usize);
+    let (a, b) = (numbers("a")?, numbers("b")?);
+    if dimension != h.dimension() {
+        return Err(Error::DimensionMismatch);
+    }
+    if a.len() != b.len() {
+        return Err(Error::Parse("Invalid Lanczos checkpoint!"));
+    }
+    let mut vectors = DiskVectors::open(&vectors_path(checkpoint), dimension)?;
+    // Vectors written after the last checkpoint are dropped, and computed again.
+    if vectors.count() <= a.len() {
+        return Err(Error::Invalid("The Lanczos vectors of the checkpoint are missing!"));
+    }
+    vectors.truncate(a.len() + 1)?;
+    log::event(Level::Info, "lanczos::resume", "resume", &[("steps", a.len() as f64)]);
+    run_checkpointed(h, vectors, a, b, max_steps, tolerance, checkpoint, interval.max(1))
+}
+
+/// Continues the Lanczos run in `vectors` with the coefficients `a` and `b`, writing the checkpoint every `interval` steps
+/// and removing the files once it converges.
+#[allow(clippy::too_many_arguments)]
+fn run_checkpointed<H: LinearOperator + ?Sized>(
+    h: &H,
+    mut vectors: DiskVectors,
+    a: Vec<f64>,
+    b: Vec<f64>,
+    max_steps: usize,
+    tolerance: f64,
+    checkpoint: &Path,
+    interval: usize,
+) -> Result<(f64, Vec<f64>), Error> {
+    let dimension = h.dimension();
+    let save = |vectors: &DiskVectors, a: &[f64], b: &[f64]| {
+        if !a.len().is_multiple_of(interval) {
+            return Ok(());
+        }
+        let list = |x: &[f64]| Json::Array(x.iter().map(|v| Json::Number(*v)).collect());
+        let json = Json::Object(vec![
+            ("dimension".to_string(), Json::Integer(dimension as u64)),
+            ("interval".to_string(), Json::Integer(interval as u64)),
+            ("a".to_string(), list(a)),
+            ("b".to_string(), list(b)),
+        ]);
+        // The vectors must be on disk before the checkpoint refers to them, and the checkpoint is replaced in one step.
+        let mut partial = checkpoint.as_os_str().to_owned();
+        partial.push(".partial");
+        vectors
+            .sync()
+            .and_then(|_| fs::write(&partial, json.to_string()))
+            .and_then(|_| fs::rename(&partial, checkpoint))?;
+        log::event(Level::Debug, "lanczos::checkpoint", "checkpoint", &[("steps", a.len() as f64)]);
+        Ok(())
+    };
+    let res = lanczos_on_disk(h, &mut vectors, a, b, max_steps, tolerance, save)?;
+    drop(vectors);
+    let _ = fs::remove_file(vectors_path(checkpoint));
+    let _ = fs::remove_file(checkpoint);
+    Ok(res)
+}
+
+/// Represents a single particle Green's function G(z) = <0|c (z - (H - E_0))^{-1} c†|0> + <0|c† (z + (H - E_0))^{-1} c|0>
+/// by the continued fractions of its particle and hole parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GreensFunction {
+    /// The continued fraction of <0|c (z - H)^{-1} c†|0>.
+    particle: ContinuedFraction,
+    /// The continued fraction of <0|c† (z - H)^{-1} c|0>.
+    hole: ContinuedFraction,
+    /// The ground state energy E_0.
+    ground_state_energy: f64,
+}
+
+impl GreensFunction {
+    /// Returns the continued fraction of the particle (inverse photoemission) part.
+    pub fn particle(&self) -> &ContinuedFraction {
+        &self.particle
+    }
+
+    /// Returns the continued fraction of the hole (photoemission) part.
+    pub fn hole(&self) -> &ContinuedFraction {
+        &self.hole
+    }
+
+    /// Returns the ground state energy the excitation energies are measured from.
+    pub fn ground_state_energy(&self) -> f64 {
+        self.ground_state_energy
+    }
+
+    /// Returns G(z) at the complex frequency `z`.
+    pub fn evaluate(&self, z: Complex) -> Complex {
+        let e0 = self.ground_state_energy;
+        self.particle.evaluate(z + e0) - self.hole.evaluate(-z + e0)
+    }
+
+    /// Returns the poles and weights of G(z), relative to the ground state energy.
+    pub fn poles(&self) -> Poles {
+        let e0 = self.ground_state_energy;
+        let particle = self.particle.poles();
+        let hole = self.hole.poles();
+        let positions = particle
+            .positions()
+            .iter()
+            .map(|e| e - e0)
+            .chain(hole.positions().iter().map(|e| e0 - e))
+            .collect();
+        let weights = particle
+            .weights()
+            .iter()
+            .chain(hole.weights())
+            .copied()
+            .collect();
+        Poles::new(positions, weights)
+    }
+}
+
+/// Returns the single particle Green's function of a ground state from Lanczos runs in the N+1 and N-1 particle spaces.
+///
+/// # Arguments
+///
+/// * `h_particle` - The Hamiltonian in the space containing c†|0>.
+/// * `particle` - The vector c†|0>.
+/// * `h_hole` - The Hamiltonian in the space containing c|0>.
+/// * `hole` - The vector c|0>.
+/// * `ground_state_energy` - The energy E_0 of the ground state |0>.
+/// * `n_steps` - The maximal number of Lanczos steps for each part.
+pub fn greens_function<P: LinearOperator + ?Sized, Q: LinearOperator + ?Sized>(
+    h_particle: &P,
+    particle: &[f64],
+    h_hole: &Q,
+    hole: &[f64],
+    ground_state_energy: f64,
+    n_steps: usize,
+) -> GreensFunction {
+    GreensFunction {
+        particle: tridiagonalize(h_particle, particle, n_steps),
+        hole: tridiagonalize(h_hole, hole, n_steps),
+        ground_state_energy,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::linalg::SparseMatrix;
+    use crate::spectral;
+    use crate::spectrum::Spectrum;
+    use crate::{Operator, AC};
+
+    fn chain(l: u64) -> Operator {
+        let mut terms = Vec::new();
+        for i in 0..l - 1 {
+            terms.push((-1.0, vec![AC::Create(i), AC::Annihilate(i + 1)]));
+            terms.push((-1.0, vec![AC::Create(i + 1), AC::Annihilate(i)]));
+        }
+        Operator::new(terms)
+    }
+
+    #[test]
+    fn test_ground_state() {
+        let h = chain(6);
+        let basis = Basis::fixed_particle_number(6, 3);
+        let m = basis.sparse_matrix(&h);
+        let start: Vec<f64> = (0..basis.dimension()).map(|i| 1.0 + 0.1 * i as f64).collect();
+        let (e0, x) = ground_state(&m, &start, 100, 1e-10).unwrap();
+        let budget = config::Config::default().with_memory_budget(8 * 20 * 19);
+        assert!(matches!(config::scoped(budget, || ground_state(&m, &start, 100, 1e-10)), Err(Error::MemoryBudget { .. })));
+        let exact = Spectrum::new(&h, basis).ground_state_energy();
+        assert!((e0 - exact).abs() < 1e-10);
+        assert!((norm(&x) - 1.0).abs() < 1e-12);
+        let (e1, y) = ground_state_out_of_core(&m, &start, 100, 1e-10, &std::env::temp_dir()).unwrap();
+        assert!((e1 - exact).abs() < 1e-10);
+        assert!((dot(&x, &y).abs() - 1.0).abs() < 1e-8);
+        // The out-of-core solver only holds the two vectors of the product in memory.
+        let budget = config::Config::default().with_memory_budget(16 * 20 - 1);
+        let out_of_core = config::scoped(budget, || ground_state_out_of_core(&m, &start, 100, 1e-10, &std::env::temp_dir()));
+        assert!(matches!(out_of_core, Err(Error::MemoryBudget { .. })));
+    }
+
+    #[test]
+    fn test_out_of_core_chunks() {
+        // A vector longer than one chunk of the store: -1 on the first element and 1 elsewhere has two eigenvalues,
+        // so Lanczos converges in two steps.
+        let n = 70_000;
+        let m = SparseMatrix::from_triplets(n, (0..n).map(|i| (i, i, if i == 0 { -1.0 } else { 1.0 })).collect());
+        let start = vec![1.0; n];
+        let (e0, x) = ground_state_out_of_core(&m, &start, 10, 1e-10, &std::env::temp_dir()).unwrap();
+        assert!((e0 + 1.0).abs() < 1e-10);
+        assert!((x[0].abs() - 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_resume() {
+        let h = chain(8);
+        let basis = Basis::fixed_particle_number(8, 4);
+        let m = basis.sparse_matrix(&h);
+        let start: Vec<f64> = (0..basis.dimension()).map(|i| 1.0 + (i as f64).sin()).collect();
+        let checkpoint = std::env::temp_dir().join(format!("rust_ed_lanczos_{}.json", std::process::id()));
+        // The first run stops before converging, leaving the checkpoint of its last multiple of three steps behind.
+        assert!(matches!(ground_state_checkpointed(&m, &start, 10, 1e-12, &checkpoint, 3), Err(Error::SolverNotConverged)));
+        assert!(checkpoint.exists());
+        let other = Basis::fixed_particle_number(8, 3).sparse_matrix(&h);
+        assert!(matches!(resume(&other, &checkpoint, 200, 1e-10), Err(Error::DimensionMismatch)));
+        let (e0, _) = resume(&m, &checkpoint, 200, 1e-10).unwrap();
+        let (exact, _) = ground_state(&m, &start, 200, 1e-10).unwrap();
+        assert!((e0 - exact).abs() < 1e-10);
+        assert!(!checkpoint.exists() && !vectors_path(&checkpoint).exists());
+        assert!(matches!(resume(&m, &checkpoint, 200, 1e-10), Err(Error::IoError(e)) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+
+    #[test]
+    fn test_greens_function() {
+        let h = chain(2);
+        let spectrum = Spectrum::new(&h, Basis::fock_space(2));
+        let c = Operator::new(vec![(1.0, vec![AC::Annihilate(0)])]);
+        let cd = Operator::new(vec![(1.0, vec![AC::Create(0)])]);
+        let exact = spectral::greens_function(&spectrum, &c, &cd, f64::INFINITY);
+
+        let n1 = Basis::fixed_particle_number(2, 1);
+        let n1_spectrum = Spectrum::new(&h, n1.clone());
+        let ground = n1_spectrum.eigenstate(0);
+        let (plus, minus) = (Basis::fixed_particle_number(2, 2), Basis::fixed_particle_number(2, 0));
+        let g = greens_function(
+            &plus.sparse_matrix(&h),
+            &plus.vector(&ground.clone().apply(cd)),
+            &minus.sparse_matrix(&h),
+            &minus.vector(&ground.apply(c)),
+            n1_spectrum.ground_state_energy(),
+            10,
+        );
+        let z = Complex::new(0.3, 0.1);
+        assert!((g.evaluate(z) - exact.evaluate(z)).abs() < 1e-12);
+        assert!((g.poles().total_weight() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_continued_fraction_poles() {
+        let h = chain(6);
+        let basis = Basis::fixed_particle_number(6, 1);
+        let m = basis.matrix(&h);
+        let mut start = vec![0.0; 6];
+        start[0] = 1.0;
+        let cf = tridiagonalize(&m, &start, 20);
+        assert_eq!(cf.depth(), 6);
+        let z = Complex::new(-0.4, 0.2);
+        assert!((cf.evaluate(z) - cf.poles().evaluate(z)).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,302 @@
+use crate::hermitian::HermitianOperator;
+use crate::models::Graph;
+use crate::transport::Hopping;
+use crate::twist::{twisted_boundary_hamiltonian, BoundaryBond};
+use crate::Operator;
+
+/// Distances closer than this are treated as equal when sorting bonds into neighbour shells.
+const DISTANCE_TOLERANCE: f64 = 1e-8;
+
+/// The boundary condition of a cluster along one of its lattice directions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Boundary {
+    /// No bonds cross the boundary.
+    Open,
+    /// Bonds cross the boundary without a phase.
+    Periodic,
+    /// Bonds crossing the boundary pick up the phase e^{iθ}.
+    Twisted(f64),
+}
+
+impl Boundary {
+    /// Returns the image shifts of the cluster that bonds can reach along this direction.
+    fn shifts(self) -> &'static [i32] {
+        match self {
+            Boundary::Open => &[0],
+            _ => &[-1, 0, 1],
+        }
+    }
+
+    /// Returns the twist θ of this boundary, zero unless twisted.
+    fn twist(self) -> f64 {
+        match self {
+            Boundary::Twisted(theta) => theta,
+            _ => 0.0,
+        }
+    }
+}
+
+/// This represents a bond between two sites of a cluster.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Bond {
+    /// The first site.
+    pub i: u64,
+    /// The second site.
+    pub j: u64,
+    /// The displacement r_i - r_j', where j' is the image of j the bond connects to.
+    pub displacement: [f64; 2],
+    /// The image shift of j in units of the cluster, nonzero for bonds crossing a boundary.
+    pub winding: [f64; 2],
+}
+
+/// Represents a finite cluster of a two dimensional lattice: the site coordinates and the nearest and next nearest neighbour bonds.
+/// One dimensional lattices are clusters of a single cell along the second direction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lattice {
+    /// The position of each site, with the sublattice index running fastest, then the first and second cell index.
+    positions: Vec<[f64; 2]>,
+    /// The nearest neighbour bonds.
+    nearest: Vec<Bond>,
+    /// The next nearest neighbour bonds.
+    next_nearest: Vec<Bond>,
+    /// The boundary conditions along the two lattice directions.
+    boundaries: [Boundary; 2],
+    /// The vectors L_1 a_1 and L_2 a_2 spanning the cluster.
+    periods: [[f64; 2]; 2],
+}
+
+impl Lattice {
+    /// Returns the cluster of `cells` unit cells of the lattice with primitive vectors `a` and the sites `basis` in each cell.
+    ///
+    /// Neighbour shells are found by sorting all distances between sites, including images across periodic boundaries.
+    /// Along a periodic direction the cluster should be at least two cells long; very small periodic clusters then contain
+    /// the same pair of sites several times, once for every image it is connected through.
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The primitive vectors a_1 and a_2.
+    /// * `basis` - The positions of the sites of a unit cell.
+    /// * `cells` - The number of cells L_1 and L_2 along each primitive vector.
+    /// * `boundaries` - The boundary conditions along each primitive vector.
+    pub fn new(a: [[f64; 2]; 2], basis: &[[f64; 2]], cells: [usize; 2], boundaries: [Boundary; 2]) -> Lattice {
+        let mut positions = Vec::with_capacity(basis.len() * cells[0] * cells[1]);
+        for n2 in 0..cells[1] {
+            for n1 in 0..cells[0] {
+                for b in basis {
+                    positions.push([
+                        b[0] + n1 as f64 * a[0][0] + n2 as f64 * a[1][0],
+                        b[1] + n1 as f64 * a[0][1] + n2 as f64 * a[1][1],
+                    ]);
+                }
+            }
+        }
+        let extent = |d: usize, n: i32| {
+            let l = n as f64 * cells[d] as f64;
+            [l * a[d][0], l * a[d][1]]
+        };
+        let mut candidates = Vec::new();
+        for i in 0..positions.len() {
+            for j in i + 1..positions.len() {
+                for n1 in boundaries[0].shifts() {
+                    for n2 in boundaries[1].shifts() {
+                        let (s1, s2) = (extent(0, *n1), extent(1, *n2));
+                        let displacement = [
+                            positions[i][0] - positions[j][0] - s1[0] - s2[0],
+                            positions[i][1] - positions[j][1] - s1[1] - s2[1],
+                        ];
+                        candidates.push(Bond {
+                            i: i as u64,
+                            j: j as u64,
+                            displacement,
+                            winding: [*n1 as f64, *n2 as f64],
+                        });
+                    }
+                }
+            }
+        }
+        let length = |b: &Bond| b.displacement[0].hypot(b.displacement[1]);
+        let mut distances: Vec<f64> = candidates.iter().map(length).collect();
+        distances.sort_by(|x, y| x.total_cmp(y));
+        distances.dedup_by(|x, y| (*x - *y).abs() < DISTANCE_TOLERANCE);
+        let shell = |n: usize| -> Vec<Bond> {
+            distances.get(n).map_or(Vec::new(), |d| {
+                candidates
+                    .iter()
+                    .filter(|b| (length(b) - d).abs() < DISTANCE_TOLERANCE)
+                    .copied()
+                    .collect()
+            })
+        };
+        Lattice {
+            nearest: shell(0),
+            next_nearest: shell(1),
+            positions,
+            boundaries,
+            periods: [extent(0, 1), extent(1, 1)],
+        }
+    }
+
+    /// Returns a chain of `length` sites with unit spacing.
+    pub fn chain(length: usize, boundary: Boundary) -> Lattice {
+        Lattice::new([[1.0, 0.0], [0.0, 1.0]], &[[0.0, 0.0]], [length, 1], [boundary, Boundary::Open])
+    }
+
+    /// Returns a two leg ladder of `length` rungs, with the boundary condition `boundary` along the legs.
+    pub fn ladder(length: usize, boundary: Boundary) -> Lattice {
+        Lattice::new([[1.0, 0.0], [0.0, 1.0]], &[[0.0, 0.0]], [length, 2], [boundary, Boundary::Open])
+    }
+
+    /// Returns an L_x × L_y square lattice cluster.
+    pub fn square(cells: [usize; 2], boundaries: [Boundary; 2]) -> Lattice {
+        Lattice::new([[1.0, 0.0], [0.0, 1.0]], &[[0.0, 0.0]], cells, boundaries)
+    }
+
+    /// Returns a triangular lattice cluster of L_1 × L_2 sites.
+    pub fn triangular(cells: [usize; 2], boundaries: [Boundary; 2]) -> Lattice {
+        Lattice::new([[1.0, 0.0], [0.5, 0.75f64.sqrt()]], &[[0.0, 0.0]], cells, boundaries)
+    }
+
+    /// Returns a honeycomb lattice cluster of L_1 × L_2 cells with two sites each and unit bond length.
+    pub fn honeycomb(cells: [usize; 2], boundaries: [Boundary; 2]) -> Lattice {
+        let s = 3f64.sqrt();
+        Lattice::new([[s, 0.0], [s / 2.0, 1.5]], &[[0.0, 0.0], [0.0, 1.0]], cells, boundaries)
+    }
+
+    /// Returns a kagome lattice cluster of L_1 × L_2 cells with three sites each and unit bond length.
+    pub fn kagome(cells: [usize; 2], boundaries: [Boundary; 2]) -> Lattice {
+        let s = 3f64.sqrt();
+        Lattice::new(
+            [[2.0, 0.0], [1.0, s]],
+            &[[0.0, 0.0], [1.0, 0.0], [0.5, s / 2.0]],
+            cells,
+            boundaries,
+        )
+    }
+
+    /// Returns the number of sites.
+    pub fn n_sites(&self) -> u64 {
+        self.positions.len() as u64
+    }
+
+    /// Returns the position of each site.
+    pub fn positions(&self) -> &[[f64; 2]] {
+        &self.positions
+    }
+
+    /// Returns the boundary conditions along the two lattice directions.
+    pub fn boundaries(&self) -> [Boundary; 2] {
+        self.boundaries
+    }
+
+    /// Returns the vectors L_1 a_1 and L_2 a_2 spanning the cluster, the periods along periodic directions.
+    pub fn periods(&self) -> [[f64; 2]; 2] {
+        self.periods
+    }
+
+    /// Returns the nearest neighbour bonds.
+    pub fn nearest_neighbours(&self) -> &[Bond] {
+        &self.nearest
+    }
+
+    /// Returns the next nearest neighbour bonds.
+    pub fn next_nearest_neighbours(&self) -> &[Bond] {
+        &self.next_nearest
+    }
+
+    /// Returns the nearest (`next_nearest` false) or next nearest neighbours of `site`, once for every bond connecting them.
+    pub fn neighbours(&self, site: u64, next_nearest: bool) -> Vec<u64> {
+        let bonds = if next_nearest { &self.next_nearest } else { &self.nearest };
+        bonds
+            .iter()
+            .filter_map(|b| match (b.i == site, b.j == site) {
+                (true, _) => Some(b.j),
+                (_, true) => Some(b.i),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the nearest neighbour graph of the cluster, for the model builders.
+    pub fn graph(&self) -> Graph {
+        Graph::new(self.n_sites(), self.nearest.iter().map(|b| (b.i, b.j)).collect())
+    }
+
+    /// Returns the next nearest neighbour graph of the cluster.
+    pub fn next_nearest_graph(&self) -> Graph {
+        Graph::new(self.n_sites(), self.next_nearest.iter().map(|b| (b.i, b.j)).collect())
+    }
+
+    /// Returns the nearest neighbour hoppings with amplitude `t`, with displacements projected onto `direction` for the transport functions.
+    pub fn hoppings(&self, t: f64, direction: [f64; 2]) -> Vec<Hopping> {
+        self.nearest
+            .iter()
+            .map(|b| Hopping::new(b.i, b.j, t, b.displacement[0] * direction[0] + b.displacement[1] * direction[1]))
+            .collect()
+    }
+
+    /// Returns the nearest neighbour hoppings with amplitude `t` and their windings, for `twist::twisted_boundary_hamiltonian`.
+    pub fn boundary_bonds(&self, t: f64) -> Vec<BoundaryBond> {
+        self.nearest
+            .iter()
+            .map(|b| BoundaryBond::new(Hopping::new(b.i, b.j, t, 0.0), b.winding))
+            .collect()
+    }
+
+    /// Returns the nearest neighbour hopping Hamiltonian -t Σ_<ij> (c†_i c_j + h.c.) of spinless fermions plus `interaction`,
+    /// including the phases of twisted boundaries.
+    pub fn hopping_hamiltonian(&self, t: f64, interaction: &Operator) -> HermitianOperator {
+        let theta = [self.boundaries[0].twist(), self.boundaries[1].twist()];
+        twisted_boundary_hamiltonian(&self.boundary_bonds(t), interaction, theta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::linalg::{LinearOperator, Matrix};
+
+    #[test]
+    fn test_bond_counts() {
+        let p = [Boundary::Periodic, Boundary::Periodic];
+        let chain = Lattice::chain(4, Boundary::Periodic);
+        assert_eq!((chain.nearest_neighbours().len(), chain.next_nearest_neighbours().len()), (4, 4));
+        let open = Lattice::chain(4, Boundary::Open);
+        assert_eq!((open.nearest_neighbours().len(), open.next_nearest_neighbours().len()), (3, 2));
+        assert_eq!(Lattice::ladder(3, Boundary::Open).nearest_neighbours().len(), 7);
+        let square = Lattice::square([3, 3], p);
+        assert_eq!((square.nearest_neighbours().len(), square.next_nearest_neighbours().len()), (18, 18));
+        // The coordination numbers 6, 3 and 4 of the triangular, honeycomb and kagome lattices.
+        assert_eq!(Lattice::triangular([3, 3], p).nearest_neighbours().len(), 27);
+        let honeycomb = Lattice::honeycomb([3, 3], p);
+        assert_eq!(honeycomb.nearest_neighbours().len(), 27);
+        assert_eq!(honeycomb.neighbours(0, false).len(), 3);
+        assert_eq!(Lattice::kagome([3, 3], p).nearest_neighbours().len(), 54);
+    }
+
+    #[test]
+    fn test_twisted_chain() {
+        // A single particle on a twisted ring has the energies -2 cos((2πm + θ)/L).
+        let (l, theta) = (4, 0.7);
+        let h = Lattice::chain(l, Boundary::Twisted(theta)).hopping_hamiltonian(1.0, &Operator::new(Vec::new()));
+        let m = h.realified(&Basis::fixed_particle_number(l as u64, 1));
+        let mut dense = Matrix::zeros(2 * l, 2 * l);
+        let mut y = vec![0.0; 2 * l];
+        for c in 0..2 * l {
+            let mut x = vec![0.0; 2 * l];
+            x[c] = 1.0;
+            m.apply(&x, &mut y);
+            for r in 0..2 * l {
+                dense[(r, c)] = y[r];
+            }
+        }
+        let mut expected: Vec<f64> = (0..l)
+            .map(|m| -2.0 * ((2.0 * std::f64::consts::PI * m as f64 + theta) / l as f64).cos())
+            .collect();
+        expected.sort_by(|a, b| a.total_cmp(b));
+        let e = dense.eigh().0;
+        for (k, x) in expected.iter().enumerate() {
+            assert!((e[2 * k] - x).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use crate::dos::{histogram, Histogram};
+use crate::error::Error;
+
+/// The mean gap ratio <r> of uncorrelated levels, 2 ln 2 - 1, characteristic of integrable and localized systems.
+pub const POISSON_MEAN_GAP_RATIO: f64 = 0.386_294_361_119_890_6;
+
+/// The mean gap ratio <r> of the Gaussian orthogonal ensemble, characteristic of chaotic systems with time reversal symmetry.
+pub const GOE_MEAN_GAP_RATIO: f64 = 0.5307;
+
+/// Returns the spacings δ_n = E_{n+1} - E_n of the sorted eigenvalues.
+fn spacings(energies: &[f64]) -> Vec<f64> {
+    let mut sorted = energies.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    sorted.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Returns the consecutive gap ratios r_n = min(δ_n, δ_{n+1}) / max(δ_n, δ_{n+1}) of the eigenvalues of a single symmetry sector.
+/// Ratios of two vanishing gaps, from exact degeneracies, are skipped.
+pub fn gap_ratios(energies: &[f64]) -> Vec<f64> {
+    spacings(energies)
+        .windows(2)
+        .filter_map(|w| {
+            let (lo, hi) = (w[0].min(w[1]), w[0].max(w[1]));
+            (hi > 0.0).then_some(lo / hi)
+        })
+        .collect()
+}
+
+/// Returns the mean gap ratio <r> of the eigenvalues of a single symmetry sector, to be compared with
+/// `POISSON_MEAN_GAP_RATIO` and `GOE_MEAN_GAP_RATIO`. Unlike the spacing distribution it needs no unfolding.
+///
+/// # Errors
+///
+/// * If there are fewer than three distinct eigenvalues, this function returns an Error.
+pub fn mean_gap_ratio(energies: &[f64]) -> Result<f64, Error> {
+    let ratios = gap_ratios(energies);
+    if ratios.is_empty() {
+        return Err(Error::Invalid("Gap ratios need at least three distinct eigenvalues!"));
+    }
+    Ok(ratios.iter().sum::<f64>() / ratios.len() as f64)
+}
+
+/// Returns the distribution P(s) of level spacings in units of the mean spacing, s = δ_n / <δ>, normalized to unit area.
+///
+/// The spectrum is unfolded with a single global mean spacing, so `energies` should be restricted to a part of the
+/// spectrum with a roughly constant density of states.
+///
+/// # Arguments
+///
+/// * `energies` - The eigenvalues of a single symmetry sector.
+/// * `n_bins` - The number of histogram bins.
+/// * `max_spacing` - The largest normalized spacing binned.
+///
+/// # Errors
+///
+/// * If there are fewer than two eigenvalues, no bins or a non-positive `max_spacing`, this function returns an Error.
+pub fn spacing_distribution(energies: &[f64], n_bins: usize, max_spacing: f64) -> Result<Histogram, Error> {
+    let spacings = spacings(energies);
+    if spacings.is_empty() {
+        return Err(Error::Invalid("Level spacings need at least two eigenvalues!"));
+    }
+    let mean = spacings.iter().sum::<f64>() / spacings.len() as f64;
+    let normalized: Vec<f64> = spacings.iter().map(|s| s / mean).collect();
+    let mut h = histogram(&normalized, n_bins, Some((0.0, max_spacing)))?;
+    h.normalize();
+    Ok(h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linalg::Matrix;
+    use crate::random::Rng;
+
+    #[test]
+    fn test_poisson_levels() {
+        let mut rng = Rng::new(1);
+        let energies: Vec<f64> = (0..20000).map(|_| rng.uniform()).collect();
+        let r = mean_gap_ratio(&energies).unwrap();
+        assert!((r - POISSON_MEAN_GAP_RATIO).abs() < 0.01);
+        let p = spacing_distribution(&energies, 20, 4.0).unwrap();
+        // P(s) = e^{-s} for uncorrelated levels.
+        assert!((p.density()[0] - (1.0 - (-0.2_f64).exp()) / 0.2).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_goe_levels() {
+        let n = 100;
+        let mut rng = Rng::new(2);
+        let mut ratios = Vec::new();
+        for _ in 0..40 {
+            let mut m = Matrix::zeros(n, n);
+            for i in 0..n {
+                for j in 0..=i {
+                    let x = rng.normal();
+                    m[(i, j)] = x;
+                    m[(j, i)] = x;
+                }
+            }
+            let (energies, _) = m.eigh();
+            // The bulk of the semicircle.
+            ratios.extend(gap_ratios(&energies[n / 4..3 * n / 4]));
+        }
+        let r = ratios.iter().sum::<f64>() / ratios.len() as f64;
+        assert!((r - GOE_MEAN_GAP_RATIO).abs() < 0.03);
+        assert!(mean_gap_ratio(&[1.0, 1.0]).is_err());
+    }
+}
@@ -1,16 +1,151 @@
+//! The determinant algebra of the crate root (`AC`, `Slater`, `Operator`, `State`) builds on `core` and `alloc` alone;
+//! the solvers, models and input/output of the modules need the default feature "std". `cargo check-no-std` builds
+//! the crate root for thumbv7em-none-eabi, a target without std.
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 // use std::convert::TryInto;
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+use core::fmt;
+use core::hash::Hasher;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::option::Option;
-use std::fmt;
+#[cfg(feature = "std")]
+use std::hash::BuildHasherDefault;
+use crate::error::Error;
+
+#[cfg(feature = "std")]
+pub mod basis;
+#[cfg(feature = "std")]
+pub mod bench;
+#[cfg(feature = "std")]
+pub mod bosons;
+#[cfg(feature = "std")]
+pub mod chemistry;
+#[cfg(feature = "std")]
+pub mod cli;
+#[cfg(feature = "std")]
+pub mod complex;
+#[cfg(feature = "std")]
+pub mod config;
+#[cfg(feature = "std")]
+pub mod cpt;
+#[cfg(feature = "std")]
+pub mod csf;
+#[cfg(feature = "std")]
+pub mod disk;
+#[cfg(feature = "std")]
+pub mod disorder;
+#[cfg(feature = "std")]
+pub mod dmft;
+#[cfg(feature = "std")]
+pub mod dos;
+#[cfg(feature = "std")]
+pub mod double_double;
+#[cfg(feature = "std")]
+pub mod dynamics;
+#[cfg(feature = "std")]
+pub mod entanglement;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod eth;
+#[cfg(feature = "std")]
+pub mod ftlm;
+#[cfg(feature = "std")]
+pub mod gaps;
+#[cfg(feature = "std")]
+pub mod hermitian;
+#[cfg(feature = "std")]
+pub mod input;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod json;
+#[cfg(feature = "std")]
+pub mod kpm;
+#[cfg(feature = "std")]
+pub mod lanczos;
+#[cfg(feature = "std")]
+pub mod lattice;
+#[cfg(feature = "std")]
+pub mod level_statistics;
+#[cfg(feature = "std")]
+pub mod linalg;
+#[cfg(feature = "std")]
+pub mod log;
+#[cfg(feature = "std")]
+pub mod mbl;
+#[cfg(feature = "std")]
+pub mod models;
+#[cfg(feature = "std")]
+pub mod momentum;
+#[cfg(feature = "std")]
+pub mod observables;
+#[cfg(feature = "std")]
+pub mod operators;
+#[cfg(feature = "std")]
+pub mod orthogonalize;
+#[cfg(feature = "std")]
+pub mod pade;
+#[cfg(feature = "std")]
+pub mod peierls;
+#[cfg(feature = "std")]
+pub mod photoemission;
+#[cfg(feature = "std")]
+pub mod plot;
+#[cfg(feature = "std")]
+pub mod random;
+#[cfg(feature = "std")]
+pub mod repl;
+#[cfg(feature = "std")]
+pub mod selected_ci;
+#[cfg(feature = "std")]
+pub mod self_energy;
+#[cfg(feature = "std")]
+pub mod spectral;
+#[cfg(feature = "std")]
+pub mod spectrum;
+#[cfg(feature = "std")]
+pub mod susceptibility;
+#[cfg(feature = "std")]
+pub mod sweep;
+#[cfg(feature = "std")]
+pub mod table;
+#[cfg(feature = "std")]
+pub mod thermo;
+#[cfg(feature = "std")]
+pub mod transport;
+#[cfg(feature = "std")]
+pub mod twist;
+#[cfg(feature = "std")]
+pub mod typicality;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 /// This represents a creation/annihilation operator
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum AC {
     /// Create and Annihilate requires a state/position to act on
     Create(u64),
     Annihilate(u64),
 }
 
+/// The number of orbitals of a Slater determinant, one bit of its index each.
+pub const MAX_ORBITALS: u64 = u64::BITS as u64;
+
+impl AC {
+    /// Returns the orbital this operator acts on.
+    pub fn orbital(&self) -> u64 {
+        match self {
+            AC::Create(pos) | AC::Annihilate(pos) => *pos,
+        }
+    }
+}
+
 /// This represents an operator, acting on Slater determinants
+#[derive(Debug, Clone)]
 pub struct Operator {
     /// Each operator consists of a sum of terms.
     /// Each term in the operator is an amplitude and a sequence of creation/annihilation operators.
@@ -26,15 +161,200 @@ impl Operator {
     pub fn new(terms : Vec<(f64, Vec<AC>)>) -> Operator {
         Operator { terms }
     }
+
+    /// Returns an operator with the terms given, after checking that every orbital fits in a Slater determinant.
+    ///
+    /// # Arguments
+    ///
+    /// * `terms` - a Vec containing tuples of amplitudes and Vec<AC>
+    ///
+    /// # Errors
+    ///
+    /// * If a term acts on an orbital of at least `MAX_ORBITALS`, this function returns `Error::InvalidOrbital`.
+    pub fn checked_new(terms: Vec<(f64, Vec<AC>)>) -> Result<Operator, Error> {
+        match terms.iter().flat_map(|(_, ac)| ac).find(|c| c.orbital() >= MAX_ORBITALS) {
+            Some(c) => Err(Error::InvalidOrbital(c.orbital())),
+            None => Ok(Operator { terms }),
+        }
+    }
+
+    /// Returns the Slater determinants, with amplitudes, obtained by applying this operator to a single Slater determinant.
+    /// Determinants may appear more than once in the result, one entry per contributing term.
+    ///
+    /// # Arguments
+    ///
+    /// * `slater` - The Slater determinant to apply this operator to.
+    pub fn apply_slater(&self, slater: &Slater) -> Vec<(f64, Slater)> {
+        self.terms
+            .iter()
+            .filter_map(|(fac, ac)| {
+                TermMask::new(ac)
+                    .and_then(|mask| mask.apply(slater))
+                    .map(|(phase, ns)| (fac * phase as f64, ns))
+            })
+            .collect()
+    }
+
+    /// Returns the terms of this operator compiled to bit masks, without the terms that vanish on every determinant.
+    /// Compile once and reuse the masks when applying the operator to many determinants.
+    pub fn masks(&self) -> Vec<(f64, TermMask)> {
+        self.terms.iter().filter_map(|(fac, ac)| TermMask::new(ac).map(|mask| (*fac, mask))).collect()
+    }
+
+    /// Returns the Hermitian adjoint of this operator.
+    pub fn adjoint(&self) -> Operator {
+        let terms = self
+            .terms
+            .iter()
+            .map(|(fac, ac)| {
+                let reversed = ac
+                    .iter()
+                    .rev()
+                    .map(|c| match c {
+                        AC::Create(i) => AC::Annihilate(*i),
+                        AC::Annihilate(i) => AC::Create(*i),
+                    })
+                    .collect();
+                (*fac, reversed)
+            })
+            .collect();
+        Operator { terms }
+    }
+
+    /// Returns the operator product AB of this operator A and `other` B, where B acts first.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The operator B.
+    pub fn product(&self, other: &Operator) -> Operator {
+        let mut terms = Vec::with_capacity(self.terms.len() * other.terms.len());
+        for (a, ac_a) in &self.terms {
+            for (b, ac_b) in &other.terms {
+                terms.push((a * b, ac_a.iter().chain(ac_b).copied().collect()));
+            }
+        }
+        Operator { terms }
+    }
 }
 
 /// This represents a single, unique, Slater determinant.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Slater {
     /// The unique index of the Slater determinant.
     index: u64,
 }
 
+/// Represents a string of creation and annihilation operators compiled to bit operations on the index of a Slater determinant:
+/// the string acts if the checked orbitals have the required occupations, flips the occupations of the touched orbitals,
+/// and picks up the phase of the parity of the occupied orbitals in the parity mask.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TermMask {
+    /// The orbitals whose occupation decides whether the string acts.
+    checked: u64,
+    /// The occupations the checked orbitals must have.
+    required: u64,
+    /// The orbitals whose occupation the string changes.
+    flipped: u64,
+    /// The orbitals whose occupations give the phase.
+    parity: u64,
+    /// Whether the phase has an extra factor -1, from the orbitals changed by the string itself.
+    negative: bool,
+}
+
+impl TermMask {
+    /// Returns the string of operators `ac` compiled to masks, or None if it vanishes on every determinant, e.g. c†_0 c†_0.
+    ///
+    /// # Arguments
+    ///
+    /// * `ac` - The creation/annihilation operators, the rightmost one acting first.
+    pub fn new(ac: &[AC]) -> Option<TermMask> {
+        let mut mask = TermMask {
+            checked: 0,
+            required: 0,
+            flipped: 0,
+            parity: 0,
+            negative: false,
+        };
+        for c in ac.iter().rev() {
+            let (pos, occupied) = match c {
+                AC::Create(pos) => (*pos, false),
+                AC::Annihilate(pos) => (*pos, true),
+            };
+            debug_assert!(pos < MAX_ORBITALS, "Orbital {} does not fit in a determinant!", pos);
+            let bit = 1u64 << pos;
+            let below = bit - 1;
+            // The phase counts the occupied orbitals below pos in the intermediate determinant, the initial one with the flips so far.
+            mask.parity ^= below;
+            mask.negative ^= (mask.flipped & below).count_ones() % 2 == 1;
+            if mask.checked & bit != 0 {
+                if ((mask.required ^ mask.flipped) & bit != 0) != occupied {
+                    return None;
+                }
+            } else {
+                mask.checked |= bit;
+                if occupied {
+                    mask.required |= bit;
+                }
+            }
+            mask.flipped ^= bit;
+        }
+        Some(mask)
+    }
+
+    /// Returns the phase and the Slater determinant obtained by applying the operator string to `slater`, or None if it vanishes.
+    pub fn apply(&self, slater: &Slater) -> Option<(i32, Slater)> {
+        if slater.index & self.checked != self.required {
+            return None;
+        }
+        let odd = (slater.index & self.parity).count_ones() % 2 == 1;
+        let phase = if odd != self.negative { -1 } else { 1 };
+        Some((phase, Slater::new(slater.index ^ self.flipped)))
+    }
+}
+
+/// Hashes Slater determinants by multiplying their index with a large odd constant and folding the high bits of the product
+/// down (FxHash), which is much cheaper than the default SipHash. It offers no protection against adversarially chosen keys, which determinants are not.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SlaterHasher {
+    hash: u64,
+}
+
+impl SlaterHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SlaterHasher::SEED);
+    }
+}
+
+impl Hasher for SlaterHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0u8; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    fn finish(&self) -> u64 {
+        // The product only carries the key into higher bits, so keys differing in their high bits alone would share
+        // all the low bits; the rotation folds the well mixed high bits down.
+        self.hash.rotate_left(26)
+    }
+}
+
+/// A HashMap keyed by Slater determinants, hashed with `SlaterHasher`.
+#[cfg(feature = "std")]
+pub type SlaterMap<V> = HashMap<Slater, V, BuildHasherDefault<SlaterHasher>>;
+
+/// A map keyed by Slater determinants; without std there is no HashMap, so the determinants are kept in order.
+#[cfg(not(feature = "std"))]
+pub type SlaterMap<V> = BTreeMap<Slater, V>;
+
 impl fmt::Binary for Slater {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
         fmt::Binary::fmt(&self.index, f)
@@ -52,6 +372,11 @@ impl Slater {
         Self { index }
     }
 
+    /// Returns the unique index of the Slater determinant, bit i being set if state i is occupied.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
     /// Returns a Slater determinant corresponding to the supplied states being occupied.
     ///
     /// # Arguments
@@ -60,14 +385,18 @@ impl Slater {
     ///
     /// # Errors
     ///
-    /// * If the supplied vector contains duplicates of any index this function returns an Error.
-    pub fn from_vec(arr: Vec<u64>) -> Result<Self, &'static str> {
+    /// * If the supplied vector contains duplicates of any index this function returns `Error::DuplicateOrbital`.
+    /// * If an index is at least `MAX_ORBITALS`, this function returns `Error::InvalidOrbital`.
+    pub fn from_vec(arr: Vec<u64>) -> Result<Self, Error> {
         let mut index: u64 = 0;
         let mut added_indices: Vec<u64> = Vec::new();
         for i in arr.iter() {
+            if *i >= MAX_ORBITALS {
+                return Err(Error::InvalidOrbital(*i));
+            }
             index += 1 << *i;
-            match added_indices.binary_search(&i) {
-                Ok(_) => return Err("State array contains repeated index!"),
+            match added_indices.binary_search(i) {
+                Ok(_) => return Err(Error::DuplicateOrbital(*i)),
                 Err(pos) => added_indices.insert(pos, *i),
             }
         }
@@ -84,6 +413,7 @@ impl Slater {
     ///
     /// * If the single particle state j is already occupied, this function returns None.
     fn create(self, &j: &u64) -> Option<Self> {
+        debug_assert!(j < MAX_ORBITALS, "Orbital {} does not fit in a determinant!", j);
         match self.index & (1 << j) {
             0 => Some(Self {
                 index: self.index | (1 << j),
@@ -101,6 +431,7 @@ impl Slater {
     ///
     /// * If the single particle state j is already empty, this function returns None.
     fn annihilate(self, &j: &u64) -> Option<Self> {
+        debug_assert!(j < MAX_ORBITALS, "Orbital {} does not fit in a determinant!", j);
         match self.index & (1 << j) {
             0 => None,
             _ => Some(Self {
@@ -119,37 +450,43 @@ impl Slater {
     /// * If the reuslt of applying op to this state is None, this function returns None.
     pub fn apply(&self, op: &AC) -> Option<(i32, Self)> {
         match op {
-            AC::Create(pos) => {
-                if let Some(new_state) = self.create(&pos) {
-                    if (!self.index & (1 << pos) - 1).count_ones() % 2 == 0 {
-                        return Some((1, new_state));
-                    } else {
-                        return Some((-1, new_state));
-                    };
+            AC::Create(pos) => self.create(pos).map(|new_state| {
+                if (self.index & ((1 << pos) - 1)).count_ones().is_multiple_of(2) {
+                    (1, new_state)
                 } else {
-                    return None;
+                    (-1, new_state)
                 }
-            }
-            AC::Annihilate(pos) => {
-                if let Some(new_state) = self.annihilate(&pos) {
-                    if (self.index & (1 << pos) - 1).count_ones() % 2 == 0 {
-                        return Some((1, new_state));
-                    } else {
-                        return Some((-1, new_state));
-                    };
+            }),
+            AC::Annihilate(pos) => self.annihilate(pos).map(|new_state| {
+                if (self.index & ((1 << pos) - 1)).count_ones().is_multiple_of(2) {
+                    (1, new_state)
                 } else {
-                    return None;
+                    (-1, new_state)
                 }
-            }
+            }),
         }
     }
+
+    /// Returns the result of applying the creation/annihilation operator op to this state like `apply`,
+    /// after checking that its orbital fits in the determinant.
+    ///
+    /// # Errors
+    ///
+    /// * If the orbital of op is at least `MAX_ORBITALS`, this function returns `Error::InvalidOrbital`.
+    pub fn checked_apply(&self, op: &AC) -> Result<Option<(i32, Self)>, Error> {
+        if op.orbital() >= MAX_ORBITALS {
+            return Err(Error::InvalidOrbital(op.orbital()));
+        }
+        Ok(self.apply(op))
+    }
 }
 
 /// Represents a many body state as a linear combination of Slater determinants.
+#[derive(Debug, Clone)]
 pub struct State {
     /// A HashMap with the Slater determinants as keys and their amplitudes as values.
     /// Slater determinants with 0 amplitude should not be included in this map.
-    amplitudes: HashMap<Slater, f64>,
+    amplitudes: SlaterMap<f64>,
 }
 
 impl State {
@@ -164,75 +501,143 @@ impl State {
     }
 
     /// Returns a State object corresponding to the result of applying the operator `op` to this state.
+    /// Amplitudes that cancel, to rounding error relative to the norm of this state, are removed.
     ///
     /// # Arguments
     ///
     /// * `op` - The operator object to apply to this state.
     pub fn apply(self, op: Operator) -> State {
-        let mut res: HashMap<Slater, f64> = HashMap::new();
-        for (fac, mut ac) in op.terms {
-            ac.reverse();
-            'states: for (state, amp) in &self.amplitudes {
-                let mut tmp_states: HashMap<Slater, f64> = HashMap::new();
-                tmp_states.insert(*state, *amp);
-                for c in &ac {
-                    let mut next_states: HashMap<Slater, f64> = HashMap::new();
-                    for (s, v) in &tmp_states {
-                        if let Some((phase, ns)) = s.apply(c) {
-                            let ai = next_states.entry(ns).or_insert(0 as f64);
-                            *ai += v * phase as f64;
-                        } else {
-                            next_states.clear();
-                            continue 'states;
-                        }
+        let terms = op.masks();
+        let cutoff = Cutoff::new(self.amplitudes.values());
+        let determinants: Vec<(Slater, f64)> = self.amplitudes.into_iter().collect();
+        let mut res = apply_terms(&terms, &determinants);
+        cutoff.retain(&mut res);
+        State { amplitudes: res }
+    }
+
+    /// Returns a State object corresponding to the result of applying the operator `op` to this state, with the determinants
+    /// split between `n_threads` threads, where 0 selects the configured number, see `config::Config`.
+    /// The partial results of the threads are summed at the end, and amplitudes that cancel are removed like in `apply`.
+    ///
+    /// # Arguments
+    ///
+    /// * `op` - The operator to apply to this state.
+    /// * `n_threads` - The number of threads.
+    /// * `threshold` - The number of determinants below which the operator is applied on the calling thread, e.g. `PARALLEL_THRESHOLD`.
+    ///   A single thread also applies it on the calling thread, which serves targets without threads such as wasm32.
+    #[cfg(feature = "std")]
+    pub fn apply_parallel(self, op: Operator, n_threads: usize, threshold: usize) -> State {
+        let n_threads = sweep::thread_count(n_threads);
+        if self.amplitudes.len() < threshold || n_threads == 1 {
+            return self.apply(op);
+        }
+        let terms = op.masks();
+        let cutoff = Cutoff::new(self.amplitudes.values());
+        let determinants: Vec<(Slater, f64)> = self.amplitudes.into_iter().collect();
+        let chunk = determinants.len().div_ceil(n_threads);
+        let partial = sweep::parallel_map(n_threads, n_threads, |k| {
+            let end = (k*chunk + chunk).min(determinants.len());
+            apply_terms(&terms, &determinants[(k*chunk).min(end)..end])
+        });
+        let mut parts = partial.into_iter();
+        let mut res = parts.next().unwrap_or_default();
+        for part in parts {
+            for (s, v) in part {
+                *res.entry(s).or_insert(0 as f64) += v;
+            }
+        }
+        cutoff.retain(&mut res);
+        State { amplitudes: res }
+    }
+
+    /// Returns the states obtained by applying each of `ops` to this state, computed in one pass over the determinants,
+    /// e.g. to measure many observables on the same state. Amplitudes that cancel are removed like in `apply`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ops` - The operators to apply to this state.
+    pub fn apply_many(&self, ops: &[&Operator]) -> Vec<State> {
+        let masks: Vec<Vec<(f64, TermMask)>> = ops.iter().map(|op| op.masks()).collect();
+        let cutoff = Cutoff::new(self.amplitudes.values());
+        let mut res: Vec<SlaterMap<f64>> = ops.iter().map(|_| SlaterMap::default()).collect();
+        for (state, amp) in &self.amplitudes {
+            for (terms, r) in masks.iter().zip(res.iter_mut()) {
+                for (fac, mask) in terms {
+                    if let Some((phase, ns)) = mask.apply(state) {
+                        *r.entry(ns).or_insert(0 as f64) += fac * phase as f64 * amp;
                     }
-                    next_states.retain(|_, amp| amp.abs() > f64::EPSILON);
-                    tmp_states = next_states;
-                }
-                for (s, v) in &tmp_states {
-                    let a = res.entry(*s).or_insert(0 as f64);
-                    *a += fac*v;
                 }
             }
         }
-        res.retain(|_, v| v.abs() > f64::EPSILON);
-        State { amplitudes: res }
+        res.into_iter()
+            .map(|mut amplitudes| {
+                cutoff.retain(&mut amplitudes);
+                State { amplitudes }
+            })
+            .collect()
     }
 }
 
+/// The number of determinants below which `State::apply_parallel` is not worth spawning threads for.
+#[cfg(feature = "std")]
+pub const PARALLEL_THRESHOLD: usize = 4096;
+
+/// Represents the size below which an amplitude of an operator applied to a state has cancelled: rounding error relative to
+/// the norm of the state, as in `orthogonalize::orthogonalize_state`. The norm is kept in units of the largest amplitude of the
+/// state, so that the comparison needs no square root, which core lacks, and does not underflow for states of small norm.
+struct Cutoff {
+    /// The largest magnitude of the amplitudes of the state.
+    scale: f64,
+    /// The squared norm of the state in units of `scale`, times the squared machine epsilon.
+    threshold: f64,
+}
 
-pub fn run() -> Result<(), &'static str> {
-    let n1 = Operator::new(vec![(1.0, vec![
-        AC::Create(1),
-        AC::Annihilate(1),
-    ])]);
-    let s = State::new(vec![(Slater::new(7), 0.33), (Slater::new(2), 0.33), (Slater::new(14), 0.33)]);
-    println!("Initial state :");
-    print!("\t");
-    for (key, val) in &s.amplitudes {
-        print!(" {:+5}|{:08b}> ", val, key)
+impl Cutoff {
+    /// Returns the cutoff of the state with the `amplitudes`.
+    fn new<'a, I: Iterator<Item = &'a f64> + Clone>(amplitudes: I) -> Cutoff {
+        let scale = amplitudes.clone().fold(0.0, |m: f64, a| m.max(a.abs()));
+        let norm_sqr: f64 = if scale > 0.0 { amplitudes.map(|a| (a / scale) * (a / scale)).sum() } else { 0.0 };
+        Cutoff {
+            scale,
+            threshold: f64::EPSILON * f64::EPSILON * norm_sqr,
+        }
     }
-    print!("\n");
 
-    let ns = s.apply(n1);
-    println!("Final state :");
-    print!("\t");
-    for (key, val) in &ns.amplitudes {
-        print!(" {:+5}|{:08b}> ", val, key)
+    /// Removes the amplitudes of `res` that have cancelled.
+    fn retain(&self, res: &mut SlaterMap<f64>) {
+        res.retain(|_, v| self.scale > 0.0 && (*v / self.scale) * (*v / self.scale) > self.threshold);
     }
-    print!("\n");
+}
 
-    Ok(())
+/// Returns the sum of the compiled `terms` applied to the `determinants` with their amplitudes, before dropping zeros.
+fn apply_terms(terms: &[(f64, TermMask)], determinants: &[(Slater, f64)]) -> SlaterMap<f64> {
+    let mut res: SlaterMap<f64> = SlaterMap::default();
+    for (fac, mask) in terms {
+        for (state, amp) in determinants {
+            if let Some((phase, ns)) = mask.apply(state) {
+                let a = res.entry(ns).or_insert(0 as f64);
+                *a += fac * phase as f64 * amp;
+            }
+        }
+    }
+    res
 }
 
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_from_vec() {
         let state = Slater::from_vec(vec![0, 1, 2]).unwrap();
         assert_eq!(state.index, 7);
+        assert!(matches!(Slater::from_vec(vec![0, 2, 0]), Err(Error::DuplicateOrbital(0))));
+        assert!(matches!(Slater::from_vec(vec![1, 64]), Err(Error::InvalidOrbital(64))));
+        assert!(matches!(Slater::new(1).checked_apply(&AC::Create(70)), Err(Error::InvalidOrbital(70))));
+        assert_eq!(Slater::new(1).checked_apply(&AC::Create(63)).unwrap(), Some((-1, Slater::new(1 | 1 << 63))));
+        assert!(Operator::checked_new(vec![(1.0, vec![AC::Create(64), AC::Annihilate(0)])]).is_err());
     }
     #[test]
     fn test_from_uint() {
@@ -252,6 +657,16 @@ mod tests {
     }
 
     #[test]
+    fn test_apply_phase() {
+        // The phase counts the occupied orbitals below the one created or annihilated.
+        assert_eq!(Slater::new(0b01).apply(&AC::Create(1)), Some((-1, Slater::new(0b11))));
+        assert_eq!(Slater::new(0b10).apply(&AC::Create(2)), Some((-1, Slater::new(0b110))));
+        assert_eq!(Slater::new(0b11).apply(&AC::Annihilate(1)), Some((-1, Slater::new(0b01))));
+        assert_eq!(Slater::new(0b100).apply(&AC::Create(0)), Some((1, Slater::new(0b101))));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
     fn test_new_state() {
         let s = State::new(vec![(Slater::new(7), 0.33), (Slater::new(2), 0.33), (Slater::new(14), 0.33)]);
         let mut check = HashMap::new();
@@ -264,6 +679,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "std")]
     fn test_apply_state() {
         let a = Operator::new(vec![(1.0, vec![ AC::Create(0), AC::Annihilate(1)])]);
         let s = State::new(vec![(Slater::new(7), 0.33), (Slater::new(2), 0.33), (Slater::new(14), 0.33)]);
@@ -275,4 +691,79 @@ mod tests {
             assert_eq!(val, check.get(&key.index).unwrap());
         }
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_apply_parallel() {
+        let hop = Operator::new((0..10).map(|i| (-1.0, vec![AC::Create((i + 1) % 10), AC::Annihilate(i)])).collect());
+        let s = State::new((0..1024).map(|i| (Slater::new(i), 1.0 + i as f64)).collect());
+        let serial = s.clone().apply(hop.clone());
+        let parallel = s.clone().apply_parallel(hop.clone(), 4, 0);
+        assert_eq!(serial.amplitudes.len(), parallel.amplitudes.len());
+        for (key, val) in &serial.amplitudes {
+            assert!((val - parallel.amplitudes[key]).abs() < 1e-12);
+        }
+        // The amplitudes of a state of small norm are all far below one, and kept.
+        let small = State::new(s.amplitudes.iter().map(|(k, v)| (*k, 1e-20 * v)).collect());
+        assert_eq!(small.apply_parallel(hop, 4, 0).amplitudes.len(), serial.amplitudes.len());
+    }
+
+    #[test]
+    fn test_apply_many() {
+        let n0 = Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let hop = Operator::new(vec![(-1.0, vec![AC::Create(1), AC::Annihilate(0)]), (-1.0, vec![AC::Create(0), AC::Annihilate(1)])]);
+        for scale in [1.0, 1e-20] {
+            let s = State::new(vec![(Slater::new(1), 0.6 * scale), (Slater::new(2), 0.8 * scale)]);
+            let many = s.apply_many(&[&n0, &hop]);
+            for (res, op) in many.iter().zip(vec![n0.clone(), hop.clone()]) {
+                let single = s.clone().apply(op);
+                assert_eq!(res.amplitudes.len(), single.amplitudes.len());
+                for (key, val) in &single.amplitudes {
+                    assert!((val - res.amplitudes[key]).abs() < 1e-12 * scale);
+                }
+            }
+            // The amplitudes of a state of small norm are as far from the cutoff as those of a normalized one.
+            assert_eq!(many[1].amplitudes.len(), 2);
+        }
+        // An amplitude below rounding error relative to the norm of the state is dropped.
+        let residue = Operator::new(vec![(1e-17, vec![AC::Create(1), AC::Annihilate(0)])]);
+        assert!(State::new(vec![(Slater::new(1), 1.0)]).apply_many(&[&residue])[0].amplitudes.is_empty());
+    }
+
+    #[test]
+    fn test_term_mask() {
+        // Compare with applying the operators one at a time on every determinant of six orbitals.
+        let strings = vec![
+            vec![AC::Create(4), AC::Annihilate(1)],
+            vec![AC::Create(2), AC::Annihilate(2)],
+            vec![AC::Create(0), AC::Create(5), AC::Annihilate(3), AC::Annihilate(1)],
+            vec![AC::Annihilate(1), AC::Create(3), AC::Create(1), AC::Annihilate(3)],
+        ];
+        for ac in &strings {
+            let mask = TermMask::new(ac).unwrap();
+            for index in 0..64 {
+                let s = Slater::new(index);
+                let expected = ac.iter().rev().try_fold((1, s), |(phase, s), c| s.apply(c).map(|(p, ns)| (phase * p, ns)));
+                assert_eq!(mask.apply(&s), expected);
+            }
+        }
+        assert_eq!(TermMask::new(&[AC::Create(0), AC::Create(0)]), None);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_slater_hasher() {
+        let hash = |index: u64| {
+            let mut h = SlaterHasher::default();
+            std::hash::Hash::hash(&Slater::new(index), &mut h);
+            h.finish()
+        };
+        assert_eq!(hash(5), hash(5));
+        // Neighbouring determinants spread over the high bits, which the table uses to pick its groups.
+        let high: std::collections::HashSet<u64> = (0..64).map(|i| hash(i) >> 57).collect();
+        assert!(high.len() > 32);
+        // Determinants differing only in high orbitals spread over the low bits, which pick the bucket.
+        let low: std::collections::HashSet<u64> = (0..64).map(|k| hash(k << 40) & 0x3ff).collect();
+        assert_eq!(low.len(), 64);
+    }
 }
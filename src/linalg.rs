@@ -0,0 +1,790 @@
+use std::cell::Cell;
+use std::ops::{Index, IndexMut};
+use std::sync::RwLock;
+
+use crate::complex::Complex;
+
+/// This represents a dense, row-major matrix of real numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    /// Returns a matrix of the given shape with all elements set to zero.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows` - The number of rows.
+    /// * `cols` - The number of columns.
+    pub fn zeros(rows: usize, cols: usize) -> Matrix {
+        Matrix {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+
+    /// Returns the n×n identity matrix.
+    pub fn identity(n: usize) -> Matrix {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            m[(i, i)] = 1.0;
+        }
+        m
+    }
+
+    /// Returns a matrix of the given shape with the supplied elements, stored row by row.
+    ///
+    /// # Panics
+    ///
+    /// * If the number of elements does not match the shape.
+    pub fn from_vec(rows: usize, cols: usize, data: Vec<f64>) -> Matrix {
+        assert_eq!(data.len(), rows * cols, "Matrix data does not match its shape!");
+        Matrix { rows, cols, data }
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the elements of the matrix, stored row by row.
+    pub fn data(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Returns a copy of column j.
+    pub fn column(&self, j: usize) -> Vec<f64> {
+        (0..self.rows).map(|i| self[(i, j)]).collect()
+    }
+
+    /// Returns the transpose of this matrix.
+    pub fn transpose(&self) -> Matrix {
+        let mut t = Matrix::zeros(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+        t
+    }
+
+    /// Returns the matrix-vector product of this matrix and `v`, computed by the installed `Backend`.
+    pub fn matvec(&self, v: &[f64]) -> Vec<f64> {
+        assert_eq!(v.len(), self.cols, "Vector length does not match the matrix!");
+        let mut res = vec![0.0; self.rows];
+        if self.cols > 0 {
+            backend().gemv(self, v, &mut res);
+        }
+        res
+    }
+
+    /// Returns the matrix product of this matrix and `other`, computed by the installed `Backend`.
+    pub fn matmul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows, "Matrix shapes do not match!");
+        backend().matmul(self, other)
+    }
+
+    /// Returns the eigenvalues, in ascending order, and the eigenvectors, stored as columns, of this symmetric matrix.
+    /// Only the lower triangle of the matrix is referenced.
+    ///
+    /// The matrix is diagonalized by the installed `Backend`; the `Native` one reduces it to tridiagonal form by Householder
+    /// transformations and then diagonalizes it with the implicit QL algorithm.
+    ///
+    /// # Panics
+    ///
+    /// * If the matrix is not square.
+    pub fn eigh(&self) -> (Vec<f64>, Matrix) {
+        assert_eq!(self.rows, self.cols, "Only square matrices can be diagonalized!");
+        if self.rows == 0 {
+            return (Vec::new(), Matrix::zeros(0, 0));
+        }
+        backend().eigh(self)
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+/// A linear map on real vectors, the only thing iterative solvers need to know about a Hamiltonian.
+pub trait LinearOperator {
+    /// Returns the dimension of the space the operator acts on.
+    fn dimension(&self) -> usize;
+
+    /// Computes y = A x.
+    fn apply(&self, x: &[f64], y: &mut [f64]);
+}
+
+impl LinearOperator for Matrix {
+    fn dimension(&self) -> usize {
+        self.rows
+    }
+
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        if self.cols == 0 {
+            y.iter_mut().for_each(|yi| *yi = 0.0);
+            return;
+        }
+        backend().gemv(self, x, y);
+    }
+}
+
+/// This represents a square sparse matrix of real numbers in compressed sparse row format.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix {
+    n: usize,
+    /// Row i occupies the entries row_ptr[i]..row_ptr[i + 1] of `cols` and `values`.
+    row_ptr: Vec<usize>,
+    cols: Vec<usize>,
+    values: Vec<f64>,
+}
+
+impl SparseMatrix {
+    /// Returns the n×n sparse matrix with the supplied (row, column, value) entries.
+    /// Repeated entries are summed and explicit zeros are dropped.
+    pub fn from_triplets(n: usize, triplets: Vec<(usize, usize, f64)>) -> SparseMatrix {
+        let mut rows: Vec<Vec<(usize, f64)>> = vec![Vec::new(); n];
+        for (i, j, v) in triplets {
+            rows[i].push((j, v));
+        }
+        let mut row_ptr = Vec::with_capacity(n + 1);
+        let mut cols = Vec::new();
+        let mut values = Vec::new();
+        row_ptr.push(0);
+        for mut row in rows {
+            row.sort_by_key(|(j, _)| *j);
+            let mut k = 0;
+            while k < row.len() {
+                let j = row[k].0;
+                let mut v = 0.0;
+                while k < row.len() && row[k].0 == j {
+                    v += row[k].1;
+                    k += 1;
+                }
+                if v != 0.0 {
+                    cols.push(j);
+                    values.push(v);
+                }
+            }
+            row_ptr.push(cols.len());
+        }
+        SparseMatrix {
+            n,
+            row_ptr,
+            cols,
+            values,
+        }
+    }
+
+    /// Returns the number of stored non-zero elements.
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the column indices and values of the non-zero elements in row i.
+    pub fn row(&self, i: usize) -> (&[usize], &[f64]) {
+        let r = self.row_ptr[i]..self.row_ptr[i + 1];
+        (&self.cols[r.clone()], &self.values[r])
+    }
+}
+
+impl LinearOperator for SparseMatrix {
+    fn dimension(&self) -> usize {
+        self.n
+    }
+
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        backend().sparse_apply(self, x, y);
+    }
+}
+
+/// Represents an implementation of the dense and sparse kernels of this module, e.g. one calling an optimized BLAS and LAPACK.
+/// Every kernel defaults to the pure Rust implementation of `Native`, so a backend only overrides the kernels it accelerates.
+/// The kernels may assume that the shapes of their arguments match and that the matrices are not empty.
+pub trait Backend: Sync {
+    /// Returns the eigenvalues, in ascending order, and the eigenvectors, stored as columns, of the symmetric matrix `m`,
+    /// referencing only its lower triangle, see `Matrix::eigh`.
+    fn eigh(&self, m: &Matrix) -> (Vec<f64>, Matrix) {
+        let n = m.rows;
+        let mut v = m.clone();
+        let mut d = vec![0.0; n];
+        let mut e = vec![0.0; n];
+        tred2(&mut v, &mut d, &mut e);
+        tql2(&mut v, &mut d, &mut e);
+        (d, v)
+    }
+
+    /// Returns the eigenvalues and eigenvectors of a symmetric tridiagonal matrix, see `tridiagonal_eigh`.
+    fn tridiagonal_eigh(&self, diagonal: &[f64], off_diagonal: &[f64]) -> (Vec<f64>, Matrix) {
+        let n = diagonal.len();
+        let mut v = Matrix::identity(n);
+        let mut d = diagonal.to_vec();
+        let mut e = vec![0.0; n];
+        e[1..n].copy_from_slice(off_diagonal);
+        tql2(&mut v, &mut d, &mut e);
+        (d, v)
+    }
+
+    /// Returns the matrix product a b, see `Matrix::matmul`.
+    fn matmul(&self, a: &Matrix, b: &Matrix) -> Matrix {
+        let mut res = Matrix::zeros(a.rows, b.cols);
+        for i in 0..a.rows {
+            for k in 0..a.cols {
+                let aik = a[(i, k)];
+                if aik == 0.0 {
+                    continue;
+                }
+                for j in 0..b.cols {
+                    res.data[i * b.cols + j] += aik * b.data[k * b.cols + j];
+                }
+            }
+        }
+        res
+    }
+
+    /// Computes y = m x for the sparse matrix `m`.
+    fn sparse_apply(&self, m: &SparseMatrix, x: &[f64], y: &mut [f64]) {
+        for (i, yi) in y.iter_mut().enumerate() {
+            let (cols, values) = m.row(i);
+            *yi = cols.iter().zip(values).map(|(j, v)| v * x[*j]).sum();
+        }
+    }
+
+    /// Computes y = m x for the dense matrix `m`, see `Matrix::matvec`.
+    fn gemv(&self, m: &Matrix, x: &[f64], y: &mut [f64]) {
+        for (yi, row) in y.iter_mut().zip(m.data.chunks(m.cols)) {
+            *yi = native_dot(row, x);
+        }
+    }
+
+    /// Returns the scalar product of two vectors of the same length, see `dot`.
+    fn dot(&self, a: &[f64], b: &[f64]) -> f64 {
+        native_dot(a, b)
+    }
+
+    /// Returns the matrix product a b of complex matrices, see `ComplexMatrix::matmul`.
+    fn complex_matmul(&self, a: &ComplexMatrix, b: &ComplexMatrix) -> ComplexMatrix {
+        let n = a.n;
+        let mut res = ComplexMatrix::zeros(n);
+        for i in 0..n {
+            for k in 0..n {
+                let aik = a.data[i * n + k];
+                for j in 0..n {
+                    res.data[i * n + j] += aik * b.data[k * n + j];
+                }
+            }
+        }
+        res
+    }
+}
+
+/// Returns the scalar product of two vectors, the kernel of `Native`.
+fn native_dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// The pure Rust kernels, installed until `set_backend` replaces them.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Native;
+
+impl Backend for Native {}
+
+static BACKEND: RwLock<&'static dyn Backend> = RwLock::new(&Native);
+
+thread_local! {
+    /// The backend of the innermost `scoped_backend` call on this thread.
+    static SCOPED: Cell<Option<&'static dyn Backend>> = const { Cell::new(None) };
+}
+
+/// Installs `backend` for the kernels of this module in the whole process, replacing an earlier backend.
+pub fn set_backend(backend: &'static dyn Backend) {
+    *BACKEND.write().unwrap() = backend;
+}
+
+/// Returns the backend of the calling thread: that of `scoped_backend` while it runs, otherwise the installed one.
+pub fn backend() -> &'static dyn Backend {
+    SCOPED.with(|scoped| scoped.get()).unwrap_or_else(|| *BACKEND.read().unwrap())
+}
+
+/// Returns f() evaluated with `backend` in place of the installed backend on the calling thread, e.g. to compare two backends
+/// without affecting other threads. Threads spawned by `f` use the installed backend.
+pub fn scoped_backend<R, F: FnOnce() -> R>(backend: &'static dyn Backend, f: F) -> R {
+    /// Puts the backend of the enclosing scope back, also when `f` panics.
+    struct Restore(Option<&'static dyn Backend>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SCOPED.with(|scoped| scoped.set(self.0));
+        }
+    }
+
+    let _restore = Restore(SCOPED.with(|scoped| scoped.replace(Some(backend))));
+    f()
+}
+
+/// This represents a dense, row-major square matrix of complex numbers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexMatrix {
+    n: usize,
+    data: Vec<Complex>,
+}
+
+impl ComplexMatrix {
+    /// Returns the n×n matrix with all elements set to zero.
+    pub fn zeros(n: usize) -> ComplexMatrix {
+        ComplexMatrix {
+            n,
+            data: vec![Complex::default(); n * n],
+        }
+    }
+
+    /// Returns the n×n identity matrix.
+    pub fn identity(n: usize) -> ComplexMatrix {
+        let mut m = ComplexMatrix::zeros(n);
+        for i in 0..n {
+            m[(i, i)] = Complex::from(1.0);
+        }
+        m
+    }
+
+    /// Returns the complex matrix with the same elements as the real square matrix `m`.
+    pub fn from_real(m: &Matrix) -> ComplexMatrix {
+        assert_eq!(m.rows, m.cols, "Only square matrices are supported!");
+        ComplexMatrix {
+            n: m.rows,
+            data: m.data.iter().map(|x| Complex::from(*x)).collect(),
+        }
+    }
+
+    /// Returns the number of rows (and columns).
+    pub fn dimension(&self) -> usize {
+        self.n
+    }
+
+    /// Returns the matrix product of this matrix and `other`, computed by the installed `Backend`.
+    pub fn matmul(&self, other: &ComplexMatrix) -> ComplexMatrix {
+        assert_eq!(self.n, other.n, "Matrix shapes do not match!");
+        if self.n == 0 {
+            return ComplexMatrix::zeros(0);
+        }
+        backend().complex_matmul(self, other)
+    }
+
+    /// Returns the inverse of this matrix, computed by Gauss-Jordan elimination with partial pivoting,
+    /// or None if the matrix is singular.
+    pub fn inverse(&self) -> Option<ComplexMatrix> {
+        let n = self.n;
+        let mut a = self.clone();
+        let mut inv = ComplexMatrix::identity(n);
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|i, j| a[(*i, col)].abs().total_cmp(&a[(*j, col)].abs()))
+                .filter(|p| a[(*p, col)].abs() > 0.0)?;
+            if pivot != col {
+                for j in 0..n {
+                    a.data.swap(pivot * n + j, col * n + j);
+                    inv.data.swap(pivot * n + j, col * n + j);
+                }
+            }
+            let d = a[(col, col)].inv();
+            for j in 0..n {
+                a[(col, j)] *= d;
+                inv[(col, j)] *= d;
+            }
+            for i in 0..n {
+                if i == col {
+                    continue;
+                }
+                let f = a[(i, col)];
+                if f == Complex::default() {
+                    continue;
+                }
+                for j in 0..n {
+                    let (aj, ij) = (a[(col, j)], inv[(col, j)]);
+                    a[(i, j)] -= f * aj;
+                    inv[(i, j)] -= f * ij;
+                }
+            }
+        }
+        Some(inv)
+    }
+
+    /// Returns the determinant of this matrix, computed by Gaussian elimination with partial pivoting.
+    pub fn determinant(&self) -> Complex {
+        let n = self.n;
+        let mut a = self.clone();
+        let mut det = Complex::from(1.0);
+        for col in 0..n {
+            let pivot = (col..n)
+                .max_by(|i, j| a[(*i, col)].abs().total_cmp(&a[(*j, col)].abs()))
+                .unwrap();
+            if a[(pivot, col)].abs() == 0.0 {
+                return Complex::default();
+            }
+            if pivot != col {
+                for j in 0..n {
+                    a.data.swap(pivot * n + j, col * n + j);
+                }
+                det = -det;
+            }
+            let d = a[(col, col)];
+            det *= d;
+            for i in col + 1..n {
+                let f = a[(i, col)] / d;
+                for j in col..n {
+                    let aj = a[(col, j)];
+                    a[(i, j)] -= f * aj;
+                }
+            }
+        }
+        det
+    }
+}
+
+impl Index<(usize, usize)> for ComplexMatrix {
+    type Output = Complex;
+    fn index(&self, (i, j): (usize, usize)) -> &Complex {
+        &self.data[i * self.n + j]
+    }
+}
+
+impl IndexMut<(usize, usize)> for ComplexMatrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut Complex {
+        &mut self.data[i * self.n + j]
+    }
+}
+
+/// Returns the eigenvalues, in ascending order, and the eigenvectors, stored as columns, of a symmetric tridiagonal matrix.
+///
+/// # Arguments
+///
+/// * `diagonal` - The diagonal elements.
+/// * `off_diagonal` - The elements directly below (and above) the diagonal, one fewer than the diagonal.
+pub fn tridiagonal_eigh(diagonal: &[f64], off_diagonal: &[f64]) -> (Vec<f64>, Matrix) {
+    let n = diagonal.len();
+    assert_eq!(off_diagonal.len() + 1, n.max(1), "Off-diagonal must have one element fewer than the diagonal!");
+    if n == 0 {
+        return (Vec::new(), Matrix::zeros(0, 0));
+    }
+    backend().tridiagonal_eigh(diagonal, off_diagonal)
+}
+
+/// Returns the scalar product of two vectors, computed by the installed `Backend`.
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    debug_assert_eq!(a.len(), b.len(), "Vector lengths do not match!");
+    if a.is_empty() {
+        return 0.0;
+    }
+    backend().dot(a, b)
+}
+
+/// Returns the Euclidean norm of a vector, computed by the installed `Backend`.
+pub fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Householder reduction of the symmetric matrix in `v` to tridiagonal form.
+/// On return `d` holds the diagonal, `e[1..]` the subdiagonal and `v` the accumulated transformation.
+fn tred2(v: &mut Matrix, d: &mut [f64], e: &mut [f64]) {
+    let n = d.len();
+    for j in 0..n {
+        d[j] = v[(n - 1, j)];
+    }
+    for i in (1..n).rev() {
+        let scale: f64 = d[..i].iter().map(|x| x.abs()).sum();
+        let mut h = 0.0;
+        if scale == 0.0 {
+            e[i] = d[i - 1];
+            for j in 0..i {
+                d[j] = v[(i - 1, j)];
+                v[(i, j)] = 0.0;
+                v[(j, i)] = 0.0;
+            }
+        } else {
+            for dk in d[..i].iter_mut() {
+                *dk /= scale;
+                h += *dk * *dk;
+            }
+            let mut f = d[i - 1];
+            let mut g = if f > 0.0 { -h.sqrt() } else { h.sqrt() };
+            e[i] = scale * g;
+            h -= f * g;
+            d[i - 1] = f - g;
+            for ej in e[..i].iter_mut() {
+                *ej = 0.0;
+            }
+            for j in 0..i {
+                f = d[j];
+                v[(j, i)] = f;
+                g = e[j] + v[(j, j)] * f;
+                for k in j + 1..i {
+                    g += v[(k, j)] * d[k];
+                    e[k] += v[(k, j)] * f;
+                }
+                e[j] = g;
+            }
+            f = 0.0;
+            for j in 0..i {
+                e[j] /= h;
+                f += e[j] * d[j];
+            }
+            let hh = f / (h + h);
+            for j in 0..i {
+                e[j] -= hh * d[j];
+            }
+            for j in 0..i {
+                f = d[j];
+                g = e[j];
+                for k in j..i {
+                    v[(k, j)] -= f * e[k] + g * d[k];
+                }
+                d[j] = v[(i - 1, j)];
+                v[(i, j)] = 0.0;
+            }
+        }
+        d[i] = h;
+    }
+    for i in 0..n - 1 {
+        v[(n - 1, i)] = v[(i, i)];
+        v[(i, i)] = 1.0;
+        let h = d[i + 1];
+        if h != 0.0 {
+            for k in 0..=i {
+                d[k] = v[(k, i + 1)] / h;
+            }
+            for j in 0..=i {
+                let mut g = 0.0;
+                for k in 0..=i {
+                    g += v[(k, i + 1)] * v[(k, j)];
+                }
+                for k in 0..=i {
+                    v[(k, j)] -= g * d[k];
+                }
+            }
+        }
+        for k in 0..=i {
+            v[(k, i + 1)] = 0.0;
+        }
+    }
+    for j in 0..n {
+        d[j] = v[(n - 1, j)];
+        v[(n - 1, j)] = 0.0;
+    }
+    v[(n - 1, n - 1)] = 1.0;
+    e[0] = 0.0;
+}
+
+/// Implicit QL diagonalization of the symmetric tridiagonal matrix given by `d` and `e[1..]`.
+/// The rotations are accumulated into `v`; on return `d` holds the sorted eigenvalues.
+fn tql2(v: &mut Matrix, d: &mut [f64], e: &mut [f64]) {
+    let n = d.len();
+    if n == 0 {
+        return;
+    }
+    for i in 1..n {
+        e[i - 1] = e[i];
+    }
+    e[n - 1] = 0.0;
+
+    let mut f = 0.0;
+    let mut tst1: f64 = 0.0;
+    let eps = f64::EPSILON;
+    for l in 0..n {
+        tst1 = tst1.max(d[l].abs() + e[l].abs());
+        let mut m = l;
+        while m < n - 1 && e[m].abs() > eps * tst1 {
+            m += 1;
+        }
+        if m > l {
+            loop {
+                let mut g = d[l];
+                let mut p = (d[l + 1] - g) / (2.0 * e[l]);
+                let mut r = p.hypot(1.0);
+                if p < 0.0 {
+                    r = -r;
+                }
+                d[l] = e[l] / (p + r);
+                d[l + 1] = e[l] * (p + r);
+                let dl1 = d[l + 1];
+                let mut h = g - d[l];
+                for di in d[l + 2..n].iter_mut() {
+                    *di -= h;
+                }
+                f += h;
+
+                p = d[m];
+                let mut c = 1.0;
+                let mut c2 = c;
+                let mut c3 = c;
+                let el1 = e[l + 1];
+                let mut s = 0.0;
+                let mut s2 = 0.0;
+                for i in (l..m).rev() {
+                    c3 = c2;
+                    c2 = c;
+                    s2 = s;
+                    g = c * e[i];
+                    h = c * p;
+                    r = p.hypot(e[i]);
+                    e[i + 1] = s * r;
+                    s = e[i] / r;
+                    c = p / r;
+                    p = c * d[i] - s * g;
+                    d[i + 1] = h + s * (c * g + s * d[i]);
+                    for k in 0..v.rows {
+                        h = v[(k, i + 1)];
+                        v[(k, i + 1)] = s * v[(k, i)] + c * h;
+                        v[(k, i)] = c * v[(k, i)] - s * h;
+                    }
+                }
+                p = -s * s2 * c3 * el1 * e[l] / dl1;
+                e[l] = s * p;
+                d[l] = c * p;
+                if e[l].abs() <= eps * tst1 {
+                    break;
+                }
+            }
+        }
+        d[l] += f;
+        e[l] = 0.0;
+    }
+
+    for i in 0..n - 1 {
+        let mut k = i;
+        let mut p = d[i];
+        for (j, &dj) in d.iter().enumerate().skip(i + 1) {
+            if dj < p {
+                k = j;
+                p = dj;
+            }
+        }
+        if k != i {
+            d[k] = d[i];
+            d[i] = p;
+            for r in 0..v.rows {
+                v.data.swap(r * v.cols + i, r * v.cols + k);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eigh() {
+        let m = Matrix::from_vec(3, 3, vec![2.0, -1.0, 0.0, -1.0, 2.0, -1.0, 0.0, -1.0, 2.0]);
+        let (vals, vecs) = m.eigh();
+        let s = 2.0_f64.sqrt();
+        for (v, c) in vals.iter().zip(&[2.0 - s, 2.0, 2.0 + s]) {
+            assert!((v - c).abs() < 1e-12);
+        }
+        for (j, val) in vals.iter().enumerate() {
+            let x = vecs.column(j);
+            let mx = m.matvec(&x);
+            for (a, b) in mx.iter().zip(&x) {
+                assert!((a - val * b).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_backend() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts the eigenvalue problems, dense products and scalar products it computes, with the native kernels.
+        struct Counting([AtomicUsize; 4]);
+        impl Backend for Counting {
+            fn eigh(&self, m: &Matrix) -> (Vec<f64>, Matrix) {
+                self.0[0].fetch_add(1, Ordering::Relaxed);
+                Native.eigh(m)
+            }
+
+            fn gemv(&self, m: &Matrix, x: &[f64], y: &mut [f64]) {
+                self.0[1].fetch_add(1, Ordering::Relaxed);
+                Native.gemv(m, x, y)
+            }
+
+            fn dot(&self, a: &[f64], b: &[f64]) -> f64 {
+                self.0[2].fetch_add(1, Ordering::Relaxed);
+                Native.dot(a, b)
+            }
+
+            fn complex_matmul(&self, a: &ComplexMatrix, b: &ComplexMatrix) -> ComplexMatrix {
+                self.0[3].fetch_add(1, Ordering::Relaxed);
+                Native.complex_matmul(a, b)
+            }
+        }
+        static COUNTING: Counting = Counting([AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0)]);
+        let m = Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        // The backend only applies on this thread, so other tests running meanwhile neither see nor disturb it.
+        let (vals, product) = scoped_backend(&COUNTING, || {
+            let (vals, _) = m.eigh();
+            let mut y = vec![0.0; 2];
+            m.apply(&[1.0, 2.0], &mut y);
+            assert_eq!(m.matvec(&[3.0, 4.0]), vec![4.0, 3.0]);
+            assert_eq!(norm(&y), 5.0_f64.sqrt());
+            (vals, ComplexMatrix::from_real(&m).matmul(&ComplexMatrix::from_real(&m)))
+        });
+        let counts: Vec<usize> = COUNTING.0.iter().map(|c| c.load(Ordering::Relaxed)).collect();
+        assert_eq!(counts, vec![1, 2, 1, 1]);
+        assert!((vals[0] + 1.0).abs() < 1e-12 && (vals[1] - 1.0).abs() < 1e-12);
+        assert_eq!(product, ComplexMatrix::identity(2));
+        assert_eq!(m.matmul(&m), Matrix::identity(2));
+    }
+
+    #[test]
+    fn test_sparse_matrix() {
+        let m = SparseMatrix::from_triplets(2, vec![(0, 1, 1.0), (1, 0, 2.0), (0, 1, 0.5), (1, 1, 0.0)]);
+        assert_eq!(m.nnz(), 2);
+        let mut y = vec![0.0; 2];
+        m.apply(&[1.0, 2.0], &mut y);
+        assert_eq!(y, vec![3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_complex_inverse() {
+        let mut m = ComplexMatrix::zeros(2);
+        m[(0, 0)] = Complex::new(0.0, 1.0);
+        m[(0, 1)] = Complex::new(2.0, 0.0);
+        m[(1, 0)] = Complex::new(1.0, -1.0);
+        m[(1, 1)] = Complex::new(3.0, 0.5);
+        let p = m.matmul(&m.inverse().unwrap());
+        for i in 0..2 {
+            for j in 0..2 {
+                let e = if i == j { 1.0 } else { 0.0 };
+                assert!((p[(i, j)] - e).abs() < 1e-14);
+            }
+        }
+        assert!(ComplexMatrix::zeros(2).inverse().is_none());
+        // det = i(3 + 0.5i) - 2(1 - i)
+        assert!((m.determinant() - Complex::new(-2.5, 5.0)).abs() < 1e-14);
+        assert_eq!(ComplexMatrix::zeros(2).determinant(), Complex::default());
+    }
+
+    #[test]
+    fn test_tridiagonal_eigh() {
+        let (vals, _) = tridiagonal_eigh(&[0.0, 0.0], &[1.0]);
+        assert!((vals[0] + 1.0).abs() < 1e-14);
+        assert!((vals[1] - 1.0).abs() < 1e-14);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::json::Json;
+
+/// The severity of a log record; a logger at some level records everything at or above it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error = 1,
+    Warn = 2,
+    Info = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        }
+    }
+}
+
+/// The format of the log records.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Format {
+    /// One line per record for reading in a terminal, with progress records drawn as a bar.
+    Text,
+    /// One JSON object per line, for machines.
+    Json,
+}
+
+/// Represents the installed logger.
+struct Logger {
+    format: Format,
+    start: Instant,
+    sink: Box<dyn Write + Send>,
+}
+
+/// The most verbose level recorded, 0 when logging is off, checked before any record is formatted.
+static MAX_LEVEL: AtomicUsize = AtomicUsize::new(0);
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+/// Installs a logger writing the records at `level` and above to `sink` in `format`, replacing an earlier logger.
+/// Logging is off until a logger is installed.
+pub fn init(level: Level, format: Format, sink: Box<dyn Write + Send>) {
+    *LOGGER.lock().unwrap() = Some(Logger {
+        format,
+        start: Instant::now(),
+        sink,
+    });
+    MAX_LEVEL.store(level as usize, Ordering::Relaxed);
+}
+
+/// Installs a logger writing to standard error, see `init`.
+pub fn init_stderr(level: Level, format: Format) {
+    init(level, format, Box::new(io::stderr()));
+}
+
+/// Removes the logger, turning logging off.
+pub fn disable() {
+    MAX_LEVEL.store(0, Ordering::Relaxed);
+    *LOGGER.lock().unwrap() = None;
+}
+
+/// Returns whether records at `level` are recorded.
+pub fn enabled(level: Level) -> bool {
+    level as usize <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+/// Records the event `message` of the component `target` with the numerical `fields`, e.g. `("dimension", 1024.0)`.
+pub fn event(level: Level, target: &str, message: &str, fields: &[(&str, f64)]) {
+    if !enabled(level) {
+        return;
+    }
+    let mut guard = LOGGER.lock().unwrap();
+    let Some(logger) = guard.as_mut() else {
+        return;
+    };
+    let time = logger.start.elapsed().as_secs_f64();
+    let mut line = String::new();
+    match logger.format {
+        Format::Text if message == "progress" => {
+            let done = fields.iter().find(|(k, _)| *k == "done").map_or(0.0, |f| f.1);
+            let total = fields.iter().find(|(k, _)| *k == "total").map_or(1.0, |f| f.1).max(1.0);
+            let filled = (30.0 * done / total).round() as usize;
+            let _ = write!(
+                line,
+                "{:10.3}s {} [{}{}] {}/{}",
+                time,
+                target,
+                "#".repeat(filled.min(30)),
+                " ".repeat(30 - filled.min(30)),
+                done,
+                total
+            );
+        }
+        Format::Text => {
+            let _ = write!(line, "{:10.3}s {:5} {}: {}", time, level.name(), target, message);
+            for (k, v) in fields {
+                let _ = write!(line, " {}={}", k, v);
+            }
+        }
+        Format::Json => {
+            let mut entries = vec![
+                ("time".to_string(), Json::Number(time)),
+                ("level".to_string(), Json::String(level.name().to_string())),
+                ("target".to_string(), Json::String(target.to_string())),
+                ("message".to_string(), Json::String(message.to_string())),
+            ];
+            entries.extend(fields.iter().map(|(k, v)| (k.to_string(), Json::Number(*v))));
+            let _ = write!(line, "{}", Json::Object(entries));
+        }
+    }
+    let _ = writeln!(logger.sink, "{}", line);
+    let _ = logger.sink.flush();
+}
+
+/// Records the progress `done` out of `total` of the component `target`, drawn as a bar in the text format.
+pub fn progress(target: &str, done: usize, total: usize) {
+    event(Level::Info, target, "progress", &[("done", done as f64), ("total", total as f64)]);
+}
+
+/// Represents a timed section of work, recorded when opened and, with its duration and recorded fields, when dropped.
+#[derive(Debug)]
+pub struct Span {
+    level: Level,
+    target: &'static str,
+    start: Option<Instant>,
+    fields: Vec<(&'static str, f64)>,
+}
+
+impl Span {
+    /// Adds the field `name` to the record written when the span closes, e.g. the size of a result.
+    pub fn record(&mut self, name: &'static str, value: f64) {
+        if self.start.is_some() {
+            self.fields.push((name, value));
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if let Some(start) = self.start {
+            self.fields.push(("seconds", start.elapsed().as_secs_f64()));
+            event(self.level, self.target, "close", &self.fields);
+        }
+    }
+}
+
+/// Returns an open span of `target` with the numerical `fields`. When logging at `level` is off, the span costs nothing beyond this check.
+pub fn span(level: Level, target: &'static str, fields: &[(&'static str, f64)]) -> Span {
+    let start = if enabled(level) {
+        event(level, target, "open", fields);
+        Some(Instant::now())
+    } else {
+        None
+    };
+    Span {
+        level,
+        target,
+        start,
+        fields: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    /// A sink that keeps the records, to inspect them after logging.
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_records() {
+        let buffer = Buffer::default();
+        init(Level::Debug, Format::Json, Box::new(buffer.clone()));
+        {
+            let mut s = span(Level::Debug, "test_json_records", &[("n", 3.0)]);
+            s.record("nnz", 7.0);
+            event(Level::Trace, "test_json_records", "hidden", &[]);
+        }
+        disable();
+        event(Level::Error, "test_json_records", "after", &[]);
+        let text = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        // Other tests may log while this logger is installed, so only the records of this test are inspected.
+        let records: Vec<Json> = text
+            .lines()
+            .map(|l| crate::json::parse(l).unwrap())
+            .filter(|r| r.get("target").and_then(|t| t.as_str()) == Some("test_json_records"))
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].get("message").unwrap().as_str(), Some("open"));
+        assert_eq!(records[0].get("n").unwrap().as_f64(), Some(3.0));
+        assert_eq!(records[1].get("nnz").unwrap().as_f64(), Some(7.0));
+        assert!(records[1].get("seconds").unwrap().as_f64().unwrap() >= 0.0);
+    }
+}
@@ -1,8 +1,11 @@
+use std::env;
 use std::process;
 
 fn main() {
-    if let Err(e) = rust_ed::run() {
-        println!("Error {}", e);
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(e) = rust_ed::cli::run(&args) {
+        eprintln!("Error {}", e);
+        eprintln!("{}", rust_ed::cli::USAGE);
 
         process::exit(1);
     }
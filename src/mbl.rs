@@ -0,0 +1,197 @@
+use crate::basis::Basis;
+use crate::complex::Complex;
+use crate::disorder::{disorder_average, Disorder, Distribution, Realization};
+use crate::entanglement::entropy;
+use crate::error::Error;
+use crate::level_statistics::mean_gap_ratio;
+use crate::models::{heisenberg, magnetic_field, Graph};
+use crate::operators::{Spin, SpinConvention};
+use crate::spectrum::Spectrum;
+use crate::{Slater, State};
+
+/// The parameters of a many-body localization scan of the random field Heisenberg chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MblParameters {
+    /// The even number of sites L of the open chain.
+    pub length: u64,
+    /// The exchange J.
+    pub coupling: f64,
+    /// The fraction of eigenstates, taken from the middle of the spectrum, that count as interior eigenstates.
+    pub fraction: f64,
+    /// The times t of the imbalance after a quench from the Néel state.
+    pub times: Vec<f64>,
+    /// The number of disorder realizations at each disorder strength.
+    pub n_realizations: usize,
+    /// The seed of the disorder ensembles.
+    pub seed: u64,
+    /// The number of threads, where 0 selects the configured number, see `config::Config`.
+    pub n_threads: usize,
+}
+
+/// Represents the disorder averaged diagnostics of many-body localization at a single disorder strength, with standard errors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MblPoint {
+    /// The disorder strength W.
+    pub disorder: f64,
+    /// The mean gap ratio <r> of the interior eigenstates, GOE_MEAN_GAP_RATIO when ergodic and POISSON_MEAN_GAP_RATIO when localized.
+    pub gap_ratio: f64,
+    /// The standard error of `gap_ratio`.
+    pub gap_ratio_error: f64,
+    /// The half chain entanglement entropy of the interior eigenstates, following a volume law when ergodic and an area law when localized.
+    pub entropy: f64,
+    /// The standard error of `entropy`.
+    pub entropy_error: f64,
+    /// The imbalance I(t) at each time, which decays when ergodic and saturates at a finite value when localized.
+    pub imbalance: Vec<f64>,
+    /// The standard error of `imbalance`.
+    pub imbalance_error: Vec<f64>,
+}
+
+/// Returns the gap ratio, mean half chain entropy and imbalance dynamics of a single realization of the random fields.
+fn diagnostics(parameters: &MblParameters, basis: &Basis, realization: &Realization) -> Vec<f64> {
+    let l = parameters.length;
+    let conv = SpinConvention::Blocked { n_sites: l };
+    let chain = Graph::new(l, (0..l - 1).map(|i| (i, i + 1)).collect());
+    let mut h = heisenberg(&chain, parameters.coupling, parameters.coupling, 0.0, conv);
+    // +Σ_i h_i S^z_i
+    h.terms.extend(magnetic_field(conv, &realization.onsite, -1.0, 0.0).terms);
+    let spectrum = Spectrum::new(&h, basis.clone());
+    let n = spectrum.energies().len();
+    let n_interior = ((parameters.fraction * n as f64).round() as usize).clamp(1, n);
+    let interior = (n - n_interior) / 2..(n + n_interior) / 2;
+    let mut res = vec![mean_gap_ratio(&spectrum.energies()[interior.clone()]).unwrap_or(f64::NAN)];
+    let half: Vec<u64> = (0..l / 2)
+        .flat_map(|i| [conv.orbital(i, Spin::Up), conv.orbital(i, Spin::Down)])
+        .collect();
+    let entropies: Vec<f64> = interior.clone().map(|k| entropy(&spectrum.eigenstate(k), &half)).collect();
+    res.push(entropies.iter().sum::<f64>() / entropies.len() as f64);
+    // I = (1/L) Σ_i (-1)^i (n_i↑ - n_i↓) is diagonal in the basis.
+    let imbalance: Vec<f64> = basis
+        .states()
+        .iter()
+        .map(|s| {
+            let sum: f64 = (0..l)
+                .map(|i| {
+                    let up = s.index & (1 << conv.orbital(i, Spin::Up)) != 0;
+                    let sign = if i % 2 == 0 { 1.0 } else { -1.0 };
+                    if up {
+                        sign
+                    } else {
+                        -sign
+                    }
+                })
+                .sum();
+            sum / l as f64
+        })
+        .collect();
+    let neel = (0..l).fold(0, |acc, i| {
+        let spin = if i % 2 == 0 { Spin::Up } else { Spin::Down };
+        acc | (1 << conv.orbital(i, spin))
+    });
+    let psi0 = basis.vector(&State::new(vec![(Slater::new(neel), 1.0)]));
+    let v = spectrum.vectors();
+    let overlaps = v.transpose().matvec(&psi0);
+    for t in &parameters.times {
+        let phases: Vec<Complex> = spectrum
+            .energies()
+            .iter()
+            .zip(&overlaps)
+            .map(|(e, c)| Complex::from_polar(*c, -e * t))
+            .collect();
+        let mut value = 0.0;
+        for (b, ib) in imbalance.iter().enumerate() {
+            let mut amp = Complex::default();
+            for (k, p) in phases.iter().enumerate() {
+                amp += *p * v[(b, k)];
+            }
+            value += amp.norm_sqr() * ib;
+        }
+        res.push(value);
+    }
+    res
+}
+
+/// Returns the standard many-body localization diagnostics of the random field Heisenberg chain
+/// H = J Σ_i S_i · S_i+1 + Σ_i h_i S^z_i, h_i uniform on [-W, W], for each disorder strength W.
+///
+/// Each realization is fully diagonalized in the S^z = 0 sector, which limits the chain to about 14 sites; the interior eigenstates
+/// enter the gap ratio and the half chain entanglement entropy, and the full spectrum the imbalance after a quench from |↑↓↑↓...>.
+///
+/// # Arguments
+///
+/// * `parameters` - The chain, the diagnostics and the disorder ensembles.
+/// * `disorder_strengths` - The disorder strengths W.
+///
+/// # Errors
+///
+/// * If the chain length is odd or smaller than four, this function returns an Error.
+pub fn mbl(parameters: &MblParameters, disorder_strengths: &[f64]) -> Result<Vec<MblPoint>, Error> {
+    let l = parameters.length;
+    if l % 2 == 1 || l < 4 {
+        return Err(Error::Invalid("The MBL chain needs an even number of at least four sites!"));
+    }
+    let conv = SpinConvention::Blocked { n_sites: l };
+    let up_mask = (0..l).fold(0u64, |acc, i| acc | (1 << conv.orbital(i, Spin::Up)));
+    let basis = Basis::new(
+        Basis::single_occupancy(conv, l)
+            .states()
+            .iter()
+            .filter(|s| (s.index & up_mask).count_ones() as u64 == l / 2)
+            .copied()
+            .collect(),
+    );
+    let chain = Graph::new(l, (0..l - 1).map(|i| (i, i + 1)).collect());
+    let n_times = parameters.times.len();
+    Ok(disorder_strengths
+        .iter()
+        .enumerate()
+        .map(|(k, w)| {
+            let disorder = Disorder {
+                onsite: Distribution::Uniform(*w),
+                bonds: Distribution::None,
+            };
+            let avg = disorder_average(
+                &disorder,
+                &chain,
+                parameters.n_realizations,
+                parameters.seed.wrapping_add(k as u64),
+                parameters.n_threads,
+                |r| diagnostics(parameters, &basis, r),
+            );
+            MblPoint {
+                disorder: *w,
+                gap_ratio: avg.mean[0],
+                gap_ratio_error: avg.error[0],
+                entropy: avg.mean[1],
+                entropy_error: avg.error[1],
+                imbalance: avg.mean[2..2 + n_times].to_vec(),
+                imbalance_error: avg.error[2..2 + n_times].to_vec(),
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ergodic_and_localized() {
+        let parameters = MblParameters {
+            length: 8,
+            coupling: 1.0,
+            fraction: 0.5,
+            times: vec![0.0, 50.0],
+            n_realizations: 20,
+            seed: 1,
+            n_threads: 0,
+        };
+        let points = mbl(&parameters, &[0.5, 10.0]).unwrap();
+        let (ergodic, localized) = (&points[0], &points[1]);
+        assert!((ergodic.imbalance[0] - 1.0).abs() < 1e-12);
+        assert!(ergodic.gap_ratio > localized.gap_ratio);
+        assert!(ergodic.entropy > 2.0 * localized.entropy);
+        assert!(localized.imbalance[1] > 0.5 && ergodic.imbalance[1].abs() < 0.3);
+        assert!(mbl(&MblParameters { length: 5, ..parameters }, &[1.0]).is_err());
+    }
+}
@@ -0,0 +1,771 @@
+use crate::bosons::BosonBasis;
+use crate::complex::Complex;
+use crate::hermitian::HermitianOperator;
+use crate::lattice::{Bond, Boundary, Lattice};
+use crate::linalg::SparseMatrix;
+use crate::operators::{s_minus, s_plus, spin_product, sz, transverse_spin_product, Spin, SpinConvention};
+use crate::random::Rng;
+use crate::{Operator, AC};
+
+/// Represents the connectivity of a lattice cluster: a number of sites and the undirected bonds between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    /// The number of sites.
+    pub n_sites: u64,
+    /// The bonds (i, j), each listed once.
+    pub bonds: Vec<(u64, u64)>,
+}
+
+impl Graph {
+    /// Returns the graph of `n_sites` sites connected by `bonds`.
+    pub fn new(n_sites: u64, bonds: Vec<(u64, u64)>) -> Graph {
+        Graph { n_sites, bonds }
+    }
+
+    /// Returns the sublattice sign ±1 of each site of a bipartite graph, with site 0 (and the first site of every further component) at +1,
+    /// e.g. for staggered fields, or None if the graph is not bipartite.
+    pub fn bipartition(&self) -> Option<Vec<f64>> {
+        let n = self.n_sites as usize;
+        let mut neighbours = vec![Vec::new(); n];
+        for (i, j) in &self.bonds {
+            neighbours[*i as usize].push(*j as usize);
+            neighbours[*j as usize].push(*i as usize);
+        }
+        let mut signs = vec![0.0; n];
+        for root in 0..n {
+            if signs[root] != 0.0 {
+                continue;
+            }
+            signs[root] = 1.0;
+            let mut stack = vec![root];
+            while let Some(site) = stack.pop() {
+                for next in &neighbours[site] {
+                    if signs[*next] == 0.0 {
+                        signs[*next] = -signs[site];
+                        stack.push(*next);
+                    } else if signs[*next] == signs[site] {
+                        return None;
+                    }
+                }
+            }
+        }
+        Some(signs)
+    }
+}
+
+/// Returns the one body Hamiltonian H = Σ_i ε_i n_i + Σ_(ij) t_ij (c†_i c_j + c†_j c_i) of an arbitrary hopping graph,
+/// acting directly on single particle states. Spinful models can map each (site, spin) with `SpinConvention::orbital`.
+///
+/// # Arguments
+///
+/// * `edges` - The edges (i, j, t_ij), each listed once. Note the sign: t_ij is the matrix element, not -t.
+/// * `onsite` - The on-site energies ε_i, indexed by orbital.
+pub fn tight_binding(edges: &[(u64, u64, f64)], onsite: &[f64]) -> Operator {
+    let mut terms = Vec::with_capacity(2 * edges.len() + onsite.len());
+    for (i, e) in onsite.iter().enumerate() {
+        if *e != 0.0 {
+            terms.push((*e, vec![AC::Create(i as u64), AC::Annihilate(i as u64)]));
+        }
+    }
+    for (i, j, t) in edges {
+        terms.push((*t, vec![AC::Create(*i), AC::Annihilate(*j)]));
+        terms.push((*t, vec![AC::Create(*j), AC::Annihilate(*i)]));
+    }
+    Operator::new(terms)
+}
+
+/// Returns the Fermi-Hubbard Hamiltonian H = -t Σ_<ij>σ (c†_iσ c_jσ + h.c.) + U Σ_i n_i↑ n_i↓ - μ Σ_iσ n_iσ.
+///
+/// # Arguments
+///
+/// * `lattice` - The sites and bonds <ij> of the cluster.
+/// * `t` - The hopping amplitude.
+/// * `u` - The on-site interaction U.
+/// * `mu` - The chemical potential μ.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+pub fn hubbard(lattice: &Graph, t: f64, u: f64, mu: f64, convention: SpinConvention) -> Operator {
+    let mut terms = Vec::new();
+    for (i, j) in &lattice.bonds {
+        for spin in [Spin::Up, Spin::Down] {
+            let (a, b) = (convention.orbital(*i, spin), convention.orbital(*j, spin));
+            terms.push((-t, vec![AC::Create(a), AC::Annihilate(b)]));
+            terms.push((-t, vec![AC::Create(b), AC::Annihilate(a)]));
+        }
+    }
+    for site in 0..lattice.n_sites {
+        let (up, down) = (convention.orbital(site, Spin::Up), convention.orbital(site, Spin::Down));
+        if u != 0.0 {
+            terms.push((u, vec![AC::Create(up), AC::Annihilate(up), AC::Create(down), AC::Annihilate(down)]));
+        }
+        if mu != 0.0 {
+            terms.push((-mu, vec![AC::Create(up), AC::Annihilate(up)]));
+            terms.push((-mu, vec![AC::Create(down), AC::Annihilate(down)]));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the XXZ Hamiltonian H = Σ_<ij> [J_xy (S^x_i S^x_j + S^y_i S^y_j) + J_z S^z_i S^z_j] - h Σ_i S^z_i in the fermionic
+/// representation S_i = (1/2) Σ_σσ' c†_iσ σ_σσ' c_iσ'. J_xy = J_z gives the Heisenberg model.
+///
+/// The Hamiltonian conserves the occupation of every site, so it should be represented in `Basis::single_occupancy`,
+/// where it acts as the spin model; other sectors contain spurious empty and doubly occupied sites.
+///
+/// # Arguments
+///
+/// * `lattice` - The sites and bonds <ij> of the cluster.
+/// * `jxy` - The transverse exchange J_xy.
+/// * `jz` - The longitudinal exchange J_z.
+/// * `field` - The longitudinal field h.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+pub fn heisenberg(lattice: &Graph, jxy: f64, jz: f64, field: f64, convention: SpinConvention) -> Operator {
+    let mut terms = Vec::new();
+    for (i, j) in &lattice.bonds {
+        if jxy != 0.0 {
+            let xy = transverse_spin_product(convention, *i, *j);
+            terms.extend(xy.terms.into_iter().map(|(a, ac)| (jxy * a, ac)));
+        }
+        if jz != 0.0 {
+            let zz = sz(convention, *i).product(&sz(convention, *j));
+            terms.extend(zz.terms.into_iter().map(|(a, ac)| (jz * a, ac)));
+        }
+    }
+    if field != 0.0 {
+        for site in 0..lattice.n_sites {
+            terms.extend(sz(convention, site).terms.into_iter().map(|(a, ac)| (-field * a, ac)));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the t-J Hamiltonian H = -t Σ_<ij>σ (c†_iσ c_jσ + h.c.) + J Σ_<ij> (S_i · S_j - n_i n_j / 4).
+///
+/// The projection onto states without double occupancy is not part of the operator, but of the basis: represented in
+/// `Basis::no_double_occupancy`, contributions leading to doubly occupied sites are discarded, which gives the projected hopping.
+///
+/// # Arguments
+///
+/// * `lattice` - The sites and bonds <ij> of the cluster.
+/// * `t` - The hopping amplitude.
+/// * `j` - The exchange J.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+pub fn t_j(lattice: &Graph, t: f64, j: f64, convention: SpinConvention) -> Operator {
+    let mut terms = hubbard(lattice, t, 0.0, 0.0, convention).terms;
+    if j != 0.0 {
+        for (a, b) in &lattice.bonds {
+            terms.extend(spin_product(convention, *a, *b).terms.into_iter().map(|(x, ac)| (j * x, ac)));
+            for sa in [Spin::Up, Spin::Down] {
+                for sb in [Spin::Up, Spin::Down] {
+                    let (oa, ob) = (convention.orbital(*a, sa), convention.orbital(*b, sb));
+                    terms.push((-j / 4.0, vec![AC::Create(oa), AC::Annihilate(oa), AC::Create(ob), AC::Annihilate(ob)]));
+                }
+            }
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the sparse matrix of the Bose-Hubbard Hamiltonian H = -t Σ_<ij> (b†_i b_j + h.c.) + (U/2) Σ_i n_i (n_i - 1) - μ Σ_i n_i
+/// in `basis`, whose occupation cutoff n_max truncates the bosonic Hilbert space.
+///
+/// # Arguments
+///
+/// * `lattice` - The sites and bonds <ij> of the cluster.
+/// * `t` - The hopping amplitude.
+/// * `u` - The on-site interaction U.
+/// * `mu` - The chemical potential μ.
+/// * `basis` - The bosonic Fock states, e.g. from `BosonBasis::fixed_particle_number`.
+pub fn bose_hubbard(lattice: &Graph, t: f64, u: f64, mu: f64, basis: &BosonBasis) -> SparseMatrix {
+    let mut hoppings = Vec::with_capacity(2 * lattice.bonds.len());
+    for (i, j) in &lattice.bonds {
+        hoppings.push((*i as usize, *j as usize, -t));
+        hoppings.push((*j as usize, *i as usize, -t));
+    }
+    basis.sparse_matrix(&hoppings, |n| {
+        n.iter()
+            .map(|ni| {
+                let ni = *ni as f64;
+                u / 2.0 * ni * (ni - 1.0) - mu * ni
+            })
+            .sum()
+    })
+}
+
+/// Returns the single impurity Anderson model H = ε_d Σ_σ n_dσ + U n_d↑ n_d↓ + Σ_kσ ε_k n_kσ + Σ_kσ V_k (d†_σ c_kσ + h.c.)
+/// of an impurity coupled to a discretized bath. The impurity is site 0 and bath level k is site k + 1.
+///
+/// # Arguments
+///
+/// * `eps_d` - The impurity level ε_d.
+/// * `u` - The impurity interaction U.
+/// * `bath_energies` - The bath levels ε_k.
+/// * `hybridizations` - The hybridizations V_k, one per bath level.
+/// * `convention` - The mapping of (site, spin) onto single particle states, for 1 + bath_energies.len() sites.
+///
+/// # Panics
+///
+/// * If the numbers of bath energies and hybridizations differ.
+pub fn siam(eps_d: f64, u: f64, bath_energies: &[f64], hybridizations: &[f64], convention: SpinConvention) -> Operator {
+    assert_eq!(bath_energies.len(), hybridizations.len(), "Every bath level needs a hybridization!");
+    // The isolated impurity is a single Hubbard site with μ = -ε_d.
+    let mut terms = hubbard(&Graph::new(1, Vec::new()), 0.0, u, -eps_d, convention).terms;
+    for (k, (e, v)) in bath_energies.iter().zip(hybridizations).enumerate() {
+        let site = k as u64 + 1;
+        for spin in [Spin::Up, Spin::Down] {
+            let (d, c) = (convention.orbital(0, spin), convention.orbital(site, spin));
+            terms.push((*e, vec![AC::Create(c), AC::Annihilate(c)]));
+            terms.push((*v, vec![AC::Create(d), AC::Annihilate(c)]));
+            terms.push((*v, vec![AC::Create(c), AC::Annihilate(d)]));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the Kitaev chain H = Σ_i [-t (c†_i c_i+1 + h.c.) + Δ (c_i c_i+1 + c†_i+1 c†_i)] - μ Σ_i n_i of `length` spinless sites
+/// with open boundaries. The pairing only conserves fermion parity, so the Hamiltonian should be represented in `Basis::fixed_parity`.
+///
+/// The chain is topological for |μ| < 2t, where the even and odd ground states become degenerate up to corrections exponentially small
+/// in the length, and exactly degenerate at t = Δ, μ = 0.
+///
+/// # Arguments
+///
+/// * `t` - The hopping amplitude.
+/// * `delta` - The p-wave pairing amplitude Δ.
+/// * `mu` - The chemical potential μ.
+/// * `length` - The number of sites.
+pub fn kitaev_chain(t: f64, delta: f64, mu: f64, length: u64) -> Operator {
+    let mut terms = Vec::new();
+    for i in 0..length.saturating_sub(1) {
+        let j = i + 1;
+        terms.push((-t, vec![AC::Create(i), AC::Annihilate(j)]));
+        terms.push((-t, vec![AC::Create(j), AC::Annihilate(i)]));
+        if delta != 0.0 {
+            terms.push((delta, vec![AC::Annihilate(i), AC::Annihilate(j)]));
+            terms.push((delta, vec![AC::Create(j), AC::Create(i)]));
+        }
+    }
+    if mu != 0.0 {
+        for i in 0..length {
+            terms.push((-mu, vec![AC::Create(i), AC::Annihilate(i)]));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Appends the spin dependent hopping Σ_σσ' c†_iσ A_σσ' c_jσ' + h.c. to the real and imaginary parts of an operator.
+fn push_spin_hopping(
+    i: u64,
+    j: u64,
+    a: [[Complex; 2]; 2],
+    convention: SpinConvention,
+    real: &mut Vec<(f64, Vec<AC>)>,
+    imaginary: &mut Vec<(f64, Vec<AC>)>,
+) {
+    let spins = [Spin::Up, Spin::Down];
+    for (s, row) in spins.iter().zip(a) {
+        for (t, amp) in spins.iter().zip(row) {
+            let (p, q) = (convention.orbital(i, *s), convention.orbital(j, *t));
+            for (x, ops) in [(amp, vec![AC::Create(p), AC::Annihilate(q)]), (amp.conj(), vec![AC::Create(q), AC::Annihilate(p)])] {
+                if x.re != 0.0 {
+                    real.push((x.re, ops.clone()));
+                }
+                if x.im != 0.0 {
+                    imaginary.push((x.im, ops));
+                }
+            }
+        }
+    }
+}
+
+/// Returns the unit vector along the displacement of `bond`.
+fn bond_direction(bond: &Bond) -> [f64; 2] {
+    let [x, y] = bond.displacement;
+    let r = x.hypot(y);
+    [x / r, y / r]
+}
+
+/// Returns the Rashba and Dresselhaus spin-orbit hoppings
+/// H = Σ_<ij> [iλ_R c†_i (σ_x d_y - σ_y d_x) c_j + iλ_D c†_i (σ_x d_x - σ_y d_y) c_j + h.c.],
+/// with d the unit vector from j to i, as a Hermitian operator with complex amplitudes.
+///
+/// # Arguments
+///
+/// * `bonds` - The bonds <ij>, e.g. `Lattice::nearest_neighbours`.
+/// * `rashba` - The Rashba coupling λ_R.
+/// * `dresselhaus` - The Dresselhaus coupling λ_D.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+pub fn spin_orbit_hopping(bonds: &[Bond], rashba: f64, dresselhaus: f64, convention: SpinConvention) -> HermitianOperator {
+    let (mut real, mut imaginary) = (Vec::new(), Vec::new());
+    for bond in bonds {
+        let [dx, dy] = bond_direction(bond);
+        // i(σ_x d_y - σ_y d_x) = [[0, -d_x + i d_y], [d_x + i d_y, 0]] and i(σ_x d_x - σ_y d_y) = [[0, -d_y + i d_x], [d_y + i d_x, 0]].
+        let up_down = Complex::new(-rashba * dx - dresselhaus * dy, rashba * dy + dresselhaus * dx);
+        let down_up = Complex::new(rashba * dx + dresselhaus * dy, rashba * dy + dresselhaus * dx);
+        let a = [[Complex::default(), up_down], [down_up, Complex::default()]];
+        push_spin_hopping(bond.i, bond.j, a, convention, &mut real, &mut imaginary);
+    }
+    HermitianOperator::new(Operator::new(real), Operator::new(imaginary))
+}
+
+/// Returns the atomic spin-orbit coupling λ L · S = λ [L_z S_z + (L_+ S_- + L_- S_+)/2] of a shell of angular momentum l,
+/// whose matrix elements are real in the basis of spherical harmonics. Its eigenvalues are λ l/2 for j = l + 1/2 and -λ (l + 1)/2 for j = l - 1/2.
+///
+/// # Arguments
+///
+/// * `lambda` - The coupling λ.
+/// * `l` - The orbital angular momentum of the shell.
+/// * `orbital` - The single particle state of the spherical harmonic m (-l ≤ m ≤ l) with spin σ.
+pub fn atomic_spin_orbit<F: Fn(i64, Spin) -> u64>(lambda: f64, l: i64, orbital: F) -> Operator {
+    let mut terms = Vec::new();
+    for m in -l..=l {
+        for spin in [Spin::Up, Spin::Down] {
+            let o = orbital(m, spin);
+            if m != 0 {
+                terms.push((lambda * m as f64 * spin.sz(), vec![AC::Create(o), AC::Annihilate(o)]));
+            }
+        }
+        if m < l {
+            // L_+ S_- |m, ↑> = √(l(l + 1) - m(m + 1)) |m + 1, ↓>
+            let amp = lambda / 2.0 * ((l * (l + 1) - m * (m + 1)) as f64).sqrt();
+            let (up, down) = (orbital(m, Spin::Up), orbital(m + 1, Spin::Down));
+            terms.push((amp, vec![AC::Create(down), AC::Annihilate(up)]));
+            terms.push((amp, vec![AC::Create(up), AC::Annihilate(down)]));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the Zeeman coupling H = -Σ_i h_i · S_i to site dependent magnetic fields, where S^y_i = (S^+_i - S^-_i)/2i
+/// makes fields with a y component complex.
+///
+/// # Arguments
+///
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `fields` - The fields (h_x, h_y, h_z) on each site, indexed by site.
+pub fn zeeman(convention: SpinConvention, fields: &[[f64; 3]]) -> HermitianOperator {
+    let (mut real, mut imaginary) = (Vec::new(), Vec::new());
+    for (site, [hx, hy, hz]) in fields.iter().enumerate() {
+        let site = site as u64;
+        if *hz != 0.0 {
+            real.extend(sz(convention, site).terms.into_iter().map(|(a, ac)| (-hz * a, ac)));
+        }
+        for (sign, op) in [(1.0, s_plus(convention, site)), (-1.0, s_minus(convention, site))] {
+            for (a, ac) in op.terms {
+                // -h_x S^x = -(h_x/2)(S^+ + S^-) and -h_y S^y = i (h_y/2)(S^+ - S^-).
+                if *hx != 0.0 {
+                    real.push((-hx / 2.0 * a, ac.clone()));
+                }
+                if *hy != 0.0 {
+                    imaginary.push((sign * hy / 2.0 * a, ac));
+                }
+            }
+        }
+    }
+    HermitianOperator::new(Operator::new(real), Operator::new(imaginary))
+}
+
+/// Returns the coupling H = -Σ_i ε_i (h_z S^z_i + h_x S^x_i) to a longitudinal and a transverse field with the sign ε_i on each site,
+/// both real. Equal signs give a uniform field, the signs of `Graph::bipartition` a staggered one.
+///
+/// # Arguments
+///
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `signs` - The sign, or more generally the relative strength, ε_i of the field on each site, indexed by site.
+/// * `longitudinal` - The field h_z.
+/// * `transverse` - The field h_x.
+pub fn magnetic_field(convention: SpinConvention, signs: &[f64], longitudinal: f64, transverse: f64) -> Operator {
+    let fields: Vec<[f64; 3]> = signs.iter().map(|e| [e * transverse, 0.0, e * longitudinal]).collect();
+    zeeman(convention, &fields).real
+}
+
+/// Represents a power law interaction V(r) = C / r^α, truncated beyond a cutoff distance.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PowerLaw {
+    /// The strength C.
+    pub strength: f64,
+    /// The exponent α, e.g. 1 for Coulomb and 3 for dipolar interactions.
+    pub exponent: f64,
+    /// The largest distance with nonzero interaction.
+    pub cutoff: f64,
+}
+
+impl PowerLaw {
+    /// Returns the interaction C / r^α for r ≤ `cutoff`.
+    pub fn new(strength: f64, exponent: f64, cutoff: f64) -> PowerLaw {
+        PowerLaw {
+            strength,
+            exponent,
+            cutoff,
+        }
+    }
+
+    /// Returns V(r).
+    pub fn potential(&self, r: f64) -> f64 {
+        if r > self.cutoff {
+            0.0
+        } else {
+            self.strength / r.powf(self.exponent)
+        }
+    }
+}
+
+/// Returns the couplings V_ij, i ≤ j, of the density-density interaction H = Σ_{i≤j} V_ij n_i n_j on a cluster.
+///
+/// Along periodic (and twisted) directions the pair potential is summed over all periodic images within the cutoff,
+/// V_ij = Σ_n V(|r_i - r_j + n·L|), and each site interacts with its own images through V_ii = (1/2) Σ_{n≠0} V(|n·L|).
+/// The image sums converge with the cutoff for α larger than the dimension; slower tails, like Coulomb, depend on the cutoff
+/// and call for a neutralizing background.
+///
+/// # Arguments
+///
+/// * `lattice` - The cluster.
+/// * `v` - The pair potential.
+pub fn long_range_couplings(lattice: &Lattice, v: &PowerLaw) -> Vec<(u64, u64, f64)> {
+    let periods = lattice.periods();
+    let boundaries = lattice.boundaries();
+    let images = |d: usize| -> i64 {
+        let length = periods[d][0].hypot(periods[d][1]);
+        if boundaries[d] == Boundary::Open || length == 0.0 {
+            0
+        } else {
+            (v.cutoff / length).ceil() as i64 + 1
+        }
+    };
+    let (m1, m2) = (images(0), images(1));
+    let positions = lattice.positions();
+    let mut couplings = Vec::new();
+    for i in 0..positions.len() {
+        for j in i..positions.len() {
+            let mut sum = 0.0;
+            for n1 in -m1..=m1 {
+                for n2 in -m2..=m2 {
+                    if i == j && n1 == 0 && n2 == 0 {
+                        continue;
+                    }
+                    let dx = positions[i][0] - positions[j][0] + n1 as f64 * periods[0][0] + n2 as f64 * periods[1][0];
+                    let dy = positions[i][1] - positions[j][1] + n1 as f64 * periods[0][1] + n2 as f64 * periods[1][1];
+                    sum += v.potential(dx.hypot(dy));
+                }
+            }
+            if i == j {
+                sum /= 2.0;
+            }
+            if sum != 0.0 {
+                couplings.push((i as u64, j as u64, sum));
+            }
+        }
+    }
+    couplings
+}
+
+/// Returns the density-density interaction H = Σ V_ij n_i n_j, with n_i the total density of the orbitals of site i.
+///
+/// # Arguments
+///
+/// * `couplings` - The couplings (i, j, V_ij), e.g. from `long_range_couplings`.
+/// * `orbitals` - The single particle states of each site, e.g. both spins from `SpinConvention::orbital`.
+pub fn density_interaction(couplings: &[(u64, u64, f64)], orbitals: &[Vec<u64>]) -> Operator {
+    let mut terms = Vec::new();
+    for (i, j, v) in couplings {
+        for a in &orbitals[*i as usize] {
+            for b in &orbitals[*j as usize] {
+                terms.push((*v, vec![AC::Create(*a), AC::Annihilate(*a), AC::Create(*b), AC::Annihilate(*b)]));
+            }
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Represents one disorder realization of the Sachdev-Ye-Kitaev model, together with the seed that reproduces it.
+#[derive(Debug, Clone)]
+pub struct Syk {
+    /// The seed the couplings were drawn with.
+    pub seed: u64,
+    /// The couplings J_ijkl, i < j < k < l.
+    pub couplings: Vec<([u64; 4], f64)>,
+    /// The Hamiltonian H = Σ_{i<j<k<l} J_ijkl χ_i χ_j χ_k χ_l.
+    pub hamiltonian: HermitianOperator,
+}
+
+/// Returns the fermionic expansion of the Majorana operator χ_m as (amplitude, operator) pairs.
+/// Orbital a carries χ_2a = (c_a + c†_a)/√2 and χ_2a+1 = i(c†_a - c_a)/√2, so that {χ_m, χ_n} = δ_mn.
+fn majorana(m: u64) -> [(Complex, AC); 2] {
+    let a = m / 2;
+    let s = std::f64::consts::FRAC_1_SQRT_2;
+    if m.is_multiple_of(2) {
+        [(Complex::from(s), AC::Create(a)), (Complex::from(s), AC::Annihilate(a))]
+    } else {
+        [(Complex::new(0.0, s), AC::Create(a)), (Complex::new(0.0, -s), AC::Annihilate(a))]
+    }
+}
+
+/// Returns a realization of the SYK model of `n_majorana` Majorana fermions on n_majorana/2 orbitals, with all-to-all couplings J_ijkl
+/// drawn independently from a normal distribution with zero mean and variance `variance`; the conventional choice is 3! J²/N³.
+///
+/// The Hamiltonian conserves only fermion parity and has complex matrix elements in the fermionic basis.
+/// Equal seeds give equal realizations.
+///
+/// # Arguments
+///
+/// * `n_majorana` - The number N of Majorana fermions.
+/// * `variance` - The variance of the couplings.
+/// * `seed` - The seed of the random couplings.
+///
+/// # Panics
+///
+/// * If `n_majorana` is odd.
+pub fn syk(n_majorana: u64, variance: f64, seed: u64) -> Syk {
+    assert!(n_majorana.is_multiple_of(2), "Majorana fermions come in pairs!");
+    let mut rng = Rng::new(seed);
+    let sigma = variance.sqrt();
+    let (mut real, mut imaginary) = (Vec::new(), Vec::new());
+    let mut couplings = Vec::new();
+    for i in 0..n_majorana {
+        for j in i + 1..n_majorana {
+            for k in j + 1..n_majorana {
+                for l in k + 1..n_majorana {
+                    let coupling = sigma * rng.normal();
+                    couplings.push(([i, j, k, l], coupling));
+                    let factors = [majorana(i), majorana(j), majorana(k), majorana(l)];
+                    for choice in 0..16 {
+                        let mut amp = Complex::from(coupling);
+                        let mut ops = Vec::with_capacity(4);
+                        for (n, f) in factors.iter().enumerate() {
+                            let (a, op) = f[(choice >> n) & 1];
+                            amp *= a;
+                            ops.push(op);
+                        }
+                        // The same creation or annihilation operator twice gives zero.
+                        if (1..4).any(|n| ops[..n].contains(&ops[n])) {
+                            continue;
+                        }
+                        if amp.re != 0.0 {
+                            real.push((amp.re, ops.clone()));
+                        }
+                        if amp.im != 0.0 {
+                            imaginary.push((amp.im, ops));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Syk {
+        seed,
+        couplings,
+        hamiltonian: HermitianOperator::new(Operator::new(real), Operator::new(imaginary)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::lattice::{Boundary, Lattice};
+    use crate::linalg::{LinearOperator, Matrix};
+    use crate::spectrum::Spectrum;
+
+    #[test]
+    fn test_hubbard_dimer() {
+        // The half filled dimer has E_0 = (U - √(U² + 16t²))/2 - 2μ.
+        let (t, u, mu) = (1.0, 4.0, 0.5);
+        let h = hubbard(&Graph::new(2, vec![(0, 1)]), t, u, mu, SpinConvention::Blocked { n_sites: 2 });
+        let spectrum = Spectrum::new(&h, Basis::fixed_particle_number(4, 2));
+        let expected = (u - (u * u + 16.0 * t * t).sqrt()) / 2.0 - 2.0 * mu;
+        assert!((spectrum.ground_state_energy() - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_xxz_dimer() {
+        // The singlet has -J_xy/2 - J_z/4, the triplet J_xy/2 - J_z/4 for m = 0 and J_z/4 ∓ h for m = ±1.
+        let (jxy, jz, h) = (1.0, 0.5, 0.2);
+        let conv = SpinConvention::Interleaved;
+        let basis = Basis::single_occupancy(conv, 2);
+        assert_eq!(basis.dimension(), 4);
+        let spectrum = Spectrum::new(&heisenberg(&Graph::new(2, vec![(0, 1)]), jxy, jz, h, conv), basis);
+        let mut expected = [-jxy / 2.0 - jz / 4.0, jxy / 2.0 - jz / 4.0, jz / 4.0 - h, jz / 4.0 + h];
+        expected.sort_by(|a, b| a.total_cmp(b));
+        for (e, x) in spectrum.energies().iter().zip(expected) {
+            assert!((e - x).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_t_j() {
+        let conv = SpinConvention::Blocked { n_sites: 3 };
+        // Two particles on three sites: three ways to place the hole and four spin configurations.
+        assert_eq!(Basis::no_double_occupancy(conv, 3, 2).dimension(), 12);
+        // On the dimer the hopping is blocked entirely, leaving the singlet at -J and the triplet at zero.
+        let conv = SpinConvention::Blocked { n_sites: 2 };
+        let h = t_j(&Graph::new(2, vec![(0, 1)]), 1.0, 0.4, conv);
+        let spectrum = Spectrum::new(&h, Basis::no_double_occupancy(conv, 2, 2));
+        for (e, x) in spectrum.energies().iter().zip([-0.4, 0.0, 0.0, 0.0]) {
+            assert!((e - x).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_bose_hubbard_dimer() {
+        // Two bosons on a dimer: |20> - |02> at U and (U ± √(U² + 16t²))/2 for the symmetric states, all shifted by -2μ.
+        let (t, u, mu) = (1.0, 3.0, 0.25);
+        let dimer = Graph::new(2, vec![(0, 1)]);
+        let basis = BosonBasis::fixed_particle_number(2, 2, 2);
+        let h = bose_hubbard(&dimer, t, u, mu, &basis);
+        let mut dense = Matrix::zeros(3, 3);
+        for r in 0..3 {
+            let (cols, values) = h.row(r);
+            for (c, v) in cols.iter().zip(values) {
+                dense[(r, *c)] = *v;
+            }
+        }
+        let root = (u * u + 16.0 * t * t).sqrt();
+        let expected = [(u - root) / 2.0, u, (u + root) / 2.0];
+        for (e, x) in dense.eigh().0.iter().zip(expected) {
+            assert!((e - (x - 2.0 * mu)).abs() < 1e-12);
+        }
+        // Hard-core bosons cannot hop on a filled dimer.
+        let hard_core = bose_hubbard(&dimer, t, u, mu, &BosonBasis::fixed_particle_number(2, 2, 1));
+        assert_eq!(hard_core.dimension(), 1);
+    }
+
+    #[test]
+    fn test_siam_single_particle() {
+        // A single electron hybridizing with one bath level has the bonding and antibonding energies of a two level system.
+        let (eps_d, e, v) = (-1.0, 0.5, 0.3);
+        let h = siam(eps_d, 5.0, &[e], &[v], SpinConvention::Interleaved);
+        let spectrum = Spectrum::new(&h, Basis::fixed_particle_number(4, 1));
+        let root = ((eps_d - e) * (eps_d - e) / 4.0 + v * v).sqrt();
+        let (low, high) = ((eps_d + e) / 2.0 - root, (eps_d + e) / 2.0 + root);
+        for (x, y) in spectrum.energies().iter().zip([low, low, high, high]) {
+            assert!((x - y).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_kitaev_chain_zero_modes() {
+        // At t = Δ, μ = 0 every bond contributes ±t independently, and the Majorana zero modes make both parities degenerate.
+        let (t, l) = (1.0, 4);
+        let h = kitaev_chain(t, t, 0.0, l);
+        let even = Spectrum::new(&h, Basis::fixed_parity(l, false));
+        let odd = Spectrum::new(&h, Basis::fixed_parity(l, true));
+        assert_eq!(even.basis().dimension(), 8);
+        assert!((even.ground_state_energy() + t * (l - 1) as f64).abs() < 1e-12);
+        assert!((odd.ground_state_energy() + t * (l - 1) as f64).abs() < 1e-12);
+        // Deep in the trivial phase the odd sector costs the energy of an extra particle.
+        let h = kitaev_chain(t, t, -6.0, l);
+        let gap = Spectrum::new(&h, Basis::fixed_parity(l, true)).ground_state_energy()
+            - Spectrum::new(&h, Basis::fixed_parity(l, false)).ground_state_energy();
+        assert!(gap > 1.0);
+    }
+
+    fn dense<H: LinearOperator>(h: &H) -> Matrix {
+        let n = h.dimension();
+        let mut m = Matrix::zeros(n, n);
+        let mut x = vec![0.0; n];
+        let mut y = vec![0.0; n];
+        for c in 0..n {
+            x[c] = 1.0;
+            h.apply(&x, &mut y);
+            x[c] = 0.0;
+            for r in 0..n {
+                m[(r, c)] = y[r];
+            }
+        }
+        m
+    }
+
+    #[test]
+    fn test_syk() {
+        // With four Majoranas, (χ_0 χ_1 χ_2 χ_3)² = 1/16, so the energies are ±|J|/4.
+        let model = syk(4, 1.0, 7);
+        assert_eq!(model.couplings.len(), 1);
+        let j = model.couplings[0].1;
+        let e = dense(&model.hamiltonian.realified(&Basis::fock_space(2))).eigh().0;
+        for (k, x) in e.iter().enumerate() {
+            let expected = if k < 4 { -j.abs() / 4.0 } else { j.abs() / 4.0 };
+            assert!((x - expected).abs() < 1e-12);
+        }
+        // Larger realizations are Hermitian and reproducible.
+        let model = syk(8, 0.5, 11);
+        assert_eq!(model.couplings.len(), 70);
+        assert_eq!(model.couplings, syk(8, 0.5, 11).couplings);
+        let m = dense(&model.hamiltonian.realified(&Basis::fock_space(4)));
+        for r in 0..m.rows() {
+            for c in 0..r {
+                assert!((m[(r, c)] - m[(c, r)]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tight_binding() {
+        // A triangle with one bond of strength 2: the single particle matrix [[ε, 1, 2], [1, 0, 1], [2, 1, 0]].
+        let h = tight_binding(&[(0, 1, 1.0), (1, 2, 1.0), (0, 2, 2.0)], &[0.5]);
+        let spectrum = Spectrum::new(&h, Basis::fixed_particle_number(3, 1));
+        let m = Matrix::from_vec(3, 3, vec![0.5, 1.0, 2.0, 1.0, 0.0, 1.0, 2.0, 1.0, 0.0]);
+        for (e, x) in spectrum.energies().iter().zip(m.eigh().0) {
+            assert!((e - x).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_long_range_couplings() {
+        let coulomb = PowerLaw::new(1.0, 1.0, 2.5);
+        // Two spinless particles on an open chain of three sites: V_01 = V_12 = 1 and V_02 = 1/2.
+        let open = Lattice::chain(3, Boundary::Open);
+        let couplings = long_range_couplings(&open, &coulomb);
+        let orbitals: Vec<Vec<u64>> = (0..3).map(|i| vec![i]).collect();
+        let spectrum = Spectrum::new(&density_interaction(&couplings, &orbitals), Basis::fixed_particle_number(3, 2));
+        for (e, x) in spectrum.energies().iter().zip([0.5, 1.0, 1.0]) {
+            assert!((e - x).abs() < 1e-12);
+        }
+        // On a ring of four sites the opposite site is reached through two images at distance 2, the own images lie beyond the cutoff.
+        let ring = long_range_couplings(&Lattice::chain(4, Boundary::Periodic), &coulomb);
+        assert!(ring.contains(&(0, 1, 1.0)) && ring.contains(&(0, 2, 1.0)) && ring.contains(&(0, 3, 1.0)));
+        assert_eq!(ring.len(), 6);
+        let wide = long_range_couplings(&Lattice::chain(4, Boundary::Periodic), &PowerLaw::new(1.0, 1.0, 4.5));
+        assert!(wide.contains(&(0, 0, 0.25)));
+    }
+
+    #[test]
+    fn test_spin_orbit() {
+        // A Rashba bond with hopping t has the Kramers degenerate energies ±√(t² + λ²), each twice more in the real representation.
+        let conv = SpinConvention::Interleaved;
+        let dimer = Lattice::new([[1.0, 1.0], [0.0, 1.0]], &[[0.0, 0.0]], [2, 1], [Boundary::Open, Boundary::Open]);
+        let (t, lambda) = (1.0, 0.6);
+        let mut h = spin_orbit_hopping(dimer.nearest_neighbours(), lambda, 0.0, conv);
+        h.real.terms.extend(hubbard(&dimer.graph(), t, 0.0, 0.0, conv).terms);
+        let e = dense(&h.realified(&Basis::fixed_particle_number(4, 1))).eigh().0;
+        let root = (t * t + lambda * lambda).sqrt();
+        for (k, x) in e.iter().enumerate() {
+            assert!((x - if k < 4 { -root } else { root }).abs() < 1e-12);
+        }
+        // A p shell splits into j = 3/2 at λ/2 and j = 1/2 at -λ.
+        let ls = atomic_spin_orbit(lambda, 1, |m, s| conv.orbital((m + 1) as u64, s));
+        let spectrum = Spectrum::new(&ls, Basis::fixed_particle_number(6, 1));
+        for (k, x) in spectrum.energies().iter().enumerate() {
+            assert!((x - if k < 2 { -lambda } else { lambda / 2.0 }).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fields() {
+        // A single spin in the field h has the energies ±|h|/2, each twice in the real representation.
+        let conv = SpinConvention::Interleaved;
+        let basis = Basis::single_occupancy(conv, 1);
+        let h = [0.3, -0.4, 1.2];
+        let e = dense(&zeeman(conv, &[h]).realified(&basis)).eigh().0;
+        let norm = (h[0] * h[0] + h[1] * h[1] + h[2] * h[2]).sqrt();
+        for (k, x) in e.iter().enumerate() {
+            assert!((x - if k < 2 { -norm / 2.0 } else { norm / 2.0 }).abs() < 1e-12);
+        }
+        // A staggered longitudinal field on a ring polarizes the Néel state |↑↓↑↓>, which has energy -4 h/2.
+        let ring = Lattice::chain(4, Boundary::Periodic).graph();
+        let signs = ring.bipartition().unwrap();
+        assert_eq!(signs, vec![1.0, -1.0, 1.0, -1.0]);
+        let spectrum = Spectrum::new(&magnetic_field(conv, &signs, 0.5, 0.0), Basis::single_occupancy(conv, 4));
+        assert!((spectrum.ground_state_energy() + 1.0).abs() < 1e-12);
+        assert_eq!(spectrum.ground_state_degeneracy(), 1);
+        assert!(Lattice::triangular([3, 3], [Boundary::Periodic; 2]).graph().bipartition().is_none());
+    }
+}
@@ -0,0 +1,357 @@
+use std::f64::consts::PI;
+
+use crate::error::Error;
+use crate::linalg::{dot, Matrix};
+use crate::observables::{one_rdm, spin_correlations, spin_resolved_density_correlations};
+use crate::operators::{Spin, SpinConvention};
+use crate::spectrum::Spectrum;
+use crate::{Slater, State};
+
+/// Returns the momenta k = 2π (m_1/L_1, ..., m_d/L_d), 0 ≤ m_i < L_i, allowed by periodic boundary conditions on a hypercubic
+/// cluster with unit lattice spacing, with the first direction running fastest.
+///
+/// # Arguments
+///
+/// * `lengths` - The linear sizes L_i of the cluster.
+pub fn momentum_grid(lengths: &[usize]) -> Vec<Vec<f64>> {
+    let total: usize = lengths.iter().product();
+    (0..total)
+        .map(|index| {
+            let mut rest = index;
+            lengths
+                .iter()
+                .map(|l| {
+                    let m = rest % l;
+                    rest /= l;
+                    2.0 * PI * m as f64 / *l as f64
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Represents a quantity tabulated on a set of momenta.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MomentumTable {
+    /// The momenta k.
+    pub momenta: Vec<Vec<f64>>,
+    /// The value at each momentum.
+    pub values: Vec<f64>,
+}
+
+/// Returns the momentum distribution n(k) = (1/N) Σ_ij e^{ik·(r_i - r_j)} <c†_i c_j> of a single species of N single particle states.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `orbitals` - The single particle states, e.g. all spin up orbitals of a lattice.
+/// * `positions` - The position r_i of each orbital.
+/// * `momenta` - The momenta k.
+pub fn momentum_distribution(
+    state: &State,
+    orbitals: &[u64],
+    positions: &[Vec<f64>],
+    momenta: &[Vec<f64>],
+) -> MomentumTable {
+    let n_orbitals = orbitals.iter().max().map_or(0, |o| o + 1);
+    let rho = one_rdm(state, n_orbitals);
+    let mut species = Matrix::zeros(orbitals.len(), orbitals.len());
+    for (i, oi) in orbitals.iter().enumerate() {
+        for (j, oj) in orbitals.iter().enumerate() {
+            species[(i, j)] = rho[(*oi as usize, *oj as usize)];
+        }
+    }
+    fourier_transform(&species, positions, momenta)
+}
+
+/// Returns the lattice Fourier transform (1/N) Σ_ij e^{ik·(r_i - r_j)} C_ij of a real symmetric correlation matrix C on N sites.
+///
+/// # Arguments
+///
+/// * `correlations` - The correlation matrix C_ij.
+/// * `positions` - The position r_i belonging to row and column i.
+/// * `momenta` - The momenta k.
+pub fn fourier_transform(correlations: &Matrix, positions: &[Vec<f64>], momenta: &[Vec<f64>]) -> MomentumTable {
+    let n = correlations.rows();
+    let values = momenta
+        .iter()
+        .map(|k| {
+            let phases: Vec<f64> = positions.iter().map(|r| dot(k, r)).collect();
+            let mut sum = 0.0;
+            for i in 0..n {
+                for j in 0..n {
+                    // C is real symmetric, so only the cosine survives the sum over i, j.
+                    sum += (phases[i] - phases[j]).cos() * correlations[(i, j)];
+                }
+            }
+            sum / n as f64
+        })
+        .collect();
+    MomentumTable {
+        momenta: momenta.to_vec(),
+        values,
+    }
+}
+
+/// Returns the static spin structure factor S(q) = (1/N) Σ_ij e^{iq·(r_i - r_j)} <S_i · S_j>.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `sites` - The lattice sites.
+/// * `positions` - The position of each site.
+/// * `momenta` - The momenta q, e.g. from `momentum_grid`.
+pub fn spin_structure_factor(
+    state: &State,
+    convention: SpinConvention,
+    sites: &[u64],
+    positions: &[Vec<f64>],
+    momenta: &[Vec<f64>],
+) -> MomentumTable {
+    fourier_transform(&spin_correlations(state, convention, sites).total(), positions, momenta)
+}
+
+/// Returns the static charge structure factor N(q) = (1/N) Σ_ij e^{iq·(r_i - r_j)} (<n_i n_j> - <n_i><n_j>).
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `sites` - The lattice sites.
+/// * `positions` - The position of each site.
+/// * `momenta` - The momenta q, e.g. from `momentum_grid`.
+pub fn charge_structure_factor(
+    state: &State,
+    convention: SpinConvention,
+    sites: &[u64],
+    positions: &[Vec<f64>],
+    momenta: &[Vec<f64>],
+) -> MomentumTable {
+    let correlations = spin_resolved_density_correlations(state, convention, sites).charge();
+    fourier_transform(&correlations, positions, momenta)
+}
+
+/// Returns the state obtained by replacing every single particle state i by π(i), c†_i → c†_π(i), including the fermionic sign
+/// of restoring the ascending order of the orbitals in each Slater determinant.
+///
+/// # Arguments
+///
+/// * `state` - The state.
+/// * `permutation` - The permutation π, indexed by orbital.
+pub fn permute_orbitals(state: &State, permutation: &[u64]) -> State {
+    State::new(
+        state
+            .amplitudes
+            .iter()
+            .map(|(slater, amp)| {
+                let mapped: Vec<u64> = (0..64)
+                    .filter(|i| slater.index & (1 << i) != 0)
+                    .map(|i| permutation[i as usize])
+                    .collect();
+                let mut inversions = 0;
+                for (a, pa) in mapped.iter().enumerate() {
+                    inversions += mapped[a + 1..].iter().filter(|pb| *pb < pa).count();
+                }
+                let index = mapped.iter().fold(0, |acc, p| acc | (1 << p));
+                let sign = if inversions % 2 == 0 { 1.0 } else { -1.0 };
+                (Slater::new(index), sign * amp)
+            })
+            .collect(),
+    )
+}
+
+/// Returns the permutation of spin orbitals that moves both spins of site i to site π(i).
+///
+/// # Arguments
+///
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `site_permutation` - The permutation π of the sites, indexed by site.
+pub fn spinful_permutation(convention: SpinConvention, site_permutation: &[u64]) -> Vec<u64> {
+    let mut res = vec![0; 2 * site_permutation.len()];
+    for (site, target) in site_permutation.iter().enumerate() {
+        for spin in [Spin::Up, Spin::Down] {
+            res[convention.orbital(site as u64, spin) as usize] = convention.orbital(*target, spin);
+        }
+    }
+    res
+}
+
+/// Returns the orbital permutations of all translations T_1^{m_1} ... T_d^{m_d}, in the order of `momentum_grid`.
+fn translation_group(generators: &[Vec<u64>], lengths: &[usize]) -> Vec<Vec<u64>> {
+    let n_orbitals = generators.first().map_or(0, |g| g.len());
+    let total: usize = lengths.iter().product();
+    (0..total)
+        .map(|index| {
+            let mut rest = index;
+            let mut perm: Vec<u64> = (0..n_orbitals as u64).collect();
+            for (g, l) in generators.iter().zip(lengths) {
+                for _ in 0..rest % l {
+                    for p in perm.iter_mut() {
+                        *p = g[*p as usize];
+                    }
+                }
+                rest /= l;
+            }
+            perm
+        })
+        .collect()
+}
+
+/// Returns the weight <ψ|P_k|ψ>/<ψ|ψ> of a state in each momentum sector, using the projectors P_k = (1/N) Σ_m e^{-ik·m} T_m
+/// onto the irreducible representations of the translation group of a periodic cluster. The weights sum to one.
+/// States with real amplitudes have equal weights at k and -k.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `generators` - The orbital permutation translating by one lattice spacing along each direction, e.g. from `spinful_permutation`.
+/// * `lengths` - The linear sizes L_i of the cluster, such that T_i^{L_i} = 1.
+pub fn momentum_content(state: &State, generators: &[Vec<u64>], lengths: &[usize]) -> MomentumTable {
+    let norm_sqr: f64 = state.amplitudes.values().map(|a| a * a).sum();
+    let overlaps: Vec<f64> = translation_group(generators, lengths)
+        .iter()
+        .map(|perm| {
+            let translated = permute_orbitals(state, perm);
+            let overlap: f64 = translated
+                .amplitudes
+                .iter()
+                .filter_map(|(slater, amp)| state.amplitudes.get(slater).map(|a| a * amp))
+                .sum();
+            overlap / norm_sqr
+        })
+        .collect();
+    let momenta = momentum_grid(lengths);
+    // The translation T_m has the same enumeration as the momenta, so its displacement is the grid point scaled by L_i / 2π.
+    let displacements: Vec<Vec<f64>> = momenta
+        .iter()
+        .map(|k| k.iter().zip(lengths).map(|(ki, l)| ki * *l as f64 / (2.0 * PI)).collect())
+        .collect();
+    let values = momenta
+        .iter()
+        .map(|k| {
+            // <ψ|T_m|ψ> = <ψ|T_{-m}|ψ> for real amplitudes, so only the cosine survives.
+            let sum: f64 = displacements.iter().zip(&overlaps).map(|(m, o)| dot(k, m).cos() * o).sum();
+            sum / overlaps.len() as f64
+        })
+        .collect();
+    MomentumTable { momenta, values }
+}
+
+/// Returns the momentum of each eigenstate of `spectrum`.
+///
+/// Real eigenvectors of a degenerate multiplet generally mix k and -k, so the momenta are determined for each multiplet as a whole,
+/// by the traces of the projectors P_k over it, and assigned to its states in the order of `momentum_grid`.
+///
+/// # Arguments
+///
+/// * `spectrum` - The spectrum of a translation invariant Hamiltonian.
+/// * `generators` - The orbital permutation translating by one lattice spacing along each direction.
+/// * `lengths` - The linear sizes L_i of the cluster.
+/// * `tolerance` - The largest energy difference between states of the same multiplet, e.g. `DEGENERACY_TOLERANCE`.
+///
+/// # Errors
+///
+/// * If some multiplet does not have an integer number of states in each momentum sector, i.e. the Hamiltonian is not translation invariant,
+///   this function returns an Error.
+pub fn momentum_labels(
+    spectrum: &Spectrum,
+    generators: &[Vec<u64>],
+    lengths: &[usize],
+    tolerance: f64,
+) -> Result<Vec<Vec<f64>>, Error> {
+    let energies = spectrum.energies();
+    let mut labels = Vec::with_capacity(energies.len());
+    let mut start = 0;
+    while start < energies.len() {
+        let end = (start + 1..energies.len())
+            .find(|n| energies[*n] - energies[n - 1] >= tolerance)
+            .unwrap_or(energies.len());
+        let mut counts = vec![0.0; lengths.iter().product()];
+        for n in start..end {
+            let content = momentum_content(&spectrum.eigenstate(n), generators, lengths);
+            for (c, v) in counts.iter_mut().zip(content.values) {
+                *c += v;
+            }
+        }
+        for (k, c) in momentum_grid(lengths).into_iter().zip(counts) {
+            if (c - c.round()).abs() > 1e-6 {
+                return Err(Error::Invalid("The eigenstates are not translation invariant!"));
+            }
+            labels.extend(std::iter::repeat_n(k, c.round() as usize));
+        }
+        start = end;
+    }
+    Ok(labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::spectrum::DEGENERACY_TOLERANCE;
+    use crate::{Operator, AC};
+
+    fn ring(l: u64) -> Operator {
+        let mut terms = Vec::new();
+        for i in 0..l {
+            terms.push((-1.0, vec![AC::Create(i), AC::Annihilate((i + 1) % l)]));
+            terms.push((-1.0, vec![AC::Create((i + 1) % l), AC::Annihilate(i)]));
+        }
+        Operator::new(terms)
+    }
+
+    #[test]
+    fn test_momentum_grid() {
+        let grid = momentum_grid(&[2, 3]);
+        assert_eq!(grid.len(), 6);
+        assert_eq!(grid[1], vec![PI, 0.0]);
+        assert!((grid[2][1] - 2.0 * PI / 3.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_free_fermion_ring() {
+        // A single free fermion on a ring of four sites occupies k = 0 in the ground state.
+        let l = 4;
+        let spectrum = Spectrum::new(&ring(l), Basis::fixed_particle_number(l, 1));
+        let positions: Vec<Vec<f64>> = (0..l).map(|i| vec![i as f64]).collect();
+        let nk = momentum_distribution(&spectrum.eigenstate(0), &[0, 1, 2, 3], &positions, &momentum_grid(&[4]));
+        assert!((nk.values[0] - 1.0).abs() < 1e-12);
+        assert!(nk.values[2].abs() < 1e-12);
+        // Σ_k n(k) is the number of particles.
+        assert!((nk.values.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_structure_factors_of_dimer() {
+        // The two-site singlet has antiferromagnetic correlations: S(0) = 0 and S(π) = (3/4 + 3/4 + 2 · 3/4) / 2.
+        let state = State::new(vec![
+            (crate::Slater::new(0b0110), 1.0),
+            (crate::Slater::new(0b1001), -1.0),
+        ]);
+        let positions = vec![vec![0.0], vec![1.0]];
+        let momenta = momentum_grid(&[2]);
+        let s = spin_structure_factor(&state, SpinConvention::Interleaved, &[0, 1], &positions, &momenta);
+        assert!(s.values[0].abs() < 1e-12);
+        assert!((s.values[1] - 1.5).abs() < 1e-12);
+        // The charge is frozen at one particle per site.
+        let n = charge_structure_factor(&state, SpinConvention::Interleaved, &[0, 1], &positions, &momenta);
+        assert!(n.values.iter().all(|v| v.abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_momentum_labels() {
+        // Two free fermions on a ring of three sites: {k = 0, 2π/3} and {0, 4π/3} at E = -1, {2π/3, 4π/3} at E = 2.
+        // Translating |0, 2> to |1, 0> reorders the orbitals, so the labels depend on the fermionic sign.
+        let l = 3;
+        let spectrum = Spectrum::new(&ring(l), Basis::fixed_particle_number(l, 2));
+        let shift = vec![vec![1, 2, 0]];
+        let labels = momentum_labels(&spectrum, &shift, &[3], DEGENERACY_TOLERANCE).unwrap();
+        let expected = [2.0 * PI / 3.0, 4.0 * PI / 3.0, 0.0];
+        for (k, e) in labels.iter().zip(expected) {
+            assert!((k[0] - e).abs() < 1e-12);
+        }
+        // The spin up and spin down orbitals of each site move together.
+        assert_eq!(spinful_permutation(SpinConvention::Blocked { n_sites: 3 }, &[1, 2, 0]), vec![1, 2, 0, 4, 5, 3]);
+    }
+}
@@ -0,0 +1,682 @@
+
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::linalg::Matrix;
+use crate::operators::{self, PairField, Spin, SpinConvention};
+use crate::{Operator, Slater, State, AC};
+
+/// Returns <ψ|ψ>.
+fn norm_sqr(state: &State) -> f64 {
+    state.amplitudes.values().map(|a| a * a).sum()
+}
+
+/// Returns the expectation value <ψ|O|ψ>/<ψ|ψ> of `op` in `state`.
+///
+/// # Arguments
+///
+/// * `state` - The state |ψ>, which need not be normalized.
+/// * `op` - The operator O.
+pub fn expectation_value(state: &State, op: &Operator) -> f64 {
+    let mut res = 0.0;
+    for (slater, amp) in &state.amplitudes {
+        for (fac, s) in op.apply_slater(slater) {
+            if let Some(bra) = state.amplitudes.get(&s) {
+                res += bra * fac * amp;
+            }
+        }
+    }
+    res / norm_sqr(state)
+}
+
+/// Returns the expectation values <ψ|O|ψ>/<ψ|ψ> of each of `ops` in `state`, applying all of them in one pass over the determinants.
+///
+/// # Arguments
+///
+/// * `state` - The state |ψ>, which need not be normalized.
+/// * `ops` - The operators O.
+pub fn expectation_values(state: &State, ops: &[&Operator]) -> Vec<f64> {
+    let total = norm_sqr(state);
+    state
+        .apply_many(ops)
+        .iter()
+        .map(|ket| {
+            let overlap: f64 = ket.amplitudes.iter().filter_map(|(s, a)| state.amplitudes.get(s).map(|bra| bra * a)).sum();
+            overlap / total
+        })
+        .collect()
+}
+
+/// Returns the probabilities p_s = |<s|ψ>|²/<ψ|ψ> of the determinants in `state`.
+fn probabilities(state: &State) -> impl Iterator<Item = f64> + '_ {
+    let total = norm_sqr(state);
+    state.amplitudes.values().map(move |a| a * a / total)
+}
+
+/// Returns the inverse participation ratio Σ_s p_s² of `state` in the determinant basis, with p_s = |<s|ψ>|²/<ψ|ψ>.
+/// It is one for a single determinant and 1/D for a state spread evenly over D determinants.
+pub fn inverse_participation_ratio(state: &State) -> f64 {
+    probabilities(state).map(|p| p * p).sum()
+}
+
+/// Returns the Shannon (participation) entropy -Σ_s p_s ln p_s of `state` in the determinant basis.
+pub fn participation_entropy(state: &State) -> f64 {
+    probabilities(state).filter(|p| *p > 0.0).map(|p| -p * p.ln()).sum()
+}
+
+/// Returns the weight max_s p_s of the leading determinant, a simple measure of how far `state` is from a single reference.
+pub fn leading_weight(state: &State) -> f64 {
+    probabilities(state).fold(0.0, f64::max)
+}
+
+/// Returns the connected density-density correlations <n_i n_j> - <n_i><n_j> between the single particle states `orbitals`.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `orbitals` - The single particle states; row and column i of the result correspond to `orbitals[i]`.
+pub fn density_correlations(state: &State, orbitals: &[u64]) -> Matrix {
+    // The densities are diagonal in the Slater determinants.
+    let n = orbitals.len();
+    let mut densities = vec![0.0; n];
+    let mut products = Matrix::zeros(n, n);
+    for (slater, amp) in &state.amplitudes {
+        let p = amp * amp;
+        let occupied: Vec<bool> = orbitals.iter().map(|o| (slater.index >> o) & 1 == 1).collect();
+        for i in 0..n {
+            if !occupied[i] {
+                continue;
+            }
+            densities[i] += p;
+            for j in 0..n {
+                if occupied[j] {
+                    products[(i, j)] += p;
+                }
+            }
+        }
+    }
+    let total = norm_sqr(state);
+    let mut res = Matrix::zeros(n, n);
+    for i in 0..n {
+        for j in 0..n {
+            res[(i, j)] = products[(i, j)] / total - densities[i] * densities[j] / (total * total);
+        }
+    }
+    res
+}
+
+/// Represents the connected density-density correlations <n_iσ n_jσ'> - <n_iσ><n_jσ'> of a spinful lattice model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpinResolvedCorrelations {
+    /// The correlations between spin up densities.
+    pub up_up: Matrix,
+    /// The correlations between spin up densities on site i and spin down densities on site j.
+    pub up_down: Matrix,
+    /// The correlations between spin down densities.
+    pub down_down: Matrix,
+}
+
+impl SpinResolvedCorrelations {
+    /// Returns the connected charge correlations <n_i n_j> - <n_i><n_j> with n_i = n_i↑ + n_i↓.
+    pub fn charge(&self) -> Matrix {
+        self.combine(1.0)
+    }
+
+    /// Returns the connected longitudinal spin correlations <S^z_i S^z_j> - <S^z_i><S^z_j>.
+    pub fn spin(&self) -> Matrix {
+        let mut res = self.combine(-1.0);
+        for i in 0..res.rows() {
+            for j in 0..res.cols() {
+                res[(i, j)] /= 4.0;
+            }
+        }
+        res
+    }
+
+    /// Returns uu + s (ud + du) + dd, where du is the transpose of ud.
+    fn combine(&self, s: f64) -> Matrix {
+        let n = self.up_up.rows();
+        let mut res = Matrix::zeros(n, n);
+        for i in 0..n {
+            for j in 0..n {
+                res[(i, j)] = self.up_up[(i, j)]
+                    + s * (self.up_down[(i, j)] + self.up_down[(j, i)])
+                    + self.down_down[(i, j)];
+            }
+        }
+        res
+    }
+}
+
+/// Returns the spin resolved connected density-density correlations between the lattice sites `sites`.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `sites` - The lattice sites; row and column i of the results correspond to `sites[i]`.
+pub fn spin_resolved_density_correlations(
+    state: &State,
+    convention: SpinConvention,
+    sites: &[u64],
+) -> SpinResolvedCorrelations {
+    let n = sites.len();
+    let orbitals: Vec<u64> = sites
+        .iter()
+        .map(|s| convention.orbital(*s, Spin::Up))
+        .chain(sites.iter().map(|s| convention.orbital(*s, Spin::Down)))
+        .collect();
+    let all = density_correlations(state, &orbitals);
+    let block = |r: usize, c: usize| {
+        let mut m = Matrix::zeros(n, n);
+        for i in 0..n {
+            for j in 0..n {
+                m[(i, j)] = all[(r + i, c + j)];
+            }
+        }
+        m
+    };
+    SpinResolvedCorrelations {
+        up_up: block(0, 0),
+        up_down: block(0, n),
+        down_down: block(n, n),
+    }
+}
+
+/// Represents the equal time spin-spin correlations between the sites of a cluster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpinCorrelations {
+    /// The longitudinal correlations <S^z_i S^z_j>.
+    pub longitudinal: Matrix,
+    /// The transverse correlations <S^x_i S^x_j + S^y_i S^y_j>.
+    pub transverse: Matrix,
+}
+
+impl SpinCorrelations {
+    /// Returns the full correlations <S_i · S_j>.
+    pub fn total(&self) -> Matrix {
+        let n = self.longitudinal.rows();
+        let mut res = Matrix::zeros(n, n);
+        for i in 0..n {
+            for j in 0..n {
+                res[(i, j)] = self.longitudinal[(i, j)] + self.transverse[(i, j)];
+            }
+        }
+        res
+    }
+
+    /// Returns the correlations of a chain, whose sites are ordered along the chain, averaged over all pairs at equal distance.
+    ///
+    /// # Arguments
+    ///
+    /// * `periodic` - Whether the chain is closed, in which case distances are measured the short way around and run up to L/2.
+    pub fn chain_table(&self, periodic: bool) -> SpinCorrelationTable {
+        let longitudinal = distance_average(&self.longitudinal, periodic);
+        let transverse = distance_average(&self.transverse, periodic);
+        SpinCorrelationTable {
+            distances: (0..longitudinal.len()).collect(),
+            total: longitudinal.iter().zip(&transverse).map(|(l, t)| l + t).collect(),
+            longitudinal,
+            transverse,
+        }
+    }
+}
+
+/// Represents distance resolved spin-spin correlations of a chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpinCorrelationTable {
+    /// The distances |i - j|.
+    pub distances: Vec<usize>,
+    /// The averaged <S^z_i S^z_j>.
+    pub longitudinal: Vec<f64>,
+    /// The averaged <S^x_i S^x_j + S^y_i S^y_j>.
+    pub transverse: Vec<f64>,
+    /// The averaged <S_i · S_j>.
+    pub total: Vec<f64>,
+}
+
+/// Returns the average of m_ij over all pairs of chain sites at each distance.
+fn distance_average(m: &Matrix, periodic: bool) -> Vec<f64> {
+    let l = m.rows();
+    let max = if periodic { l / 2 } else { l.saturating_sub(1) };
+    let mut sums = vec![0.0; max + 1];
+    let mut counts = vec![0usize; max + 1];
+    for i in 0..l {
+        for j in 0..l {
+            let d = i.abs_diff(j);
+            let d = if periodic { d.min(l - d) } else { d };
+            sums[d] += m[(i, j)];
+            counts[d] += 1;
+        }
+    }
+    sums.iter().zip(counts).map(|(s, c)| s / c as f64).collect()
+}
+
+/// Returns the spin-spin correlations between the lattice sites `sites`.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `sites` - The lattice sites; row and column i of the results correspond to `sites[i]`.
+pub fn spin_correlations(state: &State, convention: SpinConvention, sites: &[u64]) -> SpinCorrelations {
+    let n = sites.len();
+    let mut longitudinal = Matrix::zeros(n, n);
+    let mut transverse = Matrix::zeros(n, n);
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i..n).map(move |j| (i, j))).collect();
+    let ops: Vec<Operator> = pairs
+        .iter()
+        .flat_map(|&(i, j)| {
+            let zz = operators::sz(convention, sites[i]).product(&operators::sz(convention, sites[j]));
+            vec![zz, operators::transverse_spin_product(convention, sites[i], sites[j])]
+        })
+        .collect();
+    let values = expectation_values(state, &ops.iter().collect::<Vec<_>>());
+    for (&(i, j), v) in pairs.iter().zip(values.chunks(2)) {
+        longitudinal[(i, j)] = v[0];
+        longitudinal[(j, i)] = v[0];
+        transverse[(i, j)] = v[1];
+        transverse[(j, i)] = v[1];
+    }
+    SpinCorrelations {
+        longitudinal,
+        transverse,
+    }
+}
+
+/// Returns the pairing correlations P_ab = <Δ†_a Δ_b> between the pair fields `fields`.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `fields` - The pair fields, e.g. `PairField::s_wave` or `PairField::d_wave` on every site.
+pub fn pairing_correlations(state: &State, convention: SpinConvention, fields: &[PairField]) -> Matrix {
+    let operators: Vec<Operator> = fields.iter().map(|f| f.operator(convention)).collect();
+    let n = fields.len();
+    let creations: Vec<Operator> = operators.iter().map(|a| a.adjoint()).collect();
+    let products: Vec<Operator> = creations.iter().flat_map(|a| operators.iter().map(move |b| a.product(b))).collect();
+    let values = expectation_values(state, &products.iter().collect::<Vec<_>>());
+    Matrix::from_vec(n, n, values)
+}
+
+/// Returns the string of a fermion parity operator Π_k (-1)^{n_k} over `orbitals`, for use with `string_correlation`.
+pub fn parity_string(orbitals: &[u64]) -> Vec<(u64, f64)> {
+    orbitals.iter().map(|o| (*o, 1.0)).collect()
+}
+
+/// Returns the string of exp(iπ Σ_k S^z_k) over the lattice sites `sites`, for use with `string_correlation`.
+pub fn sz_string(convention: SpinConvention, sites: &[u64]) -> Vec<(u64, f64)> {
+    sites
+        .iter()
+        .flat_map(|s| {
+            [
+                (convention.orbital(*s, Spin::Up), 0.5),
+                (convention.orbital(*s, Spin::Down), -0.5),
+            ]
+        })
+        .collect()
+}
+
+/// Returns the string correlation <O_L exp(iπ Σ_k w_k n_k) O_R>.
+///
+/// The string is diagonal in the determinants, so it is applied as a phase to each determinant reached by O_R instead of being expanded into operators.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `left` - The operator O_L.
+/// * `string` - The orbitals k and weights w_k of the string, e.g. from `parity_string` or `sz_string`.
+/// * `right` - The operator O_R.
+pub fn string_correlation(state: &State, left: &Operator, string: &[(u64, f64)], right: &Operator) -> Complex {
+    let mut res = Complex::default();
+    for (slater, amp) in &state.amplitudes {
+        for (f, middle) in right.apply_slater(slater) {
+            let theta: f64 = string
+                .iter()
+                .filter(|(o, _)| (middle.index >> o) & 1 == 1)
+                .map(|(_, w)| w)
+                .sum();
+            let phase = Complex::from_polar(1.0, std::f64::consts::PI * theta);
+            for (g, end) in left.apply_slater(&middle) {
+                if let Some(bra) = state.amplitudes.get(&end) {
+                    res += phase * (bra * g * f * amp);
+                }
+            }
+        }
+    }
+    res / norm_sqr(state)
+}
+
+/// Returns the fermionic sign of the single excitation c†_i c_j, i ≠ j, acting on a determinant with occupations `index`.
+fn excitation_sign(index: u64, i: u64, j: u64) -> f64 {
+    let (lo, hi) = if i < j { (i, j) } else { (j, i) };
+    // The occupied states strictly between lo and hi.
+    let between = index & ((1u64 << hi) - 1) & !((1u64 << (lo + 1)) - 1);
+    if between.count_ones().is_multiple_of(2) {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+/// Returns the one-body reduced density matrix ρ_ij = <c†_i c_j> of `state`.
+///
+/// Rather than applying c†_i c_j for every element, each determinant is excited directly and the sign
+/// is read off from the occupied states between i and j, following the Slater-Condon rules.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `n_orbitals` - The number of single particle states.
+pub fn one_rdm(state: &State, n_orbitals: u64) -> Matrix {
+    let n = n_orbitals as usize;
+    let mut rho = Matrix::zeros(n, n);
+    for (slater, amp) in &state.amplitudes {
+        let index = slater.index;
+        for j in (0..n_orbitals).filter(|j| (index >> j) & 1 == 1) {
+            rho[(j as usize, j as usize)] += amp * amp;
+            let removed = index & !(1 << j);
+            for i in (0..n_orbitals).filter(|i| (removed >> i) & 1 == 0 && *i != j) {
+                let excited = Slater::new(removed | (1 << i));
+                if let Some(bra) = state.amplitudes.get(&excited) {
+                    rho[(i as usize, j as usize)] += bra * amp * excitation_sign(index, i, j);
+                }
+            }
+        }
+    }
+    let total = norm_sqr(state);
+    for i in 0..n {
+        for j in 0..n {
+            rho[(i, j)] /= total;
+        }
+    }
+    rho
+}
+
+/// Represents the natural orbitals of a state, the eigenvectors of its one-body reduced density matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NaturalOrbitals {
+    /// The natural occupation numbers, in descending order.
+    pub occupations: Vec<f64>,
+    /// The natural orbitals expressed in the original single particle states, stored as columns in the order of `occupations`.
+    pub orbitals: Matrix,
+}
+
+/// Returns the natural orbitals and occupation spectrum of `state`.
+/// Pass `orbitals` to `operators::rotate_orbitals` to express an operator in the natural orbital basis.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `n_orbitals` - The number of single particle states.
+pub fn natural_orbitals(state: &State, n_orbitals: u64) -> NaturalOrbitals {
+    let (values, vectors) = one_rdm(state, n_orbitals).eigh();
+    let n = values.len();
+    let mut orbitals = Matrix::zeros(n, n);
+    // eigh sorts ascending, natural orbitals are listed from the most occupied.
+    for (c, j) in (0..n).rev().enumerate() {
+        for i in 0..n {
+            orbitals[(i, c)] = vectors[(i, j)];
+        }
+    }
+    NaturalOrbitals {
+        occupations: values.into_iter().rev().collect(),
+        orbitals,
+    }
+}
+
+/// Returns the index of the ordered pair i < j among all such pairs.
+fn pair_index(i: u64, j: u64) -> usize {
+    (j * (j - 1) / 2 + i) as usize
+}
+
+/// Represents the two-body reduced density matrix Γ_ijkl = <c†_i c†_j c_k c_l>.
+///
+/// Only the elements with i < j and k < l are stored, the rest follow from antisymmetry under exchange of i and j or of k and l.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwoRdm {
+    n_orbitals: u64,
+    /// Γ_ijkl with row (i, j) and column (k, l) indexed by `pair_index`.
+    pairs: Matrix,
+}
+
+impl TwoRdm {
+    /// Returns the number of single particle states.
+    pub fn n_orbitals(&self) -> u64 {
+        self.n_orbitals
+    }
+
+    /// Returns Γ_ij,kl for i < j and k < l as a matrix over ordered pairs, with (i, j) at j(j - 1)/2 + i.
+    pub fn pair_matrix(&self) -> &Matrix {
+        &self.pairs
+    }
+
+    /// Returns the element Γ_ijkl = <c†_i c†_j c_k c_l>.
+    pub fn element(&self, i: u64, j: u64, k: u64, l: u64) -> f64 {
+        if i == j || k == l {
+            return 0.0;
+        }
+        let (row, s1) = if i < j { (pair_index(i, j), 1.0) } else { (pair_index(j, i), -1.0) };
+        let (col, s2) = if k < l { (pair_index(k, l), 1.0) } else { (pair_index(l, k), -1.0) };
+        s1 * s2 * self.pairs[(row, col)]
+    }
+
+    /// Returns the one-body reduced density matrix ρ_il = Σ_j Γ_ijjl / (N - 1) of a state with `n_particles` particles.
+    ///
+    /// # Errors
+    ///
+    /// * If there are fewer than two particles, where the two-body density matrix vanishes and does not determine
+    ///   the one-body one, this function returns an Error.
+    pub fn contract(&self, n_particles: u64) -> Result<Matrix, Error> {
+        if n_particles < 2 {
+            return Err(Error::Invalid("The two-body density matrix of fewer than two particles cannot be contracted!"));
+        }
+        let n = self.n_orbitals as usize;
+        let mut rho = Matrix::zeros(n, n);
+        for i in 0..self.n_orbitals {
+            for l in 0..self.n_orbitals {
+                let sum: f64 = (0..self.n_orbitals).map(|j| self.element(i, j, j, l)).sum();
+                rho[(i as usize, l as usize)] = sum / (n_particles - 1) as f64;
+            }
+        }
+        Ok(rho)
+    }
+}
+
+/// Returns the two-body reduced density matrix of `state`.
+///
+/// # Arguments
+///
+/// * `state` - The state, which need not be normalized.
+/// * `n_orbitals` - The number of single particle states.
+pub fn two_rdm(state: &State, n_orbitals: u64) -> TwoRdm {
+    let n_pairs = pair_index(0, n_orbitals.max(1));
+    let mut pairs = Matrix::zeros(n_pairs, n_pairs);
+    let apply = |s: &Slater, a: AC, b: AC| {
+        s.apply(&a)
+            .and_then(|(p1, s1)| s1.apply(&b).map(|(p2, s2)| ((p1 * p2) as f64, s2)))
+    };
+    for (slater, amp) in &state.amplitudes {
+        for l in 1..n_orbitals {
+            for k in 0..l {
+                let Some((p_kl, removed)) = apply(slater, AC::Annihilate(l), AC::Annihilate(k)) else {
+                    continue;
+                };
+                for j in 1..n_orbitals {
+                    for i in 0..j {
+                        let Some((p_ij, excited)) = apply(&removed, AC::Create(j), AC::Create(i)) else {
+                            continue;
+                        };
+                        if let Some(bra) = state.amplitudes.get(&excited) {
+                            pairs[(pair_index(i, j), pair_index(k, l))] += bra * amp * p_kl * p_ij;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let total = norm_sqr(state);
+    for r in 0..n_pairs {
+        for c in 0..n_pairs {
+            pairs[(r, c)] /= total;
+        }
+    }
+    TwoRdm { n_orbitals, pairs }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expectation_value() {
+        let s = 1.0 / 2.0_f64.sqrt();
+        let state = State::new(vec![(Slater::new(0b01), s), (Slater::new(0b10), s)]);
+        let hop = Operator::new(vec![
+            (1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (1.0, vec![AC::Create(1), AC::Annihilate(0)]),
+        ]);
+        assert!((expectation_value(&state, &hop) - 1.0).abs() < 1e-14);
+        assert!((expectation_values(&state, &[&hop, &hop])[1] - 1.0).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_localization_measures() {
+        let state = State::new(vec![
+            (Slater::new(0b0011), 1.0),
+            (Slater::new(0b0101), 1.0),
+            (Slater::new(0b1001), -1.0),
+            (Slater::new(0b0110), 1.0),
+        ]);
+        assert!((inverse_participation_ratio(&state) - 0.25).abs() < 1e-14);
+        assert!((participation_entropy(&state) - 4.0_f64.ln()).abs() < 1e-14);
+        assert!((leading_weight(&state) - 0.25).abs() < 1e-14);
+        let single = State::new(vec![(Slater::new(0b0011), 2.0)]);
+        assert!((inverse_participation_ratio(&single) - 1.0).abs() < 1e-14);
+        assert!(participation_entropy(&single).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_density_correlations() {
+        // A singlet of one particle on two sites: perfectly anticorrelated densities.
+        let state = State::new(vec![(Slater::new(0b0110), 1.0), (Slater::new(0b1001), -1.0)]);
+        let c = density_correlations(&state, &[0, 1, 2, 3]);
+        assert!((c[(0, 0)] - 0.25).abs() < 1e-14);
+        assert!((c[(0, 3)] - 0.25).abs() < 1e-14);
+        assert!((c[(0, 1)] + 0.25).abs() < 1e-14);
+        let resolved = spin_resolved_density_correlations(&state, SpinConvention::Interleaved, &[0, 1]);
+        let spin = resolved.spin();
+        // <S^z_0 S^z_1> = -1/4 for the singlet.
+        assert!((spin[(0, 1)] + 0.25).abs() < 1e-14);
+        let charge = resolved.charge();
+        assert!(charge[(0, 1)].abs() < 1e-14 && charge[(0, 0)].abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_one_rdm_against_operators() {
+        let state = State::new(vec![
+            (Slater::new(0b0011), 0.3),
+            (Slater::new(0b0101), -0.5),
+            (Slater::new(0b1010), 0.4),
+            (Slater::new(0b1100), 0.7),
+        ]);
+        let rho = one_rdm(&state, 4);
+        for i in 0..4 {
+            for j in 0..4 {
+                let op = Operator::new(vec![(1.0, vec![AC::Create(i), AC::Annihilate(j)])]);
+                assert!((rho[(i as usize, j as usize)] - expectation_value(&state, &op)).abs() < 1e-14);
+            }
+        }
+        let trace: f64 = (0..4).map(|i| rho[(i, i)]).sum();
+        assert!((trace - 2.0).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_two_rdm() {
+        let state = State::new(vec![
+            (Slater::new(0b00111), 0.3),
+            (Slater::new(0b01011), -0.5),
+            (Slater::new(0b10101), 0.4),
+            (Slater::new(0b11100), 0.7),
+        ]);
+        let gamma = two_rdm(&state, 5);
+        for (i, j, k, l) in [(0, 1, 0, 1), (1, 0, 0, 1), (2, 3, 1, 2), (0, 4, 2, 1), (3, 4, 1, 0)] {
+            let op = Operator::new(vec![(
+                1.0,
+                vec![AC::Create(i), AC::Create(j), AC::Annihilate(k), AC::Annihilate(l)],
+            )]);
+            assert!((gamma.element(i, j, k, l) - expectation_value(&state, &op)).abs() < 1e-14);
+        }
+        let rho = one_rdm(&state, 5);
+        let contracted = gamma.contract(3).unwrap();
+        for i in 0..5 {
+            for j in 0..5 {
+                assert!((rho[(i, j)] - contracted[(i, j)]).abs() < 1e-14);
+            }
+        }
+        assert!(gamma.contract(1).is_err());
+        assert!(gamma.contract(0).is_err());
+    }
+
+    #[test]
+    fn test_natural_orbitals() {
+        // One particle in the bonding orbital of two sites is a single determinant in natural orbitals.
+        let s = 1.0 / 2.0_f64.sqrt();
+        let state = State::new(vec![(Slater::new(0b01), s), (Slater::new(0b10), s)]);
+        let natural = natural_orbitals(&state, 2);
+        assert!((natural.occupations[0] - 1.0).abs() < 1e-14 && natural.occupations[1].abs() < 1e-14);
+        assert!((natural.orbitals[(0, 0)].abs() - s).abs() < 1e-14);
+        let n0 = Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let rotated = operators::rotate_orbitals(&n0, &natural.orbitals);
+        let bonding = State::new(vec![(Slater::new(0b01), 1.0)]);
+        assert!((expectation_value(&bonding, &rotated) - 0.5).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_pairing_correlations() {
+        // The dimer singlet (|↑, ↓> - |↓, ↑>)/√2 is the bond pair Δ†_01 acting on the vacuum.
+        let convention = SpinConvention::Interleaved;
+        let pair = PairField {
+            pairs: vec![(0, 1, 1.0)],
+        };
+        let state = State::new(vec![(Slater::new(0), 1.0)]).apply(pair.operator(convention).adjoint());
+        let p = pairing_correlations(&state, convention, &[pair, PairField::s_wave(0)]);
+        assert!((p[(0, 0)] - 1.0).abs() < 1e-14);
+        assert!(p[(1, 1)].abs() < 1e-14);
+        // On-site pairs of a doubly occupied site.
+        let doublon = State::new(vec![(Slater::new(0b11), 1.0)]);
+        let p = pairing_correlations(&doublon, convention, &[PairField::s_wave(0)]);
+        assert!((p[(0, 0)] - 1.0).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_string_correlation() {
+        // The Néel state ↑↓↑ has <S^z_0 exp(iπ S^z_1) S^z_2> = (1/2) e^{-iπ/2} (1/2).
+        let convention = SpinConvention::Interleaved;
+        let state = State::new(vec![(Slater::from_vec(vec![0, 3, 4]).unwrap(), 1.0)]);
+        let c = string_correlation(
+            &state,
+            &operators::sz(convention, 0),
+            &sz_string(convention, &[1]),
+            &operators::sz(convention, 2),
+        );
+        assert!(c.re.abs() < 1e-15 && (c.im + 0.25).abs() < 1e-15);
+        // There are two particles in orbitals 1 to 4.
+        let n = operators::number(0);
+        let p = string_correlation(&state, &n, &parity_string(&[1, 2, 3, 4]), &n);
+        assert!((p.re - 1.0).abs() < 1e-15);
+    }
+
+    #[test]
+    fn test_singlet_spin_correlations() {
+        let state = State::new(vec![(Slater::new(0b0110), 1.0), (Slater::new(0b1001), -1.0)]);
+        let c = spin_correlations(&state, SpinConvention::Interleaved, &[0, 1]);
+        let total = c.total();
+        assert!((total[(0, 1)] + 0.75).abs() < 1e-14);
+        assert!((total[(0, 0)] - 0.75).abs() < 1e-14);
+        assert!((c.transverse[(0, 1)] + 0.5).abs() < 1e-14);
+        let table = c.chain_table(false);
+        assert_eq!(table.distances, vec![0, 1]);
+        assert!((table.total[1] + 0.75).abs() < 1e-14);
+    }
+}
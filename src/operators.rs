@@ -0,0 +1,369 @@
+use std::collections::HashMap;
+
+use crate::error::Error;
+use crate::linalg::Matrix;
+use crate::{Operator, AC};
+
+/// Terms with amplitudes below this magnitude are dropped from rotated operators.
+const ROTATION_CUTOFF: f64 = 1e-12;
+
+/// The spin projection of a spin-1/2 fermion.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Spin {
+    Up,
+    Down,
+}
+
+impl Spin {
+    /// Returns the spin projection S^z in units of ħ, +1/2 or -1/2.
+    pub fn sz(self) -> f64 {
+        match self {
+            Spin::Up => 0.5,
+            Spin::Down => -0.5,
+        }
+    }
+}
+
+/// This represents how the spin orbitals (site, spin) of a spinful lattice model are mapped onto single particle states.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SpinConvention {
+    /// Site i has spin up in orbital 2i and spin down in orbital 2i + 1.
+    Interleaved,
+    /// Site i has spin up in orbital i and spin down in orbital i + n_sites.
+    Blocked { n_sites: u64 },
+}
+
+impl SpinConvention {
+    /// Returns the single particle state of `site` with spin `spin`.
+    pub fn orbital(&self, site: u64, spin: Spin) -> u64 {
+        match (self, spin) {
+            (SpinConvention::Interleaved, Spin::Up) => 2 * site,
+            (SpinConvention::Interleaved, Spin::Down) => 2 * site + 1,
+            (SpinConvention::Blocked { .. }, Spin::Up) => site,
+            (SpinConvention::Blocked { n_sites }, Spin::Down) => site + n_sites,
+        }
+    }
+}
+
+/// Returns the number operator n_i = c†_i c_i of a single particle state.
+pub fn number(orbital: u64) -> Operator {
+    Operator::new(vec![(1.0, vec![AC::Create(orbital), AC::Annihilate(orbital)])])
+}
+
+/// Returns the total number operator Σ_i n_i over the supplied single particle states.
+pub fn total_number(orbitals: &[u64]) -> Operator {
+    Operator::new(
+        orbitals
+            .iter()
+            .map(|o| (1.0, vec![AC::Create(*o), AC::Annihilate(*o)]))
+            .collect(),
+    )
+}
+
+/// Returns the total spin projection S^z = Σ_i (n_i↑ - n_i↓)/2 of `n_sites` spinful sites.
+pub fn total_sz(convention: SpinConvention, n_sites: u64) -> Operator {
+    let mut terms = Vec::new();
+    for site in 0..n_sites {
+        for spin in [Spin::Up, Spin::Down] {
+            let o = convention.orbital(site, spin);
+            terms.push((spin.sz(), vec![AC::Create(o), AC::Annihilate(o)]));
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Returns the spin projection S^z_i = (n_i↑ - n_i↓)/2 of a single site.
+pub fn sz(convention: SpinConvention, site: u64) -> Operator {
+    let (up, down) = (convention.orbital(site, Spin::Up), convention.orbital(site, Spin::Down));
+    Operator::new(vec![
+        (0.5, vec![AC::Create(up), AC::Annihilate(up)]),
+        (-0.5, vec![AC::Create(down), AC::Annihilate(down)]),
+    ])
+}
+
+/// Returns the spin raising operator S^+_i = c†_i↑ c_i↓ of a single site.
+pub fn s_plus(convention: SpinConvention, site: u64) -> Operator {
+    let (up, down) = (convention.orbital(site, Spin::Up), convention.orbital(site, Spin::Down));
+    Operator::new(vec![(1.0, vec![AC::Create(up), AC::Annihilate(down)])])
+}
+
+/// Returns the spin lowering operator S^-_i = c†_i↓ c_i↑ of a single site.
+pub fn s_minus(convention: SpinConvention, site: u64) -> Operator {
+    let (up, down) = (convention.orbital(site, Spin::Up), convention.orbital(site, Spin::Down));
+    Operator::new(vec![(1.0, vec![AC::Create(down), AC::Annihilate(up)])])
+}
+
+/// Returns the transverse spin coupling S^x_i S^x_j + S^y_i S^y_j = (S^+_i S^-_j + S^-_i S^+_j)/2.
+pub fn transverse_spin_product(convention: SpinConvention, i: u64, j: u64) -> Operator {
+    let mut terms = s_plus(convention, i).product(&s_minus(convention, j)).terms;
+    terms.extend(s_minus(convention, i).product(&s_plus(convention, j)).terms);
+    Operator::new(terms.into_iter().map(|(a, ac)| (a / 2.0, ac)).collect())
+}
+
+/// Returns the spin product S_i · S_j.
+pub fn spin_product(convention: SpinConvention, i: u64, j: u64) -> Operator {
+    let mut terms = sz(convention, i).product(&sz(convention, j)).terms;
+    terms.extend(transverse_spin_product(convention, i, j).terms);
+    Operator::new(terms)
+}
+
+/// Returns the total spin S² = Σ_ij S_i · S_j of `n_sites` spinful sites, with eigenvalues S(S + 1).
+pub fn total_spin_squared(convention: SpinConvention, n_sites: u64) -> Operator {
+    let mut terms = Vec::new();
+    for i in 0..n_sites {
+        for j in 0..n_sites {
+            terms.extend(spin_product(convention, i, j).terms);
+        }
+    }
+    Operator::new(terms)
+}
+
+/// Represents a pair field Δ = Σ_b f_b Δ_b, a linear combination of singlet pairs.
+///
+/// The on-site pair is Δ_ii = c_i↓ c_i↑, and the bond pair Δ_ij = (c_i↓ c_j↑ - c_i↑ c_j↓)/√2 for i ≠ j.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairField {
+    /// The sites (i, j) and form factor f of each pair.
+    pub pairs: Vec<(u64, u64, f64)>,
+}
+
+impl PairField {
+    /// Returns the on-site s-wave pair field c_i↓ c_i↑.
+    pub fn s_wave(site: u64) -> PairField {
+        PairField {
+            pairs: vec![(site, site, 1.0)],
+        }
+    }
+
+    /// Returns the extended s-wave pair field Σ_δ Δ_{i,i+δ} / 2 over the supplied neighbours.
+    pub fn extended_s_wave(site: u64, neighbours: &[u64]) -> PairField {
+        PairField {
+            pairs: neighbours.iter().map(|n| (site, *n, 0.5)).collect(),
+        }
+    }
+
+    /// Returns the d_{x²-y²} pair field (Σ_x Δ_{i,i±x} - Σ_y Δ_{i,i±y}) / 2 of a square lattice site.
+    ///
+    /// # Arguments
+    ///
+    /// * `site` - The site i.
+    /// * `x_neighbours` - The neighbours of i along x.
+    /// * `y_neighbours` - The neighbours of i along y.
+    pub fn d_wave(site: u64, x_neighbours: &[u64], y_neighbours: &[u64]) -> PairField {
+        let x = x_neighbours.iter().map(|n| (site, *n, 0.5));
+        let y = y_neighbours.iter().map(|n| (site, *n, -0.5));
+        PairField { pairs: x.chain(y).collect() }
+    }
+
+    /// Returns the pair annihilation operator Δ.
+    pub fn operator(&self, convention: SpinConvention) -> Operator {
+        let mut terms = Vec::new();
+        for (i, j, f) in &self.pairs {
+            let i_down = convention.orbital(*i, Spin::Down);
+            let j_up = convention.orbital(*j, Spin::Up);
+            if i == j {
+                terms.push((*f, vec![AC::Annihilate(i_down), AC::Annihilate(j_up)]));
+            } else {
+                let s = f / 2.0_f64.sqrt();
+                let (i_up, j_down) = (convention.orbital(*i, Spin::Up), convention.orbital(*j, Spin::Down));
+                terms.push((s, vec![AC::Annihilate(i_down), AC::Annihilate(j_up)]));
+                terms.push((-s, vec![AC::Annihilate(i_up), AC::Annihilate(j_down)]));
+            }
+        }
+        Operator::new(terms)
+    }
+}
+
+/// Returns `op` expressed in rotated single particle states d_p, related to the original ones by c_i = Σ_p U_ip d_p.
+///
+/// Every term of k operators expands into up to n^k terms, so this is meant for moderate numbers of orbitals, e.g. to obtain
+/// a Hamiltonian in natural orbitals. Equal terms are merged and amplitudes below 1e-12 are dropped.
+///
+/// # Arguments
+///
+/// * `op` - The operator in the original single particle states.
+/// * `u` - The real orthogonal matrix U, whose columns are the new orbitals expressed in the old ones.
+pub fn rotate_orbitals(op: &Operator, u: &Matrix) -> Operator {
+    let n = u.cols() as u64;
+    let mut merged: HashMap<Vec<AC>, f64> = HashMap::new();
+    let mut order = Vec::new();
+    for (fac, ac) in &op.terms {
+        let mut partial: Vec<(f64, Vec<AC>)> = vec![(*fac, Vec::with_capacity(ac.len()))];
+        for c in ac {
+            let i = match c {
+                AC::Create(i) | AC::Annihilate(i) => *i as usize,
+            };
+            let mut next = Vec::with_capacity(partial.len() * n as usize);
+            for (amp, seq) in &partial {
+                for p in 0..n {
+                    let a = amp * u[(i, p as usize)];
+                    if a.abs() < ROTATION_CUTOFF {
+                        continue;
+                    }
+                    let mut seq = seq.clone();
+                    seq.push(match c {
+                        AC::Create(_) => AC::Create(p),
+                        AC::Annihilate(_) => AC::Annihilate(p),
+                    });
+                    next.push((a, seq));
+                }
+            }
+            partial = next;
+        }
+        for (amp, seq) in partial {
+            let entry = merged.entry(seq.clone()).or_insert_with(|| {
+                order.push(seq);
+                0.0
+            });
+            *entry += amp;
+        }
+    }
+    Operator::new(
+        order
+            .into_iter()
+            .filter_map(|seq| {
+                let amp = merged[&seq];
+                (amp.abs() >= ROTATION_CUTOFF).then_some((amp, seq))
+            })
+            .collect(),
+    )
+}
+
+/// The couplings [J, i, j, ...] of a QuSpin operator string, as (J, sites) pairs.
+pub type QuSpinCouplings = Vec<(f64, Vec<u64>)>;
+
+/// Returns the alternatives (amplitude, operators) a single QuSpin fermion symbol acting on `orbital` expands into.
+fn quspin_symbol(symbol: char, orbital: u64) -> Result<Vec<(f64, Vec<AC>)>, Error> {
+    Ok(match symbol {
+        '+' => vec![(1.0, vec![AC::Create(orbital)])],
+        '-' => vec![(1.0, vec![AC::Annihilate(orbital)])],
+        'n' => vec![(1.0, vec![AC::Create(orbital), AC::Annihilate(orbital)])],
+        // z = n - 1/2
+        'z' => vec![(1.0, vec![AC::Create(orbital), AC::Annihilate(orbital)]), (-0.5, Vec::new())],
+        'I' => vec![(1.0, Vec::new())],
+        _ => return Err(Error::Parse("Unknown QuSpin operator symbol!")),
+    })
+}
+
+/// Returns the operator of a QuSpin static list, e.g. `[("+-", vec![(-t, vec![0, 1]), (-t, vec![1, 0])])]` for ["+-", [[-t, 0, 1], [-t, 1, 0]]].
+///
+/// Each entry is an operator string of the fermion symbols + (c†), - (c), n (c†c), z (n - 1/2) and I, together with couplings
+/// [J, i, j, ...] carrying one site index per symbol, and stands for Σ J c_i c_j ... with the symbols in the order of the string.
+/// For spinful fermions the string is split by | into symbols acting on spin up and spin down, e.g. "n|n" for n_i↑ n_j↓,
+/// as in QuSpin's spinful fermion basis; without | the site indices are the single particle states.
+///
+/// # Arguments
+///
+/// * `static_list` - The operator strings and their couplings.
+/// * `convention` - The mapping of (site, spin) onto single particle states, needed for strings containing |.
+///
+/// # Errors
+///
+/// * If a symbol is unknown, a coupling has the wrong number of sites, or a spinful string is given without convention,
+///   this function returns an Error.
+pub fn quspin_operator(
+    static_list: &[(&str, QuSpinCouplings)],
+    convention: Option<SpinConvention>,
+) -> Result<Operator, Error> {
+    let mut terms = Vec::new();
+    for (opstr, couplings) in static_list {
+        let (symbols, spins): (Vec<char>, Vec<Option<Spin>>) = match opstr.split_once('|') {
+            Some((up, down)) => up
+                .chars()
+                .map(|c| (c, Some(Spin::Up)))
+                .chain(down.chars().map(|c| (c, Some(Spin::Down))))
+                .unzip(),
+            None => opstr.chars().map(|c| (c, None)).unzip(),
+        };
+        for (j, sites) in couplings {
+            if sites.len() != symbols.len() {
+                return Err(Error::Parse("A QuSpin coupling needs one site per operator symbol!"));
+            }
+            let mut partial = vec![(*j, Vec::new())];
+            for ((symbol, spin), site) in symbols.iter().zip(&spins).zip(sites) {
+                let orbital = match spin {
+                    Some(spin) => convention.ok_or(Error::Parse("Spinful QuSpin strings need a spin convention!"))?.orbital(*site, *spin),
+                    None => *site,
+                };
+                let factor = quspin_symbol(*symbol, orbital)?;
+                partial = partial
+                    .iter()
+                    .flat_map(|(a, seq)| {
+                        factor.iter().map(move |(b, ac)| (a * b, seq.iter().chain(ac).copied().collect::<Vec<_>>()))
+                    })
+                    .collect();
+            }
+            terms.extend(partial);
+        }
+    }
+    Ok(Operator::new(terms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::models::{hubbard, Graph};
+    use crate::Slater;
+
+    #[test]
+    fn test_conventions() {
+        assert_eq!(SpinConvention::Interleaved.orbital(3, Spin::Down), 7);
+        assert_eq!(SpinConvention::Blocked { n_sites: 4 }.orbital(3, Spin::Down), 7);
+        assert_eq!(SpinConvention::Blocked { n_sites: 4 }.orbital(3, Spin::Up), 3);
+    }
+
+    #[test]
+    fn test_total_sz() {
+        let sz = total_sz(SpinConvention::Interleaved, 2);
+        // Spin up on site 0 and 1, spin down on site 1.
+        let res = sz.apply_slater(&Slater::from_vec(vec![0, 2, 3]).unwrap());
+        let total: f64 = res.iter().map(|(a, _)| a).sum();
+        assert!((total - 0.5).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_rotate_orbitals() {
+        // Bonding and antibonding orbitals diagonalize a single hopping.
+        let s = 1.0 / 2.0_f64.sqrt();
+        let u = Matrix::from_vec(2, 2, vec![s, s, s, -s]);
+        let hop = Operator::new(vec![
+            (-1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (-1.0, vec![AC::Create(1), AC::Annihilate(0)]),
+        ]);
+        let rotated = rotate_orbitals(&hop, &u);
+        assert_eq!(rotated.terms.len(), 2);
+        for (amp, ac) in &rotated.terms {
+            match ac[..] {
+                [AC::Create(0), AC::Annihilate(0)] => assert!((amp + 1.0).abs() < 1e-14),
+                [AC::Create(1), AC::Annihilate(1)] => assert!((amp - 1.0).abs() < 1e-14),
+                _ => panic!("unexpected term {:?}", ac),
+            }
+        }
+    }
+
+    #[test]
+    fn test_spin_product_on_site() {
+        // S_i · S_i = 3/4 on a singly occupied site.
+        let s2 = spin_product(SpinConvention::Blocked { n_sites: 1 }, 0, 0);
+        let res = crate::State::new(vec![(Slater::new(0b01), 1.0)]).apply(s2);
+        assert!((res.amplitudes[&Slater::new(0b01)] - 0.75).abs() < 1e-14);
+    }
+
+    #[test]
+    fn test_quspin_operator() {
+        let conv = SpinConvention::Interleaved;
+        let (t, u) = (1.0, 4.0);
+        let hop = vec![(-t, vec![0, 1]), (-t, vec![1, 0])];
+        let list = [("+-|", hop.clone()), ("|+-", hop), ("n|n", vec![(u, vec![0, 0]), (u, vec![1, 1])])];
+        let h = quspin_operator(&list, Some(conv)).unwrap();
+        let basis = Basis::fock_space(4);
+        let reference = hubbard(&Graph::new(2, vec![(0, 1)]), t, u, 0.0, conv);
+        assert_eq!(basis.matrix(&h), basis.matrix(&reference));
+        // z = n - 1/2 on an empty orbital.
+        let z = quspin_operator(&[("z", vec![(2.0, vec![0])])], None).unwrap();
+        assert_eq!(z.apply_slater(&Slater::new(0)), vec![(-1.0, Slater::new(0))]);
+        assert!(quspin_operator(&[("n|n", vec![(1.0, vec![0, 0])])], None).is_err());
+        assert!(quspin_operator(&[("x", vec![(1.0, vec![0])])], None).is_err());
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::BTreeSet;
+
+use crate::linalg::{dot, norm};
+use crate::{Slater, State};
+
+/// The policies of repeating the projections of Gram–Schmidt. One pass loses orthogonality in floating point when the vector
+/// is nearly in the span of the others, and a second pass restores it to working precision.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reorthogonalization {
+    /// One pass.
+    Once,
+    /// Two passes, always.
+    Twice,
+    /// A second pass only if the first removed most of the vector, its norm falling below 1/√2 of the original.
+    IfNeeded,
+}
+
+/// Subtracts the projection of `w` on the normalized vector `v` from `w`, returning the overlap <v|w>.
+pub fn project_out(w: &mut [f64], v: &[f64]) -> f64 {
+    let overlap = dot(v, w);
+    for (wi, vi) in w.iter_mut().zip(v) {
+        *wi -= overlap * vi;
+    }
+    overlap
+}
+
+/// Makes `w` orthogonal to the orthonormal `vectors` by modified Gram–Schmidt, and returns the norm of the result.
+///
+/// # Arguments
+///
+/// * `w` - The vector to orthogonalize.
+/// * `vectors` - The orthonormal vectors.
+/// * `policy` - When to repeat the projections.
+pub fn orthogonalize(w: &mut [f64], vectors: &[Vec<f64>], policy: Reorthogonalization) -> f64 {
+    let before = norm(w);
+    for v in vectors {
+        project_out(w, v);
+    }
+    let after = norm(w);
+    let again = match policy {
+        Reorthogonalization::Once => false,
+        Reorthogonalization::Twice => true,
+        Reorthogonalization::IfNeeded => after < before * std::f64::consts::FRAC_1_SQRT_2,
+    };
+    if !again {
+        return after;
+    }
+    for v in vectors {
+        project_out(w, v);
+    }
+    norm(w)
+}
+
+/// Returns orthonormal vectors spanning `vectors`, by modified Gram–Schmidt in order. Vectors whose remainder is below
+/// `tolerance` times their norm are taken to be dependent on the earlier ones and dropped.
+///
+/// # Arguments
+///
+/// * `vectors` - The vectors to orthonormalize.
+/// * `policy` - When to repeat the projections.
+/// * `tolerance` - The relative norm below which a remainder is dropped.
+pub fn gram_schmidt(vectors: &[Vec<f64>], policy: Reorthogonalization, tolerance: f64) -> Vec<Vec<f64>> {
+    let mut basis: Vec<Vec<f64>> = Vec::new();
+    for v in vectors {
+        let mut w = v.clone();
+        let remainder = orthogonalize(&mut w, &basis, policy);
+        if remainder > tolerance * norm(v) {
+            w.iter_mut().for_each(|x| *x /= remainder);
+            basis.push(w);
+        }
+    }
+    basis
+}
+
+/// Returns orthonormal vectors q_0, ..., q_{k-1} such that q_0, ..., q_j span `vectors[0..=j]`, by Householder reflections.
+/// The result is orthonormal to working precision however close to dependent the vectors are, which Gram–Schmidt only
+/// reaches with reorthogonalization. The signs are those of Gram–Schmidt: <q_j|vectors[j]> is not negative.
+///
+/// # Panics
+///
+/// * If the vectors differ in length or there are more vectors than their length.
+pub fn householder(vectors: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let k = vectors.len();
+    let n = vectors.first().map_or(0, |v| v.len());
+    assert!(vectors.iter().all(|v| v.len() == n), "The vectors must have the same length!");
+    assert!(k <= n, "There are more vectors than dimensions!");
+    let mut columns = vectors.to_vec();
+    // The reflector I - 2 u u^T of column j acts on the components j.. only; u is zero if the column already vanishes below j.
+    let mut reflectors: Vec<Vec<f64>> = Vec::with_capacity(k);
+    let mut diagonal = Vec::with_capacity(k);
+    for j in 0..k {
+        let x = &columns[j][j..];
+        let alpha = if x[0] > 0.0 { -norm(x) } else { norm(x) };
+        let mut u = x.to_vec();
+        u[0] -= alpha;
+        let nu = norm(&u);
+        if nu > 0.0 {
+            u.iter_mut().for_each(|ui| *ui /= nu);
+        }
+        for column in columns.iter_mut().skip(j) {
+            reflect(&mut column[j..], &u);
+        }
+        diagonal.push(columns[j][j]);
+        reflectors.push(u);
+    }
+    (0..k)
+        .map(|j| {
+            let mut q = vec![0.0; n];
+            q[j] = if diagonal[j] < 0.0 { -1.0 } else { 1.0 };
+            for (i, u) in reflectors.iter().enumerate().rev() {
+                reflect(&mut q[i..], u);
+            }
+            q
+        })
+        .collect()
+}
+
+/// Reflects `x` in the plane orthogonal to the normalized `u`: x - 2 <u|x> u.
+fn reflect(x: &mut [f64], u: &[f64]) {
+    let overlap = 2.0 * dot(u, x);
+    for (xi, ui) in x.iter_mut().zip(u) {
+        *xi -= overlap * ui;
+    }
+}
+
+/// Returns the overlap <a|b> of two states.
+fn overlap(a: &State, b: &State) -> f64 {
+    let (small, large) = if a.amplitudes.len() <= b.amplitudes.len() { (a, b) } else { (b, a) };
+    small.amplitudes.iter().filter_map(|(s, x)| large.amplitudes.get(s).map(|y| x * y)).sum()
+}
+
+/// Makes `state` orthogonal to the orthonormal `states` by modified Gram–Schmidt like `orthogonalize`, and returns its norm.
+/// Amplitudes that cancel, to rounding error relative to the norm of the state before the projections, are removed.
+pub fn orthogonalize_state(state: &mut State, states: &[State], policy: Reorthogonalization) -> f64 {
+    let before = overlap(state, state).sqrt();
+    let passes = |state: &mut State| {
+        for v in states {
+            let o = overlap(v, state);
+            for (s, x) in &v.amplitudes {
+                *state.amplitudes.entry(*s).or_insert(0.0) -= o * x;
+            }
+        }
+        state.amplitudes.retain(|_, x| x.abs() > f64::EPSILON * before);
+        overlap(state, state).sqrt()
+    };
+    let after = passes(state);
+    match policy {
+        Reorthogonalization::Once => after,
+        Reorthogonalization::IfNeeded if after >= before * std::f64::consts::FRAC_1_SQRT_2 => after,
+        _ => passes(state),
+    }
+}
+
+/// Returns orthonormal states spanning `states`, by modified Gram–Schmidt in order, dropping dependent states like `gram_schmidt`.
+pub fn gram_schmidt_states(states: &[State], policy: Reorthogonalization, tolerance: f64) -> Vec<State> {
+    let mut basis: Vec<State> = Vec::new();
+    for v in states {
+        let mut w = v.clone();
+        let remainder = orthogonalize_state(&mut w, &basis, policy);
+        if remainder > tolerance * overlap(v, v).sqrt() {
+            w.amplitudes.values_mut().for_each(|x| *x /= remainder);
+            basis.push(w);
+        }
+    }
+    basis
+}
+
+/// Returns orthonormal states spanning `states` like `householder`, computed as dense vectors over the determinants of the states.
+/// Amplitudes at rounding error relative to the norm of their state are removed.
+///
+/// # Panics
+///
+/// * If there are more states than determinants among them.
+pub fn householder_states(states: &[State]) -> Vec<State> {
+    let determinants: Vec<Slater> = states
+        .iter()
+        .flat_map(|s| s.amplitudes.keys().copied())
+        .collect::<BTreeSet<Slater>>()
+        .into_iter()
+        .collect();
+    let vectors: Vec<Vec<f64>> = states
+        .iter()
+        .map(|s| determinants.iter().map(|d| s.amplitudes.get(d).copied().unwrap_or(0.0)).collect())
+        .collect();
+    householder(&vectors)
+        .into_iter()
+        .map(|q| {
+            let cutoff = f64::EPSILON * norm(&q);
+            State::new(
+                determinants
+                    .iter()
+                    .zip(q)
+                    .filter(|(_, x)| x.abs() > cutoff)
+                    .map(|(d, x)| (*d, x))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_orthogonalize() {
+        // Nearly dependent columns: one pass of Gram–Schmidt leaves overlaps far above the rounding error.
+        let eps = 1e-9;
+        let vectors = vec![vec![1.0, eps, 0.0, 0.0], vec![1.0, 0.0, eps, 0.0], vec![1.0, 0.0, 0.0, eps]];
+        let max_overlap = |q: &[Vec<f64>]| {
+            (0..q.len()).flat_map(|i| (0..i).map(move |j| (i, j))).map(|(i, j)| dot(&q[i], &q[j]).abs()).fold(0.0, f64::max)
+        };
+        let once = gram_schmidt(&vectors, Reorthogonalization::Once, 1e-14);
+        let twice = gram_schmidt(&vectors, Reorthogonalization::Twice, 1e-14);
+        let needed = gram_schmidt(&vectors, Reorthogonalization::IfNeeded, 1e-14);
+        let reflected = householder(&vectors);
+        assert!(max_overlap(&once) > 1e-12);
+        for q in [&twice, &needed, &reflected] {
+            assert_eq!(q.len(), 3);
+            assert!(max_overlap(q) < 1e-14);
+        }
+        for (a, b) in twice.iter().zip(&reflected) {
+            assert!(a.iter().zip(b).all(|(x, y)| (x - y).abs() < 1e-6));
+        }
+        assert_eq!(gram_schmidt(&[vec![1.0, 1.0], vec![2.0, 2.0]], Reorthogonalization::Twice, 1e-12).len(), 1);
+    }
+
+    #[test]
+    fn test_orthogonalize_states() {
+        let a = State::new(vec![(Slater::new(1), 1.0), (Slater::new(2), 1.0)]);
+        let b = State::new(vec![(Slater::new(1), 1.0), (Slater::new(4), 2.0)]);
+        let c = State::new(vec![(Slater::new(2), 1.0), (Slater::new(4), -2.0)]);
+        let q = gram_schmidt_states(&[a, b.clone(), c.clone()], Reorthogonalization::IfNeeded, 1e-12);
+        // c = a - b lies in the span of a and b.
+        assert_eq!(q.len(), 2);
+        assert!(overlap(&q[0], &q[1]).abs() < 1e-15);
+        assert!((overlap(&q[1], &q[1]) - 1.0).abs() < 1e-15);
+        let h = householder_states(&[b.clone(), c]);
+        assert!(overlap(&h[0], &h[1]).abs() < 1e-15);
+        assert!((overlap(&h[0], &b) - 5f64.sqrt()).abs() < 1e-15);
+        // The cutoff of cancelled amplitudes scales with the state, so small states keep theirs.
+        let s = 1.0 / 2f64.sqrt();
+        let bonding = State::new(vec![(Slater::new(1), s), (Slater::new(2), s)]);
+        let mut small = State::new(vec![(Slater::new(1), 1e-20), (Slater::new(2), 2e-20)]);
+        let remainder = orthogonalize_state(&mut small, &[bonding], Reorthogonalization::Twice);
+        assert_eq!(small.amplitudes.len(), 2);
+        assert!((remainder - 0.5e-20 * 2f64.sqrt()).abs() < 1e-35);
+    }
+}
@@ -0,0 +1,120 @@
+use std::f64::consts::PI;
+
+use crate::complex::Complex;
+use crate::error::Error;
+
+/// Represents a Padé approximant in continued fraction (Thiele) form, interpolating a function through a set of complex points.
+/// Used to continue Matsubara data to the real frequency axis, following Vidberg and Serene.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pade {
+    /// The interpolation points z_i.
+    points: Vec<Complex>,
+    /// The continued fraction coefficients a_i.
+    coefficients: Vec<Complex>,
+}
+
+impl Pade {
+    /// Returns the Padé approximant through the supplied points and values.
+    ///
+    /// # Arguments
+    ///
+    /// * `points` - The interpolation points z_i, e.g. iω_n.
+    /// * `values` - The function values f(z_i).
+    ///
+    /// # Errors
+    ///
+    /// * If points and values differ in length, or the recursion breaks down because of repeated points or vanishing values, this function returns an Error.
+    pub fn new(points: &[Complex], values: &[Complex]) -> Result<Pade, Error> {
+        if points.len() != values.len() {
+            return Err(Error::DimensionMismatch);
+        }
+        let n = points.len();
+        let mut g = values.to_vec();
+        let mut coefficients = Vec::with_capacity(n);
+        for p in 0..n {
+            coefficients.push(g[p]);
+            let gp = g[p];
+            for i in p + 1..n {
+                let denominator = (points[i] - points[p]) * g[i];
+                if denominator.abs() == 0.0 {
+                    return Err(Error::Invalid("Padé recursion broke down!"));
+                }
+                g[i] = (gp - g[i]) / denominator;
+            }
+        }
+        Ok(Pade {
+            points: points.to_vec(),
+            coefficients,
+        })
+    }
+
+    /// Returns the Padé approximant through the Matsubara data G(iω_n).
+    ///
+    /// # Arguments
+    ///
+    /// * `frequencies` - The Matsubara frequencies ω_n.
+    /// * `values` - The Green's function G(iω_n).
+    ///
+    /// # Errors
+    ///
+    /// * If the continued fraction cannot be constructed, this function returns an Error.
+    pub fn from_matsubara(frequencies: &[f64], values: &[Complex]) -> Result<Pade, Error> {
+        let points: Vec<Complex> = frequencies.iter().map(|w| Complex::new(0.0, *w)).collect();
+        Pade::new(&points, values)
+    }
+
+    /// Returns the continued fraction coefficients a_i.
+    pub fn coefficients(&self) -> &[Complex] {
+        &self.coefficients
+    }
+
+    /// Returns the value of the approximant at `z`.
+    pub fn evaluate(&self, z: Complex) -> Complex {
+        let n = self.coefficients.len();
+        if n == 0 {
+            return Complex::default();
+        }
+        let (mut a_prev, mut a) = (Complex::default(), self.coefficients[0]);
+        let (mut b_prev, mut b) = (Complex::from(1.0), Complex::from(1.0));
+        for k in 1..n {
+            let f = (z - self.points[k - 1]) * self.coefficients[k];
+            let a_next = a + f * a_prev;
+            let b_next = b + f * b_prev;
+            a_prev = a;
+            b_prev = b;
+            a = a_next;
+            b = b_next;
+        }
+        a / b
+    }
+
+    /// Returns the spectral function -Im G(ω + iη)/π of the continued Green's function on the real frequencies `omegas`.
+    ///
+    /// # Arguments
+    ///
+    /// * `omegas` - The real frequencies.
+    /// * `eta` - The distance above the real axis at which the approximant is evaluated.
+    pub fn spectral_function(&self, omegas: &[f64], eta: f64) -> Vec<f64> {
+        omegas
+            .iter()
+            .map(|w| -self.evaluate(Complex::new(*w, eta)).im / PI)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spectral::{matsubara_frequencies, Poles, Statistics};
+
+    #[test]
+    fn test_two_poles() {
+        let poles = Poles::new(vec![-1.0, 0.5], vec![0.4, 0.6]);
+        let freqs = matsubara_frequencies(10.0, 8, Statistics::Fermion);
+        let values = poles.matsubara(&freqs);
+        let pade = Pade::from_matsubara(&freqs, &values).unwrap();
+        for z in &[Complex::new(0.2, 0.05), Complex::new(-0.9, 0.1)] {
+            assert!((pade.evaluate(*z) - poles.evaluate(*z)).abs() < 1e-8);
+        }
+    }
+}
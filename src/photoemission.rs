@@ -0,0 +1,165 @@
+use crate::basis::Basis;
+use crate::error::Error;
+use crate::gaps::sector_ground_state;
+use crate::lanczos::{greens_function, GreensFunction};
+use crate::operators::{Spin, SpinConvention};
+use crate::spectral::{Kernel, Poles};
+use crate::{Operator, AC};
+
+/// Represents the photoemission and inverse photoemission spectra of an N particle ground state,
+/// with one single particle Green's function per orbital.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Photoemission {
+    /// The (site, spin) of each orbital.
+    orbitals: Vec<(u64, Spin)>,
+    /// The Green's function of each orbital.
+    greens_functions: Vec<GreensFunction>,
+}
+
+impl Photoemission {
+    /// Returns the (site, spin) of each orbital.
+    pub fn orbitals(&self) -> &[(u64, Spin)] {
+        &self.orbitals
+    }
+
+    /// Returns the Green's function G_kk(z) of orbital `k`.
+    pub fn greens_function(&self, k: usize) -> &GreensFunction {
+        &self.greens_functions[k]
+    }
+
+    /// Returns the removal poles of orbital `k`, at ω = E_0 - E_n(N - 1) with weights |<n|c_k|0>|².
+    pub fn removal_poles(&self, k: usize) -> Poles {
+        let g = &self.greens_functions[k];
+        let poles = g.hole().poles();
+        let positions = poles.positions().iter().map(|e| g.ground_state_energy() - e).collect();
+        Poles::new(positions, poles.weights().to_vec())
+    }
+
+    /// Returns the addition poles of orbital `k`, at ω = E_n(N + 1) - E_0 with weights |<n|c†_k|0>|².
+    pub fn addition_poles(&self, k: usize) -> Poles {
+        let g = &self.greens_functions[k];
+        let poles = g.particle().poles();
+        let positions = poles.positions().iter().map(|e| e - g.ground_state_energy()).collect();
+        Poles::new(positions, poles.weights().to_vec())
+    }
+
+    /// Returns the photoemission spectrum A⁻(ω) = Σ_k Σ_n |<n|c_k|0>|² δ(ω - E_0 + E_n), broadened by `kernel`, on `omegas`.
+    pub fn photoemission(&self, omegas: &[f64], kernel: &Kernel) -> Vec<f64> {
+        self.sum_spectra(omegas, |k| self.removal_poles(k).spectral_function(omegas, kernel))
+    }
+
+    /// Returns the inverse photoemission spectrum A⁺(ω) = Σ_k Σ_n |<n|c†_k|0>|² δ(ω - E_n + E_0), broadened by `kernel`, on `omegas`.
+    pub fn inverse_photoemission(&self, omegas: &[f64], kernel: &Kernel) -> Vec<f64> {
+        self.sum_spectra(omegas, |k| self.addition_poles(k).spectral_function(omegas, kernel))
+    }
+
+    /// Returns the sum over all orbitals of the spectra `spectrum(k)`.
+    fn sum_spectra<F: Fn(usize) -> Vec<f64>>(&self, omegas: &[f64], spectrum: F) -> Vec<f64> {
+        let mut total = vec![0.0; omegas.len()];
+        for k in 0..self.orbitals.len() {
+            for (t, a) in total.iter_mut().zip(spectrum(k)) {
+                *t += a;
+            }
+        }
+        total
+    }
+}
+
+/// Returns the photoemission and inverse photoemission spectra of the ground state of the (N↑, N↓) sector.
+///
+/// The ground state is found by Lanczos, c_k|0> and c†_k|0> are built in the (N↑ ∓ 1, N↓) or (N↑, N↓ ∓ 1) sectors
+/// matching the spin of orbital k, and a Green's function Lanczos run in each of these sectors gives the spectra.
+/// The Hamiltonian must conserve N↑ and N↓.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `n_sites` - The number of sites.
+/// * `n_up` - The number of spin up particles N↑.
+/// * `n_down` - The number of spin down particles N↓.
+/// * `orbitals` - The (site, spin) of the orbitals c_k.
+/// * `n_steps` - The maximal number of Lanczos steps of each Green's function.
+///
+/// # Errors
+///
+/// * If N↑ or N↓ exceeds the number of sites, this function returns an Error.
+/// * If the ground state Lanczos does not converge, this function returns the Error.
+/// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+pub fn photoemission(
+    hamiltonian: &Operator,
+    convention: SpinConvention,
+    n_sites: u64,
+    n_up: u64,
+    n_down: u64,
+    orbitals: &[(u64, Spin)],
+    n_steps: usize,
+) -> Result<Photoemission, Error> {
+    if n_up > n_sites || n_down > n_sites {
+        return Err(Error::Invalid("There are more particles of one spin than sites!"));
+    }
+    let sector = |n_up: u64, n_down: u64| Basis::fixed_spin_numbers(convention, n_sites, n_up, n_down);
+    let basis = sector(n_up, n_down);
+    let (e0, x) = sector_ground_state(&basis.try_sparse_matrix(hamiltonian)?)?;
+    let ground = basis.state(&x);
+    let mut greens_functions = Vec::with_capacity(orbitals.len());
+    for (site, spin) in orbitals {
+        let o = convention.orbital(*site, *spin);
+        let (plus, minus) = match spin {
+            Spin::Up => (sector(n_up + 1, n_down), sector(n_up.saturating_sub(1), n_down)),
+            Spin::Down => (sector(n_up, n_down + 1), sector(n_up, n_down.saturating_sub(1))),
+        };
+        let create = Operator::new(vec![(1.0, vec![AC::Create(o)])]);
+        let annihilate = Operator::new(vec![(1.0, vec![AC::Annihilate(o)])]);
+        greens_functions.push(greens_function(
+            &plus.try_sparse_matrix(hamiltonian)?,
+            &plus.vector(&ground.clone().apply(create)),
+            &minus.try_sparse_matrix(hamiltonian)?,
+            &minus.vector(&ground.clone().apply(annihilate)),
+            e0,
+            n_steps,
+        ));
+    }
+    Ok(Photoemission {
+        orbitals: orbitals.to_vec(),
+        greens_functions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::complex::Complex;
+    use crate::models::{hubbard, Graph};
+    use crate::spectral;
+    use crate::spectrum::Spectrum;
+
+    #[test]
+    fn test_hubbard_dimer() {
+        let conv = SpinConvention::Interleaved;
+        // μ = U/2 makes half filling the ground state of the Fock space.
+        let h = hubbard(&Graph::new(2, vec![(0, 1)]), 1.0, 4.0, 2.0, conv);
+        let orbitals = [(0, Spin::Up), (0, Spin::Down)];
+        let spectra = photoemission(&h, conv, 2, 1, 1, &orbitals, 20).unwrap();
+        // At half filling each orbital is half occupied, and removal and addition mirror each other.
+        for k in 0..2 {
+            assert!((spectra.removal_poles(k).total_weight() - 0.5).abs() < 1e-10);
+            assert!((spectra.addition_poles(k).total_weight() - 0.5).abs() < 1e-10);
+        }
+        let omegas: Vec<f64> = (0..9).map(|i| 0.5 * (i as f64 - 4.0)).collect();
+        let kernel = Kernel::Lorentzian(0.1);
+        let removal = spectra.photoemission(&omegas, &kernel);
+        let addition = spectra.inverse_photoemission(&omegas, &kernel);
+        for (r, a) in removal.iter().zip(addition.iter().rev()) {
+            assert!((r - a).abs() < 1e-10);
+        }
+        let exact = Spectrum::new(&h, Basis::fock_space(4));
+        let c = Operator::new(vec![(1.0, vec![AC::Annihilate(0)])]);
+        let cd = Operator::new(vec![(1.0, vec![AC::Create(0)])]);
+        let reference = spectral::greens_function(&exact, &c, &cd, f64::INFINITY);
+        let z = Complex::new(0.7, 0.2);
+        let shift = exact.ground_state_energy() - spectra.greens_function(0).ground_state_energy();
+        assert!(shift.abs() < 1e-10);
+        assert!((spectra.greens_function(0).evaluate(z) - reference.evaluate(z)).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::table::ResultTable;
+
+/// The size of a figure in SVG user units.
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 480.0;
+
+/// The margins between the figure border and the axes: left, right, top, bottom.
+const MARGINS: [f64; 4] = [70.0, 130.0, 40.0, 55.0];
+
+/// The colours of successive line series.
+const PALETTE: [&str; 6] = ["#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b"];
+
+/// Samples of the viridis colour map, from low to high values.
+const VIRIDIS: [[f64; 3]; 5] = [
+    [68.0, 1.0, 84.0],
+    [59.0, 82.0, 139.0],
+    [33.0, 145.0, 140.0],
+    [94.0, 201.0, 98.0],
+    [253.0, 231.0, 37.0],
+];
+
+/// Returns about `n` round tick positions covering [lo, hi].
+fn ticks(lo: f64, hi: f64, n: usize) -> Vec<f64> {
+    let raw = (hi - lo) / n as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0].iter().map(|m| m * magnitude).find(|s| *s >= raw).unwrap_or(10.0 * magnitude);
+    let first = (lo / step).ceil() as i64;
+    let last = (hi / step).floor() as i64;
+    (first..=last).map(|k| k as f64 * step).collect()
+}
+
+/// Returns the label of the tick `t` with as many decimals as the spacing `step` of the ticks needs.
+fn tick_label(t: f64, step: f64) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, t)
+}
+
+/// Returns the range [lo, hi] of `values`, widened if it is empty or a single point.
+fn range<'a, I: Iterator<Item = &'a f64>>(values: I) -> (f64, f64) {
+    let (lo, hi) = values.filter(|x| x.is_finite()).fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), x| (lo.min(*x), hi.max(*x)));
+    if lo > hi {
+        (0.0, 1.0)
+    } else if lo == hi {
+        (lo - 0.5, hi + 0.5)
+    } else {
+        (lo, hi)
+    }
+}
+
+/// Returns `text` with the characters special to XML escaped.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Returns the viridis colour of `t` in [0, 1] as "rgb(r,g,b)".
+fn colour(t: f64) -> String {
+    let x = t.clamp(0.0, 1.0) * (VIRIDIS.len() - 1) as f64;
+    let k = (x.floor() as usize).min(VIRIDIS.len() - 2);
+    let f = x - k as f64;
+    let c: Vec<String> = (0..3).map(|i| format!("{:.0}", VIRIDIS[k][i] * (1.0 - f) + VIRIDIS[k + 1][i] * f)).collect();
+    format!("rgb({})", c.join(","))
+}
+
+/// Represents the axes of a figure: the data ranges and their mapping onto the plot area.
+struct Axes {
+    x: (f64, f64),
+    y: (f64, f64),
+}
+
+impl Axes {
+    fn px(&self, x: f64) -> f64 {
+        MARGINS[0] + (x - self.x.0) / (self.x.1 - self.x.0) * (WIDTH - MARGINS[0] - MARGINS[1])
+    }
+
+    fn py(&self, y: f64) -> f64 {
+        HEIGHT - MARGINS[3] - (y - self.y.0) / (self.y.1 - self.y.0) * (HEIGHT - MARGINS[2] - MARGINS[3])
+    }
+
+    /// Writes the frame, ticks, title and axis labels; a logarithmic y axis labels 10^y.
+    fn draw(&self, svg: &mut String, title: &str, x_label: &str, y_label: &str, log_y: bool) {
+        let (x0, x1, y0, y1) = (self.px(self.x.0), self.px(self.x.1), self.py(self.y.0), self.py(self.y.1));
+        let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="none" stroke="black"/>"#, x0, y1, x1 - x0, y0 - y1);
+        let step = |t: &[f64]| if t.len() > 1 { t[1] - t[0] } else { 1.0 };
+        let x_ticks = ticks(self.x.0, self.x.1, 6);
+        for t in &x_ticks {
+            let label = tick_label(*t, step(&x_ticks));
+            let _ = writeln!(svg, r#"<line x1="{0:.1}" y1="{1:.1}" x2="{0:.1}" y2="{2:.1}" stroke="black"/>"#, self.px(*t), y0, y0 + 5.0);
+            let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-size="12">{}</text>"#, self.px(*t), y0 + 18.0, label);
+        }
+        let y_ticks = ticks(self.y.0, self.y.1, 6);
+        for t in y_ticks.iter().copied() {
+            let label = tick_label(t, step(&y_ticks));
+            let label = if log_y { format!("1e{}", label) } else { label };
+            let _ = writeln!(svg, r#"<line x1="{:.1}" y1="{1:.1}" x2="{2:.1}" y2="{1:.1}" stroke="black"/>"#, x0 - 5.0, self.py(t), x0);
+            let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="end" font-size="12">{}</text>"#, x0 - 8.0, self.py(t) + 4.0, label);
+        }
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="24" text-anchor="middle" font-size="16">{}</text>"#, (x0 + x1) / 2.0, escape(title));
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-size="14">{}</text>"#, (x0 + x1) / 2.0, HEIGHT - 12.0, escape(x_label));
+        let _ = writeln!(
+            svg,
+            r#"<text x="18" y="{0:.1}" text-anchor="middle" font-size="14" transform="rotate(-90 18 {0:.1})">{1}</text>"#,
+            (y0 + y1) / 2.0,
+            escape(y_label)
+        );
+    }
+}
+
+/// Returns the opening tag of an SVG document of the figure size.
+fn header() -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"sans-serif\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n",
+        WIDTH, HEIGHT
+    )
+}
+
+/// Represents a figure of curves y(x), e.g. a density of states, a correlation function against distance or a sweep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinePlot {
+    /// The title above the axes.
+    pub title: String,
+    /// The label of the x axis.
+    pub x_label: String,
+    /// The label of the y axis.
+    pub y_label: String,
+    /// Whether the y axis is logarithmic, for the decay of correlations; non-positive values are left out.
+    pub log_y: bool,
+    /// The curves as (name, x, y).
+    series: Vec<(String, Vec<f64>, Vec<f64>)>,
+}
+
+impl LinePlot {
+    /// Returns a figure without curves.
+    pub fn new(title: &str, x_label: &str, y_label: &str) -> LinePlot {
+        LinePlot {
+            title: title.to_string(),
+            x_label: x_label.to_string(),
+            y_label: y_label.to_string(),
+            log_y: false,
+            series: Vec::new(),
+        }
+    }
+
+    /// Returns the figure of the columns `y_columns` of `table` against the column `x_column`, labeled by the column names.
+    ///
+    /// # Errors
+    ///
+    /// * If a column is missing from `table`, this function returns an Error.
+    pub fn from_table(table: &ResultTable, x_column: &str, y_columns: &[&str]) -> Result<LinePlot, Error> {
+        let x = table.column(x_column).ok_or(Error::Invalid("No column with this name!"))?;
+        let mut plot = LinePlot::new(table.metadata("model").unwrap_or(""), x_column, &y_columns.join(", "));
+        for name in y_columns {
+            plot.add_series(name, &x, &table.column(name).ok_or(Error::Invalid("No column with this name!"))?);
+        }
+        Ok(plot)
+    }
+
+    /// Adds the curve through the points (x_i, y_i), named `name` in the legend.
+    ///
+    /// # Panics
+    ///
+    /// * If `x` and `y` differ in length, this function panics.
+    pub fn add_series(&mut self, name: &str, x: &[f64], y: &[f64]) {
+        assert_eq!(x.len(), y.len(), "Every point needs an x and a y value!");
+        self.series.push((name.to_string(), x.to_vec(), y.to_vec()));
+    }
+
+    /// Returns the figure as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let transform = |y: f64| if self.log_y { if y > 0.0 { y.log10() } else { f64::NAN } } else { y };
+        let curves: Vec<Vec<(f64, f64)>> = self
+            .series
+            .iter()
+            .map(|(_, x, y)| x.iter().zip(y).map(|(x, y)| (*x, transform(*y))).filter(|(x, y)| x.is_finite() && y.is_finite()).collect())
+            .collect();
+        let axes = Axes {
+            x: range(curves.iter().flatten().map(|(x, _)| x)),
+            y: range(curves.iter().flatten().map(|(_, y)| y)),
+        };
+        let mut svg = header();
+        axes.draw(&mut svg, &self.title, &self.x_label, &self.y_label, self.log_y);
+        for (k, ((name, _, _), curve)) in self.series.iter().zip(&curves).enumerate() {
+            let c = PALETTE[k % PALETTE.len()];
+            let points: Vec<String> = curve.iter().map(|(x, y)| format!("{:.2},{:.2}", axes.px(*x), axes.py(*y))).collect();
+            let _ = writeln!(svg, r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/>"#, points.join(" "), c);
+            let y = MARGINS[2] + 10.0 + 20.0 * k as f64;
+            let x = WIDTH - MARGINS[1] + 10.0;
+            let _ = writeln!(svg, r#"<line x1="{0:.1}" y1="{1:.1}" x2="{2:.1}" y2="{1:.1}" stroke="{3}" stroke-width="2"/>"#, x, y, x + 20.0, c);
+            let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-size="12">{}</text>"#, x + 25.0, y + 4.0, escape(name));
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Writes the figure as an SVG file to `path`.
+    pub fn write_svg<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_svg())
+    }
+}
+
+/// Returns an SVG heat map of `values[i][j]` at (x_i, y_j), e.g. A(k, ω) from `Cpt::spectral_function` with momenta along x and
+/// frequencies along y, with a colour bar on the right.
+///
+/// # Arguments
+///
+/// * `values` - The values, one row per x.
+/// * `x` - The x coordinates, e.g. the index or length along a path of momenta.
+/// * `y` - The y coordinates, e.g. the frequencies.
+/// * `labels` - The title and the x and y axis labels.
+///
+/// # Panics
+///
+/// * If the shape of `values` does not match `x` and `y`, this function panics.
+pub fn heat_map_svg(values: &[Vec<f64>], x: &[f64], y: &[f64], labels: [&str; 3]) -> String {
+    assert!(values.len() == x.len() && values.iter().all(|r| r.len() == y.len()), "The values must have one row per x and one column per y!");
+    // Every value fills the cell up to halfway to its neighbours.
+    let edges = |c: &[f64]| -> Vec<f64> {
+        let n = c.len();
+        (0..=n)
+            .map(|k| match (k, n) {
+                (_, 1) => c[0] + k as f64 - 0.5,
+                (0, _) => c[0] - (c[1] - c[0]) / 2.0,
+                (k, n) if k == n => c[n - 1] + (c[n - 1] - c[n - 2]) / 2.0,
+                (k, _) => (c[k - 1] + c[k]) / 2.0,
+            })
+            .collect()
+    };
+    let mut svg = header();
+    if x.is_empty() || y.is_empty() {
+        svg.push_str("</svg>\n");
+        return svg;
+    }
+    let (xe, ye) = (edges(x), edges(y));
+    let axes = Axes {
+        x: range(xe.iter()),
+        y: range(ye.iter()),
+    };
+    let (lo, hi) = range(values.iter().flatten());
+    for (i, row) in values.iter().enumerate() {
+        for (j, v) in row.iter().enumerate() {
+            let (x0, x1) = (axes.px(xe[i]), axes.px(xe[i + 1]));
+            let (y0, y1) = (axes.py(ye[j]), axes.py(ye[j + 1]));
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="{}"/>"#,
+                x0.min(x1),
+                y0.min(y1),
+                (x1 - x0).abs() + 0.3,
+                (y1 - y0).abs() + 0.3,
+                colour((v - lo) / (hi - lo))
+            );
+        }
+    }
+    axes.draw(&mut svg, labels[0], labels[1], labels[2], false);
+    let (top, bottom) = (MARGINS[2], HEIGHT - MARGINS[3]);
+    let left = WIDTH - MARGINS[1] + 20.0;
+    for k in 0..50 {
+        let t = k as f64 / 49.0;
+        let y = bottom - t * (bottom - top);
+        let _ = writeln!(svg, r#"<rect x="{:.1}" y="{:.2}" width="20" height="{:.2}" fill="{}"/>"#, left, y - (bottom - top) / 49.0, (bottom - top) / 49.0 + 0.3, colour(t));
+    }
+    for (t, v) in [(0.0, lo), (1.0, hi)] {
+        let _ = writeln!(svg, r#"<text x="{:.1}" y="{:.1}" font-size="12">{:.3}</text>"#, left + 25.0, bottom - t * (bottom - top) + 4.0, v);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg() {
+        let t = ticks(0.0, 1.0, 5);
+        assert_eq!(t.len(), 6);
+        assert_eq!(tick_label(t[3], t[1] - t[0]), "0.6");
+        let mut table = ResultTable::new(&["r", "c"]);
+        for r in 1..5 {
+            table.push(vec![r as f64, (-(r as f64)).exp()]).unwrap();
+        }
+        table.set_metadata("model", "a < b");
+        let mut plot = LinePlot::from_table(&table, "r", &["c"]).unwrap();
+        plot.log_y = true;
+        let svg = plot.to_svg();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert!(svg.contains("a &lt; b") && svg.contains("<polyline"));
+        assert!(LinePlot::from_table(&table, "r", &["d"]).is_err());
+        let map = heat_map_svg(&[vec![0.0, 1.0], vec![2.0, 3.0], vec![4.0, 5.0]], &[0.0, 1.0, 2.0], &[-1.0, 1.0], ["A", "k", "w"]);
+        assert_eq!(map.matches("rgb(68,1,84)").count(), 2);
+        assert_eq!(map.matches("rgb(253,231,37)").count(), 2);
+    }
+}
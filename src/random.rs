@@ -0,0 +1,88 @@
+use std::f64::consts::PI;
+
+/// This represents a small, seedable pseudo random number generator (xoshiro256**),
+/// used for random starting vectors and stochastic trace estimates so that runs are reproducible.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    /// Returns a generator whose state is derived from `seed` with SplitMix64.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - Any 64 bit seed; equal seeds produce equal sequences.
+    pub fn new(seed: u64) -> Rng {
+        let mut sm = seed;
+        let mut state = [0u64; 4];
+        for s in state.iter_mut() {
+            sm = sm.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = sm;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            *s = z ^ (z >> 31);
+        }
+        Rng { state }
+    }
+
+    /// Returns the next 64 random bits.
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    /// Returns a number drawn uniformly from [0, 1).
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a number drawn from the standard normal distribution.
+    pub fn normal(&mut self) -> f64 {
+        let u = 1.0 - self.uniform();
+        let v = self.uniform();
+        (-2.0 * u.ln()).sqrt() * (2.0 * PI * v).cos()
+    }
+
+    /// Returns a vector of length `n` with independent random ±1 entries.
+    pub fn sign_vector(&mut self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|_| if self.next_u64() >> 63 == 0 { 1.0 } else { -1.0 })
+            .collect()
+    }
+
+    /// Returns a vector of length `n` with independent standard normal entries.
+    pub fn normal_vector(&mut self, n: usize) -> Vec<f64> {
+        (0..n).map(|_| self.normal()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..10 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn test_uniform_mean() {
+        let mut rng = Rng::new(7);
+        let mean: f64 = (0..10000).map(|_| rng.uniform()).sum::<f64>() / 10000.0;
+        assert!((mean - 0.5).abs() < 0.01);
+    }
+}
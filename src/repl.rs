@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead, Write};
+
+use crate::error::Error;
+use crate::io::{from_openfermion, to_openfermion};
+use crate::observables::expectation_value;
+use crate::{Operator, Slater, SlaterMap, State, AC};
+
+/// The help message of the interactive mode.
+pub const HELP: &str = "Commands:
+    op <name> = <terms>        Define an operator, e.g. op h = -1.0 [0^ 1] + -1.0 [1^ 0]
+    state <name> = <terms>     Define a state from determinants of occupied orbitals, e.g. state psi = 0.6 [0] + 0.8 [1]
+    apply <op> <state> <name>  Define the state <name> = <op>|<state>
+    expect <op> <state>        Print <state|op|state> / <state|state>
+    norm <state>               Print the norm of a state
+    print <name>               Print an operator or a state
+    list                       List the defined operators and states
+    help                       Print this message
+    quit                       Leave";
+
+/// Returns the state written as "amplitude [i j ...]" terms joined by "+", each the determinant with the orbitals i, j, ... occupied.
+/// Repeated determinants are summed.
+///
+/// # Errors
+///
+/// * If a term is malformed or occupies an orbital twice, this function returns an Error.
+pub fn parse_state(text: &str) -> Result<State, Error> {
+    let mut amplitudes: SlaterMap<f64> = SlaterMap::default();
+    for (a, ac) in from_openfermion(text)?.terms {
+        let orbitals = ac
+            .iter()
+            .map(|c| match c {
+                AC::Annihilate(i) => Ok(*i),
+                AC::Create(_) => Err(Error::Invalid("States are written with occupied orbitals only!")),
+            })
+            .collect::<Result<_, _>>()?;
+        *amplitudes.entry(Slater::from_vec(orbitals)?).or_insert(0.0) += a;
+    }
+    Ok(State::new(amplitudes.into_iter().collect()))
+}
+
+/// Returns `state` as "amplitude|occupations>" terms sorted by determinant, with orbital 0 as the rightmost occupation.
+pub fn format_state(state: &State) -> String {
+    let sorted: BTreeMap<Slater, f64> = state.amplitudes.iter().map(|(s, a)| (*s, *a)).collect();
+    if sorted.is_empty() {
+        return "0".to_string();
+    }
+    let width = sorted.keys().map(|s| 64 - s.index.leading_zeros() as usize).max().unwrap_or(0).max(1);
+    sorted
+        .iter()
+        .map(|(s, a)| format!("{:+}|{:0width$b}>", a, s.index, width = width))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Represents an interactive session: the operators and states defined so far.
+#[derive(Debug, Clone, Default)]
+pub struct Repl {
+    /// The operators by name.
+    operators: HashMap<String, Operator>,
+    /// The states by name.
+    states: HashMap<String, State>,
+}
+
+impl Repl {
+    /// Returns a session without definitions.
+    pub fn new() -> Repl {
+        Repl::default()
+    }
+
+    /// Returns the state called `name`.
+    fn state(&self, name: &str) -> Result<&State, Error> {
+        self.states.get(name).ok_or(Error::Invalid("No state with this name!"))
+    }
+
+    /// Returns the operator called `name`.
+    fn operator(&self, name: &str) -> Result<&Operator, Error> {
+        self.operators.get(name).ok_or(Error::Invalid("No operator with this name!"))
+    }
+
+    /// Returns the output of the command `line`, empty for definitions, see `HELP`.
+    ///
+    /// # Errors
+    ///
+    /// * If the command is unknown, malformed or refers to undefined names, this function returns an Error.
+    pub fn execute(&mut self, line: &str) -> Result<String, Error> {
+        let line = line.trim();
+        let (command, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        match command {
+            "" => Ok(String::new()),
+            "op" | "state" => {
+                let (name, definition) = rest.split_once('=').ok_or(Error::Parse("Definitions are written as <name> = <terms>!"))?;
+                let name = name.trim();
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(Error::Invalid("Names must be single words!"));
+                }
+                if command == "op" {
+                    self.operators.insert(name.to_string(), from_openfermion(definition)?);
+                } else {
+                    self.states.insert(name.to_string(), parse_state(definition)?);
+                }
+                Ok(String::new())
+            }
+            "apply" => {
+                let [op, state, name] = words.as_slice() else {
+                    return Err(Error::Invalid("Usage: apply <op> <state> <name>"));
+                };
+                let res = self.state(state)?.clone().apply(self.operator(op)?.clone());
+                let out = format_state(&res);
+                self.states.insert(name.to_string(), res);
+                Ok(out)
+            }
+            "expect" => {
+                let [op, state] = words.as_slice() else {
+                    return Err(Error::Invalid("Usage: expect <op> <state>"));
+                };
+                let state = self.state(state)?;
+                if state.amplitudes.is_empty() {
+                    return Err(Error::Invalid("The state vanishes!"));
+                }
+                Ok(expectation_value(state, self.operator(op)?).to_string())
+            }
+            "norm" => {
+                let [state] = words.as_slice() else {
+                    return Err(Error::Invalid("Usage: norm <state>"));
+                };
+                Ok(self.state(state)?.amplitudes.values().map(|a| a * a).sum::<f64>().sqrt().to_string())
+            }
+            "print" => {
+                let [name] = words.as_slice() else {
+                    return Err(Error::Invalid("Usage: print <name>"));
+                };
+                match (self.operators.get(*name), self.states.get(*name)) {
+                    (Some(op), _) => Ok(to_openfermion(op)),
+                    (None, Some(state)) => Ok(format_state(state)),
+                    (None, None) => Err(Error::Invalid("Nothing with this name!")),
+                }
+            }
+            "list" => {
+                let mut operators: Vec<&String> = self.operators.keys().collect();
+                let mut states: Vec<&String> = self.states.keys().collect();
+                operators.sort();
+                states.sort();
+                let join = |names: Vec<&String>| names.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(" ");
+                Ok(format!("operators: {}\nstates: {}", join(operators), join(states)))
+            }
+            "help" => Ok(HELP.to_string()),
+            _ => Err(Error::Invalid("Unknown command, try help!")),
+        }
+    }
+
+    /// Runs the session on the lines of `input`, writing a prompt, the output and errors to `output`, until "quit" or the end of the input.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, mut output: W) -> io::Result<()> {
+        write!(output, "> ")?;
+        output.flush()?;
+        for line in input.lines() {
+            let line = line?;
+            if matches!(line.trim(), "quit" | "exit") {
+                break;
+            }
+            match self.execute(&line) {
+                Ok(out) if out.is_empty() => {}
+                Ok(out) => writeln!(output, "{}", out)?,
+                Err(e) => writeln!(output, "Error {}", e)?,
+            }
+            write!(output, "> ")?;
+            output.flush()?;
+        }
+        writeln!(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session() {
+        let input = "op h = -1.0 [0^ 1] + -1.0 [1^ 0]\n\
+                     state psi = 1.0 [0] + 1.0 [1]\n\
+                     expect h psi\n\
+                     apply h psi phi\n\
+                     print psi\n\
+                     frobnicate\n\
+                     quit\n\
+                     list\n";
+        let mut output = Vec::new();
+        Repl::new().run(input.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // The bonding orbital has energy -t, and h maps it onto minus itself.
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], "> > > -1");
+        assert_eq!(lines[1], "> -1|01> -1|10>");
+        assert_eq!(lines[2], "> +1|01> +1|10>");
+        assert_eq!(lines[3], "> Error Unknown command, try help!");
+        assert!(!output.contains("operators:"));
+        assert!(matches!(parse_state("1.0 [0 0]"), Err(Error::DuplicateOrbital(0))));
+        assert!(parse_state("1.0 [0^]").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use crate::basis::Basis;
+use crate::error::Error;
+use crate::lanczos::ground_state;
+use crate::linalg::LinearOperator;
+use crate::random::Rng;
+use crate::{Operator, Slater, SlaterMap};
+
+/// The required residual of the Lanczos ground state in each selected space.
+const SELECTION_TOLERANCE: f64 = 1e-10;
+
+/// The parameters of the selection of determinants.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SelectionParameters {
+    /// The heat-bath threshold ε: external determinants a with max_i |H_ai c_i| > ε are added.
+    pub epsilon: f64,
+    /// The maximal number of determinants added per iteration, the most important ones first.
+    pub max_added: usize,
+    /// The maximal number of selection iterations.
+    pub max_iterations: usize,
+    /// The energy change between iterations below which the selection has converged.
+    pub tolerance: f64,
+}
+
+/// Represents the result of a selected configuration interaction calculation.
+#[derive(Debug, Clone)]
+pub struct SelectedCi {
+    /// The selected determinants.
+    pub basis: Basis,
+    /// The variational ground state energy in the selected space.
+    pub energy: f64,
+    /// The normalized ground state coefficients c_i in `basis`.
+    pub vector: Vec<f64>,
+    /// The dimension of the selected space and its ground state energy at each iteration.
+    pub history: Vec<(usize, f64)>,
+    /// Whether the energy converged, or no more determinants passed the threshold, within the maximal number of iterations.
+    pub converged: bool,
+}
+
+/// Returns, for every determinant a outside `basis` reached from it by `hamiltonian`,
+/// the sum Σ_i H_ai c_i and the largest single contribution max_i |H_ai c_i|.
+pub(crate) fn external_couplings(hamiltonian: &Operator, basis: &Basis, vector: &[f64]) -> SlaterMap<(f64, f64)> {
+    let mut couplings: SlaterMap<(f64, f64)> = SlaterMap::default();
+    for (slater, c) in basis.states().iter().zip(vector) {
+        let mut column: SlaterMap<f64> = SlaterMap::default();
+        for (amp, a) in hamiltonian.apply_slater(slater) {
+            if basis.index(&a).is_none() {
+                *column.entry(a).or_insert(0.0) += amp;
+            }
+        }
+        for (a, h) in column {
+            let entry = couplings.entry(a).or_insert((0.0, 0.0));
+            entry.0 += h * c;
+            entry.1 = entry.1.max((h * c).abs());
+        }
+    }
+    couplings
+}
+
+/// Returns the ground state of `hamiltonian` in a determinant space grown iteratively from `reference` by importance.
+///
+/// Each iteration diagonalizes the Hamiltonian by Lanczos in the current space and adds the external determinants a
+/// connected to it with heat-bath importance max_i |H_ai c_i| above the threshold, until the energy changes by less than the tolerance.
+/// With a vanishing threshold the selection grows into the full space connected to the reference.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian.
+/// * `reference` - The starting determinants, e.g. a Hartree-Fock determinant.
+/// * `parameters` - The threshold, growth and convergence settings.
+///
+/// # Errors
+///
+/// * If `reference` is empty or Lanczos does not converge in a selected space, this function returns an Error.
+/// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+pub fn selected_ci(
+    hamiltonian: &Operator,
+    reference: &[Slater],
+    parameters: &SelectionParameters,
+) -> Result<SelectedCi, Error> {
+    if reference.is_empty() {
+        return Err(Error::Invalid("Selected CI needs at least one reference determinant!"));
+    }
+    let mut basis = Basis::new(reference.to_vec());
+    let mut start = vec![1.0; basis.dimension()];
+    let mut history = Vec::new();
+    let mut converged = false;
+    let (mut energy, mut vector) = (0.0, Vec::new());
+    for _ in 0..parameters.max_iterations {
+        let h = basis.try_sparse_matrix(hamiltonian)?;
+        (energy, vector) = ground_state(&h, &start, h.dimension(), SELECTION_TOLERANCE)?;
+        let previous = history.last().map(|(_, e)| *e);
+        history.push((basis.dimension(), energy));
+        if previous.is_some_and(|e: f64| (e - energy).abs() < parameters.tolerance) {
+            converged = true;
+            break;
+        }
+        let mut candidates: Vec<(Slater, f64)> = external_couplings(hamiltonian, &basis, &vector)
+            .into_iter()
+            .filter(|(_, (_, importance))| *importance > parameters.epsilon)
+            .map(|(a, (_, importance))| (a, importance))
+            .collect();
+        if candidates.is_empty() {
+            converged = true;
+            break;
+        }
+        candidates.sort_by(|x, y| y.1.total_cmp(&x.1).then(x.0.cmp(&y.0)));
+        candidates.truncate(parameters.max_added);
+        let mut states = basis.states().to_vec();
+        states.extend(candidates.into_iter().map(|(a, _)| a));
+        let next = Basis::new(states);
+        // The previous ground state, padded with zeros, starts the next Lanczos run.
+        start = vec![0.0; next.dimension()];
+        for (slater, c) in basis.states().iter().zip(&vector) {
+            start[next.index(slater).unwrap()] = *c;
+        }
+        basis = next;
+    }
+    Ok(SelectedCi {
+        basis,
+        energy,
+        vector,
+        history,
+        converged,
+    })
+}
+
+/// Returns the diagonal matrix element <a|H|a> of `hamiltonian`.
+fn diagonal(hamiltonian: &Operator, a: &Slater) -> f64 {
+    hamiltonian
+        .apply_slater(a)
+        .into_iter()
+        .filter(|(_, s)| s == a)
+        .map(|(amp, _)| amp)
+        .sum()
+}
+
+/// Returns the Epstein-Nesbet second order correction E2 = Σ_a |Σ_i H_ai c_i|² / (E - H_aa) to the variational energy E
+/// of the state with coefficients c_i in `basis`, summed over all external determinants a connected to it by `hamiltonian`.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian.
+/// * `basis` - The variational space, e.g. from `selected_ci` or `Basis::excitations`.
+/// * `vector` - The normalized variational ground state in `basis`.
+/// * `energy` - The variational energy E.
+pub fn en_pt2(hamiltonian: &Operator, basis: &Basis, vector: &[f64], energy: f64) -> f64 {
+    external_couplings(hamiltonian, basis, vector)
+        .into_iter()
+        .map(|(a, (coupling, _))| coupling * coupling / (energy - diagonal(hamiltonian, &a)))
+        .sum()
+}
+
+/// Returns a stochastic estimate of the Epstein-Nesbet correction of `en_pt2` and its standard error,
+/// for variational spaces whose external space is too large to enumerate at once.
+///
+/// Each batch draws `n_samples` variational determinants i with probability p_i ∝ |c_i|, and only the external determinants
+/// connected to the drawn ones enter. With w_i the number of times i is drawn and x_ai = H_ai c_i the batch estimate
+/// Σ_a [(Σ_i w_i x_ai / p_i)² - Σ_i w_i x_ai² / p_i²] / (N (N - 1) (E - H_aa)) is unbiased.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - The Hamiltonian.
+/// * `basis` - The variational space.
+/// * `vector` - The normalized variational ground state in `basis`.
+/// * `energy` - The variational energy E.
+/// * `n_samples` - The number N ≥ 2 of draws per batch.
+/// * `n_batches` - The number of independent batches, at least two for an error estimate.
+/// * `seed` - The seed of the random draws.
+///
+/// # Panics
+///
+/// * If `n_samples` is smaller than two.
+pub fn stochastic_en_pt2(
+    hamiltonian: &Operator,
+    basis: &Basis,
+    vector: &[f64],
+    energy: f64,
+    n_samples: usize,
+    n_batches: usize,
+    seed: u64,
+) -> (f64, f64) {
+    assert!(n_samples >= 2, "The stochastic estimate needs at least two samples per batch!");
+    let total: f64 = vector.iter().map(|c| c.abs()).sum();
+    let cumulative: Vec<f64> = vector
+        .iter()
+        .scan(0.0, |acc, c| {
+            *acc += c.abs() / total;
+            Some(*acc)
+        })
+        .collect();
+    let mut rng = Rng::new(seed);
+    let n = n_samples as f64;
+    let batches: Vec<f64> = (0..n_batches)
+        .map(|_| {
+            let mut counts: HashMap<usize, f64> = HashMap::new();
+            for _ in 0..n_samples {
+                let r = rng.uniform();
+                let i = cumulative.partition_point(|c| *c < r).min(vector.len() - 1);
+                *counts.entry(i).or_insert(0.0) += 1.0;
+            }
+            let mut sums: SlaterMap<(f64, f64)> = SlaterMap::default();
+            for (i, w) in counts {
+                let (c, p) = (vector[i], vector[i].abs() / total);
+                let mut column: SlaterMap<f64> = SlaterMap::default();
+                for (amp, a) in hamiltonian.apply_slater(&basis.states()[i]) {
+                    if basis.index(&a).is_none() {
+                        *column.entry(a).or_insert(0.0) += amp;
+                    }
+                }
+                for (a, h) in column {
+                    let x = h * c / p;
+                    let entry = sums.entry(a).or_insert((0.0, 0.0));
+                    entry.0 += w * x;
+                    entry.1 += w * x * x;
+                }
+            }
+            sums.into_iter()
+                .map(|(a, (linear, square))| (linear * linear - square) / (n * (n - 1.0) * (energy - diagonal(hamiltonian, &a))))
+                .sum()
+        })
+        .collect();
+    let m = batches.len() as f64;
+    let mean = batches.iter().sum::<f64>() / m;
+    let error = if batches.len() < 2 {
+        0.0
+    } else {
+        (batches.iter().map(|b| (b - mean).powi(2)).sum::<f64>() / (m - 1.0) / m).sqrt()
+    };
+    (mean, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hubbard, Graph};
+    use crate::operators::SpinConvention;
+    use crate::random::Rng;
+
+    #[test]
+    fn test_hubbard_ring() {
+        let conv = SpinConvention::Interleaved;
+        let ring = Graph::new(6, (0..6).map(|i| (i, (i + 1) % 6)).collect());
+        let h = hubbard(&ring, 1.0, 4.0, 0.0, conv);
+        let m = Basis::fixed_spin_numbers(conv, 6, 3, 3).sparse_matrix(&h);
+        let (exact, _) = ground_state(&m, &Rng::new(1).normal_vector(m.dimension()), m.dimension(), 1e-10).unwrap();
+        // The Néel determinant |↑↓↑↓↑↓>.
+        let neel = Slater::new((0..6).map(|i| 1 << (2 * i + i % 2)).sum());
+        let mut parameters = SelectionParameters {
+            epsilon: 0.0,
+            max_added: usize::MAX,
+            max_iterations: 20,
+            tolerance: 1e-10,
+        };
+        let full = selected_ci(&h, &[neel], &parameters).unwrap();
+        assert!(full.converged);
+        assert!((full.energy - exact).abs() < 1e-8);
+        // A finite threshold gives a smaller space with a variational energy, which decreases as the space grows.
+        parameters.epsilon = 0.02;
+        let selected = selected_ci(&h, &[neel], &parameters).unwrap();
+        assert!(selected.basis.dimension() < full.basis.dimension());
+        assert!(selected.energy > exact - 1e-10 && selected.energy - exact < 0.1);
+        assert!(selected.history.windows(2).all(|w| w[1].0 > w[0].0 && w[1].1 <= w[0].1 + 1e-10));
+    }
+
+    #[test]
+    fn test_en_pt2() {
+        let conv = SpinConvention::Interleaved;
+        let ring = Graph::new(6, (0..6).map(|i| (i, (i + 1) % 6)).collect());
+        let h = hubbard(&ring, 1.0, 4.0, 0.0, conv);
+        let m = Basis::fixed_spin_numbers(conv, 6, 3, 3).sparse_matrix(&h);
+        let (exact, _) = ground_state(&m, &Rng::new(1).normal_vector(m.dimension()), m.dimension(), 1e-10).unwrap();
+        let neel = Slater::new((0..6).map(|i| 1 << (2 * i + i % 2)).sum());
+        let parameters = SelectionParameters {
+            epsilon: 0.05,
+            max_added: usize::MAX,
+            max_iterations: 20,
+            tolerance: 1e-10,
+        };
+        let sci = selected_ci(&h, &[neel], &parameters).unwrap();
+        // The correction is negative and brings the energy closer to the exact one.
+        let e2 = en_pt2(&h, &sci.basis, &sci.vector, sci.energy);
+        assert!(e2 < 0.0);
+        assert!((sci.energy + e2 - exact).abs() < (sci.energy - exact).abs());
+        let (estimate, error) = stochastic_en_pt2(&h, &sci.basis, &sci.vector, sci.energy, 200, 40, 7);
+        assert!(error > 0.0 && (estimate - e2).abs() < 4.0 * error);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::linalg::{ComplexMatrix, Matrix};
+use crate::spectral::GreensFunctionMatrix;
+use crate::{Operator, AC};
+
+/// Returns the one-body part h_ij of `op` between `orbitals`, collected from all terms of the form c†_i c_j.
+/// Terms written as c_j c†_i contribute -h_ij; constants and terms with more than two operators are ignored.
+///
+/// # Arguments
+///
+/// * `op` - The operator to extract the one-body part from.
+/// * `orbitals` - The orbitals labelling the rows and columns of the matrix.
+pub fn one_body_matrix(op: &Operator, orbitals: &[u64]) -> Matrix {
+    let n = orbitals.len();
+    let position = |o: &u64| orbitals.iter().position(|x| x == o);
+    let mut h = Matrix::zeros(n, n);
+    for (amp, ac) in &op.terms {
+        let (i, j, sign) = match ac.as_slice() {
+            [AC::Create(i), AC::Annihilate(j)] => (i, j, 1.0),
+            [AC::Annihilate(j), AC::Create(i)] => (i, j, -1.0),
+            _ => continue,
+        };
+        if let (Some(i), Some(j)) = (position(i), position(j)) {
+            h[(i, j)] += sign * amp;
+        }
+    }
+    h
+}
+
+/// Returns the non-interacting Green's function G0(z) = (z - h)^{-1}, or None if z is an eigenvalue of h.
+///
+/// # Arguments
+///
+/// * `h` - The one-body Hamiltonian matrix.
+/// * `z` - The complex frequency, ω + iη on the real axis or iω_n on the Matsubara axis.
+pub fn noninteracting_greens_function(h: &Matrix, z: Complex) -> Option<ComplexMatrix> {
+    inverse_propagator(h, z).inverse()
+}
+
+/// Returns the self-energy Σ(z) = G0(z)^{-1} - G(z)^{-1} on the supplied frequencies,
+/// with the non-interacting reference built from the one-body part of `hamiltonian`.
+///
+/// # Arguments
+///
+/// * `g` - The interacting Green's function matrix.
+/// * `hamiltonian` - The Hamiltonian the interacting Green's function was computed from.
+/// * `frequencies` - The complex frequencies, ω + iη on the real axis or iω_n on the Matsubara axis.
+///
+/// # Errors
+///
+/// * If G(z) is singular at any of the frequencies, this function returns an Error.
+pub fn self_energy(
+    g: &GreensFunctionMatrix,
+    hamiltonian: &Operator,
+    frequencies: &[Complex],
+) -> Result<Vec<ComplexMatrix>, Error> {
+    let h = one_body_matrix(hamiltonian, g.orbitals());
+    frequencies
+        .iter()
+        .map(|z| {
+            let g_inv = g
+                .evaluate(*z)
+                .inverse()
+                .ok_or(Error::Invalid("Green's function is singular!"))?;
+            let mut sigma = inverse_propagator(&h, *z);
+            let n = sigma.dimension();
+            for i in 0..n {
+                for j in 0..n {
+                    sigma[(i, j)] -= g_inv[(i, j)];
+                }
+            }
+            Ok(sigma)
+        })
+        .collect()
+}
+
+/// Returns z - h.
+fn inverse_propagator(h: &Matrix, z: Complex) -> ComplexMatrix {
+    let mut m = ComplexMatrix::from_real(h);
+    for i in 0..m.dimension() {
+        for j in 0..m.dimension() {
+            m[(i, j)] = -m[(i, j)];
+        }
+        m[(i, i)] += z;
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::spectral::{greens_function_matrix, matsubara_frequencies, Statistics};
+    use crate::spectrum::Spectrum;
+
+    #[test]
+    fn test_hubbard_atom() {
+        let u = 2.0;
+        let h = Operator::new(vec![
+            (u, vec![AC::Create(0), AC::Annihilate(0), AC::Create(1), AC::Annihilate(1)]),
+            (-u / 2.0, vec![AC::Create(0), AC::Annihilate(0)]),
+            (-u / 2.0, vec![AC::Create(1), AC::Annihilate(1)]),
+        ]);
+        let spectrum = Spectrum::new(&h, Basis::fock_space(2));
+        let beta = 5.0;
+        let g = greens_function_matrix(&spectrum, &[0], beta);
+        let freqs: Vec<Complex> = matsubara_frequencies(beta, 6, Statistics::Fermion)
+            .into_iter()
+            .map(|w| Complex::new(0.0, w))
+            .collect();
+        let sigma = self_energy(&g, &h, &freqs).unwrap();
+        for (z, s) in freqs.iter().zip(&sigma) {
+            let exact = (u * u / 4.0) / *z + u / 2.0;
+            assert!((s[(0, 0)] - exact).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::f64::consts::PI;
+
+use crate::complex::Complex;
+use crate::linalg::{ComplexMatrix, Matrix};
+use crate::spectrum::Spectrum;
+use crate::{Operator, AC};
+
+/// Lehmann terms with weights below this magnitude are dropped.
+const WEIGHT_CUTOFF: f64 = 1e-14;
+
+/// This represents a broadening kernel, replacing the delta functions of a Lehmann sum by normalized peaks.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Kernel {
+    /// A Lorentzian with the given half width at half maximum.
+    Lorentzian(f64),
+    /// A Gaussian with the given standard deviation.
+    Gaussian(f64),
+}
+
+impl Kernel {
+    /// Returns the value of the broadened delta function at distance `x` from the peak.
+    pub fn evaluate(&self, x: f64) -> f64 {
+        match self {
+            Kernel::Lorentzian(eta) => eta / (PI * (x * x + eta * eta)),
+            Kernel::Gaussian(sigma) => {
+                (-x * x / (2.0 * sigma * sigma)).exp() / (sigma * (2.0 * PI).sqrt())
+            }
+        }
+    }
+}
+
+/// The exchange statistics of the operators entering a Green's function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Statistics {
+    /// Anticommutator Green's function, e.g. the single particle Green's function.
+    Fermion,
+    /// Commutator Green's function, e.g. density or spin response functions.
+    Boson,
+}
+
+/// Represents a Lehmann sum G(z) = Σ_p w_p / (z - ε_p) by its pole positions ε_p and weights w_p.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Poles {
+    /// The positions of the poles.
+    positions: Vec<f64>,
+    /// The weights of the poles.
+    weights: Vec<f64>,
+}
+
+impl Poles {
+    /// Returns a Lehmann sum with the supplied pole positions and weights.
+    ///
+    /// # Panics
+    ///
+    /// * If `positions` and `weights` differ in length.
+    pub fn new(positions: Vec<f64>, weights: Vec<f64>) -> Poles {
+        assert_eq!(positions.len(), weights.len(), "Every pole needs a weight!");
+        Poles { positions, weights }
+    }
+
+    /// Returns the positions of the poles.
+    pub fn positions(&self) -> &[f64] {
+        &self.positions
+    }
+
+    /// Returns the weights of the poles.
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Returns the sum of all pole weights.
+    pub fn total_weight(&self) -> f64 {
+        self.weights.iter().sum()
+    }
+
+    /// Returns G(z) = Σ_p w_p / (z - ε_p) at the complex frequency `z`.
+    pub fn evaluate(&self, z: Complex) -> Complex {
+        self.positions
+            .iter()
+            .zip(&self.weights)
+            .map(|(p, w)| *w / (z - *p))
+            .fold(Complex::default(), |acc, x| acc + x)
+    }
+
+    /// Returns the spectral function Σ_p w_p δ(ω - ε_p), broadened by `kernel`, on the frequencies `omegas`.
+    pub fn spectral_function(&self, omegas: &[f64], kernel: &Kernel) -> Vec<f64> {
+        omegas
+            .iter()
+            .map(|omega| {
+                self.positions
+                    .iter()
+                    .zip(&self.weights)
+                    .map(|(p, w)| w * kernel.evaluate(omega - p))
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Returns G(iω_n) on the supplied Matsubara frequencies ω_n.
+    /// Poles at zero energy are skipped at ω_n = 0, where their bosonic weight vanishes.
+    ///
+    /// # Arguments
+    ///
+    /// * `frequencies` - The Matsubara frequencies, e.g. from `matsubara_frequencies`.
+    pub fn matsubara(&self, frequencies: &[f64]) -> Vec<Complex> {
+        frequencies
+            .iter()
+            .map(|omega| {
+                let z = Complex::new(0.0, *omega);
+                self.positions
+                    .iter()
+                    .zip(&self.weights)
+                    .filter(|(p, _)| **p != 0.0 || *omega != 0.0)
+                    .map(|(p, w)| *w / (z - *p))
+                    .fold(Complex::default(), |acc, x| acc + x)
+            })
+            .collect()
+    }
+}
+
+/// Returns the first `n_frequencies` non-negative Matsubara frequencies at inverse temperature `beta`,
+/// ω_n = (2n + 1)π/β for fermions and ω_n = 2nπ/β for bosons.
+pub fn matsubara_frequencies(beta: f64, n_frequencies: usize, statistics: Statistics) -> Vec<f64> {
+    let offset = match statistics {
+        Statistics::Fermion => 1.0,
+        Statistics::Boson => 0.0,
+    };
+    (0..n_frequencies)
+        .map(|n| (2.0 * n as f64 + offset) * PI / beta)
+        .collect()
+}
+
+/// Returns the single particle Green's function G(iω_n) = <<c; c†>>(iω_n) on the first `n_frequencies`
+/// fermionic Matsubara frequencies at inverse temperature `beta`.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, in a basis containing all relevant particle numbers.
+/// * `annihilate` - The annihilation operator c.
+/// * `create` - The creation operator c†.
+/// * `beta` - The (finite) inverse temperature.
+/// * `n_frequencies` - The number of Matsubara frequencies.
+pub fn matsubara_greens_function(
+    spectrum: &Spectrum,
+    annihilate: &Operator,
+    create: &Operator,
+    beta: f64,
+    n_frequencies: usize,
+) -> Vec<Complex> {
+    greens_function(spectrum, annihilate, create, beta)
+        .matsubara(&matsubara_frequencies(beta, n_frequencies, Statistics::Fermion))
+}
+
+/// Returns the Lehmann representation of the Green's function <<a; b>>(z) at inverse temperature `beta`.
+///
+/// The poles are the excitation energies E_n - E_m with weights (w_m ± w_n) <m|a|n><n|b|m>,
+/// where w_m are Boltzmann weights and the sign is + for fermions and - for bosons.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, in a basis closed under `a` and `b`.
+/// * `a` - The operator on the left of the Green's function.
+/// * `b` - The operator on the right of the Green's function.
+/// * `beta` - The inverse temperature, `f64::INFINITY` for the ground state.
+/// * `statistics` - Whether the anticommutator or commutator Green's function is computed.
+pub fn lehmann(
+    spectrum: &Spectrum,
+    a: &Operator,
+    b: &Operator,
+    beta: f64,
+    statistics: Statistics,
+) -> Poles {
+    let weights = spectrum.boltzmann_weights(beta);
+    lehmann_sum(
+        spectrum.energies(),
+        &weights,
+        &spectrum.matrix_elements(a),
+        &spectrum.matrix_elements(b),
+        statistics,
+    )
+}
+
+/// Returns the Lehmann sum for operators with eigenstate matrix elements `am` and `bm`.
+fn lehmann_sum(
+    energies: &[f64],
+    weights: &[f64],
+    am: &Matrix,
+    bm: &Matrix,
+    statistics: Statistics,
+) -> Poles {
+    let sign = match statistics {
+        Statistics::Fermion => 1.0,
+        Statistics::Boson => -1.0,
+    };
+    let mut positions = Vec::new();
+    let mut residues = Vec::new();
+    for m in 0..energies.len() {
+        for n in 0..energies.len() {
+            let thermal = weights[m] + sign * weights[n];
+            if thermal.abs() < WEIGHT_CUTOFF {
+                continue;
+            }
+            let w = thermal * am[(m, n)] * bm[(n, m)];
+            if w.abs() > WEIGHT_CUTOFF {
+                positions.push(energies[n] - energies[m]);
+                residues.push(w);
+            }
+        }
+    }
+    Poles::new(positions, residues)
+}
+
+/// Returns the single particle Green's function G(z) = <<c; c†>>(z) at inverse temperature `beta`.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, in a basis containing all relevant particle numbers.
+/// * `annihilate` - The annihilation operator c.
+/// * `create` - The creation operator c†.
+/// * `beta` - The inverse temperature, `f64::INFINITY` for the ground state.
+pub fn greens_function(
+    spectrum: &Spectrum,
+    annihilate: &Operator,
+    create: &Operator,
+    beta: f64,
+) -> Poles {
+    lehmann(spectrum, annihilate, create, beta, Statistics::Fermion)
+}
+
+/// Represents the matrix G_ij(z) = <<c_i; c†_j>>(z) of single particle Green's functions between a set of orbitals.
+#[derive(Debug, Clone)]
+pub struct GreensFunctionMatrix {
+    /// The orbitals labelling the rows and columns.
+    orbitals: Vec<u64>,
+    /// The Lehmann sums of all elements, stored row by row.
+    elements: Vec<Poles>,
+}
+
+impl GreensFunctionMatrix {
+    /// Returns the orbitals labelling the rows and columns.
+    pub fn orbitals(&self) -> &[u64] {
+        &self.orbitals
+    }
+
+    /// Returns the Lehmann sum of G_ij, where i and j are positions in `orbitals`.
+    pub fn element(&self, i: usize, j: usize) -> &Poles {
+        &self.elements[i * self.orbitals.len() + j]
+    }
+
+    /// Returns the matrix G(z) at the complex frequency `z`.
+    pub fn evaluate(&self, z: Complex) -> ComplexMatrix {
+        let n = self.orbitals.len();
+        let mut g = ComplexMatrix::zeros(n);
+        for i in 0..n {
+            for j in 0..n {
+                g[(i, j)] = self.element(i, j).evaluate(z);
+            }
+        }
+        g
+    }
+}
+
+/// Returns the matrix of single particle Green's functions G_ij(z) = <<c_i; c†_j>>(z) between `orbitals`.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, in a basis containing all relevant particle numbers.
+/// * `orbitals` - The orbitals to include in the matrix.
+/// * `beta` - The inverse temperature, `f64::INFINITY` for the ground state.
+pub fn greens_function_matrix(
+    spectrum: &Spectrum,
+    orbitals: &[u64],
+    beta: f64,
+) -> GreensFunctionMatrix {
+    let weights = spectrum.boltzmann_weights(beta);
+    let annihilators: Vec<Matrix> = orbitals
+        .iter()
+        .map(|o| spectrum.matrix_elements(&Operator::new(vec![(1.0, vec![AC::Annihilate(*o)])])))
+        .collect();
+    let creators: Vec<Matrix> = annihilators.iter().map(|c| c.transpose()).collect();
+    let mut elements = Vec::with_capacity(orbitals.len() * orbitals.len());
+    for a in &annihilators {
+        for b in &creators {
+            elements.push(lehmann_sum(spectrum.energies(), &weights, a, b, Statistics::Fermion));
+        }
+    }
+    GreensFunctionMatrix {
+        orbitals: orbitals.to_vec(),
+        elements,
+    }
+}
+
+/// Returns the response function χ(z) = -<<a; b>>(z) at inverse temperature `beta`.
+/// With this sign convention the spectral function of χ(z) with b = a† is positive for positive frequencies.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian.
+/// * `a` - The measured operator.
+/// * `b` - The perturbing operator.
+/// * `beta` - The inverse temperature, `f64::INFINITY` for the ground state.
+pub fn response_function(spectrum: &Spectrum, a: &Operator, b: &Operator, beta: f64) -> Poles {
+    let poles = lehmann(spectrum, a, b, beta, Statistics::Boson);
+    Poles::new(
+        poles.positions,
+        poles.weights.into_iter().map(|w| -w).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::AC;
+
+    fn two_site() -> Spectrum {
+        let hop = Operator::new(vec![
+            (-1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (-1.0, vec![AC::Create(1), AC::Annihilate(0)]),
+        ]);
+        Spectrum::new(&hop, Basis::fock_space(2))
+    }
+
+    #[test]
+    fn test_greens_function() {
+        let spectrum = two_site();
+        let c = Operator::new(vec![(1.0, vec![AC::Annihilate(0)])]);
+        let cd = Operator::new(vec![(1.0, vec![AC::Create(0)])]);
+        let g = greens_function(&spectrum, &c, &cd, f64::INFINITY);
+        assert!((g.total_weight() - 1.0).abs() < 1e-12);
+        for (p, w) in g.positions().iter().zip(g.weights()) {
+            assert!((p.abs() - 1.0).abs() < 1e-12);
+            assert!((w - 0.5).abs() < 1e-12);
+        }
+        let g_finite = greens_function(&spectrum, &c, &cd, 2.0);
+        assert!((g_finite.total_weight() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_matsubara() {
+        let level = Operator::new(vec![(0.7, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let spectrum = Spectrum::new(&level, Basis::fock_space(1));
+        let c = Operator::new(vec![(1.0, vec![AC::Annihilate(0)])]);
+        let cd = Operator::new(vec![(1.0, vec![AC::Create(0)])]);
+        let beta = 10.0;
+        let g = matsubara_greens_function(&spectrum, &c, &cd, beta, 8);
+        for (n, gn) in g.iter().enumerate() {
+            let omega = (2 * n + 1) as f64 * PI / beta;
+            let exact = 1.0 / Complex::new(-0.7, omega);
+            assert!((*gn - exact).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_kernel_normalization() {
+        let omegas: Vec<f64> = (0..4001).map(|i| -20.0 + 0.01 * i as f64).collect();
+        let poles = Poles::new(vec![0.5], vec![1.0]);
+        for kernel in &[Kernel::Gaussian(0.1), Kernel::Lorentzian(0.05)] {
+            let a = poles.spectral_function(&omegas, kernel);
+            let integral: f64 = a.iter().sum::<f64>() * 0.01;
+            assert!((integral - 1.0).abs() < 1e-2);
+        }
+    }
+}
@@ -0,0 +1,217 @@
+use crate::basis::Basis;
+use crate::config;
+use crate::error::Error;
+use crate::linalg::Matrix;
+use crate::{Operator, State};
+
+/// Energies below this distance from each other are treated as degenerate.
+pub const DEGENERACY_TOLERANCE: f64 = 1e-10;
+
+/// Represents the complete set of eigenvalues and eigenvectors of a Hamiltonian in a basis, obtained by full diagonalization.
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    /// The basis the Hamiltonian was diagonalized in.
+    basis: Basis,
+    /// The eigenvalues in ascending order.
+    energies: Vec<f64>,
+    /// The eigenvectors, stored as columns in the same order as the eigenvalues.
+    vectors: Matrix,
+}
+
+impl Spectrum {
+    /// Returns the spectrum of `hamiltonian` in `basis`, obtained by full diagonalization of the dense Hamiltonian matrix.
+    ///
+    /// # Arguments
+    ///
+    /// * `hamiltonian` - The (Hermitian) Hamiltonian to diagonalize.
+    /// * `basis` - The basis to represent the Hamiltonian in.
+    ///
+    /// # Panics
+    ///
+    /// * If the dense matrix and its eigenvectors exceed the memory budget of the configuration, see `try_new`.
+    pub fn new(hamiltonian: &Operator, basis: Basis) -> Spectrum {
+        Spectrum::try_new(hamiltonian, basis).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Returns the spectrum of `hamiltonian` in `basis`, as `new`, or an error if the diagonalization does not fit in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `hamiltonian` - The (Hermitian) Hamiltonian to diagonalize.
+    /// * `basis` - The basis to represent the Hamiltonian in.
+    ///
+    /// # Errors
+    ///
+    /// * `Error::MemoryBudget` if the dense matrix and its eigenvectors exceed the memory budget of the configuration, see `config::check_memory`.
+    pub fn try_new(hamiltonian: &Operator, basis: Basis) -> Result<Spectrum, Error> {
+        let n = basis.dimension() as u64;
+        config::check_memory("spectrum::new", (2 * 8u64).saturating_mul(n).saturating_mul(n))?;
+        let (energies, vectors) = basis.try_matrix(hamiltonian)?.eigh();
+        Ok(Spectrum {
+            basis,
+            energies,
+            vectors,
+        })
+    }
+
+    /// Returns the basis the spectrum was computed in.
+    pub fn basis(&self) -> &Basis {
+        &self.basis
+    }
+
+    /// Returns the eigenvalues in ascending order.
+    pub fn energies(&self) -> &[f64] {
+        &self.energies
+    }
+
+    /// Returns the eigenvectors, stored as columns.
+    pub fn vectors(&self) -> &Matrix {
+        &self.vectors
+    }
+
+    /// Returns the lowest eigenvalue.
+    ///
+    /// # Panics
+    ///
+    /// * If the basis is empty.
+    pub fn ground_state_energy(&self) -> f64 {
+        self.energies[0]
+    }
+
+    /// Returns the number of (near) degenerate states at the bottom of the spectrum.
+    pub fn ground_state_degeneracy(&self) -> usize {
+        self.energies
+            .iter()
+            .take_while(|e| **e - self.energies[0] < DEGENERACY_TOLERANCE)
+            .count()
+    }
+
+    /// Returns eigenvector n as a vector in the basis.
+    pub fn eigenvector(&self, n: usize) -> Vec<f64> {
+        self.vectors.column(n)
+    }
+
+    /// Returns eigenvector n as a State.
+    pub fn eigenstate(&self, n: usize) -> State {
+        self.basis.state(&self.eigenvector(n))
+    }
+
+    /// Returns the matrix elements <m|op|n> of `op` between all eigenstates.
+    pub fn matrix_elements(&self, op: &Operator) -> Matrix {
+        let m = self.basis.matrix(op);
+        self.vectors.transpose().matmul(&m.matmul(&self.vectors))
+    }
+
+    /// Returns the total spin S of each eigenstate, obtained from the eigenvalues S(S + 1) of `s_squared`.
+    ///
+    /// Eigenstates within `tolerance` of each other form a degenerate multiplet, whose eigenvectors may mix different spins.
+    /// The spins of a multiplet are found by diagonalizing S² within it, and assigned to its states in ascending order.
+    ///
+    /// # Arguments
+    ///
+    /// * `s_squared` - The total spin operator S², e.g. from `operators::total_spin_squared`.
+    /// * `tolerance` - The largest energy difference between states of the same multiplet, e.g. `DEGENERACY_TOLERANCE`.
+    pub fn spin_labels(&self, s_squared: &Operator, tolerance: f64) -> Vec<f64> {
+        let m = self.matrix_elements(s_squared);
+        let mut labels = Vec::with_capacity(self.energies.len());
+        let mut start = 0;
+        while start < self.energies.len() {
+            let end = (start + 1..self.energies.len())
+                .find(|n| self.energies[*n] - self.energies[n - 1] >= tolerance)
+                .unwrap_or(self.energies.len());
+            let mut block = Matrix::zeros(end - start, end - start);
+            for i in start..end {
+                for j in start..end {
+                    block[(i - start, j - start)] = m[(i, j)];
+                }
+            }
+            // S(S + 1) = x gives S = (√(1 + 4x) - 1)/2, rounded to the nearest multiple of 1/2.
+            labels.extend(
+                block
+                    .eigh()
+                    .0
+                    .iter()
+                    .map(|x| ((1.0 + 4.0 * x.max(0.0)).sqrt() - 1.0).round() / 2.0),
+            );
+            start = end;
+        }
+        labels
+    }
+
+    /// Returns the normalized Boltzmann weights e^{-β(E_n - E_0)}/Z of all eigenstates.
+    /// For infinite `beta` the weight is shared equally among the degenerate ground states.
+    ///
+    /// # Arguments
+    ///
+    /// * `beta` - The inverse temperature.
+    pub fn boltzmann_weights(&self, beta: f64) -> Vec<f64> {
+        if self.energies.is_empty() {
+            return Vec::new();
+        }
+        let e0 = self.ground_state_energy();
+        let mut weights: Vec<f64> = if beta.is_infinite() {
+            self.energies
+                .iter()
+                .map(|e| if e - e0 < DEGENERACY_TOLERANCE { 1.0 } else { 0.0 })
+                .collect()
+        } else {
+            self.energies.iter().map(|e| (-beta * (e - e0)).exp()).collect()
+        };
+        let z: f64 = weights.iter().sum();
+        for w in weights.iter_mut() {
+            *w /= z;
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::operators::{total_spin_squared, Spin, SpinConvention};
+    use crate::AC;
+
+    #[test]
+    fn test_two_site_hopping() {
+        let hop = Operator::new(vec![
+            (-1.0, vec![AC::Create(0), AC::Annihilate(1)]),
+            (-1.0, vec![AC::Create(1), AC::Annihilate(0)]),
+        ]);
+        let spectrum = Spectrum::new(&hop, Basis::fock_space(2));
+        let expected = [-1.0, 0.0, 0.0, 1.0];
+        for (e, c) in spectrum.energies().iter().zip(&expected) {
+            assert!((e - c).abs() < 1e-12);
+        }
+        assert_eq!(spectrum.ground_state_degeneracy(), 1);
+        let w = spectrum.boltzmann_weights(f64::INFINITY);
+        assert_eq!(w, vec![1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_try_new() {
+        let hop = Operator::new(vec![(-1.0, vec![AC::Create(0), AC::Annihilate(1)]), (-1.0, vec![AC::Create(1), AC::Annihilate(0)])]);
+        // The 4 x 4 matrix and its eigenvectors take 256 bytes.
+        let over = config::scoped(config::Config::default().with_memory_budget(255), || Spectrum::try_new(&hop, Basis::fock_space(2)));
+        assert!(matches!(over, Err(Error::MemoryBudget { projected: 256, budget: 255 })));
+        assert_eq!(Spectrum::try_new(&hop, Basis::fock_space(2)).unwrap().energies(), Spectrum::new(&hop, Basis::fock_space(2)).energies());
+    }
+
+    #[test]
+    fn test_hubbard_dimer_spins() {
+        // The half filled Hubbard dimer has a singlet ground state, a triplet at zero energy and two excited singlets.
+        let conv = SpinConvention::Interleaved;
+        let mut terms = Vec::new();
+        for spin in [Spin::Up, Spin::Down] {
+            let (a, b) = (conv.orbital(0, spin), conv.orbital(1, spin));
+            terms.push((-1.0, vec![AC::Create(a), AC::Annihilate(b)]));
+            terms.push((-1.0, vec![AC::Create(b), AC::Annihilate(a)]));
+        }
+        for site in 0..2 {
+            let (up, down) = (conv.orbital(site, Spin::Up), conv.orbital(site, Spin::Down));
+            terms.push((4.0, vec![AC::Create(up), AC::Annihilate(up), AC::Create(down), AC::Annihilate(down)]));
+        }
+        let spectrum = Spectrum::new(&Operator::new(terms), Basis::fixed_particle_number(4, 2));
+        let labels = spectrum.spin_labels(&total_spin_squared(conv, 2), DEGENERACY_TOLERANCE);
+        assert_eq!(labels, vec![0.0, 1.0, 1.0, 1.0, 0.0, 0.0]);
+    }
+}
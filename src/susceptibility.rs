@@ -0,0 +1,187 @@
+use crate::ftlm::Sampling;
+use crate::linalg::{dot, LinearOperator};
+use crate::operators::{total_number, total_sz, Spin, SpinConvention};
+use crate::spectrum::Spectrum;
+use crate::thermo;
+use crate::typicality;
+
+/// Represents the static response of a lattice model on a grid of temperatures, all quantities per site and in units where k_B = 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SusceptibilitySweep {
+    /// The temperatures T, in ascending order.
+    pub temperatures: Vec<f64>,
+    /// The uniform spin susceptibility χ = β (<S_z²> - <S_z>²) / N.
+    pub spin_susceptibility: Vec<f64>,
+    /// The charge compressibility κ = β (<N²> - <N>²) / N.
+    pub compressibility: Vec<f64>,
+    /// The specific heat C = β² (<H²> - <H>²) / N.
+    pub specific_heat: Vec<f64>,
+}
+
+/// Wraps a Hermitian operator A to act as A².
+struct Squared<'a, A: ?Sized>(&'a A);
+
+impl<A: LinearOperator + ?Sized> LinearOperator for Squared<'_, A> {
+    fn dimension(&self) -> usize {
+        self.0.dimension()
+    }
+
+    fn apply(&self, x: &[f64], y: &mut [f64]) {
+        let mut ax = vec![0.0; x.len()];
+        self.0.apply(x, &mut ax);
+        self.0.apply(&ax, y);
+    }
+}
+
+/// Returns the temperatures sorted in ascending order.
+fn sorted(temperatures: &[f64]) -> Vec<f64> {
+    let mut temperatures = temperatures.to_vec();
+    temperatures.sort_by(|a, b| a.total_cmp(b));
+    temperatures
+}
+
+/// Returns the susceptibility sweep of a spinful lattice model from its full spectrum.
+/// The spectrum is reused for every temperature, so sweeps over fine grids only cost one diagonalization.
+/// The basis of the spectrum must allow particle number fluctuations, e.g. a grand canonical Fock space, for the compressibility to be meaningful.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, including the chemical potential.
+/// * `convention` - The mapping of (site, spin) onto single particle states.
+/// * `n_sites` - The number of lattice sites.
+/// * `temperatures` - The finite temperatures.
+pub fn from_spectrum(
+    spectrum: &Spectrum,
+    convention: SpinConvention,
+    n_sites: u64,
+    temperatures: &[f64],
+) -> SusceptibilitySweep {
+    let temperatures = sorted(temperatures);
+    let orbitals: Vec<u64> = (0..n_sites)
+        .flat_map(|s| [convention.orbital(s, Spin::Up), convention.orbital(s, Spin::Down)])
+        .collect();
+    let n = spectrum.basis().matrix(&total_number(&orbitals));
+    let sz = spectrum.basis().matrix(&total_sz(convention, n_sites));
+    // <n|O|n> and <n|O²|n> = ||O|n>||² for O = N, S_z.
+    let mut diagonals = vec![Vec::new(); 4];
+    for i in 0..spectrum.energies().len() {
+        let v = spectrum.eigenvector(i);
+        for (k, m) in [&n, &sz].iter().enumerate() {
+            let mv = m.matvec(&v);
+            diagonals[2 * k].push(dot(&v, &mv));
+            diagonals[2 * k + 1].push(dot(&mv, &mv));
+        }
+    }
+    let betas: Vec<f64> = temperatures.iter().map(|t| 1.0 / t).collect();
+    let thermo = thermo::thermal_averages(spectrum.energies(), &diagonals, &betas);
+    let ev = &thermo.expectation_values;
+    let sites = n_sites as f64;
+    SusceptibilitySweep {
+        spin_susceptibility: (0..betas.len())
+            .map(|i| betas[i] * (ev[3][i] - ev[2][i] * ev[2][i]) / sites)
+            .collect(),
+        compressibility: (0..betas.len())
+            .map(|i| betas[i] * (ev[1][i] - ev[0][i] * ev[0][i]) / sites)
+            .collect(),
+        specific_heat: thermo.specific_heat.iter().map(|c| c / sites).collect(),
+        temperatures,
+    }
+}
+
+/// Returns the susceptibility sweep of a lattice model estimated from canonical typicality states.
+/// A single set of random vectors is propagated through all temperatures and used for every observable.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian, including the chemical potential.
+/// * `number` - The total particle number operator, represented in the same space as `h`.
+/// * `sz` - The total spin projection operator, represented in the same space as `h`.
+/// * `n_sites` - The number of lattice sites.
+/// * `temperatures` - The finite temperatures.
+/// * `sampling` - The number of random vectors and the Krylov dimension of each imaginary time step.
+/// * `max_step` - The largest imaginary time step taken by a single Krylov propagation.
+pub fn from_typicality<H: LinearOperator + ?Sized>(
+    h: &H,
+    number: &dyn LinearOperator,
+    sz: &dyn LinearOperator,
+    n_sites: u64,
+    temperatures: &[f64],
+    sampling: Sampling,
+    max_step: f64,
+) -> SusceptibilitySweep {
+    let temperatures = sorted(temperatures);
+    // Descending temperatures are ascending inverse temperatures, as returned by the typicality estimate.
+    let betas: Vec<f64> = temperatures.iter().rev().map(|t| 1.0 / t).collect();
+    let (number2, sz2, h2) = (Squared(number), Squared(sz), Squared(h));
+    let estimate = typicality::thermal_averages(h, &[number, &number2, sz, &sz2, &h2], &betas, sampling, max_step);
+    let ev = &estimate.expectation_values;
+    let sites = n_sites as f64;
+    let fluctuation = |b: usize, i: usize| betas[i] * (ev[b + 1][i] - ev[b][i] * ev[b][i]) / sites;
+    let energy_fluctuation = |i: usize| {
+        betas[i] * betas[i] * (ev[4][i] - estimate.energy[i] * estimate.energy[i]) / sites
+    };
+    SusceptibilitySweep {
+        spin_susceptibility: (0..betas.len()).rev().map(|i| fluctuation(2, i)).collect(),
+        compressibility: (0..betas.len()).rev().map(|i| fluctuation(0, i)).collect(),
+        specific_heat: (0..betas.len()).rev().map(energy_fluctuation).collect(),
+        temperatures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::table::ResultTable;
+    use crate::{Operator, AC};
+
+    fn hubbard_atom(u: f64) -> Operator {
+        Operator::new(vec![
+            (u, vec![AC::Create(0), AC::Annihilate(0), AC::Create(1), AC::Annihilate(1)]),
+            (-u / 2.0, vec![AC::Create(0), AC::Annihilate(0)]),
+            (-u / 2.0, vec![AC::Create(1), AC::Annihilate(1)]),
+        ])
+    }
+
+    #[test]
+    fn test_hubbard_atom() {
+        let u = 2.0;
+        let spectrum = Spectrum::new(&hubbard_atom(u), Basis::fock_space(2));
+        let sweep = from_spectrum(&spectrum, SpinConvention::Interleaved, 1, &[2.0, 0.5]);
+        assert_eq!(sweep.temperatures, vec![0.5, 2.0]);
+        for (i, t) in sweep.temperatures.iter().enumerate() {
+            let beta = 1.0 / t;
+            let x = (beta * u / 2.0).exp();
+            let z = 2.0 + 2.0 * x;
+            assert!((sweep.spin_susceptibility[i] - beta * x / (2.0 * z)).abs() < 1e-12);
+            assert!((sweep.compressibility[i] - 2.0 * beta / z).abs() < 1e-12);
+        }
+        let table = ResultTable::from(&sweep);
+        assert_eq!(table.rows().len(), 2);
+        assert_eq!(table.column("compressibility"), Some(sweep.compressibility.clone()));
+    }
+
+    #[test]
+    fn test_typicality_against_spectrum() {
+        let h = hubbard_atom(2.0);
+        let basis = Basis::fock_space(2);
+        let number = basis.sparse_matrix(&total_number(&[0, 1]));
+        let sz = basis.sparse_matrix(&total_sz(SpinConvention::Interleaved, 1));
+        let temperatures = [0.5, 1.0, 4.0];
+        let typical = from_typicality(
+            &basis.sparse_matrix(&h),
+            &number,
+            &sz,
+            1,
+            &temperatures,
+            Sampling::new(200, 4, 3),
+            0.5,
+        );
+        let exact = from_spectrum(&Spectrum::new(&h, basis), SpinConvention::Interleaved, 1, &temperatures);
+        for i in 0..temperatures.len() {
+            assert!((typical.spin_susceptibility[i] - exact.spin_susceptibility[i]).abs() < 0.1);
+            assert!((typical.compressibility[i] - exact.compressibility[i]).abs() < 0.1);
+            assert!((typical.specific_heat[i] - exact.specific_heat[i]).abs() < 0.1);
+        }
+    }
+}
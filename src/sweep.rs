@@ -0,0 +1,457 @@
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::basis::Basis;
+use crate::config;
+use crate::error::Error;
+use crate::lanczos::ground_state;
+use crate::linalg::{dot, LinearOperator};
+use crate::log;
+use crate::random::Rng;
+use crate::Operator;
+
+/// The required residual of the Lanczos ground states of `ground_state_sweep`.
+const SWEEP_TOLERANCE: f64 = 1e-10;
+
+/// Returns the number of threads to use for a request of `n_threads`, where 0 selects the configured number, see `config::Config`.
+pub(crate) fn thread_count(n_threads: usize) -> usize {
+    config::current().threads(n_threads)
+}
+
+/// Returns f(0), ..., f(n - 1), evaluated on `n_threads` threads that each take the next unevaluated index when done.
+/// A single thread evaluates on the calling thread without spawning, which also serves targets without threads such as wasm32.
+pub(crate) fn parallel_map<T, F>(n: usize, n_threads: usize, f: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    if thread_count(n_threads).min(n) <= 1 {
+        return (0..n).map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new((0..n).map(|_| None).collect());
+    // The workers run with the configuration of the caller, which may be scoped to it, e.g. a memory budget of a run.
+    let cfg = config::current();
+    thread::scope(|scope| {
+        for _ in 0..thread_count(n_threads).min(n) {
+            scope.spawn(|| {
+                config::scoped(cfg.clone(), || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    if index >= n {
+                        break;
+                    }
+                    let res = f(index);
+                    results.lock().unwrap()[index] = Some(res);
+                })
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|r| r.unwrap()).collect()
+}
+
+/// Returns all combinations of the values on each axis, with the first axis running fastest.
+///
+/// # Arguments
+///
+/// * `axes` - The values of each parameter.
+pub fn grid(axes: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let total: usize = axes.iter().map(|a| a.len()).product();
+    (0..total)
+        .map(|index| {
+            let mut rest = index;
+            axes.iter()
+                .map(|a| {
+                    let v = a[rest % a.len()];
+                    rest /= a.len();
+                    v
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Represents the results of a parameter sweep as a table with one row per parameter point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SweepTable {
+    /// The names of the parameters.
+    pub parameter_names: Vec<String>,
+    /// The names of the results.
+    pub result_names: Vec<String>,
+    /// The parameter values of each row.
+    pub parameters: Vec<Vec<f64>>,
+    /// The results of each row.
+    pub results: Vec<Vec<f64>>,
+}
+
+impl SweepTable {
+    /// Returns the results in the column `name`, if there is one.
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        if let Some(k) = self.parameter_names.iter().position(|n| n == name) {
+            return Some(self.parameters.iter().map(|p| p[k]).collect());
+        }
+        let k = self.result_names.iter().position(|n| n == name)?;
+        Some(self.results.iter().map(|r| r[k]).collect())
+    }
+}
+
+/// Returns the table of `calculation` evaluated at every parameter point, with the points distributed over `n_threads` threads.
+///
+/// # Arguments
+///
+/// * `parameter_names` - The names of the parameters.
+/// * `points` - The parameter values of each point, e.g. from `grid`.
+/// * `result_names` - The names of the results of `calculation`.
+/// * `n_threads` - The number of threads, where 0 selects the configured number, see `config::Config`.
+/// * `calculation` - Returns the results at a parameter point, typically by building a model, solving it and measuring observables.
+///   Its error may be of any type the errors of the sweep convert into, e.g. `Error`.
+///
+/// # Errors
+///
+/// * If the calculation fails at some point, or returns the wrong number of results, this function returns the first Error.
+pub fn sweep<F, E>(
+    parameter_names: &[&str],
+    points: &[Vec<f64>],
+    result_names: &[&str],
+    n_threads: usize,
+    calculation: F,
+) -> Result<SweepTable, E>
+where
+    F: Fn(&[f64]) -> Result<Vec<f64>, E> + Sync,
+    E: From<Error> + Send,
+{
+    let finished = AtomicUsize::new(0);
+    let results = parallel_map(points.len(), n_threads, |k| {
+        let res = calculation(&points[k]);
+        log::progress("sweep", finished.fetch_add(1, Ordering::Relaxed) + 1, points.len());
+        res
+    })
+        .into_iter()
+        .map(|r| {
+            r.and_then(|v| {
+                if v.len() == result_names.len() {
+                    Ok(v)
+                } else {
+                    Err(E::from(Error::DimensionMismatch))
+                }
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SweepTable {
+        parameter_names: parameter_names.iter().map(|s| s.to_string()).collect(),
+        result_names: result_names.iter().map(|s| s.to_string()).collect(),
+        parameters: points.to_vec(),
+        results,
+    })
+}
+
+/// Returns the parameters and results of the complete rows of a checkpoint file written by `checkpointed_sweep`,
+/// keyed by the printed parameter values. A row cut short by an interruption is ignored.
+fn read_checkpoint(path: &Path, header: &str, n_parameters: usize, n_columns: usize) -> Result<HashMap<String, Vec<f64>>, Error> {
+    let mut done = HashMap::new();
+    let contents = match fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(done),
+        Err(e) => return Err(Error::IoError(e)),
+    };
+    let mut lines = contents.lines();
+    if lines.next().is_some_and(|h| h != header) {
+        return Err(Error::Invalid("The checkpoint file belongs to a different sweep!"));
+    }
+    for line in lines {
+        let fields: Vec<&str> = line.split(',').collect();
+        if fields.len() != n_columns {
+            continue;
+        }
+        if let Ok(results) = fields[n_parameters..].iter().map(|f| f.parse()).collect::<Result<Vec<f64>, _>>() {
+            done.insert(fields[..n_parameters].join(","), results);
+        }
+    }
+    Ok(done)
+}
+
+/// Returns the table of `calculation` evaluated at every parameter point like `sweep`, recording every finished point in a checkpoint file.
+///
+/// Points already recorded in the checkpoint file, e.g. by a run that was interrupted, are not recalculated, so rerunning the same
+/// sweep resumes it. The file is comma separated, a header line with the column names followed by one row per point in the order the points finished.
+///
+/// # Arguments
+///
+/// * `parameter_names` - The names of the parameters.
+/// * `points` - The parameter values of each point, e.g. from `grid`.
+/// * `result_names` - The names of the results of `calculation`.
+/// * `n_threads` - The number of threads, where 0 selects the configured number, see `config::Config`.
+/// * `checkpoint` - The path of the checkpoint file, which is created if it does not exist.
+/// * `calculation` - Returns the results at a parameter point.
+///
+/// # Errors
+///
+/// * If the checkpoint file cannot be read or written, or has a different header, this function returns an Error.
+/// * If the calculation fails at some point, this function returns the first Error, after recording all points that succeeded.
+pub fn checkpointed_sweep<F, E>(
+    parameter_names: &[&str],
+    points: &[Vec<f64>],
+    result_names: &[&str],
+    n_threads: usize,
+    checkpoint: &Path,
+    calculation: F,
+) -> Result<SweepTable, E>
+where
+    F: Fn(&[f64]) -> Result<Vec<f64>, E> + Sync,
+    E: From<Error> + Send,
+{
+    let header = parameter_names.iter().chain(result_names).copied().collect::<Vec<_>>().join(",");
+    let key = |p: &[f64]| p.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",");
+    let done = read_checkpoint(checkpoint, &header, parameter_names.len(), parameter_names.len() + result_names.len())?;
+    let todo: Vec<usize> = (0..points.len()).filter(|k| !done.contains_key(&key(&points[*k]))).collect();
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(checkpoint)
+        .map_err(Error::from)?;
+    // A row cut short by an interruption has no newline, which must not join it to the first new row.
+    let mut last = [b'\n'];
+    if file.seek(SeekFrom::End(-1)).is_ok() {
+        file.read_exact(&mut last).map_err(Error::from)?;
+    }
+    let start = match (file.metadata().map_err(Error::from)?.len(), last[0]) {
+        (0, _) => format!("{}\n", header),
+        (_, b'\n') => String::new(),
+        _ => "\n".to_string(),
+    };
+    file.write_all(start.as_bytes()).map_err(Error::from)?;
+    let file = Mutex::new(file);
+    let finished = AtomicUsize::new(points.len() - todo.len());
+    let computed = parallel_map(todo.len(), n_threads, |k| {
+        let p = &points[todo[k]];
+        let res = calculation(p);
+        log::progress("sweep", finished.fetch_add(1, Ordering::Relaxed) + 1, points.len());
+        let res = res?;
+        if res.len() != result_names.len() {
+            return Err(E::from(Error::DimensionMismatch));
+        }
+        let row: Vec<String> = p.iter().chain(&res).map(|x| x.to_string()).collect();
+        // One write per row, so that an interruption cuts off at most the end of a row.
+        let mut f = file.lock().unwrap();
+        f.write_all(format!("{}\n", row.join(",")).as_bytes())
+            .and_then(|_| f.flush())
+            .map_err(Error::from)?;
+        Ok(res)
+    });
+    let mut computed = todo.into_iter().zip(computed).collect::<HashMap<_, _>>();
+    let results = (0..points.len())
+        .map(|k| match computed.remove(&k) {
+            Some(r) => r,
+            None => Ok(done[&key(&points[k])].clone()),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(SweepTable {
+        parameter_names: parameter_names.iter().map(|s| s.to_string()).collect(),
+        result_names: result_names.iter().map(|s| s.to_string()).collect(),
+        parameters: points.to_vec(),
+        results,
+    })
+}
+
+/// Returns the table of `calculation` on the grid of all (x, y) combinations of two parameters, with x running fastest,
+/// checkpointed and resumable like `checkpointed_sweep`.
+///
+/// # Arguments
+///
+/// * `x` - The name and values of the first parameter, e.g. the interaction.
+/// * `y` - The name and values of the second parameter, e.g. the filling.
+/// * `result_names` - The names of the results of `calculation`.
+/// * `n_threads` - The number of threads, where 0 selects the configured number, see `config::Config`.
+/// * `checkpoint` - The path of the checkpoint file.
+/// * `calculation` - Returns the results at a grid point (x, y).
+///
+/// # Errors
+///
+/// * See `checkpointed_sweep`.
+pub fn phase_diagram<F, E>(
+    x: (&str, Vec<f64>),
+    y: (&str, Vec<f64>),
+    result_names: &[&str],
+    n_threads: usize,
+    checkpoint: &Path,
+    calculation: F,
+) -> Result<SweepTable, E>
+where
+    F: Fn(&[f64]) -> Result<Vec<f64>, E> + Sync,
+    E: From<Error> + Send,
+{
+    let points = grid(&[x.1, y.1]);
+    checkpointed_sweep(&[x.0, y.0], &points, result_names, n_threads, checkpoint, calculation)
+}
+
+/// Returns the ground state energy and the ground state expectation values of `observables` of the Hamiltonian `model(p)`
+/// at every parameter point p, found with Lanczos in `basis`.
+///
+/// # Arguments
+///
+/// * `parameter_names` - The names of the parameters.
+/// * `points` - The parameter values of each point.
+/// * `model` - Returns the Hamiltonian at a parameter point, e.g. `|p| models::hubbard(&lattice, 1.0, p[0], 0.0, convention)`.
+/// * `basis` - The basis of the ground state.
+/// * `observables` - The names and operators of the observables.
+/// * `n_threads` - The number of threads, where 0 selects the configured number, see `config::Config`.
+///
+/// # Errors
+///
+/// * If a Lanczos ground state does not converge, this function returns an Error.
+/// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+pub fn ground_state_sweep<M>(
+    parameter_names: &[&str],
+    points: &[Vec<f64>],
+    model: M,
+    basis: &Basis,
+    observables: &[(&str, Operator)],
+    n_threads: usize,
+) -> Result<SweepTable, Error>
+where
+    M: Fn(&[f64]) -> Operator + Sync,
+{
+    let matrices = observables.iter().map(|(_, o)| basis.try_sparse_matrix(o)).collect::<Result<Vec<_>, _>>()?;
+    let mut names = vec!["energy"];
+    names.extend(observables.iter().map(|(n, _)| *n));
+    sweep(parameter_names, points, &names, n_threads, |p| {
+        let h = basis.try_sparse_matrix(&model(p))?;
+        let start = Rng::new(0x5eeb).normal_vector(h.dimension());
+        let (e, x) = ground_state(&h, &start, h.dimension(), SWEEP_TOLERANCE)?;
+        let norm = dot(&x, &x);
+        let mut res = vec![e];
+        let mut y = vec![0.0; x.len()];
+        for m in &matrices {
+            m.apply(&x, &mut y);
+            res.push(dot(&x, &y) / norm);
+        }
+        Ok(res)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{hubbard, Graph};
+    use crate::operators::{Spin, SpinConvention};
+    use crate::table::ResultTable;
+
+    #[test]
+    fn test_hubbard_dimer_sweep() {
+        // The double occupancy of the half filled dimer decreases with U, E_0 = (U - √(U² + 16))/2.
+        let conv = SpinConvention::Interleaved;
+        let dimer = Graph::new(2, vec![(0, 1)]);
+        let (up, down) = (conv.orbital(0, Spin::Up), conv.orbital(0, Spin::Down));
+        let double = Operator::new(vec![(
+            1.0,
+            vec![crate::AC::Create(up), crate::AC::Annihilate(up), crate::AC::Create(down), crate::AC::Annihilate(down)],
+        )]);
+        let points = grid(&[vec![0.0, 2.0, 8.0]]);
+        let table = ground_state_sweep(
+            &["U"],
+            &points,
+            |p| hubbard(&dimer, 1.0, p[0], 0.0, conv),
+            &Basis::fixed_particle_number(4, 2),
+            &[("double_occupancy", double)],
+            2,
+        )
+        .unwrap();
+        let energies = table.column("energy").unwrap();
+        for (u, e) in table.column("U").unwrap().iter().zip(energies) {
+            assert!((e - (u - (u * u + 16.0).sqrt()) / 2.0).abs() < 1e-8);
+        }
+        let d = table.column("double_occupancy").unwrap();
+        assert!((d[0] - 0.25).abs() < 1e-8 && d[1] > d[2]);
+        let mut csv = Vec::new();
+        ResultTable::from(&table).write_csv(&mut csv).unwrap();
+        assert!(String::from_utf8(csv).unwrap().contains("\nU,energy,double_occupancy\n"));
+    }
+
+    #[test]
+    fn test_scoped_budget() {
+        // The workers see the budget scoped to the caller, so the matrices they assemble are refused.
+        let dimer = Graph::new(2, vec![(0, 1)]);
+        let points = grid(&[vec![0.0, 2.0, 8.0]]);
+        let model = |p: &[f64]| hubbard(&dimer, 1.0, p[0], 0.0, SpinConvention::Interleaved);
+        let basis = Basis::fixed_particle_number(4, 2);
+        let sweep = || ground_state_sweep(&["U"], &points, model, &basis, &[], 2);
+        assert!(matches!(config::scoped(config::Config::default().with_memory_budget(1), sweep), Err(Error::MemoryBudget { budget: 1, .. })));
+        assert!(sweep().is_ok());
+        let budgets = config::scoped(config::Config::default().with_memory_budget(7), || parallel_map(4, 2, |_| config::current().memory_budget()));
+        assert_eq!(budgets, vec![Some(7); 4]);
+    }
+
+    #[test]
+    fn test_single_thread() {
+        // One thread, requested or configured, evaluates on the calling thread, as targets without threads need.
+        let caller = thread::current().id();
+        assert!(parallel_map(5, 1, |_| thread::current().id()).iter().all(|id| *id == caller));
+        let ids = config::scoped(config::Config::default().with_threads(1), || parallel_map(5, 0, |_| thread::current().id()));
+        assert!(ids.iter().all(|id| *id == caller));
+        assert_eq!(parallel_map(5, 1, |k| k * k), vec![0, 1, 4, 9, 16]);
+    }
+
+    #[test]
+    fn test_grid() {
+        let g = grid(&[vec![1.0, 2.0], vec![3.0, 4.0, 5.0]]);
+        assert_eq!(g.len(), 6);
+        assert_eq!(g[1], vec![2.0, 3.0]);
+        assert_eq!(g[2], vec![1.0, 4.0]);
+    }
+
+    #[test]
+    fn test_resume_phase_diagram() {
+        let path = std::env::temp_dir().join(format!("rust_ed_checkpoint_{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let axes = || (("x", vec![0.0, 1.0, 2.0]), ("y", vec![0.5, 1.5]));
+        // The first run is cut short at x = 2.
+        let (x, y) = axes();
+        let interrupted = phase_diagram(x, y, &["product"], 2, &path, |p| {
+            if p[0] == 2.0 {
+                Err(Error::Invalid("interrupted"))
+            } else {
+                Ok(vec![p[0] * p[1]])
+            }
+        });
+        assert!(matches!(interrupted, Err(Error::Invalid("interrupted"))));
+        // The second run only calculates the two missing points.
+        let count = AtomicUsize::new(0);
+        let (x, y) = axes();
+        let table = phase_diagram(x, y, &["product"], 2, &path, |p| {
+            count.fetch_add(1, Ordering::Relaxed);
+            Ok::<_, Error>(vec![p[0] * p[1]])
+        })
+        .unwrap();
+        assert_eq!(count.into_inner(), 2);
+        assert_eq!(table.column("product").unwrap(), vec![0.0, 0.5, 1.0, 0.0, 1.5, 3.0]);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 7);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_resume_truncated_checkpoint() {
+        let path = std::env::temp_dir().join(format!("rust_ed_truncated_{}.csv", std::process::id()));
+        // The run was interrupted while writing the row of x = 1, which has no newline.
+        fs::write(&path, "x,product\n0,0\n1,").unwrap();
+        let run = |count: &AtomicUsize| {
+            checkpointed_sweep(&["x"], &grid(&[vec![0.0, 1.0, 2.0]]), &["product"], 1, &path, |p| {
+                count.fetch_add(1, Ordering::Relaxed);
+                Ok::<_, Error>(vec![2.0 * p[0]])
+            })
+            .unwrap()
+        };
+        let count = AtomicUsize::new(0);
+        assert_eq!(run(&count).column("product").unwrap(), vec![0.0, 2.0, 4.0]);
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+        // The new rows start on their own lines, so a third run finds every point.
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().nth(3), Some("1,2"));
+        assert_eq!(run(&count).column("product").unwrap(), vec![0.0, 2.0, 4.0]);
+        assert_eq!(count.into_inner(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,327 @@
+use std::io::{self, Write};
+
+use crate::dos::{DosTable, Histogram};
+use crate::dynamics::LoschmidtEcho;
+use crate::entanglement::EntanglementSpectrum;
+use crate::error::Error;
+use crate::momentum::MomentumTable;
+use crate::observables::SpinCorrelationTable;
+use crate::susceptibility::SusceptibilitySweep;
+use crate::sweep::SweepTable;
+use crate::thermo::Thermodynamics;
+
+/// Represents a table of results with labeled columns and metadata, the common output of sweeps, dynamics and thermodynamics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultTable {
+    /// The metadata as (key, value) pairs, e.g. the model and its fixed parameters.
+    metadata: Vec<(String, String)>,
+    /// The names of the columns.
+    columns: Vec<String>,
+    /// The values of each row, one per column.
+    rows: Vec<Vec<f64>>,
+}
+
+impl ResultTable {
+    /// Returns an empty table with the columns `columns`.
+    pub fn new(columns: &[&str]) -> ResultTable {
+        ResultTable {
+            metadata: Vec::new(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Sets the metadata `key` to `value`, replacing an earlier value.
+    /// Line breaks in `value` are replaced by spaces, so that every entry fits on one header line.
+    pub fn set_metadata(&mut self, key: &str, value: &str) {
+        let value = value.replace(['\n', '\r'], " ");
+        match self.metadata.iter_mut().find(|(k, _)| k == key) {
+            Some(entry) => entry.1 = value,
+            None => self.metadata.push((key.to_string(), value)),
+        }
+    }
+
+    /// Returns the metadata `key`, if it is set.
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        self.metadata.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Returns the names of the columns.
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Returns the rows.
+    pub fn rows(&self) -> &[Vec<f64>] {
+        &self.rows
+    }
+
+    /// Appends `row` to the table.
+    ///
+    /// # Errors
+    ///
+    /// * If `row` does not have one value per column, this function returns an Error.
+    pub fn push(&mut self, row: Vec<f64>) -> Result<(), Error> {
+        if row.len() != self.columns.len() {
+            return Err(Error::DimensionMismatch);
+        }
+        self.rows.push(row);
+        Ok(())
+    }
+
+    /// Returns the values in the column `name`, if there is one.
+    pub fn column(&self, name: &str) -> Option<Vec<f64>> {
+        let k = self.columns.iter().position(|c| c == name)?;
+        Some(self.rows.iter().map(|r| r[k]).collect())
+    }
+
+    /// Writes the table as comma separated values. The metadata comes first as "# key: value" lines, then a header line with the column names.
+    /// Keys and names that would not read back unchanged, e.g. with a separator or surrounding spaces, are quoted, doubling inner quotes.
+    pub fn write_csv<W: Write>(&self, mut w: W) -> io::Result<()> {
+        for (key, value) in &self.metadata {
+            writeln!(w, "# {}: {}", quote(key, ':'), value)?;
+        }
+        let names: Vec<String> = self.columns.iter().map(|c| quote(c, ',')).collect();
+        writeln!(w, "{}", names.join(","))?;
+        for row in &self.rows {
+            let values: Vec<String> = row.iter().map(|x| format!("{:?}", x)).collect();
+            writeln!(w, "{}", values.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the table written by `ResultTable::write_csv` as `text`.
+    ///
+    /// # Errors
+    ///
+    /// * If the header is missing or a row is not a list of numbers matching it, this function returns an Error.
+    pub fn from_csv(text: &str) -> Result<ResultTable, Error> {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let mut metadata = Vec::new();
+        let header = loop {
+            let line = lines.next().ok_or(Error::Parse("The table has no header!"))?;
+            match line.strip_prefix('#') {
+                Some(entry) => {
+                    let entry = entry.strip_prefix(' ').unwrap_or(entry);
+                    let (key, value) = next_field(entry, ':')?;
+                    let value = value.ok_or(Error::Parse("Invalid metadata line!"))?;
+                    metadata.push((key, value.strip_prefix(' ').unwrap_or(value).to_string()));
+                }
+                None => break line,
+            }
+        };
+        let mut columns = Vec::new();
+        let mut rest = Some(header);
+        while let Some(text) = rest {
+            let (name, next) = next_field(text, ',')?;
+            columns.push(name);
+            rest = next;
+        }
+        let mut table = ResultTable::new(&columns.iter().map(|c| c.as_str()).collect::<Vec<_>>());
+        table.metadata = metadata;
+        for line in lines {
+            let row = line.split(',').map(|x| x.trim().parse()).collect::<Result<_, _>>().map_err(|_| Error::Parse("Invalid table value!"))?;
+            table.push(row)?;
+        }
+        Ok(table)
+    }
+}
+
+/// Returns `field` quoted if it would not read back unchanged as a field ended by `separator`: if it contains the separator or a quote,
+/// has surrounding spaces, is empty or starts like a metadata line.
+fn quote(field: &str, separator: char) -> String {
+    if field.is_empty() || field.contains([separator, '"']) || field.trim() != field || field.starts_with('#') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Returns the first field of `text`, ended by `separator`, and the text after the separator, if there is one.
+/// A quoted field is read up to its closing quote, with doubled quotes standing for one; an unquoted field is trimmed.
+///
+/// # Errors
+///
+/// * If a quoted field is not closed or is followed by anything but the separator, this function returns an Error.
+fn next_field(text: &str, separator: char) -> Result<(String, Option<&str>), Error> {
+    let quoted = match text.trim_start().strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => {
+            return Ok(match text.split_once(separator) {
+                Some((field, rest)) => (field.trim().to_string(), Some(rest)),
+                None => (text.trim().to_string(), None),
+            })
+        }
+    };
+    let mut field = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '"' {
+            field.push(c);
+        } else if quoted[i + 1..].starts_with('"') {
+            field.push('"');
+            chars.next();
+        } else {
+            let rest = quoted[i + 1..].trim_start();
+            return match rest.strip_prefix(separator) {
+                Some(rest) => Ok((field, Some(rest))),
+                None if rest.is_empty() => Ok((field, None)),
+                None => Err(Error::Parse("Invalid quoted field!")),
+            };
+        }
+    }
+    Err(Error::Parse("Invalid quoted field!"))
+}
+
+impl From<&SweepTable> for ResultTable {
+    fn from(sweep: &SweepTable) -> ResultTable {
+        let columns: Vec<&str> = sweep.parameter_names.iter().chain(&sweep.result_names).map(|s| s.as_str()).collect();
+        let mut table = ResultTable::new(&columns);
+        table.metadata.push(("parameters".to_string(), sweep.parameter_names.join(",")));
+        table.rows = sweep.parameters.iter().zip(&sweep.results).map(|(p, r)| p.iter().chain(r).copied().collect()).collect();
+        table
+    }
+}
+
+impl From<&Thermodynamics> for ResultTable {
+    fn from(thermo: &Thermodynamics) -> ResultTable {
+        let names: Vec<String> = (0..thermo.expectation_values.len()).map(|k| format!("observable_{}", k)).collect();
+        let mut columns = vec!["beta", "log_partition_function", "free_energy", "energy", "entropy", "specific_heat"];
+        columns.extend(names.iter().map(|s| s.as_str()));
+        let mut table = ResultTable::new(&columns);
+        table.rows = (0..thermo.betas.len())
+            .map(|i| {
+                let mut row = vec![
+                    thermo.betas[i],
+                    thermo.log_partition_function[i],
+                    thermo.free_energy[i],
+                    thermo.energy[i],
+                    thermo.entropy[i],
+                    thermo.specific_heat[i],
+                ];
+                row.extend(thermo.expectation_values.iter().map(|o| o[i]));
+                row
+            })
+            .collect();
+        table
+    }
+}
+
+impl From<&LoschmidtEcho> for ResultTable {
+    fn from(echo: &LoschmidtEcho) -> ResultTable {
+        let mut table = ResultTable::new(&["time", "amplitude_re", "amplitude_im", "echo"]);
+        table.rows = echo
+            .times
+            .iter()
+            .zip(&echo.amplitudes)
+            .zip(&echo.echo)
+            .map(|((t, g), l)| vec![*t, g.re, g.im, *l])
+            .collect();
+        table
+    }
+}
+
+impl From<&SusceptibilitySweep> for ResultTable {
+    fn from(sweep: &SusceptibilitySweep) -> ResultTable {
+        let mut table = ResultTable::new(&["temperature", "spin_susceptibility", "compressibility", "specific_heat"]);
+        table.rows = (0..sweep.temperatures.len())
+            .map(|i| vec![sweep.temperatures[i], sweep.spin_susceptibility[i], sweep.compressibility[i], sweep.specific_heat[i]])
+            .collect();
+        table
+    }
+}
+
+impl From<&Histogram> for ResultTable {
+    fn from(histogram: &Histogram) -> ResultTable {
+        let mut table = ResultTable::new(&["center", "lower", "upper", "density"]);
+        table.rows = histogram
+            .edges()
+            .windows(2)
+            .zip(histogram.density())
+            .map(|(edge, d)| vec![(edge[0] + edge[1]) / 2.0, edge[0], edge[1], *d])
+            .collect();
+        table
+    }
+}
+
+impl From<&DosTable> for ResultTable {
+    fn from(dos: &DosTable) -> ResultTable {
+        let mut table = ResultTable::new(&["energy", "density", "integrated"]);
+        table.rows = dos.energies.iter().zip(&dos.density).zip(&dos.integrated).map(|((e, d), n)| vec![*e, *d, *n]).collect();
+        table
+    }
+}
+
+impl From<&MomentumTable> for ResultTable {
+    fn from(momenta: &MomentumTable) -> ResultTable {
+        let d = momenta.momenta.first().map_or(0, |k| k.len());
+        let mut columns: Vec<String> = (0..d).map(|i| format!("k{}", i)).collect();
+        columns.push("value".to_string());
+        let mut table = ResultTable::new(&columns.iter().map(|c| c.as_str()).collect::<Vec<_>>());
+        table.rows = momenta.momenta.iter().zip(&momenta.values).map(|(k, v)| k.iter().chain([v]).copied().collect()).collect();
+        table
+    }
+}
+
+impl From<&SpinCorrelationTable> for ResultTable {
+    fn from(correlations: &SpinCorrelationTable) -> ResultTable {
+        let mut table = ResultTable::new(&["distance", "longitudinal", "transverse", "total"]);
+        table.rows = (0..correlations.distances.len())
+            .map(|i| {
+                vec![
+                    correlations.distances[i] as f64,
+                    correlations.longitudinal[i],
+                    correlations.transverse[i],
+                    correlations.total[i],
+                ]
+            })
+            .collect();
+        table
+    }
+}
+
+impl From<&EntanglementSpectrum> for ResultTable {
+    fn from(spectrum: &EntanglementSpectrum) -> ResultTable {
+        let mut table = ResultTable::new(&["level"]);
+        table.rows = spectrum.levels.iter().map(|xi| vec![*xi]).collect();
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::thermo::observables;
+
+    #[test]
+    fn test_csv_round_trip() {
+        let thermo = observables(&[-1.0, 0.0, 1.0], &[0.5, 2.0]);
+        let mut table = ResultTable::from(&thermo);
+        table.set_metadata("model", "three\nlevels");
+        table.set_metadata("model", "three levels");
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let text = String::from_utf8(csv).unwrap();
+        assert!(text.starts_with("# model: three levels\nbeta,log_partition_function,"));
+        let read = ResultTable::from_csv(&text).unwrap();
+        assert_eq!(read, table);
+        assert_eq!(read.column("energy"), Some(thermo.energy));
+        assert!(matches!(table.push(vec![1.0]), Err(Error::DimensionMismatch)));
+    }
+
+    #[test]
+    fn test_csv_quoting() {
+        let mut table = ResultTable::new(&["E, per site", " padded ", "\"quoted\"", "#", "plain"]);
+        table.set_metadata("time: unit", "  seconds, roughly ");
+        table.set_metadata("\"key\"", "");
+        table.push(vec![1.0, 2.0, 3.0, 4.0, 5.0]).unwrap();
+        let mut csv = Vec::new();
+        table.write_csv(&mut csv).unwrap();
+        let text = String::from_utf8(csv).unwrap();
+        assert!(text.contains("\n\"E, per site\",\" padded \",\"\"\"quoted\"\"\",\"#\",plain\n"));
+        let read = ResultTable::from_csv(&text).unwrap();
+        assert_eq!(read, table);
+        assert_eq!(read.metadata("time: unit"), Some("  seconds, roughly "));
+        assert!(ResultTable::from_csv("\"open,b\n").is_err());
+    }
+}
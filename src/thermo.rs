@@ -0,0 +1,134 @@
+use crate::linalg::dot;
+use crate::spectrum::Spectrum;
+use crate::Operator;
+
+/// Represents thermodynamic quantities on a grid of inverse temperatures, in units where k_B = 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thermodynamics {
+    /// The inverse temperatures β.
+    pub betas: Vec<f64>,
+    /// The logarithm of the partition function, ln Z.
+    pub log_partition_function: Vec<f64>,
+    /// The free energy F = -ln Z / β.
+    pub free_energy: Vec<f64>,
+    /// The internal energy E = <H>.
+    pub energy: Vec<f64>,
+    /// The entropy S = β (E - F).
+    pub entropy: Vec<f64>,
+    /// The specific heat C = β² (<H²> - <H>²).
+    pub specific_heat: Vec<f64>,
+    /// The thermal expectation values <O> of the supplied operators, one row per operator.
+    pub expectation_values: Vec<Vec<f64>>,
+}
+
+impl Thermodynamics {
+    /// Returns the partition function Z at grid point i. This may overflow for large β|E_0|, where `log_partition_function` stays finite.
+    pub fn partition_function(&self, i: usize) -> f64 {
+        self.log_partition_function[i].exp()
+    }
+}
+
+/// Returns the thermodynamics of a system with the supplied eigenvalues on a grid of inverse temperatures.
+///
+/// # Arguments
+///
+/// * `energies` - All eigenvalues of the Hamiltonian.
+/// * `betas` - The finite inverse temperatures.
+pub fn observables(energies: &[f64], betas: &[f64]) -> Thermodynamics {
+    thermal_averages(energies, &[], betas)
+}
+
+/// Returns the thermodynamics of `spectrum`, including the thermal expectation values of `operators`, on a grid of inverse temperatures.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian.
+/// * `operators` - The operators whose thermal expectation values are computed.
+/// * `betas` - The finite inverse temperatures.
+pub fn observables_with_operators(
+    spectrum: &Spectrum,
+    operators: &[Operator],
+    betas: &[f64],
+) -> Thermodynamics {
+    let diagonals: Vec<Vec<f64>> = operators
+        .iter()
+        .map(|op| {
+            let m = spectrum.basis().matrix(op);
+            (0..spectrum.energies().len())
+                .map(|n| {
+                    let v = spectrum.eigenvector(n);
+                    dot(&v, &m.matvec(&v))
+                })
+                .collect()
+        })
+        .collect();
+    thermal_averages(spectrum.energies(), &diagonals, betas)
+}
+
+/// Returns the thermodynamics from eigenvalues and the diagonal matrix elements <n|O|n> of a set of operators.
+pub(crate) fn thermal_averages(energies: &[f64], diagonals: &[Vec<f64>], betas: &[f64]) -> Thermodynamics {
+    let e0 = energies.iter().cloned().fold(f64::INFINITY, f64::min);
+    let mut res = Thermodynamics {
+        betas: betas.to_vec(),
+        log_partition_function: Vec::with_capacity(betas.len()),
+        free_energy: Vec::with_capacity(betas.len()),
+        energy: Vec::with_capacity(betas.len()),
+        entropy: Vec::with_capacity(betas.len()),
+        specific_heat: Vec::with_capacity(betas.len()),
+        expectation_values: vec![Vec::with_capacity(betas.len()); diagonals.len()],
+    };
+    for beta in betas {
+        let weights: Vec<f64> = energies.iter().map(|e| (-beta * (e - e0)).exp()).collect();
+        let z: f64 = weights.iter().sum();
+        let average = |values: &[f64]| -> f64 {
+            weights.iter().zip(values).map(|(w, v)| w * v).sum::<f64>() / z
+        };
+        let energy = average(energies);
+        let fluctuation = weights
+            .iter()
+            .zip(energies)
+            .map(|(w, e)| w * (e - energy) * (e - energy))
+            .sum::<f64>()
+            / z;
+        let log_z = z.ln() - beta * e0;
+        res.log_partition_function.push(log_z);
+        res.free_energy.push(-log_z / beta);
+        res.energy.push(energy);
+        res.entropy.push(log_z + beta * energy);
+        res.specific_heat.push(beta * beta * fluctuation);
+        for (ev, d) in res.expectation_values.iter_mut().zip(diagonals) {
+            ev.push(average(d));
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::AC;
+
+    #[test]
+    fn test_two_level_system() {
+        let delta = 1.5;
+        let betas = [1e-6, 0.5, 2.0];
+        let thermo = observables(&[0.0, delta], &betas);
+        for (i, beta) in betas.iter().enumerate() {
+            let x = (beta * delta).exp();
+            assert!((thermo.energy[i] - delta / (1.0 + x)).abs() < 1e-12);
+            let c = beta * beta * delta * delta * x / ((1.0 + x) * (1.0 + x));
+            assert!((thermo.specific_heat[i] - c).abs() < 1e-12);
+        }
+        assert!((thermo.entropy[0] - 2.0_f64.ln()).abs() < 1e-6);
+        assert!((thermo.partition_function(0) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_expectation_values() {
+        let n0 = Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let spectrum = Spectrum::new(&n0, Basis::fock_space(1));
+        let thermo = observables_with_operators(&spectrum, &[n0], &[1.0]);
+        assert!((thermo.expectation_values[0][0] - 1.0 / (1.0 + 1.0_f64.exp())).abs() < 1e-12);
+    }
+}
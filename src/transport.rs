@@ -0,0 +1,318 @@
+use crate::basis::Basis;
+use crate::error::Error;
+use crate::hermitian::HermitianOperator;
+use crate::lanczos::ground_state;
+use crate::linalg::{dot, LinearOperator};
+use crate::operators::{Spin, SpinConvention};
+use crate::random::Rng;
+use crate::spectral::Poles;
+use crate::spectrum::{Spectrum, DEGENERACY_TOLERANCE};
+use crate::{Operator, AC};
+
+/// The required residual of the Lanczos ground states of `flux_drude_weight`.
+const FLUX_TOLERANCE: f64 = 1e-10;
+
+/// This represents a single hopping process -t (c†_i c_j + c†_j c_i) on a lattice.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Hopping {
+    /// The first orbital of the bond.
+    pub i: u64,
+    /// The second orbital of the bond.
+    pub j: u64,
+    /// The hopping amplitude t.
+    pub t: f64,
+    /// The displacement r_i - r_j projected onto the direction of the applied field.
+    pub displacement: f64,
+}
+
+impl Hopping {
+    /// Returns the hopping -t (c†_i c_j + c†_j c_i) with the supplied displacement r_i - r_j.
+    pub fn new(i: u64, j: u64, t: f64, displacement: f64) -> Hopping {
+        Hopping {
+            i,
+            j,
+            t,
+            displacement,
+        }
+    }
+}
+
+/// Returns the hopping Hamiltonian -Σ t (c†_i c_j + c†_j c_i) for the supplied bonds.
+pub fn hopping_operator(hoppings: &[Hopping]) -> Operator {
+    bond_operator(hoppings, |h| -h.t, |h| -h.t)
+}
+
+/// Returns the current operator j defined by J = i j, where J = -∂H/∂A is the paramagnetic current
+/// obtained by attaching the Peierls phase e^{iA(r_i - r_j)} to the hoppings.
+///
+/// Operator amplitudes are real, so the overall factor i is left out;
+/// matrix elements of j and J between eigenstates of a real Hamiltonian only differ by this phase.
+///
+/// # Arguments
+///
+/// * `hoppings` - The bonds of the lattice, with the same convention as `hopping_operator`.
+pub fn current_operator(hoppings: &[Hopping]) -> Operator {
+    bond_operator(hoppings, |h| h.t * h.displacement, |h| -h.t * h.displacement)
+}
+
+/// Returns the kinetic energy operator along the field direction, K = -Σ t (r_i - r_j)² (c†_i c_j + c†_j c_i),
+/// which enters the diamagnetic part of the conductivity.
+pub fn stress_tensor_operator(hoppings: &[Hopping]) -> Operator {
+    bond_operator(
+        hoppings,
+        |h| -h.t * h.displacement * h.displacement,
+        |h| -h.t * h.displacement * h.displacement,
+    )
+}
+
+fn bond_operator(
+    hoppings: &[Hopping],
+    forward: impl Fn(&Hopping) -> f64,
+    backward: impl Fn(&Hopping) -> f64,
+) -> Operator {
+    let mut terms = Vec::new();
+    for h in hoppings {
+        terms.push((forward(h), vec![AC::Create(h.i), AC::Annihilate(h.j)]));
+        terms.push((backward(h), vec![AC::Create(h.j), AC::Annihilate(h.i)]));
+    }
+    Operator::new(terms)
+}
+
+/// Represents the optical conductivity σ(ω) = 2πD δ(ω) + σ_reg(ω) of a ground state.
+#[derive(Debug, Clone)]
+pub struct OpticalConductivity {
+    /// The Drude weight D.
+    pub drude_weight: f64,
+    /// The regular part σ_reg(ω) as a sum of delta functions at positive frequencies.
+    pub regular: Poles,
+}
+
+/// Returns the optical conductivity of the ground state of `spectrum`, in units where e = ħ = 1.
+///
+/// The regular part is σ_reg(ω) = (π/N) Σ_{n≠0} |<n|J|0>|²/(E_n - E_0) δ(ω - (E_n - E_0))
+/// and the Drude weight D = -<K>/(2N) - (1/N) Σ_{n≠0} |<n|J|0>|²/(E_n - E_0).
+/// States degenerate with the ground state are excluded from the sums.
+///
+/// # Arguments
+///
+/// * `spectrum` - The full spectrum of the Hamiltonian, whose hopping part is given by `hoppings`.
+/// * `hoppings` - The bonds of the lattice.
+/// * `n_sites` - The number of lattice sites N used to normalize the conductivity.
+pub fn optical_conductivity(
+    spectrum: &Spectrum,
+    hoppings: &[Hopping],
+    n_sites: usize,
+) -> OpticalConductivity {
+    let basis = spectrum.basis();
+    let ground = spectrum.eigenvector(0);
+    let e0 = spectrum.ground_state_energy();
+    let n = n_sites as f64;
+
+    let kinetic = dot(&ground, &basis.matrix(&stress_tensor_operator(hoppings)).matvec(&ground));
+    let j0 = basis.matrix(&current_operator(hoppings)).matvec(&ground);
+
+    let mut positions = Vec::new();
+    let mut weights = Vec::new();
+    let mut paramagnetic = 0.0;
+    for (k, e) in spectrum.energies().iter().enumerate() {
+        let de = e - e0;
+        if de < DEGENERACY_TOLERANCE {
+            continue;
+        }
+        let me = dot(&spectrum.eigenvector(k), &j0);
+        let w = me * me / de;
+        paramagnetic += w;
+        if w > 0.0 {
+            positions.push(de);
+            weights.push(std::f64::consts::PI * w / n);
+        }
+    }
+    OpticalConductivity {
+        drude_weight: -kinetic / (2.0 * n) - paramagnetic / n,
+        regular: Poles::new(positions, weights),
+    }
+}
+
+/// Returns the Hamiltonian with a uniform vector potential φ, twisting every hopping into -t (e^{iφ(r_i - r_j)} c†_i c_j + h.c.).
+///
+/// The total twist φL threaded through a ring of length L, with unit displacements, is an Aharonov-Bohm flux.
+///
+/// # Arguments
+///
+/// * `hoppings` - The bonds of the lattice.
+/// * `interaction` - The remaining, flux independent, part of the Hamiltonian.
+/// * `phi` - The vector potential φ along the field direction.
+pub fn twisted_hamiltonian(hoppings: &[Hopping], interaction: &Operator, phi: f64) -> HermitianOperator {
+    let real = bond_operator(
+        hoppings,
+        |h| -h.t * (phi * h.displacement).cos(),
+        |h| -h.t * (phi * h.displacement).cos(),
+    );
+    let imaginary = bond_operator(
+        hoppings,
+        |h| -h.t * (phi * h.displacement).sin(),
+        |h| h.t * (phi * h.displacement).sin(),
+    );
+    let mut real_terms = real.terms;
+    real_terms.extend(interaction.terms.iter().cloned());
+    HermitianOperator::new(Operator::new(real_terms), imaginary)
+}
+
+/// Returns the Hamiltonian with an Aharonov-Bohm flux Φ threaded through a ring, spread evenly over the bonds as φ = Φ/L.
+///
+/// # Arguments
+///
+/// * `hoppings` - The bonds of the ring, whose displacements along the ring add up to `ring_length` going once around.
+/// * `interaction` - The remaining, flux independent, part of the Hamiltonian.
+/// * `flux` - The flux Φ, in units of the flux quantum over 2π.
+/// * `ring_length` - The circumference L of the ring.
+pub fn flux_hamiltonian(hoppings: &[Hopping], interaction: &Operator, flux: f64, ring_length: f64) -> HermitianOperator {
+    twisted_hamiltonian(hoppings, interaction, flux / ring_length)
+}
+
+/// Returns the paramagnetic current J = -∂H/∂φ of the hoppings twisted by the vector potential φ, see `twisted_hamiltonian`.
+/// At φ = 0 its imaginary part is `current_operator`.
+pub fn twisted_current(hoppings: &[Hopping], phi: f64) -> HermitianOperator {
+    let real = bond_operator(
+        hoppings,
+        |h| -h.t * h.displacement * (phi * h.displacement).sin(),
+        |h| -h.t * h.displacement * (phi * h.displacement).sin(),
+    );
+    let imaginary = bond_operator(
+        hoppings,
+        |h| h.t * h.displacement * (phi * h.displacement).cos(),
+        |h| -h.t * h.displacement * (phi * h.displacement).cos(),
+    );
+    HermitianOperator::new(real, imaginary)
+}
+
+/// Returns the bonds of a single spin species, mapping the sites of the site bonds `hoppings` to spin orbitals.
+pub fn spin_hoppings(hoppings: &[Hopping], convention: SpinConvention, spin: Spin) -> Vec<Hopping> {
+    hoppings
+        .iter()
+        .map(|h| Hopping::new(convention.orbital(h.i, spin), convention.orbital(h.j, spin), h.t, h.displacement))
+        .collect()
+}
+
+/// Returns the bonds of both spin species, with the hopping amplitudes scaled by `up` and `down` respectively.
+fn scaled_spin_hoppings(hoppings: &[Hopping], convention: SpinConvention, up: f64, down: f64) -> Vec<Hopping> {
+    let mut res = spin_hoppings(hoppings, convention, Spin::Up);
+    res.iter_mut().for_each(|h| h.t *= up);
+    let mut res_down = spin_hoppings(hoppings, convention, Spin::Down);
+    res_down.iter_mut().for_each(|h| h.t *= down);
+    res.extend(res_down);
+    res
+}
+
+/// Returns the charge current J_c = J_↑ + J_↓ of spin-1/2 fermions hopping along the site bonds `hoppings`, twisted by φ.
+pub fn charge_current(hoppings: &[Hopping], convention: SpinConvention, phi: f64) -> HermitianOperator {
+    twisted_current(&scaled_spin_hoppings(hoppings, convention, 1.0, 1.0), phi)
+}
+
+/// Returns the spin current J_s = (J_↑ - J_↓)/2 of spin-1/2 fermions hopping along the site bonds `hoppings`, twisted by φ.
+pub fn spin_current(hoppings: &[Hopping], convention: SpinConvention, phi: f64) -> HermitianOperator {
+    twisted_current(&scaled_spin_hoppings(hoppings, convention, 0.5, -0.5), phi)
+}
+
+/// Returns the Drude weight D = (1/2N) ∂²E_0/∂φ² from the curvature of the ground state energy under a uniform twist φ,
+/// evaluated by a central finite difference. This matches the normalization of `optical_conductivity` without requiring the full spectrum.
+///
+/// # Arguments
+///
+/// * `basis` - The many body basis.
+/// * `hoppings` - The bonds of the lattice.
+/// * `interaction` - The remaining, flux independent, part of the Hamiltonian.
+/// * `n_sites` - The number of lattice sites N.
+/// * `step` - The finite difference step δφ.
+///
+/// # Errors
+///
+/// * If a Lanczos ground state does not converge, this function returns an Error.
+pub fn flux_drude_weight(
+    basis: &Basis,
+    hoppings: &[Hopping],
+    interaction: &Operator,
+    n_sites: usize,
+    step: f64,
+) -> Result<f64, Error> {
+    let mut rng = Rng::new(0xd1d3);
+    let mut energy = |phi: f64| -> Result<f64, Error> {
+        let h = twisted_hamiltonian(hoppings, interaction, phi).realified(basis);
+        let start = rng.normal_vector(h.dimension());
+        ground_state(&h, &start, h.dimension(), FLUX_TOLERANCE).map(|(e, _)| e)
+    };
+    let curvature = (energy(step)? + energy(-step)? - 2.0 * energy(0.0)?) / (step * step);
+    Ok(curvature / (2.0 * n_sites as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_two_site_sum_rule() {
+        let bonds = [Hopping::new(0, 1, 1.0, -1.0)];
+        let spectrum = Spectrum::new(&hopping_operator(&bonds), Basis::fixed_particle_number(2, 1));
+        let sigma = optical_conductivity(&spectrum, &bonds, 2);
+        assert!(sigma.drude_weight.abs() < 1e-12);
+        assert!((sigma.regular.total_weight() - std::f64::consts::PI / 4.0).abs() < 1e-12);
+        assert!((sigma.regular.positions()[0] - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ring_drude_weight() {
+        let bonds: Vec<Hopping> = (0..4).map(|i| Hopping::new(i, (i + 1) % 4, 1.0, -1.0)).collect();
+        let spectrum = Spectrum::new(&hopping_operator(&bonds), Basis::fixed_particle_number(4, 1));
+        let sigma = optical_conductivity(&spectrum, &bonds, 4);
+        assert!((sigma.drude_weight - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_current_is_flux_derivative() {
+        let sites: Vec<Hopping> = (0..3).map(|i| Hopping::new(i, (i + 1) % 3, 1.0, 1.0)).collect();
+        let convention = SpinConvention::Interleaved;
+        let bonds = spin_hoppings(&sites, convention, Spin::Up);
+        let basis = Basis::fock_space(6);
+        let none = Operator::new(Vec::new());
+        let (phi, delta) = (0.4, 1e-5);
+        let mut rng = Rng::new(3);
+        let x = rng.normal_vector(2 * basis.dimension());
+        let apply = |op: &HermitianOperator| {
+            let mut y = vec![0.0; x.len()];
+            op.realified(&basis).apply(&x, &mut y);
+            y
+        };
+        let plus = apply(&twisted_hamiltonian(&bonds, &none, phi + delta));
+        let minus = apply(&twisted_hamiltonian(&bonds, &none, phi - delta));
+        let j = apply(&twisted_current(&bonds, phi));
+        for k in 0..x.len() {
+            assert!((j[k] + (plus[k] - minus[k]) / (2.0 * delta)).abs() < 1e-6);
+        }
+        // The charge current of both species at φ = 0 is i times the orbital current operator.
+        let charge = charge_current(&sites, convention, 0.0);
+        let mut orbital_bonds = bonds.clone();
+        orbital_bonds.extend(spin_hoppings(&sites, convention, Spin::Down));
+        let expected = basis.matrix(&current_operator(&orbital_bonds));
+        assert_eq!(basis.matrix(&charge.imaginary), expected);
+        let spin = spin_current(&sites, convention, 0.0);
+        assert!(basis.matrix(&spin.real).data().iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_flux_drude_weight() {
+        // Three interacting spinless fermions on a ring of four sites have a closed shell ground state.
+        let bonds: Vec<Hopping> = (0..4).map(|i| Hopping::new(i, (i + 1) % 4, 1.0, -1.0)).collect();
+        let interaction = Operator::new(
+            (0..4)
+                .map(|i| (0.7, vec![AC::Create(i), AC::Annihilate(i), AC::Create((i + 1) % 4), AC::Annihilate((i + 1) % 4)]))
+                .collect(),
+        );
+        let basis = Basis::fixed_particle_number(4, 3);
+        let mut terms = hopping_operator(&bonds).terms;
+        terms.extend(interaction.terms.iter().cloned());
+        let spectrum = Spectrum::new(&Operator::new(terms), basis.clone());
+        let exact = optical_conductivity(&spectrum, &bonds, 4).drude_weight;
+        let flux = flux_drude_weight(&basis, &bonds, &interaction, 4, 1e-3).unwrap();
+        assert!((flux - exact).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,323 @@
+use std::f64::consts::PI;
+
+use crate::basis::Basis;
+use crate::complex::Complex;
+use crate::error::Error;
+use crate::hermitian::{HermitianOperator, RealifiedMatrix};
+use crate::lanczos::ground_state;
+use crate::linalg::{dot, ComplexMatrix, LinearOperator, Matrix};
+use crate::random::Rng;
+use crate::transport::Hopping;
+use crate::{Operator, AC};
+
+/// The required residual of the Lanczos ground states of `twist_average`.
+const TWIST_TOLERANCE: f64 = 1e-10;
+
+/// The smallest gap above the ground state multiplet, and the smallest overlap between neighbouring twists, accepted by `berry_curvature`.
+const CHERN_TOLERANCE: f64 = 1e-8;
+
+/// This represents a bond of a periodic cluster together with the number of times it winds around the cluster in each direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundaryBond {
+    /// The hopping -t (c†_i c_j + h.c.), whose displacement is not used.
+    pub hopping: Hopping,
+    /// The image shift w of j, in units of the cluster, through which the bond connects to i, so that c†_i c_j picks up e^{iθ·w}.
+    pub winding: [f64; 2],
+}
+
+impl BoundaryBond {
+    /// Returns the bond `hopping` with the boundary `winding`.
+    pub fn new(hopping: Hopping, winding: [f64; 2]) -> BoundaryBond {
+        BoundaryBond { hopping, winding }
+    }
+}
+
+/// Returns the Hamiltonian with twisted boundary conditions ψ(r + L_x) = e^{iθ_x} ψ(r) and ψ(r + L_y) = e^{iθ_y} ψ(r),
+/// which attach the phase e^{iθ·w} to every bond with winding w.
+///
+/// # Arguments
+///
+/// * `bonds` - The bonds of the cluster.
+/// * `interaction` - The remaining, twist independent, part of the Hamiltonian.
+/// * `theta` - The twists (θ_x, θ_y).
+pub fn twisted_boundary_hamiltonian(bonds: &[BoundaryBond], interaction: &Operator, theta: [f64; 2]) -> HermitianOperator {
+    let mut real = interaction.terms.clone();
+    let mut imaginary = Vec::new();
+    for b in bonds {
+        let h = b.hopping;
+        let phase = theta[0] * b.winding[0] + theta[1] * b.winding[1];
+        let forward = vec![AC::Create(h.i), AC::Annihilate(h.j)];
+        let backward = vec![AC::Create(h.j), AC::Annihilate(h.i)];
+        real.push((-h.t * phase.cos(), forward.clone()));
+        real.push((-h.t * phase.cos(), backward.clone()));
+        imaginary.push((-h.t * phase.sin(), forward));
+        imaginary.push((h.t * phase.sin(), backward));
+    }
+    HermitianOperator::new(Operator::new(real), Operator::new(imaginary))
+}
+
+/// Represents ground state properties on a grid of boundary twists and their twist averages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwistAverage {
+    /// The twists (θ_x, θ_y).
+    pub twists: Vec<[f64; 2]>,
+    /// The ground state energy at each twist.
+    pub energies: Vec<f64>,
+    /// The ground state expectation values at each twist, one row per observable.
+    pub expectation_values: Vec<Vec<f64>>,
+    /// The twist averaged ground state energy.
+    pub mean_energy: f64,
+    /// The twist averaged expectation values, one per observable.
+    pub mean_values: Vec<f64>,
+}
+
+/// Returns the twist averaged ground state energy and expectation values of `observables` over an n_x × n_y grid of twists.
+///
+/// The twists θ = 2π (k + 1/2)/n are the midpoints of an even grid on [0, 2π), which avoids the often degenerate periodic and
+/// antiperiodic boundary conditions. If the ground state at some twist is degenerate, the expectation values refer to an arbitrary ground state.
+///
+/// # Arguments
+///
+/// * `bonds` - The bonds of the cluster.
+/// * `interaction` - The remaining, twist independent, part of the Hamiltonian.
+/// * `basis` - The many body basis.
+/// * `observables` - The observables, with real amplitudes.
+/// * `n_twists` - The number of twists n_x and n_y in each direction; use 1 in a direction without twist averaging.
+///
+/// # Errors
+///
+/// * If a Lanczos ground state does not converge, this function returns an Error.
+/// * If a sparse matrix exceeds the memory budget of the configuration, this function returns `Error::MemoryBudget`.
+pub fn twist_average(
+    bonds: &[BoundaryBond],
+    interaction: &Operator,
+    basis: &Basis,
+    observables: &[Operator],
+    n_twists: [usize; 2],
+) -> Result<TwistAverage, Error> {
+    let grid = |k: usize, n: usize| if n > 1 { 2.0 * PI * (k as f64 + 0.5) / n as f64 } else { 0.0 };
+    let matrices = observables.iter().map(|o| basis.try_sparse_matrix(o)).collect::<Result<Vec<_>, _>>()?;
+    let d = basis.dimension();
+    let mut rng = Rng::new(0x7a157);
+    let mut res = TwistAverage {
+        twists: Vec::new(),
+        energies: Vec::new(),
+        expectation_values: vec![Vec::new(); observables.len()],
+        mean_energy: 0.0,
+        mean_values: vec![0.0; observables.len()],
+    };
+    let mut y = vec![0.0; d];
+    for ky in 0..n_twists[1] {
+        for kx in 0..n_twists[0] {
+            let theta = [grid(kx, n_twists[0]), grid(ky, n_twists[1])];
+            let h = twisted_boundary_hamiltonian(bonds, interaction, theta).realified(basis);
+            let start = rng.normal_vector(h.dimension());
+            let (e, x) = ground_state(&h, &start, h.dimension(), TWIST_TOLERANCE)?;
+            let (re, im) = x.split_at(d);
+            let norm = dot(&x, &x);
+            for (row, m) in res.expectation_values.iter_mut().zip(&matrices) {
+                // <ψ|O|ψ> = <Re ψ|O|Re ψ> + <Im ψ|O|Im ψ> for real symmetric O.
+                m.apply(re, &mut y);
+                let mut value = dot(re, &y);
+                m.apply(im, &mut y);
+                value += dot(im, &y);
+                row.push(value / norm);
+            }
+            res.twists.push(theta);
+            res.energies.push(e);
+        }
+    }
+    let n = res.energies.len() as f64;
+    res.mean_energy = res.energies.iter().sum::<f64>() / n;
+    for (mean, row) in res.mean_values.iter_mut().zip(&res.expectation_values) {
+        *mean = row.iter().sum::<f64>() / n;
+    }
+    Ok(res)
+}
+
+/// Represents the Berry curvature of a ground state multiplet on a discretized torus of boundary twists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BerryCurvature {
+    /// The number of twists n_x and n_y in each direction.
+    pub n_twists: [usize; 2],
+    /// The Berry flux F through each plaquette of the twist grid, with θ_x running fastest.
+    pub flux: Vec<f64>,
+}
+
+impl BerryCurvature {
+    /// Returns the Chern number C = (1/2π) Σ F, which is an integer up to rounding errors.
+    pub fn chern_number(&self) -> f64 {
+        self.flux.iter().sum::<f64>() / (2.0 * PI)
+    }
+}
+
+/// Returns the n lowest states of `h`, as orthonormal complex vectors.
+///
+/// Each complex eigenvector ψ appears in the real representation as both (Re ψ, Im ψ) and (-Im ψ, Re ψ) = iψ,
+/// so Gram-Schmidt over the 2n lowest real eigenvectors leaves exactly n linearly independent complex vectors.
+fn ground_multiplet(h: &RealifiedMatrix, n_states: usize) -> Result<Vec<Vec<Complex>>, Error> {
+    let n = h.dimension();
+    let mut dense = Matrix::zeros(n, n);
+    let mut x = vec![0.0; n];
+    let mut y = vec![0.0; n];
+    for c in 0..n {
+        x[c] = 1.0;
+        h.apply(&x, &mut y);
+        x[c] = 0.0;
+        for (r, v) in y.iter().enumerate() {
+            dense[(r, c)] = *v;
+        }
+    }
+    let (e, v) = dense.eigh();
+    if 2 * n_states > n || (2 * n_states < n && e[2 * n_states] - e[2 * n_states - 1] < CHERN_TOLERANCE) {
+        return Err(Error::Invalid("The ground state multiplet is not separated from the excited states!"));
+    }
+    let mut states: Vec<Vec<Complex>> = Vec::with_capacity(n_states);
+    for c in 0..2 * n_states {
+        let mut psi = RealifiedMatrix::to_complex(&v.column(c));
+        for phi in &states {
+            let overlap = inner(phi, &psi);
+            for (p, q) in psi.iter_mut().zip(phi) {
+                *p -= overlap * *q;
+            }
+        }
+        let norm = inner(&psi, &psi).re.sqrt();
+        // The remainder of the partner iψ of a previous vector vanishes, while a new vector keeps norm close to one.
+        if norm > 0.5 {
+            states.push(psi.into_iter().map(|z| z / norm).collect());
+        }
+    }
+    Ok(states)
+}
+
+/// Returns <a|b>.
+fn inner(a: &[Complex], b: &[Complex]) -> Complex {
+    let mut res = Complex::default();
+    for (x, y) in a.iter().zip(b) {
+        res += x.conj() * *y;
+    }
+    res
+}
+
+/// Returns the U(1) link variable det <Ψ(θ)|Ψ(θ')> / |det <Ψ(θ)|Ψ(θ')>| between two ground state multiplets.
+fn link(a: &[Vec<Complex>], b: &[Vec<Complex>]) -> Result<Complex, Error> {
+    let n = a.len();
+    let mut overlaps = ComplexMatrix::zeros(n);
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            overlaps[(i, j)] = inner(x, y);
+        }
+    }
+    let det = overlaps.determinant();
+    if det.abs() < CHERN_TOLERANCE {
+        return Err(Error::Invalid("The ground state changes too much between neighbouring twists!"));
+    }
+    Ok(det / det.abs())
+}
+
+/// Returns the many body Berry curvature of the `n_states` lowest states of `hamiltonian` on an n_x × n_y grid of twists
+/// θ = 2π (k_x/n_x, k_y/n_y), using the lattice field strength of Fukui, Hatsugai and Suzuki.
+///
+/// The link variables are gauge invariant, so the arbitrary phases of the eigenvectors drop out, and the Chern number of the
+/// discretized torus is an integer as long as the grid resolves the curvature. Degenerate multiplets, such as the quasi-degenerate
+/// ground states of a fractional Chern insulator, are treated as a whole through determinants of overlap matrices.
+/// Each twist is diagonalized densely, which limits this function to small clusters.
+///
+/// # Arguments
+///
+/// * `hamiltonian` - Returns the Hamiltonian at the twists (θ_x, θ_y), e.g. from `twisted_boundary_hamiltonian`.
+///   It must be periodic, H(θ + 2π) = H(θ), which holds for integer windings.
+/// * `basis` - The many body basis.
+/// * `n_states` - The number of states in the ground state multiplet.
+/// * `n_twists` - The number of twists n_x and n_y in each direction.
+///
+/// # Errors
+///
+/// * If the multiplet is not separated from the excited states by a gap at some twist, this function returns an Error.
+/// * If the multiplet changes too much between neighbouring twists for the grid to resolve it, this function returns an Error.
+pub fn berry_curvature<F>(
+    hamiltonian: F,
+    basis: &Basis,
+    n_states: usize,
+    n_twists: [usize; 2],
+) -> Result<BerryCurvature, Error>
+where
+    F: Fn([f64; 2]) -> HermitianOperator,
+{
+    let [nx, ny] = n_twists;
+    let mut multiplets = Vec::with_capacity(nx * ny);
+    for ky in 0..ny {
+        for kx in 0..nx {
+            let theta = [2.0 * PI * kx as f64 / nx as f64, 2.0 * PI * ky as f64 / ny as f64];
+            multiplets.push(ground_multiplet(&hamiltonian(theta).realified(basis), n_states)?);
+        }
+    }
+    let index = |kx: usize, ky: usize| (kx % nx) + nx * (ky % ny);
+    let mut flux = Vec::with_capacity(nx * ny);
+    for ky in 0..ny {
+        for kx in 0..nx {
+            let u1 = link(&multiplets[index(kx, ky)], &multiplets[index(kx + 1, ky)])?;
+            let u2 = link(&multiplets[index(kx + 1, ky)], &multiplets[index(kx + 1, ky + 1)])?;
+            let u3 = link(&multiplets[index(kx, ky + 1)], &multiplets[index(kx + 1, ky + 1)])?;
+            let u4 = link(&multiplets[index(kx, ky)], &multiplets[index(kx, ky + 1)])?;
+            flux.push((u1 * u2 * u3.conj() * u4.conj()).arg());
+        }
+    }
+    Ok(BerryCurvature { n_twists, flux })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_free_fermion_ring() {
+        // Twist averaging a half filled free ring converges to the infinite chain energy -2/π per site.
+        let l = 4;
+        let bonds: Vec<BoundaryBond> = (0..l)
+            .map(|i| {
+                let winding = if i == l - 1 { 1.0 } else { 0.0 };
+                BoundaryBond::new(Hopping::new(i, (i + 1) % l, 1.0, 0.0), [winding, 0.0])
+            })
+            .collect();
+        let n0 = Operator::new(vec![(1.0, vec![AC::Create(0), AC::Annihilate(0)])]);
+        let none = Operator::new(Vec::new());
+        let basis = Basis::fixed_particle_number(l, 2);
+        let avg = twist_average(&bonds, &none, &basis, &[n0], [16, 1]).unwrap();
+        assert_eq!(avg.twists.len(), 16);
+        assert!((avg.mean_energy / l as f64 + 2.0 / PI).abs() < 5e-3);
+        assert!((avg.mean_values[0] - 0.5).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_hofstadter_chern_number() {
+        // Three fermions fill the lowest band of the Hofstadter model with flux 1/3 on a 3 × 3 torus, which has |C| = 1.
+        let l = 3;
+        let alpha = 1.0 / 3.0;
+        let site = |x: u64, y: u64| (x % l) + l * (y % l);
+        let hamiltonian = |theta: [f64; 2]| {
+            let (mut real, mut imaginary) = (Vec::new(), Vec::new());
+            for y in 0..l {
+                for x in 0..l {
+                    // Landau gauge hoppings along x, twisted where the bonds wrap around the torus.
+                    let bonds = [
+                        (site(x + 1, y), 2.0 * PI * alpha * y as f64 + if x == l - 1 { theta[0] } else { 0.0 }),
+                        (site(x, y + 1), if y == l - 1 { theta[1] } else { 0.0 }),
+                    ];
+                    for (i, phase) in bonds {
+                        let j = site(x, y);
+                        real.push((-phase.cos(), vec![AC::Create(i), AC::Annihilate(j)]));
+                        real.push((-phase.cos(), vec![AC::Create(j), AC::Annihilate(i)]));
+                        imaginary.push((-phase.sin(), vec![AC::Create(i), AC::Annihilate(j)]));
+                        imaginary.push((phase.sin(), vec![AC::Create(j), AC::Annihilate(i)]));
+                    }
+                }
+            }
+            HermitianOperator::new(Operator::new(real), Operator::new(imaginary))
+        };
+        let basis = Basis::fixed_particle_number(l * l, 3);
+        let curvature = berry_curvature(hamiltonian, &basis, 1, [4, 4]).unwrap();
+        assert_eq!(curvature.flux.len(), 16);
+        assert!((curvature.chern_number().abs() - 1.0).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::ftlm::Sampling;
+use crate::lanczos::krylov;
+use crate::linalg::{dot, norm, LinearOperator};
+use crate::random::Rng;
+
+/// Represents thermal expectation values estimated from canonical typicality states, with jackknife error bars over the samples.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThermalEstimate {
+    /// The inverse temperatures β, in ascending order.
+    pub betas: Vec<f64>,
+    /// The internal energy <H>.
+    pub energy: Vec<f64>,
+    /// The statistical error of the internal energy.
+    pub energy_error: Vec<f64>,
+    /// The thermal expectation values of the supplied operators, one row per operator.
+    pub expectation_values: Vec<Vec<f64>>,
+    /// The statistical errors of the expectation values, one row per operator.
+    pub errors: Vec<Vec<f64>>,
+}
+
+/// Returns e^{-τH}|v>/||e^{-τH}|v>|| together with ln ||e^{-τH}|v>||, using a Krylov space of `n_krylov` vectors.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `v` - The vector to propagate.
+/// * `tau` - The imaginary time step.
+/// * `n_krylov` - The dimension of the Krylov space.
+pub fn imaginary_time_step<H: LinearOperator + ?Sized>(
+    h: &H,
+    v: &[f64],
+    tau: f64,
+    n_krylov: usize,
+) -> (Vec<f64>, f64) {
+    let k = krylov(h, v, n_krylov);
+    let shift = k.ritz().0.first().copied().unwrap_or(0.0);
+    let mut x = k.apply_function(|e| (-tau * (e - shift)).exp());
+    let nx = norm(&x);
+    for xi in x.iter_mut() {
+        *xi /= nx;
+    }
+    (x, nx.ln() - tau * shift)
+}
+
+/// Returns thermal expectation values at the inverse temperatures `betas`, estimated from the canonical typicality states
+/// |ψ_r(β)> = e^{-βH/2}|r> of random vectors |r> as <A>_β = Σ_r <ψ_r|A|ψ_r> / Σ_r <ψ_r|ψ_r>.
+///
+/// The random vectors are propagated in imaginary time through all requested temperatures with Krylov steps no larger than `max_step`.
+///
+/// # Arguments
+///
+/// * `h` - The Hamiltonian.
+/// * `operators` - The operators, represented in the same space as `h`, whose thermal expectation values are computed.
+/// * `betas` - The inverse temperatures; they are sorted in ascending order.
+/// * `sampling` - The number of random vectors and the Krylov dimension of each imaginary time step.
+/// * `max_step` - The largest imaginary time step Δβ/2 taken by a single Krylov propagation.
+pub fn thermal_averages<H: LinearOperator + ?Sized>(
+    h: &H,
+    operators: &[&dyn LinearOperator],
+    betas: &[f64],
+    sampling: Sampling,
+    max_step: f64,
+) -> ThermalEstimate {
+    let mut betas = betas.to_vec();
+    betas.sort_by(|a, b| a.total_cmp(b));
+    let d = h.dimension();
+    let mut rng = Rng::new(sampling.seed);
+    // samples[r][b] = (ln <ψ|ψ>, <H>, <A_i>...) for random vector r at temperature b.
+    let mut samples = Vec::with_capacity(sampling.n_random);
+    let mut w = vec![0.0; d];
+    for _ in 0..sampling.n_random {
+        let mut psi = rng.sign_vector(d);
+        let n = norm(&psi);
+        let mut log_norm = n.ln();
+        for x in psi.iter_mut() {
+            *x /= n;
+        }
+        let mut tau = 0.0;
+        let mut rows = Vec::with_capacity(betas.len());
+        for beta in &betas {
+            while beta / 2.0 - tau > f64::EPSILON * beta.max(1.0) {
+                let step = (beta / 2.0 - tau).min(max_step);
+                let (next, log_scale) = imaginary_time_step(h, &psi, step, sampling.n_lanczos);
+                psi = next;
+                log_norm += log_scale;
+                tau += step;
+            }
+            let mut row = Vec::with_capacity(operators.len() + 2);
+            row.push(2.0 * log_norm);
+            h.apply(&psi, &mut w);
+            row.push(dot(&psi, &w));
+            for op in operators {
+                op.apply(&psi, &mut w);
+                row.push(dot(&psi, &w));
+            }
+            rows.push(row);
+        }
+        samples.push(rows);
+    }
+
+    let mut res = ThermalEstimate {
+        betas: betas.clone(),
+        energy: Vec::with_capacity(betas.len()),
+        energy_error: Vec::with_capacity(betas.len()),
+        expectation_values: vec![Vec::with_capacity(betas.len()); operators.len()],
+        errors: vec![Vec::with_capacity(betas.len()); operators.len()],
+    };
+    for b in 0..betas.len() {
+        let max_log = samples
+            .iter()
+            .map(|rows| rows[b][0])
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = samples.iter().map(|rows| (rows[b][0] - max_log).exp()).collect();
+        for q in 0..=operators.len() {
+            let values: Vec<f64> = samples.iter().map(|rows| rows[b][q + 1]).collect();
+            let (mean, error) = jackknife(&weights, &values);
+            if q == 0 {
+                res.energy.push(mean);
+                res.energy_error.push(error);
+            } else {
+                res.expectation_values[q - 1].push(mean);
+                res.errors[q - 1].push(error);
+            }
+        }
+    }
+    res
+}
+
+/// Returns the weighted mean Σ w_i x_i / Σ w_i and its jackknife error.
+fn jackknife(weights: &[f64], values: &[f64]) -> (f64, f64) {
+    let total_w: f64 = weights.iter().sum();
+    let total_wx: f64 = weights.iter().zip(values).map(|(w, x)| w * x).sum();
+    let mean = total_wx / total_w;
+    let n = weights.len();
+    if n < 2 {
+        return (mean, 0.0);
+    }
+    let variance: f64 = weights
+        .iter()
+        .zip(values)
+        .map(|(w, x)| {
+            let leave_out = (total_wx - w * x) / (total_w - w);
+            (leave_out - mean) * (leave_out - mean)
+        })
+        .sum();
+    (mean, (variance * (n - 1) as f64 / n as f64).sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basis::Basis;
+    use crate::spectrum::Spectrum;
+    use crate::thermo;
+    use crate::{Operator, AC};
+
+    #[test]
+    fn test_imaginary_time_step() {
+        let h = crate::linalg::Matrix::from_vec(2, 2, vec![0.0, 1.0, 1.0, 0.0]);
+        let (x, log_norm) = imaginary_time_step(&h, &[1.0, 0.0], 0.5, 4);
+        // e^{-τσx}|0> = (cosh τ, -sinh τ)
+        let (c, s) = (0.5_f64.cosh(), 0.5_f64.sinh());
+        let n = (c * c + s * s).sqrt();
+        assert!((x[0] - c / n).abs() < 1e-12 && (x[1] + s / n).abs() < 1e-12);
+        assert!((log_norm - n.ln()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_energy_against_exact() {
+        let mut terms = Vec::new();
+        for i in 0..5 {
+            terms.push((-1.0, vec![AC::Create(i), AC::Annihilate(i + 1)]));
+            terms.push((-1.0, vec![AC::Create(i + 1), AC::Annihilate(i)]));
+            terms.push((0.5, vec![AC::Create(i), AC::Annihilate(i), AC::Create(i + 1), AC::Annihilate(i + 1)]));
+        }
+        let h = Operator::new(terms);
+        let basis = Basis::fixed_particle_number(6, 3);
+        let betas = [0.5, 1.0, 3.0];
+        let typical = thermal_averages(&basis.sparse_matrix(&h), &[], &betas, Sampling::new(40, 12, 2), 0.25);
+        let exact = thermo::observables(Spectrum::new(&h, basis).energies(), &betas);
+        for i in 0..betas.len() {
+            let deviation = (typical.energy[i] - exact.energy[i]).abs();
+            assert!(deviation < 5.0 * typical.energy_error[i] + 1e-3);
+        }
+    }
+}
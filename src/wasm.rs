@@ -0,0 +1,89 @@
+use std::ffi::{c_char, CString};
+use std::slice;
+
+use crate::input::parse_specification;
+use crate::json::Json;
+
+/// Returns the results of running the JSON specification `text`, see `input::Specification`, as a JSON object of the
+/// observables, or {"error": message} if the specification is invalid or the run fails.
+///
+/// This is what `rust_ed_run` exports to JavaScript. Build the module with
+/// `cargo rustc --release --lib --features wasm --target wasm32-unknown-unknown --crate-type cdylib`
+/// and load it with `WebAssembly.instantiate`; the exports pass strings through the memory of the module, so no bindings generator is needed.
+pub fn run_json(text: &str) -> String {
+    let results = parse_specification(text).and_then(|spec| spec.run());
+    let json = match results {
+        Ok(results) => Json::Object(results.into_iter().map(|(name, value)| (name, Json::Number(value))).collect()),
+        Err(e) => Json::Object(vec![("error".to_string(), Json::String(e.to_string()))]),
+    };
+    json.to_string()
+}
+
+/// Returns a buffer of `len` bytes in the memory of the module, for the caller to write a specification into.
+#[no_mangle]
+pub extern "C" fn rust_ed_alloc(len: usize) -> *mut u8 {
+    let mut buffer = Vec::<u8>::with_capacity(len);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    ptr
+}
+
+/// Frees a buffer returned by `rust_ed_alloc`.
+///
+/// # Safety
+///
+/// * `ptr` must come from `rust_ed_alloc(len)` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rust_ed_free(ptr: *mut u8, len: usize) {
+    drop(Vec::from_raw_parts(ptr, 0, len));
+}
+
+/// Returns the results of `run_json` of the UTF-8 specification in the `len` bytes at `ptr`, as a NUL terminated string
+/// to be freed with `rust_ed_free_string`.
+///
+/// # Safety
+///
+/// * `ptr` must point to `len` readable bytes, e.g. in a buffer from `rust_ed_alloc`.
+#[no_mangle]
+pub unsafe extern "C" fn rust_ed_run(ptr: *const u8, len: usize) -> *mut c_char {
+    let output = match std::str::from_utf8(slice::from_raw_parts(ptr, len)) {
+        Ok(text) => run_json(text),
+        Err(_) => Json::Object(vec![("error".to_string(), Json::String("The specification is not UTF-8!".to_string()))]).to_string(),
+    };
+    // The JSON text escapes control characters, so it has no NUL byte.
+    CString::new(output).unwrap_or_default().into_raw()
+}
+
+/// Frees a string returned by `rust_ed_run`.
+///
+/// # Safety
+///
+/// * `ptr` must come from `rust_ed_run` and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn rust_ed_free_string(ptr: *mut c_char) {
+    drop(CString::from_raw(ptr));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_run_json() {
+        let spec = r#"{"lattice": {"type": "chain", "size": [2]}, "model": {"type": "hubbard", "t": 1.0, "u": 4.0},
+            "sector": {"n_up": 1, "n_down": 1}, "solver": {"type": "full"}}"#;
+        // The Hubbard dimer ground state energy is (U - sqrt(U² + 16 t²)) / 2.
+        let results = crate::json::parse(&run_json(spec)).unwrap();
+        assert!((results.get("energy").and_then(|e| e.as_f64()).unwrap() - (4.0 - 32f64.sqrt()) / 2.0).abs() < 1e-10);
+        assert!(crate::json::parse(&run_json("{}")).unwrap().get("error").is_some());
+        unsafe {
+            let ptr = rust_ed_alloc(spec.len());
+            ptr.copy_from_nonoverlapping(spec.as_ptr(), spec.len());
+            let output = rust_ed_run(ptr, spec.len());
+            assert_eq!(CStr::from_ptr(output).to_str().unwrap(), run_json(spec));
+            rust_ed_free_string(output);
+            rust_ed_free(ptr, spec.len());
+        }
+    }
+}